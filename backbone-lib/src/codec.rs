@@ -0,0 +1,151 @@
+//! The serialization of payloads on the wire.
+//!
+//! Join request, RPCs, deltas and full updates all go through the [`WireCodec`] that [`ActiveCodec`] selects at
+//! compile time: [`PostcardCodec`] by default and [`JsonCodec`] with the feature `json-debug`, e.g. for the
+//! interoperation with a JavaScript client. The codec also decides the frame mode: JSON travels in text frames,
+//! postcard in binary frames. The join request names the codec by its [`WireCodec::ID`]. The relay takes codec and
+//! frame mode of a room from the join request of the host and rejects clients that join with others, so both peers
+//! always agree on the codec.
+//! Further codecs only need another implementation of the trait and a feature selecting it.
+//!
+//! A run of payloads, like the deltas of one update, gets length-prefixed entry by entry by default. This way a single
+//! corrupt entry can be told apart from the others, which still decode, instead of misaligning the rest of the run.
+
+#[cfg(not(feature = "json-debug"))]
+use postcard::{from_bytes, to_stdvec};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Turns payloads into the bytes on the wire and back.
+pub trait WireCodec {
+    /// Flags, if frames with payloads of this codec get sent as text instead of binary.
+    const TEXT_FRAMES: bool;
+
+    /// The id of the codec in the join request, like [`CODEC_POSTCARD`](protocol::CODEC_POSTCARD), so the relay can keep out clients using
+    /// another one.
+    const ID: u8;
+
+    /// Serializes a single payload.
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String>;
+
+    /// Deserializes a payload that was written with [`encode`](Self::encode).
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String>;
+
+    /// Serializes a run of payloads into one message. By default every payload gets prefixed with its size as u32,
+    /// so large payloads fit as well.
+    fn encode_sequence<T: Serialize>(values: &[T]) -> Result<Vec<u8>, String> {
+        let mut result = Vec::new();
+        for value in values {
+            let encoded = Self::encode(value)?;
+            let size = u32::try_from(encoded.len()).map_err(|_| {
+                format!(
+                    "Payload of {} bytes exceeds the length prefix",
+                    encoded.len()
+                )
+            })?;
+            result.extend(size.to_be_bytes());
+            result.extend(encoded);
+        }
+        Ok(result)
+    }
+
+    /// Deserializes a run of payloads that was written with [`encode_sequence`](Self::encode_sequence).
+    ///
+    /// Every entry decodes on its own: a corrupt one yields its error in place, the others are unaffected.
+    /// Only if the entries can not be told apart anymore, like with a length prefix beyond the end of the message,
+    /// the whole run is an error.
+    fn decode_sequence<T: DeserializeOwned>(
+        bytes: &[u8],
+    ) -> Result<Vec<Result<T, String>>, String> {
+        let mut result = Vec::new();
+        let mut remaining = bytes;
+        while !remaining.is_empty() {
+            let Some((prefix, rest)) = remaining.split_first_chunk::<LENGTH_PREFIX_SIZE>() else {
+                return Err("Truncated length prefix in run of payloads".to_string());
+            };
+            let size = u32::from_be_bytes(*prefix) as usize;
+            if rest.len() < size {
+                return Err(format!(
+                    "Payload of {} bytes exceeds the {} bytes left in the run",
+                    size,
+                    rest.len()
+                ));
+            }
+            let (entry, rest) = rest.split_at(size);
+            result.push(Self::decode(entry));
+            remaining = rest;
+        }
+        Ok(result)
+    }
+}
+
+/// The size of the length prefix in front of every payload of a run. (u32)
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// The compact binary default, see [postcard](https://docs.rs/postcard).
+#[cfg(not(feature = "json-debug"))]
+pub struct PostcardCodec;
+
+#[cfg(not(feature = "json-debug"))]
+impl WireCodec for PostcardCodec {
+    const TEXT_FRAMES: bool = false;
+    const ID: u8 = protocol::CODEC_POSTCARD;
+
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+        to_stdvec(value).map_err(|error| error.to_string())
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        from_bytes(bytes).map_err(|error| error.to_string())
+    }
+}
+
+/// Readable JSON in text frames. A run of payloads gets separated by line breaks instead of length prefixes,
+/// which never occur unescaped inside a JSON document.
+#[cfg(feature = "json-debug")]
+pub struct JsonCodec;
+
+#[cfg(feature = "json-debug")]
+impl WireCodec for JsonCodec {
+    const TEXT_FRAMES: bool = true;
+    const ID: u8 = protocol::CODEC_JSON;
+
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(value).map_err(|error| error.to_string())
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        serde_json::from_slice(bytes).map_err(|error| error.to_string())
+    }
+
+    fn encode_sequence<T: Serialize>(values: &[T]) -> Result<Vec<u8>, String> {
+        let mut result = Vec::new();
+        for value in values {
+            if !result.is_empty() {
+                result.push(b'\n');
+            }
+            result.extend(Self::encode(value)?);
+        }
+        Ok(result)
+    }
+
+    fn decode_sequence<T: DeserializeOwned>(
+        bytes: &[u8],
+    ) -> Result<Vec<Result<T, String>>, String> {
+        if bytes.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(bytes
+            .split(|&byte| byte == b'\n')
+            .map(Self::decode)
+            .collect())
+    }
+}
+
+/// The codec all connections use, selected by the features of the crate.
+#[cfg(not(feature = "json-debug"))]
+pub type ActiveCodec = PostcardCodec;
+
+/// The codec all connections use, selected by the features of the crate.
+#[cfg(feature = "json-debug")]
+pub type ActiveCodec = JsonCodec;
@@ -1,5 +1,13 @@
+pub mod codec;
 mod timer;
 mod web_socket_interface;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod threaded_backend;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tick_driver;
+#[cfg(feature = "timeline")]
+pub mod timeline;
 pub mod traits;
 pub mod transport_layer;
+pub mod turn_tracker;
@@ -0,0 +1,232 @@
+//! Optional worker thread for the backend on native builds.
+//!
+//! Normally the host runs the backend on the thread that calls [`TransportLayer::update`], which is the render
+//! loop. A backend with heavy computations, like an AI opponent or the evaluation of a large board, then blocks
+//! the frame it runs in.
+//!
+//! The [`ThreadedBackend`] moves the backend onto a worker thread. It implements [`BackEndArchitecture`] itself
+//! and forwards every call to the worker over a channel, so the transport layer does not notice the difference:
+//!
+//! ```ignore
+//! type Layer = TransportLayer<MyRpc, MyDelta, ThreadedBackend<MyBackend, MyRpc, MyDelta, MyState>, MyState>;
+//! ```
+//!
+//! The worker handles the calls strictly in order. After every call it hands back the drained commands together
+//! with a copy of the view state, which [`get_view_state`](BackEndArchitecture::get_view_state) returns from then
+//! on. Commands therefore arrive some frames later than with the backend on the render thread, but the view state
+//! always reflects exactly the deltas drained so far, so joining clients get synchronized consistently.
+//! The backend itself gets created on the worker and does not have to be `Send`, only the payloads do.
+//!
+//! This is not available in WASM builds, as there are no threads to run on.
+//!
+//! [`TransportLayer::update`]: crate::transport_layer::TransportLayer::update
+
+use crate::traits::{BackEndArchitecture, BackendCommand, PlayerSet, SerializationCap};
+use std::marker::PhantomData;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError, channel};
+
+/// A call of the transport layer, forwarded to the backend on the worker thread.
+enum BackendEvent<ServerRpcPayload> {
+    Arrival(u16),
+    Departure(u16, u16),
+    Rpc(u16, ServerRpcPayload, PlayerSet),
+    Timer(u16, PlayerSet),
+    HostCommand(ServerRpcPayload),
+    ResetRequest,
+    AllReady,
+}
+
+/// The outcome of a single call on the worker: the drained commands and the view state afterward.
+type BackendOutcome<DeltaInformation, ViewState> =
+    (Vec<BackendCommand<DeltaInformation>>, ViewState);
+
+/// Runs a backend on a worker thread, see the [module documentation](self).
+///
+/// The worker ends, when the threaded backend gets dropped, after finishing the call it is busy with.
+/// If the backend panics on the worker, the next drain returns [`BackendCommand::TerminateRoom`].
+pub struct ThreadedBackend<Backend, ServerRpcPayload, DeltaInformation, ViewState>
+where
+    DeltaInformation: SerializationCap,
+{
+    /// The calls for the worker.
+    events: Sender<BackendEvent<ServerRpcPayload>>,
+    /// The outcomes of the calls the worker finished.
+    outcomes: Receiver<BackendOutcome<DeltaInformation, ViewState>>,
+    /// The view state after the last drained outcome.
+    view_state: ViewState,
+    /// The minimum amount of players, as the backend reported it on creation.
+    min_players: u16,
+    /// Flags, if the loss of the worker has been reported already.
+    worker_lost: bool,
+    /// The backend only lives on the worker.
+    backend: PhantomData<fn() -> Backend>,
+}
+
+impl<Backend, ServerRpcPayload, DeltaInformation, ViewState>
+    ThreadedBackend<Backend, ServerRpcPayload, DeltaInformation, ViewState>
+where
+    DeltaInformation: SerializationCap,
+{
+    /// Hands a call over to the worker. A lost worker gets reported with the next drain.
+    fn forward(&self, event: BackendEvent<ServerRpcPayload>) {
+        let _ = self.events.send(event);
+    }
+}
+
+impl<Backend, ServerRpcPayload, DeltaInformation, ViewState>
+    ThreadedBackend<Backend, ServerRpcPayload, DeltaInformation, ViewState>
+where
+    ServerRpcPayload: SerializationCap + Send + 'static,
+    DeltaInformation: SerializationCap + Send + 'static,
+    ViewState: SerializationCap + Clone + Send + 'static,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState> + 'static,
+{
+    /// Starts the worker and waits until it created the backend. Gives `None` if the creation gave none.
+    fn start(create: impl FnOnce() -> Option<Backend> + Send + 'static) -> Option<Self> {
+        let (event_sender, event_receiver) = channel::<BackendEvent<ServerRpcPayload>>();
+        let (outcome_sender, outcome_receiver) = channel();
+        let (start_sender, start_receiver) = channel();
+        std::thread::spawn(move || {
+            let Some(mut backend) = create() else {
+                let _ = start_sender.send(None);
+                return;
+            };
+            if start_sender
+                .send(Some((
+                    backend.get_view_state().clone(),
+                    backend.min_players(),
+                )))
+                .is_err()
+            {
+                return;
+            }
+            while let Ok(event) = event_receiver.recv() {
+                match event {
+                    BackendEvent::Arrival(player) => backend.player_arrival(player),
+                    BackendEvent::Departure(player, players_remaining) => {
+                        backend.player_departure(player, players_remaining)
+                    }
+                    BackendEvent::Rpc(player, payload, players) => {
+                        backend.inform_rpc(player, payload, &players)
+                    }
+                    BackendEvent::Timer(timer_id, players) => {
+                        backend.timer_triggered(timer_id, &players)
+                    }
+                    BackendEvent::HostCommand(command) => backend.inform_host_command(command),
+                    BackendEvent::ResetRequest => backend.on_host_reset_request(),
+                    BackendEvent::AllReady => backend.all_players_ready(),
+                }
+                // The view state may change without any command, e.g. when a player gets registered.
+                let outcome = (backend.drain_commands(), backend.get_view_state().clone());
+                if outcome_sender.send(outcome).is_err() {
+                    return;
+                }
+            }
+        });
+        let (view_state, min_players) = start_receiver
+            .recv()
+            .expect("Backend creation failed on the worker thread")?;
+        Some(ThreadedBackend {
+            events: event_sender,
+            outcomes: outcome_receiver,
+            view_state,
+            min_players,
+            worker_lost: false,
+            backend: PhantomData,
+        })
+    }
+}
+
+impl<Backend, ServerRpcPayload, DeltaInformation, ViewState>
+    BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>
+    for ThreadedBackend<Backend, ServerRpcPayload, DeltaInformation, ViewState>
+where
+    ServerRpcPayload: SerializationCap + Send + 'static,
+    DeltaInformation: SerializationCap + Send + 'static,
+    ViewState: SerializationCap + Clone + Send + 'static,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState> + 'static,
+{
+    /// Starts the worker and waits until it created the backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the creation of the backend panics on the worker.
+    fn new(rule_variation: u16) -> Self {
+        Self::start(move || Some(Backend::new(rule_variation)))
+            .expect("A new backend always gets created")
+    }
+
+    /// Starts the worker like [`new`](Self::new), if the backend can continue from the view state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the creation of the backend panics on the worker.
+    fn from_view_state(
+        rule_variation: u16,
+        view_state: &ViewState,
+        former_player_id: u16,
+    ) -> Option<Self> {
+        let view_state = view_state.clone();
+        Self::start(move || Backend::from_view_state(rule_variation, &view_state, former_player_id))
+    }
+
+    fn player_arrival(&mut self, player: u16) {
+        self.forward(BackendEvent::Arrival(player));
+    }
+
+    fn player_departure(&mut self, player: u16, players_remaining: u16) {
+        self.forward(BackendEvent::Departure(player, players_remaining));
+    }
+
+    /// The worker gets a copy of the players, as they may have changed by the time it handles the call.
+    fn inform_rpc(&mut self, player: u16, payload: ServerRpcPayload, players: &PlayerSet) {
+        self.forward(BackendEvent::Rpc(player, payload, players.clone()));
+    }
+
+    fn timer_triggered(&mut self, timer_id: u16, players: &PlayerSet) {
+        self.forward(BackendEvent::Timer(timer_id, players.clone()));
+    }
+
+    fn inform_host_command(&mut self, command: ServerRpcPayload) {
+        self.forward(BackendEvent::HostCommand(command));
+    }
+
+    fn on_host_reset_request(&mut self) {
+        self.forward(BackendEvent::ResetRequest);
+    }
+
+    fn all_players_ready(&mut self) {
+        self.forward(BackendEvent::AllReady);
+    }
+
+    /// The minimum amount of players is queried once, when the backend gets created.
+    fn min_players(&self) -> u16 {
+        self.min_players
+    }
+
+    fn get_view_state(&self) -> &ViewState {
+        &self.view_state
+    }
+
+    /// Collects the commands of all calls the worker finished so far, without waiting for the busy one.
+    fn drain_commands(&mut self) -> Vec<BackendCommand<DeltaInformation>> {
+        let mut result = Vec::new();
+        loop {
+            match self.outcomes.try_recv() {
+                Ok((commands, view_state)) => {
+                    result.extend(commands);
+                    self.view_state = view_state;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !self.worker_lost {
+                        self.worker_lost = true;
+                        result.push(BackendCommand::TerminateRoom);
+                    }
+                    break;
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,128 @@
+//! Optional fixed-rate heartbeat for the transport layer on native builds.
+//!
+//! Normally the frontend heartbeats the transport layer by calling
+//! [`TransportLayer::update`] every frame. On the host this also drives the
+//! backend, so if the host window gets minimized and the render loop gets
+//! throttled, the games of all remote clients stall.
+//!
+//! The [`TickDriver`] moves the heartbeat into a background thread that runs at
+//! a fixed rate, independent of the render frame rate. The transport layer is
+//! shared behind a mutex; the frontend locks it via
+//! [`TickDriver::transport_layer`] to start games, register RPCs and poll
+//! updates, but must **not** call `update()` itself while the driver runs.
+//!
+//! This is not available in WASM builds, as there are no threads to run on.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let driver = TickDriver::start(transport_layer, Duration::from_millis(16));
+//! loop {
+//!     let mut transport_layer = driver.transport_layer();
+//!     while let Some(update) = transport_layer.get_next_update() { /* ... */ }
+//!     drop(transport_layer);
+//!     next_frame().await
+//! }
+//! ```
+
+use crate::traits::{BackEndArchitecture, SerializationCap, ViewStateArchitecture};
+use crate::transport_layer::TransportLayer;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Runs the heartbeat of a shared transport layer on a background thread.
+///
+/// The thread is stopped and joined when the driver gets dropped.
+pub struct TickDriver<ServerRpcPayload, DeltaInformation, Backend, ViewState>
+where
+    ServerRpcPayload: SerializationCap,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>,
+    DeltaInformation: SerializationCap + Clone,
+    ViewState: SerializationCap + Clone + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>,
+{
+    /// The transport layer shared between the frontend and the tick thread.
+    transport_layer:
+        Arc<Mutex<TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>>>,
+    /// Flags the tick thread to keep on running.
+    running: Arc<AtomicBool>,
+    /// The handle of the tick thread, taken on drop.
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<ServerRpcPayload, DeltaInformation, Backend, ViewState>
+    TickDriver<ServerRpcPayload, DeltaInformation, Backend, ViewState>
+where
+    ServerRpcPayload: SerializationCap + Send + 'static,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState> + Send + 'static,
+    DeltaInformation: SerializationCap + Clone + Send + 'static,
+    ViewState: SerializationCap
+        + Clone
+        + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>
+        + Send
+        + 'static,
+{
+    /// Takes over the transport layer and starts heartbeating it every `tick_interval`.
+    ///
+    /// The elapsed wall clock time is handed over as delta time, so backend timers keep
+    /// their real-time behavior even if a tick gets delayed.
+    pub fn start(
+        transport_layer: TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>,
+        tick_interval: Duration,
+    ) -> Self {
+        let transport_layer = Arc::new(Mutex::new(transport_layer));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_layer = transport_layer.clone();
+        let thread_running = running.clone();
+        let handle = std::thread::spawn(move || {
+            let mut last_tick = Instant::now();
+            while thread_running.load(Ordering::Relaxed) {
+                std::thread::sleep(tick_interval);
+                let now = Instant::now();
+                let delta_time = now.duration_since(last_tick).as_secs_f32();
+                last_tick = now;
+                thread_layer
+                    .lock()
+                    .expect("Transport layer lock poisoned")
+                    .update(delta_time);
+            }
+        });
+
+        TickDriver {
+            transport_layer,
+            running,
+            handle: Some(handle),
+        }
+    }
+
+    /// Locks the shared transport layer for the frontend.
+    ///
+    /// Keep the guard only as long as needed, as the tick thread is blocked meanwhile.
+    pub fn transport_layer(
+        &self,
+    ) -> MutexGuard<'_, TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>>
+    {
+        self.transport_layer
+            .lock()
+            .expect("Transport layer lock poisoned")
+    }
+}
+
+impl<ServerRpcPayload, DeltaInformation, Backend, ViewState> Drop
+    for TickDriver<ServerRpcPayload, DeltaInformation, Backend, ViewState>
+where
+    ServerRpcPayload: SerializationCap,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>,
+    DeltaInformation: SerializationCap + Clone,
+    ViewState: SerializationCap + Clone + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>,
+{
+    /// Stops the tick thread and waits for it to finish.
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
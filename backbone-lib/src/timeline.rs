@@ -0,0 +1,146 @@
+//! Frame-rate independent animation timing, available with the feature `timeline`.
+//!
+//! A [`Timeline`] accumulates the delta time of the render loop and interpolates between keyframes. Every keyframe
+//! carries the easing function for the segment that leads to it. The module does not know anything about rendering,
+//! the game maps the value of the timeline onto its own animation, e.g. a radius or a rotation.
+
+/// An easing function maps the normalized progress of a segment in the range 0..1 onto the interpolation weight.
+pub type Easing = fn(f32) -> f32;
+
+/// Progresses at constant speed.
+pub fn linear(t: f32) -> f32 {
+    t.clamp(0.0, 1.0)
+}
+
+/// A smoothstep function in the range of 0..1 with vanishing derivatives at the extrema.
+pub fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Starts slowly and accelerates (quadratic).
+pub fn ease_in(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t
+}
+
+/// Starts fast and decelerates (quadratic).
+pub fn ease_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * (2.0 - t)
+}
+
+/// A value the timeline reaches at a given point in time.
+#[derive(Clone, Copy)]
+struct Keyframe {
+    /// The time since the start of the timeline.
+    time: f32,
+    /// The value at that time.
+    value: f32,
+    /// The easing of the segment from the previous keyframe to this one.
+    easing: Easing,
+}
+
+/// A sequence of keyframes with the time passed so far.
+///
+/// ```
+/// use backbone_lib::timeline::{Timeline, linear, smoothstep};
+///
+/// // Grows to 1 within 0.25 seconds and then runs to 2 within another 0.75 seconds.
+/// let mut timeline = Timeline::new(0.0)
+///     .then(0.25, 1.0, smoothstep)
+///     .then(0.75, 2.0, linear);
+/// timeline.advance(0.25);
+/// assert_eq!(timeline.value(), 1.0);
+/// assert!(!timeline.is_finished());
+/// ```
+#[derive(Clone)]
+pub struct Timeline {
+    /// The keyframes in ascending order of time, the first one is at time 0.
+    keyframes: Vec<Keyframe>,
+    /// The time passed since the start.
+    time_passed: f32,
+}
+
+impl Timeline {
+    /// Creates a timeline that starts with the indicated value.
+    pub fn new(start_value: f32) -> Self {
+        Timeline {
+            keyframes: vec![Keyframe {
+                time: 0.0,
+                value: start_value,
+                easing: linear,
+            }],
+            time_passed: 0.0,
+        }
+    }
+
+    /// Appends a keyframe.
+    ///
+    /// # Arguments
+    /// * `duration`: The time it takes to get from the previous keyframe to this one. Negative durations count as 0.
+    /// * `value`: The value at the keyframe.
+    /// * `easing`: The easing of the segment leading to the keyframe.
+    pub fn then(mut self, duration: f32, value: f32, easing: Easing) -> Self {
+        let time = self.duration() + duration.max(0.0);
+        self.keyframes.push(Keyframe {
+            time,
+            value,
+            easing,
+        });
+        self
+    }
+
+    /// Advances the timeline by the delta time of the frame and returns if it is finished.
+    pub fn advance(&mut self, delta_time: f32) -> bool {
+        self.time_passed += delta_time.max(0.0);
+        self.is_finished()
+    }
+
+    /// Restarts the timeline from its first keyframe.
+    pub fn restart(&mut self) {
+        self.time_passed = 0.0;
+    }
+
+    /// The time passed since the start.
+    pub fn elapsed(&self) -> f32 {
+        self.time_passed
+    }
+
+    /// The time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Flags, if the time of the last keyframe has passed.
+    pub fn is_finished(&self) -> bool {
+        self.time_passed >= self.duration()
+    }
+
+    /// The progress of the whole timeline in the range 0..1, without easing.
+    pub fn progress(&self) -> f32 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            1.0
+        } else {
+            (self.time_passed / duration).min(1.0)
+        }
+    }
+
+    /// The interpolated value at the current time. After the end it is the value of the last keyframe.
+    pub fn value(&self) -> f32 {
+        // The first keyframe is at time 0, so the next one always has a predecessor.
+        let Some(next_index) = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > self.time_passed)
+        else {
+            return self.keyframes.last().map_or(0.0, |keyframe| keyframe.value);
+        };
+        let previous = &self.keyframes[next_index - 1];
+        let next = &self.keyframes[next_index];
+        let weight =
+            (next.easing)((self.time_passed - previous.time) / (next.time - previous.time));
+        previous.value + (next.value - previous.value) * weight
+    }
+}
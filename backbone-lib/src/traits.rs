@@ -29,6 +29,8 @@
 //! - **Inbound**: Player RPCs arrive via [`BackEndArchitecture::inform_rpc`]
 //! - **Outbound**: Game produces [`BackendCommand`]s (deltas, kicks, timers)
 //! - **Sync**: New clients receive [`BackEndArchitecture::get_view_state`] for full state
+//! - **Diffing**: Optionally, [`commit_view_state`] derives the deltas from a [`Diffable`] view state
+//! - **Regions**: Optionally, a large [`RegionedViewState`] gets synchronized region by region
 //!
 //! # Implementing a Game
 //!
@@ -36,13 +38,14 @@
 //! impl BackEndArchitecture<MyRpc, MyDelta, MyViewState> for MyGame {
 //!     fn new(rule_variation: u16) -> Self { /* ... */ }
 //!     fn player_arrival(&mut self, player: u16) { /* ... */ }
-//!     fn inform_rpc(&mut self, player: u16, payload: MyRpc) { /* ... */ }
+//!     fn inform_rpc(&mut self, player: u16, payload: MyRpc, players: &PlayerSet) { /* ... */ }
 //!     // ...
 //! }
 //! ```
 
-use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 /// Marker trait for types that can be serialized with postcard.
 ///
@@ -62,6 +65,59 @@ use serde::de::DeserializeOwned;
 pub trait SerializationCap: Serialize + DeserializeOwned {}
 impl<T> SerializationCap for T where T: Serialize + DeserializeOwned {}
 
+/// The players connected to the room right now, the host included.
+///
+/// The transport layer tracks the arrivals and departures and lends the current set to
+/// [`BackEndArchitecture::inform_rpc`] and [`BackEndArchitecture::timer_triggered`], so a backend
+/// can ask whether a player is still here without tracking the presence itself.
+/// Departed players whose seat got adopted with [`BackendCommand::AdoptPlayer`] are not in the set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerSet {
+    /// The ids of the connected players.
+    players: BTreeSet<u16>,
+}
+
+impl PlayerSet {
+    /// Checks if the player is connected.
+    pub fn contains(&self, player: u16) -> bool {
+        self.players.contains(&player)
+    }
+
+    /// The amount of connected players, the host included.
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Checks if nobody is connected, which only happens before the host connected.
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// The ids of the connected players in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        self.players.iter().copied()
+    }
+
+    /// Registers an arriving player.
+    pub(crate) fn insert(&mut self, player: u16) {
+        self.players.insert(player);
+    }
+
+    /// Removes a departing player.
+    pub(crate) fn remove(&mut self, player: u16) {
+        self.players.remove(&player);
+    }
+}
+
+/// Builds a set from player ids, e.g. to call a backend directly in a test.
+impl FromIterator<u16> for PlayerSet {
+    fn from_iter<T: IntoIterator<Item = u16>>(iter: T) -> Self {
+        PlayerSet {
+            players: iter.into_iter().collect(),
+        }
+    }
+}
+
 /// Commands emitted by the game backend to control the session.
 ///
 /// The transport layer polls these via [`BackEndArchitecture::drain_commands`]
@@ -72,11 +128,22 @@ impl<T> SerializationCap for T where T: Serialize + DeserializeOwned {}
 /// | Command | Network Effect | Use Case |
 /// |---------|----------------|----------|
 /// | [`Delta`](Self::Delta) | Broadcast to all clients | Incremental state change |
+/// | [`DeltaExcept`](Self::DeltaExcept) | Broadcast to all but one client | Change already predicted by the sender |
+/// | [`DeltaTo`](Self::DeltaTo) | Sent to a single player only | Private information like a hand of cards |
 /// | [`ResetViewState`](Self::ResetViewState) | Broadcast + clear client state | New game/round |
 /// | [`KickPlayer`](Self::KickPlayer) | Targeted disconnect | Rule enforcement |
 /// | [`SetTimer`](Self::SetTimer) | None (local only) | Turn limits, animations |
 /// | [`CancelTimer`](Self::CancelTimer) | None (local only) | Player acted in time |
 /// | [`TerminateRoom`](Self::TerminateRoom) | Disconnect everyone | Important player left, fatal error |
+/// | [`CancelTermination`](Self::CancelTermination) | None (local only) | The departed player returned in time |
+/// | [`Log`](Self::Log) | Recorded by the relay only | Host-side diagnostics |
+/// | [`ReportResult`](Self::ReportResult) | Handed to the relay's results sink | Game end statistics |
+/// | [`SetPaused`](Self::SetPaused) | Broadcast to all clients | Pause and resume the game |
+/// | [`AdoptPlayer`](Self::AdoptPlayer) | None (local only) | An AI takes over a departed player |
+/// | [`ReleasePlayer`](Self::ReleasePlayer) | None (local only) | A human takes the seat back |
+/// | [`UpdateRegions`](Self::UpdateRegions) | Broadcast to all clients | Changed regions of a large view state |
+/// | [`Tick`](Self::Tick) | Broadcast to all clients | Time-based effects in sync with the host |
+/// | [`RejectAction`](Self::RejectAction) | Sent to a single player only | Feedback for an ignored action |
 pub enum BackendCommand<DeltaInformation>
 where
     DeltaInformation: SerializationCap,
@@ -93,6 +160,38 @@ where
     /// - Score updated
     Delta(DeltaInformation),
 
+    /// Incremental state change for everyone except one player.
+    ///
+    /// Meant for input prediction, where the acting player has already
+    /// applied the change locally and only the others need the delta. If
+    /// the excluded player is the host (player `0`), the delta is broadcast
+    /// to all remote clients but not handed to the local frontend.
+    ///
+    /// The excluded player's transport layer still gets the delta, but only
+    /// applies it to its shadow view state, so later deltas validated via
+    /// [`ViewStateArchitecture::try_apply`] may build on it. Its frontend
+    /// must hold an equivalent state already.
+    DeltaExcept {
+        /// The player that does not receive the delta.
+        player: u16,
+        /// The delta for all other players.
+        delta: DeltaInformation,
+    },
+
+    /// Incremental state change for a single player only.
+    ///
+    /// Meant for private information, like the cards drawn into a hand.
+    /// The relay delivers it to that player only and never to spectators.
+    /// If the player is the host (player `0`), the delta only goes to the
+    /// local frontend. Keep in mind that the view state of every full update
+    /// goes to everyone, so it must not contain the private information itself.
+    DeltaTo {
+        /// The player that receives the delta.
+        player: u16,
+        /// The delta for this player.
+        delta: DeltaInformation,
+    },
+
     /// Signals a complete reset of the game state.
     ///
     /// Clients discard their current view state and request a fresh
@@ -145,6 +244,93 @@ where
     /// - An unrecoverable error occurs
     /// - The game ends and the room should close
     TerminateRoom,
+
+    /// Cancels a [`TerminateRoom`](Self::TerminateRoom) deferred by the grace period of
+    /// [`TransportLayer::set_termination_grace`](crate::transport_layer::TransportLayer::set_termination_grace).
+    ///
+    /// The relay hands out a new id to a player that rejoins, so the backend learns about the return with
+    /// [`BackEndArchitecture::player_arrival`] and decides itself, if the newcomer takes over the seat.
+    /// No-op if no termination is pending.
+    CancelTermination,
+
+    /// Sends a diagnostic line to the relay server.
+    ///
+    /// The relay records the text in its own logs, tagged with the room id,
+    /// and does not forward it to any client. This gives operators insight
+    /// into host-side decisions without a separate logging pipeline.
+    Log(String),
+
+    /// Reports the result of a finished game to the relay server.
+    ///
+    /// The blob is defined by the game, like the final scores encoded with
+    /// postcard. The relay does not interpret it, but hands it to its results
+    /// sink, which logs it or posts it to a configured URL. Nothing gets
+    /// forwarded to the clients.
+    ReportResult(Vec<u8>),
+
+    /// Pauses (`true`) or resumes (`false`) the game for everyone.
+    ///
+    /// All frontends see the state via [`TransportLayer::is_paused`](crate::transport_layer::TransportLayer::is_paused)
+    /// and may overlay a "Paused" banner and ignore input. Clients joining
+    /// later learn the state right after their full update. The backend
+    /// itself keeps running; its timers only stop if enabled with
+    /// [`TransportLayer::set_pause_timers`](crate::transport_layer::TransportLayer::set_pause_timers).
+    SetPaused(bool),
+
+    /// Marks the seat of a departed player as controlled by the backend itself.
+    ///
+    /// Instead of terminating the room when a player leaves, a backend may substitute an AI and keep
+    /// acting for the player, e.g. by scheduling a timer on the player's turn and executing the move it
+    /// picked like an RPC of that player. The transport layer counts the adopted seat as present, see
+    /// [`TransportLayer::adopted_players`](crate::transport_layer::TransportLayer::adopted_players).
+    ///
+    /// The relay hands out a new id to a player that rejoins, so the backend learns about the return with
+    /// [`BackEndArchitecture::player_arrival`] and decides itself, if the newcomer takes over the seat.
+    AdoptPlayer {
+        /// The id of the departed player, whose seat the backend takes over.
+        player: u16,
+    },
+
+    /// Ends the adoption of a seat, e.g. because a rejoining player took it over.
+    ///
+    /// No-op if the seat was never adopted.
+    ReleasePlayer {
+        /// The id the seat was adopted under.
+        player: u16,
+    },
+
+    /// Marks regions of a [`RegionedViewState`] as changed, instead of describing the change with deltas.
+    ///
+    /// The transport layer sends the content of these regions to all clients as a partial update, spectators
+    /// included. Remote clients and the local frontend get the updated view state as
+    /// [`ViewStateUpdate::Full`](crate::transport_layer::ViewStateUpdate::Full). The regions go out after the
+    /// deltas drained together with this command and carry the content of the view state at the end of the drain,
+    /// so these deltas must not depend on the changes of the regions.
+    ///
+    /// Host and clients have to enable the regions with
+    /// [`TransportLayer::enable_regions`](crate::transport_layer::TransportLayer::enable_regions).
+    /// Without it the host ignores the command and logs the misuse to the relay.
+    UpdateRegions(Vec<u16>),
+
+    /// Sends a tick without any change of the view state, e.g. to drive time-based effects in step with the host.
+    ///
+    /// All frontends, the local one included, get a
+    /// [`ViewStateUpdate::Tick`](crate::transport_layer::ViewStateUpdate::Tick). A tick goes out after the deltas
+    /// drained together with it and carries no payload, so it costs a single byte on the wire.
+    Tick,
+
+    /// Tells a single player why its action got ignored, e.g. a move that is not legal.
+    ///
+    /// The relay delivers it to that player only and never to spectators. The frontend of the player picks up
+    /// the reason with [`TransportLayer::take_rejection`](crate::transport_layer::TransportLayer::take_rejection).
+    /// If the player is the host (player `0`), the reason only goes to the local frontend. The rejection does not
+    /// wait for the deltas collected within the flush interval.
+    RejectAction {
+        /// The player whose action got ignored.
+        player: u16,
+        /// A human readable reason.
+        reason: String,
+    },
 }
 
 /// The core trait for implementing game-specific server logic.
@@ -203,12 +389,31 @@ where
     /// - `2` = Timed mode
     fn new(rule_variation: u16) -> Self;
 
+    /// Creates a game instance that continues from the view state of a client, when the client becomes the
+    /// new host of a room with host migration, see
+    /// [`TransportLayer::is_migrating`](crate::transport_layer::TransportLayer::is_migrating).
+    ///
+    /// `former_player_id` is the id the new host had as a client, it plays as player `0` from now on.
+    /// The other clients join again with new ids. The default returns `None`, so the room ends as if it had
+    /// no host migration.
+    fn from_view_state(
+        _rule_variation: u16,
+        _view_state: &ViewState,
+        _former_player_id: u16,
+    ) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
     /// Called when a new player connects to the room.
     ///
     /// The backend should:
     /// - Add the player to its internal tracking
     /// - Optionally emit a [`BackendCommand::Delta`] announcing the join
     /// - Optionally emit [`BackendCommand::KickPlayer`] if joining is not allowed
+    /// - Optionally emit [`BackendCommand::CancelTermination`] if the player takes over the seat of a departed one
     ///
     /// Note: The player will receive a full **ViewState** automatically after
     /// this method returns.
@@ -219,13 +424,20 @@ where
     /// The backend should:
     /// - Remove the player from internal tracking
     /// - Handle game-over conditions if a critical player left
-    /// - Optionally emit [`BackendCommand::TerminateRoom`] if the game cannot continue
-    fn player_departure(&mut self, player: u16);
+    /// - Optionally emit [`BackendCommand::TerminateRoom`] if the game cannot continue,
+    ///   or [`BackendCommand::AdoptPlayer`] to continue with an AI in place of the player
+    ///
+    /// # Arguments
+    /// * `player` — The player ID who left
+    /// * `players_remaining` — How many players are still in the room after the departure,
+    ///   including the host. Lets the backend decide between continuing and aborting
+    ///   without tracking the count itself.
+    fn player_departure(&mut self, player: u16, players_remaining: u16);
 
     /// Called when a player sends a game action.
     ///
     /// This is the main entry point for game logic. The backend should:
-    /// - Validate the action (ignore or kick if invalid)
+    /// - Validate the action (ignore, reject with [`BackendCommand::RejectAction`] or kick if invalid)
     /// - Update internal state
     /// - Emit appropriate [`BackendCommand::Delta`] messages
     /// - Update the **ViewState** to match
@@ -233,15 +445,49 @@ where
     /// # Arguments
     /// * `player` — The player ID who sent this action
     /// * `payload` — The deserialized game-specific action
-    fn inform_rpc(&mut self, player: u16, payload: ServerRpcPayload);
+    /// * `players` — The players connected right now, see [`PlayerSet`]
+    fn inform_rpc(&mut self, player: u16, payload: ServerRpcPayload, players: &PlayerSet);
 
     /// Called when a previously scheduled timer fires.
     ///
     /// Common responses:
-    /// - Force a default action for a timed-out player
+    /// - Force a default action for a timed-out player, or for one that is no longer in `players`
     /// - Transition to the next game phase
     /// - Emit a delta for animation completion
-    fn timer_triggered(&mut self, timer_id: u16);
+    fn timer_triggered(&mut self, timer_id: u16, players: &PlayerSet);
+
+    /// Called with a privileged command of the host, like forcing the next round.
+    ///
+    /// Commands come from
+    /// [`TransportLayer::register_host_command`](crate::transport_layer::TransportLayer::register_host_command)
+    /// and never over the network, so unlike [`inform_rpc`](Self::inform_rpc) they need no check
+    /// of who is allowed to issue them. The default ignores the command.
+    fn inform_host_command(&mut self, _command: ServerRpcPayload) {}
+
+    /// Called when the frontend of the host requests a restart, e.g. from a rematch button.
+    ///
+    /// This happens locally via
+    /// [`TransportLayer::request_reset`](crate::transport_layer::TransportLayer::request_reset)
+    /// and never over the network, so remote clients can not trigger it. The backend should
+    /// reset its state and emit [`BackendCommand::ResetViewState`]. The default ignores the request.
+    fn on_host_reset_request(&mut self) {}
+
+    /// Called once all players, including the host, flagged themselves as ready, e.g. to start the game.
+    ///
+    /// Players flag their readiness with
+    /// [`TransportLayer::set_ready`](crate::transport_layer::TransportLayer::set_ready). This gets called again,
+    /// if the readiness got lost in between, e.g. because a player took it back or a new player joined.
+    /// The default ignores it.
+    fn all_players_ready(&mut self) {}
+
+    /// The amount of players, including the host, the game needs before it can start.
+    ///
+    /// The transport layer compares this with the players present in the room, see
+    /// [`TransportLayer::enough_players`](crate::transport_layer::TransportLayer::enough_players),
+    /// so the frontend can gate a start button. The default of `1` lets the host start alone.
+    fn min_players(&self) -> u16 {
+        1
+    }
 
     /// Returns the complete current game state for client synchronization.
     ///
@@ -268,3 +514,392 @@ where
     /// ```
     fn drain_commands(&mut self) -> Vec<BackendCommand<DeltaInformation>>;
 }
+
+/// An alternative shape of the backend, where every handler returns its commands instead of queueing them.
+///
+/// The handlers mirror those of [`BackEndArchitecture`], but there is no command list to keep and drain.
+/// This makes a backend easy to test: call a handler and look at what it returned. The transport layer
+/// takes such a backend wrapped into a [`FunctionalAdapter`], so existing games are unaffected.
+///
+/// # Example
+///
+/// ```ignore
+/// impl FunctionalBackEnd<MyRpc, MyDelta, MyState> for MyBackend {
+///     fn inform_rpc(&mut self, player: u16, payload: MyRpc, _: &PlayerSet) -> Vec<BackendCommand<MyDelta>> {
+///         let delta = self.apply(player, payload);
+///         vec![BackendCommand::Delta(delta)]
+///     }
+///     // ...
+/// }
+///
+/// type Layer = TransportLayer<MyRpc, MyDelta, FunctionalAdapter<MyBackend, MyDelta>, MyState>;
+/// ```
+pub trait FunctionalBackEnd<ServerRpcPayload, DeltaInformation, ViewState>
+where
+    ServerRpcPayload: SerializationCap,
+    DeltaInformation: SerializationCap,
+    ViewState: SerializationCap + Clone,
+{
+    /// See [`BackEndArchitecture::new`].
+    fn new(rule_variation: u16) -> Self;
+
+    /// See [`BackEndArchitecture::from_view_state`]. The default returns `None`.
+    fn from_view_state(
+        _rule_variation: u16,
+        _view_state: &ViewState,
+        _former_player_id: u16,
+    ) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// See [`BackEndArchitecture::player_arrival`].
+    fn player_arrival(&mut self, player: u16) -> Vec<BackendCommand<DeltaInformation>>;
+
+    /// See [`BackEndArchitecture::player_departure`].
+    fn player_departure(
+        &mut self,
+        player: u16,
+        players_remaining: u16,
+    ) -> Vec<BackendCommand<DeltaInformation>>;
+
+    /// See [`BackEndArchitecture::inform_rpc`].
+    fn inform_rpc(
+        &mut self,
+        player: u16,
+        payload: ServerRpcPayload,
+        players: &PlayerSet,
+    ) -> Vec<BackendCommand<DeltaInformation>>;
+
+    /// See [`BackEndArchitecture::timer_triggered`].
+    fn timer_triggered(
+        &mut self,
+        timer_id: u16,
+        players: &PlayerSet,
+    ) -> Vec<BackendCommand<DeltaInformation>>;
+
+    /// See [`BackEndArchitecture::inform_host_command`]. The default ignores the command.
+    fn inform_host_command(
+        &mut self,
+        _command: ServerRpcPayload,
+    ) -> Vec<BackendCommand<DeltaInformation>> {
+        Vec::new()
+    }
+
+    /// See [`BackEndArchitecture::on_host_reset_request`]. The default ignores the request.
+    fn on_host_reset_request(&mut self) -> Vec<BackendCommand<DeltaInformation>> {
+        Vec::new()
+    }
+
+    /// See [`BackEndArchitecture::all_players_ready`]. The default ignores it.
+    fn all_players_ready(&mut self) -> Vec<BackendCommand<DeltaInformation>> {
+        Vec::new()
+    }
+
+    /// See [`BackEndArchitecture::min_players`].
+    fn min_players(&self) -> u16 {
+        1
+    }
+
+    /// See [`BackEndArchitecture::get_view_state`].
+    fn get_view_state(&self) -> &ViewState;
+}
+
+/// Bridges a [`FunctionalBackEnd`] to the drain-based [`BackEndArchitecture`] the transport layer expects,
+/// by collecting the returned commands until they get drained.
+pub struct FunctionalAdapter<Backend, DeltaInformation>
+where
+    DeltaInformation: SerializationCap,
+{
+    /// The wrapped backend.
+    backend: Backend,
+    /// The commands returned by the handlers since the last drain.
+    command_list: Vec<BackendCommand<DeltaInformation>>,
+}
+
+impl<Backend, DeltaInformation> FunctionalAdapter<Backend, DeltaInformation>
+where
+    DeltaInformation: SerializationCap,
+{
+    /// Wraps an already constructed backend.
+    pub fn wrap(backend: Backend) -> Self {
+        FunctionalAdapter {
+            backend,
+            command_list: Vec::new(),
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn backend(&self) -> &Backend {
+        &self.backend
+    }
+}
+
+impl<ServerRpcPayload, DeltaInformation, ViewState, Backend>
+    BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>
+    for FunctionalAdapter<Backend, DeltaInformation>
+where
+    ServerRpcPayload: SerializationCap,
+    DeltaInformation: SerializationCap,
+    ViewState: SerializationCap + Clone,
+    Backend: FunctionalBackEnd<ServerRpcPayload, DeltaInformation, ViewState>,
+{
+    fn new(rule_variation: u16) -> Self {
+        Self::wrap(Backend::new(rule_variation))
+    }
+
+    fn from_view_state(
+        rule_variation: u16,
+        view_state: &ViewState,
+        former_player_id: u16,
+    ) -> Option<Self> {
+        Backend::from_view_state(rule_variation, view_state, former_player_id).map(Self::wrap)
+    }
+
+    fn player_arrival(&mut self, player: u16) {
+        let commands = self.backend.player_arrival(player);
+        self.command_list.extend(commands);
+    }
+
+    fn player_departure(&mut self, player: u16, players_remaining: u16) {
+        let commands = self.backend.player_departure(player, players_remaining);
+        self.command_list.extend(commands);
+    }
+
+    fn inform_rpc(&mut self, player: u16, payload: ServerRpcPayload, players: &PlayerSet) {
+        let commands = self.backend.inform_rpc(player, payload, players);
+        self.command_list.extend(commands);
+    }
+
+    fn timer_triggered(&mut self, timer_id: u16, players: &PlayerSet) {
+        let commands = self.backend.timer_triggered(timer_id, players);
+        self.command_list.extend(commands);
+    }
+
+    fn inform_host_command(&mut self, command: ServerRpcPayload) {
+        let commands = self.backend.inform_host_command(command);
+        self.command_list.extend(commands);
+    }
+
+    fn on_host_reset_request(&mut self) {
+        let commands = self.backend.on_host_reset_request();
+        self.command_list.extend(commands);
+    }
+
+    fn all_players_ready(&mut self) {
+        let commands = self.backend.all_players_ready();
+        self.command_list.extend(commands);
+    }
+
+    fn min_players(&self) -> u16 {
+        self.backend.min_players()
+    }
+
+    fn get_view_state(&self) -> &ViewState {
+        self.backend.get_view_state()
+    }
+
+    fn drain_commands(&mut self) -> Vec<BackendCommand<DeltaInformation>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+/// Validation contract of the view state, shared by host and remote clients.
+///
+/// **Deltas**: Remote clients can not trust the host blindly: a buggy or
+/// hostile host may send deltas that do not fit the current state. The
+/// transport layer keeps a shadow copy of the last synchronized view state on
+/// every remote client and applies each incoming delta through
+/// [`try_apply`](Self::try_apply) before handing it to the frontend. If the
+/// delta is rejected, e.g. because the client missed an update, the frontend
+/// never sees it: the client requests a full update and drops the deltas until it
+/// arrives, see [`TransportLayer::is_desynchronized`](crate::transport_layer::TransportLayer::is_desynchronized).
+///
+/// **Actions**: [`is_action_legal`](Self::is_action_legal) is a dry-run of the
+/// legality check the backend performs on an RPC. The frontend can call it on
+/// its own view state to pre-validate input, and the transport layer can drop
+/// illegal RPCs before sending them (see
+/// [`TransportLayer::set_drop_illegal_rpcs`](crate::transport_layer::TransportLayer::set_drop_illegal_rpcs)).
+///
+/// **Prediction**: Fast games may show the result of an action before the host
+/// confirms it. [`predict`](Self::predict) guesses the delta the backend will
+/// answer with, and [`confirms_prediction`](Self::confirms_prediction) matches it
+/// against the authoritative delta (see
+/// [`TransportLayer::set_prediction`](crate::transport_layer::TransportLayer::set_prediction)).
+/// Turn-based games can ignore both.
+///
+/// **Invariants**: [`debug_assert_valid`](Self::debug_assert_valid) asserts the
+/// invariants of the game, like at most one winner. In debug builds the host
+/// calls it on the view state of its backend whenever the backend emitted
+/// commands, so a buggy backend gets caught right where it broke the state.
+///
+/// # Example
+///
+/// ```ignore
+/// impl ViewStateArchitecture<MyRpc, MyDelta> for MyViewState {
+///     fn try_apply(&mut self, delta: &MyDelta) -> Result<(), String> {
+///         if !self.is_consistent_with(delta) {
+///             return Err("Delta does not match the current state".to_string());
+///         }
+///         self.apply_delta(delta);
+///         Ok(())
+///     }
+///
+///     fn is_action_legal(&self, player: u16, payload: &MyRpc) -> bool {
+///         self.check_legality(player, payload)
+///     }
+/// }
+/// ```
+pub trait ViewStateArchitecture<ServerRpcPayload, DeltaInformation> {
+    /// Checks the delta against the current state and applies it on success.
+    ///
+    /// On error the state may be left in any shape, as the client heals it
+    /// with a full update from the host.
+    fn try_apply(&mut self, delta: &DeltaInformation) -> Result<(), String>;
+
+    /// Checks if the indicated player may perform the action in the current state.
+    ///
+    /// This should mirror the check the backend performs in
+    /// [`BackEndArchitecture::inform_rpc`]. The default accepts every action.
+    fn is_action_legal(&self, _player: u16, _payload: &ServerRpcPayload) -> bool {
+        true
+    }
+
+    /// Predicts the delta the backend will emit for the action of the indicated player.
+    ///
+    /// Only called on remote clients with prediction enabled. The state is the last
+    /// synchronized one with all still pending predictions applied. The default predicts nothing.
+    fn predict(&self, _player: u16, _payload: &ServerRpcPayload) -> Option<DeltaInformation> {
+        None
+    }
+
+    /// Checks if the authoritative delta from the host confirms the oldest pending prediction.
+    ///
+    /// A confirmed prediction is not handed to the frontend again. Otherwise the
+    /// predictions get rolled back with a full update. The default confirms nothing.
+    fn confirms_prediction(_predicted: &DeltaInformation, _authoritative: &DeltaInformation) -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
+    /// Panics if the state violates an invariant of the game.
+    ///
+    /// Only called in debug builds, on the host after draining the commands of the backend,
+    /// so the check may be expensive. The default checks nothing.
+    fn debug_assert_valid(&self) {}
+}
+
+/// Optional ability of a view state to work out the deltas between two of its versions.
+///
+/// Hand-authored deltas can drift from the view state changes they stand for.
+/// With this trait, a backend changes a copy of its view state instead and lets
+/// [`commit_view_state`] compute and enqueue the deltas.
+///
+/// Applying the deltas of `old.diff(&new)` in order to `old`, e.g. with
+/// [`ViewStateArchitecture::try_apply`], has to result in `new`.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut next = self.view_state.clone();
+/// next.place_stone(row, column);
+/// commit_view_state(&mut self.view_state, next, &mut self.command_list);
+/// ```
+pub trait Diffable<DeltaInformation> {
+    /// Computes the deltas that turn `self` into `other`, empty if nothing changed.
+    fn diff(&self, other: &Self) -> Vec<DeltaInformation>;
+}
+
+/// Replaces the view state of a backend with a new one and appends the deltas between
+/// both as [`BackendCommand::Delta`] to the command list, which the backend hands out in
+/// [`BackEndArchitecture::drain_commands`]. Returns the amount of deltas appended.
+pub fn commit_view_state<ViewState, DeltaInformation>(
+    view_state: &mut ViewState,
+    new_state: ViewState,
+    command_list: &mut Vec<BackendCommand<DeltaInformation>>,
+) -> usize
+where
+    ViewState: Diffable<DeltaInformation>,
+    DeltaInformation: SerializationCap,
+{
+    let deltas = view_state.diff(&new_state);
+    let amount = deltas.len();
+    command_list.extend(deltas.into_iter().map(BackendCommand::Delta));
+    *view_state = new_state;
+    amount
+}
+
+/// Optional ability of a large view state to get synchronized region by region, like the rows of a 19×19 board.
+///
+/// Authoring deltas for a big state is tedious and sending the whole state for every change is costly. With this
+/// trait, a backend changes its view state directly and marks the changed regions with
+/// [`BackendCommand::UpdateRegions`]. The transport layer then sends only these regions as a [`RegionUpdate`].
+///
+/// Setting every region of a view state to the content of the same region of another one has to make both equal.
+///
+/// # Example
+///
+/// ```ignore
+/// impl RegionedViewState for Board {
+///     type Region = Vec<Stone>;
+///     fn region_count(&self) -> u16 { 19 }
+///     fn region(&self, index: u16) -> Vec<Stone> { self.rows[index as usize].clone() }
+///     fn set_region(&mut self, index: u16, region: Vec<Stone>) -> Result<(), String> { /* ... */ }
+/// }
+///
+/// self.view_state.rows[row][column] = stone;
+/// self.command_list.push(BackendCommand::UpdateRegions(vec![row as u16]));
+/// ```
+pub trait RegionedViewState {
+    /// The content of a single region.
+    type Region: SerializationCap;
+
+    /// The amount of regions, their indices run from 0 up to this value exclusively.
+    fn region_count(&self) -> u16;
+
+    /// A copy of the content of the region with the index, which is below [`region_count`](Self::region_count).
+    fn region(&self, index: u16) -> Self::Region;
+
+    /// Replaces the content of the region with the index.
+    ///
+    /// Fails if the index is out of range or the content does not fit, like a row of the wrong length.
+    /// The region comes from the network, so this must not panic.
+    fn set_region(&mut self, index: u16, region: Self::Region) -> Result<(), String>;
+}
+
+/// The changed regions of a [`RegionedViewState`], as a partial update carries them over the network.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RegionUpdate<Region> {
+    /// The index and the content of every changed region.
+    pub regions: Vec<(u16, Region)>,
+}
+
+impl<Region> RegionUpdate<Region> {
+    /// Copies the regions with the indices out of the view state. Indices out of range are left out.
+    pub fn collect<ViewState>(view_state: &ViewState, indices: &[u16]) -> Self
+    where
+        ViewState: RegionedViewState<Region = Region>,
+    {
+        let regions = indices
+            .iter()
+            .filter(|&&index| index < view_state.region_count())
+            .map(|&index| (index, view_state.region(index)))
+            .collect();
+        RegionUpdate { regions }
+    }
+
+    /// Sets the regions on the view state in order. Stops at the first region the view state rejects.
+    pub fn apply_to<ViewState>(self, view_state: &mut ViewState) -> Result<(), String>
+    where
+        ViewState: RegionedViewState<Region = Region>,
+    {
+        for (index, region) in self.regions {
+            view_state.set_region(index, region)?;
+        }
+        Ok(())
+    }
+}
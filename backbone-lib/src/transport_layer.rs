@@ -26,7 +26,8 @@
 //!   into multiple deltas — they get coalesced during network transmission.
 //! * **ViewState**: Complete snapshot for the frontend containing all visualization data.
 //!   Used when a new client joins. On receipt, set the visualization state immediately
-//!   without animation.
+//!   without animation. Implements [`ViewStateArchitecture`] so remote clients can validate
+//!   incoming deltas before applying them.
 //! * **BackendArchitecture**: The game logic module, only present on the server side.
 //!
 //! # Frontend Integration
@@ -60,12 +61,15 @@
 //!         ConnectionState::Connected { is_server: _, player_id, rule_set } => {
 //!             if let Some(update) = net_architecture.get_next_update() {
 //!                 match update {
-//!                     ViewStateUpdate::Full(state) => {
+//!                     ViewStateUpdate::Full(state) | ViewStateUpdate::FullReset(state) => {
 //!                         // Hard-set view state (no animation)
 //!                     }
 //!                     ViewStateUpdate::Incremental(delta) => {
 //!                         // Process with animation
 //!                     }
+//!                     ViewStateUpdate::Tick => {
+//!                         // Advance time-based effects
+//!                     }
 //!                 }
 //!             }
 //!             // Send player actions to the server
@@ -78,29 +82,52 @@
 //! }
 //! ```
 
+use crate::codec::{ActiveCodec, WireCodec};
 use crate::timer::Timer;
-use crate::traits::BackendCommand::{CancelTimer, KickPlayer, SetTimer, TerminateRoom};
-use crate::traits::{BackEndArchitecture, BackendCommand, SerializationCap};
-use crate::web_socket_interface::{ConnectionInformation, ToServerCommands};
-use std::collections::VecDeque;
+use crate::traits::BackendCommand::{
+    AdoptPlayer, CancelTermination, CancelTimer, KickPlayer, Log, RejectAction, ReleasePlayer,
+    ReportResult, SetPaused, SetTimer, TerminateRoom, Tick, UpdateRegions,
+};
+use crate::traits::{
+    BackEndArchitecture, BackendCommand, PlayerSet, RegionUpdate, RegionedViewState,
+    SerializationCap, ViewStateArchitecture,
+};
+pub use crate::web_socket_interface::relay_url;
+use crate::web_socket_interface::{ConnectionInformation, ReceivedUpdate, ToServerCommands};
+use protocol::{
+    GameId, KEEPALIVE_INTERVAL_SECS, MAX_DISPLAY_NAME_SIZE, MIGRATION_TIMEOUT_SECS, RoomId,
+};
+pub use protocol::{HostDeparture, JoinRejection};
+use std::collections::{BTreeMap, VecDeque};
 
 /// State updates delivered to the frontend for rendering.
 ///
 /// The frontend should handle these differently:
 /// - [`Full`](Self::Full): Immediately set all visual state (no animation)
+/// - [`FullReset`](Self::FullReset): Like [`Full`](Self::Full), but the game was reset mid-session
 /// - [`Incremental`](Self::Incremental): Apply with animation/transition effects
+/// - [`Tick`](Self::Tick): Advance time-based effects, the state stays the same
 pub enum ViewStateUpdate<ViewState, DeltaInformation> {
     /// Complete game state snapshot.
     ///
     /// Received when:
     /// - Initially connecting to a game
-    /// - After a game reset
     /// - On server startup (for the host)
+    /// - After a rollback of failed predictions
+    /// - After a partial update of a [`RegionedViewState`]
     ///
     /// The frontend should immediately synchronize all visuals to match
     /// this state without animations.
     Full(ViewState),
 
+    /// Complete game state snapshot after the backend reset the game with
+    /// [`BackendCommand::ResetViewState`].
+    ///
+    /// Handled like [`Full`](Self::Full), but lets the frontend react to the
+    /// reset itself, e.g. with a "new round" sound, which a fresh join should
+    /// not trigger.
+    FullReset(ViewState),
+
     /// Incremental state change for animated transitions.
     ///
     /// These arrive in order and can be polled one at a time to pace
@@ -109,6 +136,38 @@ pub enum ViewStateUpdate<ViewState, DeltaInformation> {
     /// - A card being revealed
     /// - A score incrementing
     Incremental(DeltaInformation),
+
+    /// A tick of the host from [`BackendCommand::Tick`], the view state did not change.
+    ///
+    /// Lets the frontend drive time-based effects like a countdown or an idle animation
+    /// in step with the host, without the backend having to invent a delta for it.
+    Tick,
+}
+
+/// The encoding of the regions of a view state, see [`TransportLayer::enable_regions`].
+struct RegionCodec<ViewState> {
+    /// Serializes the regions with the indices into the payload of a partial update.
+    encode: fn(&ViewState, &[u16]) -> Result<Vec<u8>, String>,
+    /// Sets the regions of the payload of a partial update on the view state.
+    apply: fn(&mut ViewState, &[u8]) -> Result<(), String>,
+}
+
+/// Serializes the regions of a view state, see [`RegionCodec::encode`].
+fn encode_regions<ViewState: RegionedViewState>(
+    view_state: &ViewState,
+    indices: &[u16],
+) -> Result<Vec<u8>, String> {
+    ActiveCodec::encode(&RegionUpdate::collect(view_state, indices))
+}
+
+/// Sets the regions of a partial update on a view state, see [`RegionCodec::apply`].
+fn apply_regions<ViewState: RegionedViewState>(
+    view_state: &mut ViewState,
+    payload: &[u8],
+) -> Result<(), String> {
+    ActiveCodec::decode::<RegionUpdate<ViewState::Region>>(payload)
+        .map_err(|error| format!("Failed to decode PARTIAL_UPDATE payload: {}", error))?
+        .apply_to(view_state)
 }
 
 /// Server-only state container.
@@ -116,13 +175,108 @@ pub enum ViewStateUpdate<ViewState, DeltaInformation> {
 /// This struct exists only on the host client and manages the game backend,
 /// timers, and remote player tracking. It is created when `start_game_server()`
 /// succeeds and destroyed on disconnect or room termination.
-struct ServerContext<BackendArchitecture> {
+struct ServerContext<BackendArchitecture, ServerRpcPayload> {
     /// The backend that runs the game logic.
     back_end: BackendArchitecture,
     /// The timer to generate timing events for the backend.
     timer: Timer,
-    /// The amount of players, that are currently subscribed (not including the local player).
-    amount_of_remote_players: u16,
+    /// The players that are currently subscribed, including the local player.
+    players: PlayerSet,
+    /// The time in seconds since the last keep alive message to the relay.
+    time_since_keepalive: f32,
+    /// The most messages any client has not picked up yet, as last reported by the relay.
+    max_client_backlog: u16,
+    /// The privileged commands of the host, waiting for the next update.
+    host_command_que: VecDeque<ServerRpcPayload>,
+    /// Flags, if the host itself is ready to start the game.
+    host_ready: bool,
+    /// The amount of ready clients and of all clients, as last reported by the relay.
+    /// The relay reports nothing before the first client sent its readiness.
+    client_readiness: Option<(u16, u16)>,
+    /// Flags, if everyone was ready at the last update, so the backend learns about it only once.
+    all_ready: bool,
+    /// The seconds left until a deferred termination of the room, see [`TransportLayer::set_termination_grace`].
+    pending_termination: Option<f32>,
+    /// The seats of departed players the backend took over, see [`BackendCommand::AdoptPlayer`].
+    adopted_players: Vec<u16>,
+    /// The sequence number of the last processed RPC per client, waiting to be acknowledged.
+    rpc_acks: BTreeMap<u16, u32>,
+}
+
+impl<BackendArchitecture, ServerRpcPayload> ServerContext<BackendArchitecture, ServerRpcPayload> {
+    /// The amount of players that are currently subscribed, not including the local player.
+    fn remote_players(&self) -> u16 {
+        self.players.len().saturating_sub(1) as u16
+    }
+
+    /// The amount of ready clients and of all clients. Until the relay reports it, no client is ready.
+    fn client_readiness(&self) -> (u16, u16) {
+        self.client_readiness.unwrap_or((0, self.remote_players()))
+    }
+
+    /// Acknowledges the processed RPCs to their clients, once the updates answering them went out.
+    fn send_rpc_acks(&mut self, communicator: &mut ConnectionInformation) {
+        for (client, sequence) in std::mem::take(&mut self.rpc_acks) {
+            communicator.server_send_rpc_ack(client, sequence);
+        }
+    }
+}
+
+/// A predicted delta already handed to the frontend, waiting for confirmation by the host.
+struct PendingPrediction<DeltaInformation> {
+    /// The sequence number of the RPC the delta was predicted for.
+    rpc_sequence: u32,
+    /// The predicted delta.
+    delta: DeltaInformation,
+}
+
+/// The remote players a run of deltas goes to.
+#[derive(Clone, Copy, PartialEq)]
+enum RemoteAudience {
+    /// All remote players.
+    All,
+    /// All remote players except the indicated one.
+    Except(u16),
+    /// Only the indicated remote player.
+    To(u16),
+}
+
+impl RemoteAudience {
+    /// Sends a run of deltas to the audience.
+    fn send<DeltaInformation: SerializationCap>(
+        self,
+        communicator: &mut ConnectionInformation,
+        run: &[DeltaInformation],
+    ) {
+        match self {
+            RemoteAudience::All => communicator.server_send_delta_info(run),
+            RemoteAudience::Except(player) => {
+                communicator.server_send_delta_info_except(player, run)
+            }
+            RemoteAudience::To(player) => communicator.server_send_delta_info_to(player, run),
+        }
+    }
+}
+
+/// The time in seconds a client waits, before it tries to join the room of the new host again.
+const MIGRATION_RETRY_SECS: f32 = 0.5;
+
+/// The part the transport layer takes in a host migration, see [`TransportLayer::is_migrating`].
+enum Migration<Backend> {
+    /// Opens the room again as its new host, with the backend that continues from the last view state.
+    Host {
+        /// The backend that continues from the last view state.
+        back_end: Backend,
+        /// The token the relay handed us to take the room over with.
+        migration_token: u64,
+    },
+    /// Joins the room again as a client, until the new host took it over or the time runs out.
+    Client {
+        /// The time in seconds until the next attempt.
+        retry_in: f32,
+        /// The time in seconds left for the migration.
+        remaining: f32,
+    },
 }
 
 /// Connection lifecycle states.
@@ -131,7 +285,9 @@ struct ServerContext<BackendArchitecture> {
 ///
 /// ```text
 /// Disconnected -> AwaitingHandshake -> ExecutingHandshake -> Connected
-///      ^                                                         |
+///      ^              ^        |                                 |
+///      |              |        v (relay unreachable, if enabled) |
+///      |           RetryingConnect                               |
 ///      |___________________ (on error or disconnect) ____________|
 /// ```
 ///
@@ -153,6 +309,18 @@ pub enum ConnectionState {
     /// "Connecting..." indicator.
     AwaitingHandshake,
 
+    /// The relay could not be reached, the next attempt follows after a short delay.
+    ///
+    /// Only reached with retries enabled, see
+    /// [`TransportLayer::set_connect_retries`]. Frontend can keep showing the
+    /// "Connecting..." indicator, together with the progress.
+    RetryingConnect {
+        /// The retry that comes next, starting with `1`.
+        attempt: u8,
+        /// The amount of retries before giving up.
+        max_attempts: u8,
+    },
+
     /// Transport ready, waiting for server response with player ID and rules.
     ///
     /// The handshake message has been sent; waiting for confirmation.
@@ -212,22 +380,30 @@ pub enum ConnectionState {
 ///
 /// # Lifecycle
 ///
-/// 1. Create with [`generate_transport_layer()`](Self::generate_transport_layer)
+/// 1. Create with [`generate_transport_layer()`](Self::generate_transport_layer), or with
+///    [`generate_headless_host()`](Self::generate_headless_host) for a host without frontend
 /// 2. Call [`update()`](Self::update) every frame
 /// 3. Check [`connection_state()`](Self::connection_state) to determine UI mode
 /// 4. When disconnected: call [`start_game_server()`](Self::start_game_server) or
 ///    [`start_game_client()`](Self::start_game_client)
 /// 5. When connected: poll [`get_next_update()`](Self::get_next_update) and send
 ///    actions via [`register_server_rpc()`](Self::register_server_rpc)
+///
+/// # Multiple Instances
+///
+/// Instances share nothing, so on native builds one process may host or join several
+/// rooms at once (e.g. test bots or tournament servers). In WASM only one instance may be
+/// connected at a time, as the browser plugin manages a single socket; starting a second
+/// connection ends in `Disconnected` with an error.
 pub struct TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>
 where
     ServerRpcPayload: SerializationCap,
     Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>,
     DeltaInformation: SerializationCap + Clone,
-    ViewState: SerializationCap + Clone,
+    ViewState: SerializationCap + Clone + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>,
 {
     /// The things we have only on the server.
-    server_context: Option<ServerContext<Backend>>,
+    server_context: Option<ServerContext<Backend, ServerRpcPayload>>,
 
     /// The delta information and eventual full updates we enqueue for handing to the front end.
     state_info_que: VecDeque<ViewStateUpdate<ViewState, DeltaInformation>>,
@@ -239,6 +415,14 @@ where
     /// The core connection.
     core_connection: Option<ConnectionInformation>,
 
+    /// Shadow copy of the view state on remote clients, used to validate incoming deltas
+    /// before they reach the frontend.
+    shadow_view_state: Option<ViewState>,
+
+    /// Flags if a delta did not fit the shadow view state, so the client drops the deltas until the full update
+    /// it requested arrives.
+    desynchronized: bool,
+
     /// The current state we have.
     connection_state: ConnectionState,
 
@@ -246,7 +430,62 @@ where
     connection_string: String,
 
     /// The name of the game.
-    game_name: String,
+    game_name: GameId,
+
+    /// The compound room id `room#game` the relay assigned, known once connected.
+    room_id: Option<String>,
+
+    /// Flags if RPCs that are illegal in the current view state get dropped before sending.
+    drop_illegal_rpcs: bool,
+
+    /// Flags if remote clients apply predicted deltas for their own RPCs right away.
+    predict_rpcs: bool,
+
+    /// Predicted deltas already handed to the frontend, waiting for confirmation by the host, oldest first.
+    pending_predictions: VecDeque<PendingPrediction<DeltaInformation>>,
+
+    /// Flags if the backend timers stand still while the game is paused.
+    pause_timers: bool,
+
+    /// The time in milliseconds the host collects deltas before sending them, 0 sends them right away.
+    delta_flush_interval_millis: u32,
+
+    /// The runs of deltas the host collected for the remote players and did not send yet.
+    buffered_delta_runs: Vec<(RemoteAudience, Vec<DeltaInformation>)>,
+
+    /// The time in seconds since the oldest of the buffered deltas was collected.
+    buffered_delta_age: f32,
+
+    /// The time in seconds a termination of the room by the backend gets deferred, 0 terminates right away.
+    termination_grace_secs: f32,
+
+    /// The time in seconds since entering [`ConnectionState::Connected`], summed up from the delta times.
+    connection_age_secs: f32,
+
+    /// The encoding of the regions of the view state, if enabled.
+    region_codec: Option<RegionCodec<ViewState>>,
+
+    /// The room name, rule variation and role of the last connect, to connect again on a retry or a host migration.
+    connect_request: (RoomId, u16, bool),
+
+    /// The amount of retries of a failed connect to the relay, see [`set_connect_retries()`](Self::set_connect_retries).
+    connect_retries: u8,
+
+    /// The time in seconds between two connect attempts.
+    connect_retry_delay_secs: f32,
+
+    /// The retries of the current connect so far.
+    connect_retry: u8,
+
+    /// The time in seconds until the next connect attempt while retrying.
+    connect_retry_in: f32,
+
+    /// The host migration going on, if any.
+    migration: Option<Migration<Backend>>,
+
+    /// Flags a host without frontend, that drops the updates instead of queuing them,
+    /// see [`generate_headless_host()`](TransportLayer::generate_headless_host).
+    headless: bool,
 }
 
 impl<ServerRpcPayload, DeltaInformation, BackendArchitecture, ViewState>
@@ -255,7 +494,7 @@ where
     ServerRpcPayload: SerializationCap,
     BackendArchitecture: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>,
     DeltaInformation: SerializationCap + Clone,
-    ViewState: SerializationCap + Clone,
+    ViewState: SerializationCap + Clone + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>,
 {
     /// Creates a new transport layer instance in disconnected state.
     ///
@@ -283,9 +522,56 @@ where
             state_info_que: VecDeque::new(),
             rpc_que: VecDeque::new(),
             core_connection: None,
+            shadow_view_state: None,
+            desynchronized: false,
             connection_state: ConnectionState::Disconnected { error_string: None },
             connection_string,
-            game_name,
+            game_name: GameId::new(game_name),
+            room_id: None,
+            predict_rpcs: false,
+            pending_predictions: VecDeque::new(),
+            drop_illegal_rpcs: false,
+            pause_timers: false,
+            delta_flush_interval_millis: 0,
+            buffered_delta_runs: Vec::new(),
+            buffered_delta_age: 0.0,
+            termination_grace_secs: 0.0,
+            connection_age_secs: 0.0,
+            region_codec: None,
+            connect_request: (RoomId::new(String::new()), 0, false),
+            connect_retries: 0,
+            connect_retry_delay_secs: 0.0,
+            connect_retry: 0,
+            connect_retry_in: 0.0,
+            migration: None,
+            headless: false,
+        }
+    }
+
+    /// Creates a new transport layer for a host without any frontend, like a bot or a dedicated server
+    /// running the game simulation. It never queues updates for a frontend, so nothing piles up when
+    /// nobody calls [`get_next_update()`](Self::get_next_update), and it may only host.
+    ///
+    /// Drive it with [`step_without_render()`](Self::step_without_render) or [`update()`](Self::update),
+    /// both do the same on a headless host.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut host = TransportLayer::<MyRpc, MyDelta, MyBackend, MyState>::generate_headless_host(
+    ///     "wss://board-game-hub.de/ws".to_string(),
+    ///     "reversi".to_string(),
+    /// );
+    /// host.start_game_server("bot-room".to_string(), 0);
+    /// loop {
+    ///     host.step_without_render(0.05);
+    ///     std::thread::sleep(std::time::Duration::from_millis(50));
+    /// }
+    /// ```
+    pub fn generate_headless_host(connection_string: String, game_name: String) -> Self {
+        Self {
+            headless: true,
+            ..Self::generate_transport_layer(connection_string, game_name)
         }
     }
 
@@ -296,6 +582,7 @@ where
     ///
     /// - **Disconnected**: No-op, waiting for `start_game_server/client()`
     /// - **AwaitingHandshake**: Polls WebSocket connection readiness
+    /// - **RetryingConnect**: Waits for the next attempt to reach the relay
     /// - **ExecutingHandshake**: Waits for server response with player ID
     /// - **Connected (host)**: Processes timers, RPCs, network messages,
     ///   drains backend commands, broadcasts updates
@@ -308,11 +595,21 @@ where
     ///
     /// * `delta_time` — Seconds since last frame (used for timer updates on host)
     pub fn update(&mut self, delta_time: f32) {
+        if matches!(self.connection_state, ConnectionState::Connected { .. }) {
+            self.connection_age_secs += delta_time;
+        }
+        self.update_migration(delta_time);
         match self.connection_state {
             ConnectionState::Disconnected { error_string: _ } => {} // Nothing to do here.
             ConnectionState::AwaitingHandshake => {
                 self.connection_update_awaiting();
             }
+            ConnectionState::RetryingConnect { .. } => {
+                self.connect_retry_in -= delta_time;
+                if self.connect_retry_in <= 0.0 {
+                    self.open_connection();
+                }
+            }
             ConnectionState::ExecutingHandshake => {
                 self.connection_update_handshake();
             }
@@ -331,6 +628,21 @@ where
                 self.update_client();
             }
         }
+        if self.headless {
+            self.state_info_que.clear();
+        }
+    }
+
+    /// Advances the transport layer like [`update()`](Self::update), but drops the updates for the frontend
+    /// right away. On the host this runs the full server side: timers, RPCs, the backend and the broadcast to
+    /// the clients. Meant for simulations and tests that step a host without rendering it.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` — Seconds of simulated time since the last step
+    pub fn step_without_render(&mut self, delta_time: f32) {
+        self.update(delta_time);
+        self.state_info_que.clear();
     }
 
     /// Initiates hosting a new game room.
@@ -356,7 +668,7 @@ where
     /// }
     /// ```
     pub fn start_game_server(&mut self, room_name: String, rule_variation: u16) {
-        self.connection_initialize(room_name, rule_variation, true);
+        self.connection_initialize(RoomId::new(room_name), rule_variation, true);
     }
 
     /// Initiates joining an existing game room.
@@ -370,7 +682,8 @@ where
     ///
     /// # Panics
     ///
-    /// Panics if called while not in `Disconnected` state.
+    /// Panics if called while not in `Disconnected` state or on a
+    /// [headless host](Self::generate_headless_host).
     ///
     /// # Example
     ///
@@ -380,7 +693,8 @@ where
     /// }
     /// ```
     pub fn start_game_client(&mut self, room_name: String) {
-        self.connection_initialize(room_name, 0, false);
+        assert!(!self.headless, "A headless host can not join as client");
+        self.connection_initialize(RoomId::new(room_name), 0, false);
     }
 
     /// Gracefully disconnects from the current game.
@@ -399,6 +713,21 @@ where
     /// }
     /// ```
     pub fn disconnect(&mut self) {
+        self.disconnect_with(HostDeparture::Ended);
+    }
+
+    /// Disconnects like [`disconnect()`](Self::disconnect). On the host, the clients additionally learn why the
+    /// game is over, e.g. [`HostDeparture::Aborted`] if it can not go on. They read it with
+    /// [`host_departure()`](Self::host_departure). Clients simply disconnect.
+    pub fn disconnect_with(&mut self, departure: HostDeparture) {
+        self.migration = None;
+        if matches!(
+            self.connection_state,
+            ConnectionState::RetryingConnect { .. }
+        ) {
+            self.mark_error("Disconnected from server".to_string());
+            return;
+        }
         if let Some(connection) = self.core_connection.as_mut()
             && let ConnectionState::Connected {
                 is_server,
@@ -406,7 +735,15 @@ where
                 rule_set: _,
             } = self.connection_state
         {
-            connection.disconnect(is_server);
+            if is_server {
+                // The remote players get the buffered deltas before they learn about the departure.
+                for (audience, run) in self.buffered_delta_runs.drain(..) {
+                    audience.send(connection, &run);
+                }
+                connection.server_disconnect(departure);
+            } else {
+                connection.disconnect(false);
+            }
             self.mark_error("Disconnected from server".to_string());
             self.server_context = None;
         }
@@ -415,9 +752,12 @@ where
     /// Queues a game action to be sent to the backend.
     ///
     /// The RPC is processed during the next `update()` call:
-    /// - **Host**: Delivered directly to the local backend
+    /// - **Host**: Delivered directly to the local backend as an action of player `0`
     /// - **Client**: Serialized and sent over the network to the host
     ///
+    /// This is always a regular player action. For privileged actions of the host,
+    /// use [`register_host_command()`](Self::register_host_command).
+    ///
     /// RPCs are processed in order. Pre-validate actions on the frontend
     /// to minimize invalid requests and network traffic.
     ///
@@ -433,9 +773,134 @@ where
     /// }
     /// ```
     pub fn register_server_rpc(&mut self, payload: ServerRpcPayload) {
+        if self.drop_illegal_rpcs && !self.is_rpc_legal(&payload) {
+            return;
+        }
+        if let Some(delta) = self.predict(&payload)
+            && let Some(communicator) = &self.core_connection
+        {
+            // The queued RPCs go out in order, so this one gets the sequence number after them.
+            let rpc_sequence = communicator
+                .next_rpc_sequence()
+                .wrapping_add(self.rpc_que.len() as u32);
+            self.pending_predictions.push_back(PendingPrediction {
+                rpc_sequence,
+                delta: delta.clone(),
+            });
+            self.state_info_que
+                .push_back(ViewStateUpdate::Incremental(delta));
+        }
         self.rpc_que.push_back(payload);
     }
 
+    /// Sends all queued RPCs right away instead of waiting for the next [`update()`](Self::update),
+    /// e.g. for a critical action right before [`disconnect()`](Self::disconnect).
+    ///
+    /// On a client the RPCs get serialized and handed to the socket. On the host they reach the
+    /// backend immediately, the commands it emits in response still get processed in the next update.
+    /// Does nothing while not connected.
+    pub fn flush_rpcs(&mut self) {
+        if !matches!(self.connection_state, ConnectionState::Connected { .. }) {
+            return;
+        }
+        if let Some(server_context) = self.server_context.as_mut() {
+            while let Some(rpc) = self.rpc_que.pop_front() {
+                server_context
+                    .back_end
+                    .inform_rpc(0, rpc, &server_context.players)
+            }
+        } else if let Some(communicator) = self.core_connection.as_mut() {
+            while let Some(rpc) = self.rpc_que.pop_front() {
+                communicator.client_send_rpc_from(rpc);
+            }
+        }
+    }
+
+    /// Queues a privileged command of the host, like forcing the next round.
+    ///
+    /// The backend receives it in the next `update()` via
+    /// [`BackEndArchitecture::inform_host_command`], after the RPCs registered in the same frame,
+    /// and never as a player action. Commands never travel over the network, so remote clients
+    /// can not issue them. Returns `false` and drops the command if we are not hosting.
+    pub fn register_host_command(&mut self, command: ServerRpcPayload) -> bool {
+        match self.server_context.as_mut() {
+            Some(server_context) => {
+                server_context.host_command_que.push_back(command);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Enables or disables client-side prediction for the RPCs of remote clients.
+    ///
+    /// When enabled, [`register_server_rpc()`](Self::register_server_rpc) asks
+    /// [`ViewStateArchitecture::predict`] for the delta the host will answer with and hands
+    /// it to the frontend immediately. Incoming deltas are then matched with the oldest pending prediction
+    /// by [`ViewStateArchitecture::confirms_prediction`]: a confirmed delta is not handed out a second time.
+    /// Any other delta, like the move of another player or a timer, reaches the frontend on top of the
+    /// predictions, as long as the predictions still fit after it. Once the host acknowledged the RPC of a
+    /// prediction that got no confirming delta, or a delta leaves the predictions unfit, all pending predictions
+    /// get rolled back with a [`ViewStateUpdate::Full`] of the authoritative state. The host therefore has to
+    /// send the confirming delta to the predicting player as well (no [`DeltaExcept`](BackendCommand::DeltaExcept)).
+    /// Has no effect on the host, whose RPCs reach the backend directly. Disabled by default.
+    pub fn set_prediction(&mut self, enabled: bool) {
+        self.predict_rpcs = enabled;
+        if !enabled {
+            self.pending_predictions.clear();
+        }
+    }
+
+    /// Enables or disables dropping of illegal RPCs in [`register_server_rpc()`](Self::register_server_rpc).
+    ///
+    /// When enabled, every RPC is checked with [`ViewStateArchitecture::is_action_legal`]
+    /// against the latest known view state (the backend's on the host, the last
+    /// synchronized one on remote clients) and silently dropped if illegal. This saves
+    /// network traffic for input the backend would reject anyway. Disabled by default.
+    pub fn set_drop_illegal_rpcs(&mut self, enabled: bool) {
+        self.drop_illegal_rpcs = enabled;
+    }
+
+    /// Sets the time in milliseconds the host collects deltas, before it sends them to the remote players.
+    ///
+    /// Every update of the host that drains deltas from the backend sends them out as one frame by default.
+    /// With an interval, the deltas get buffered until the oldest one waited for that long and then go out
+    /// together, which saves the overhead of many tiny frames at the cost of some latency. The time gets
+    /// measured with the delta times passed to [`update()`](Self::update). The local player gets its deltas
+    /// right away in either case. A joining client, a reset, a partial update of regions, a tick and a graceful
+    /// disconnect flush the buffer early,
+    /// other messages like a pause or a kick are not held back and may overtake the buffered deltas.
+    /// Defaults to 0, which sends the deltas in the update they were drained in.
+    pub fn set_delta_flush_interval(&mut self, millis: u32) {
+        self.delta_flush_interval_millis = millis;
+    }
+
+    /// Sets how often a failed connect to the relay gets retried, and the delay in seconds between the attempts.
+    ///
+    /// This helps if the relay is momentarily unreachable, e.g. while it restarts. Only the opening of the web socket
+    /// gets retried, a rejection by the relay or a connection lost later on ends the connection right away.
+    /// Meanwhile the connection state is [`ConnectionState::RetryingConnect`]. The delay gets measured with the
+    /// delta times passed to [`update()`](Self::update). Defaults to no retries.
+    pub fn set_connect_retries(&mut self, attempts: u8, delay_secs: f32) {
+        self.connect_retries = attempts;
+        self.connect_retry_delay_secs = delay_secs;
+    }
+
+    /// Enables partial updates of the view state region by region, see [`RegionedViewState`].
+    ///
+    /// Host and clients both have to enable it. The host ignores [`BackendCommand::UpdateRegions`] without it and
+    /// logs the misuse to the relay, a client drops the connection with a protocol error on a partial update it can
+    /// not apply.
+    pub fn enable_regions(&mut self)
+    where
+        ViewState: RegionedViewState,
+    {
+        self.region_codec = Some(RegionCodec {
+            encode: encode_regions::<ViewState>,
+            apply: apply_regions::<ViewState>,
+        });
+    }
+
     /// Retrieves the next pending state update for the frontend.
     ///
     /// Returns `None` if no updates are queued. Updates are delivered in order
@@ -444,7 +909,9 @@ where
     /// # Update Types
     ///
     /// - [`ViewStateUpdate::Full`]: Hard-set all visuals immediately (no animation)
+    /// - [`ViewStateUpdate::FullReset`]: Hard-set all visuals, the game was reset
     /// - [`ViewStateUpdate::Incremental`]: Apply with animation/transition
+    /// - [`ViewStateUpdate::Tick`]: Advance time-based effects
     ///
     /// # Example
     ///
@@ -452,12 +919,13 @@ where
     /// // Process one update per frame for smooth animations
     /// if let Some(update) = transport_layer.get_next_update() {
     ///     match update {
-    ///         ViewStateUpdate::Full(state) => {
+    ///         ViewStateUpdate::Full(state) | ViewStateUpdate::FullReset(state) => {
     ///             game_renderer.set_state(&state);
     ///         }
     ///         ViewStateUpdate::Incremental(delta) => {
     ///             game_renderer.animate_delta(&delta);
     ///         }
+    ///         ViewStateUpdate::Tick => game_renderer.advance_clock(),
     ///     }
     /// }
     /// ```
@@ -465,6 +933,45 @@ where
         self.state_info_que.pop_front()
     }
 
+    /// The amount of updates queued for the frontend, that [`get_next_update()`](Self::get_next_update)
+    /// has not handed out yet. A growing number means the frontend consumes updates slower than they arrive.
+    pub fn pending_update_count(&self) -> usize {
+        self.state_info_que.len()
+    }
+
+    /// The amount of RPCs registered but not yet sent to the backend or the network.
+    /// Meant for debugging overlays: RPCs piling up here mean [`update()`](Self::update)
+    /// does not get called or the connection is not established.
+    pub fn pending_rpc_count(&self) -> usize {
+        self.rpc_que.len()
+    }
+
+    /// The RPCs registered but not yet sent to the backend or the network, oldest first.
+    /// Together with [`clear_pending_rpcs()`](Self::clear_pending_rpcs), the frontend can let the player
+    /// take back an input in the same frame, before [`update()`](Self::update) sends it.
+    pub fn pending_rpcs(&self) -> &VecDeque<ServerRpcPayload> {
+        &self.rpc_que
+    }
+
+    /// Drops all RPCs registered but not yet sent, e.g. when the player takes back a move before committing it.
+    ///
+    /// RPCs sent already can not be taken back. If client-side prediction showed deltas for the dropped RPCs,
+    /// all pending predictions get rolled back with a [`ViewStateUpdate::Full`] of the last synchronized state.
+    /// The deltas of RPCs sent before then reach the frontend regularly, once the host answers them.
+    pub fn clear_pending_rpcs(&mut self) {
+        if self.rpc_que.is_empty() {
+            return;
+        }
+        self.rpc_que.clear();
+        if !self.pending_predictions.is_empty()
+            && let Some(shadow) = &self.shadow_view_state
+        {
+            self.pending_predictions.clear();
+            self.state_info_que
+                .push_back(ViewStateUpdate::Full(shadow.clone()));
+        }
+    }
+
     /// Returns the current connection state.
     ///
     /// Check this after each `update()` call to:
@@ -495,16 +1002,405 @@ where
         &self.connection_state
     }
 
+    /// Returns the player id the relay assigned with the handshake, the host is always player `0`.
+    ///
+    /// This saves matching on [`connection_state()`](Self::connection_state) in code that only runs once connected.
+    /// `None` while not connected.
+    pub fn my_player_id(&self) -> Option<u16> {
+        match self.connection_state {
+            ConnectionState::Connected { player_id, .. } => Some(player_id),
+            _ => None,
+        }
+    }
+
+    /// Flags, if the view state is known, so the frontend can show it instead of a "still loading" indicator.
+    ///
+    /// A remote client enters [`ConnectionState::Connected`] before the first [`ViewStateUpdate::Full`] from the host
+    /// arrived. This turns `true` as soon as the transport layer has seen that update, which the next
+    /// [`get_next_update()`](Self::get_next_update) hands out. The host knows the view state of its backend right away.
+    /// Always `false` while not connected.
+    pub fn has_initial_sync(&self) -> bool {
+        matches!(self.connection_state, ConnectionState::Connected { .. })
+            && (self.server_context.is_some() || self.shadow_view_state.is_some())
+    }
+
+    /// Flags, if the client missed an update and waits for the full update it requested to heal.
+    ///
+    /// A delta that does not fit the view state, e.g. a move onto an occupied field after a lost update, never reaches
+    /// the frontend. The transport layer requests a full update instead and drops the following deltas until it
+    /// arrives as a [`ViewStateUpdate::Full`]. The frontend may show a "Resyncing" hint meanwhile.
+    /// Always `false` on the host and while not connected.
+    pub fn is_desynchronized(&self) -> bool {
+        self.desynchronized
+    }
+
+    /// Returns the time in seconds the session lasts, e.g. to show the duration of a match.
+    ///
+    /// The time starts when entering [`ConnectionState::Connected`] and sums up the delta times passed to
+    /// [`update()`](Self::update) from then on. It is `0` while not connected and starts anew with every connection.
+    pub fn connection_age_secs(&self) -> f32 {
+        self.connection_age_secs
+    }
+
+    /// Returns the compound room id (`room#game`) the relay stored for this session.
+    /// A `#` or `%` inside room or game name appears percent-escaped as `%23` or `%25`.
+    ///
+    /// This is exactly what the relay assigned during the handshake and is meant for
+    /// displaying a shareable room code. Returns `None` if not connected.
+    pub fn room_id(&self) -> Option<&str> {
+        self.room_id.as_deref()
+    }
+
+    /// Flags, if the host paused the game with [`BackendCommand::SetPaused`]. Valid on the host as well as on
+    /// the clients, `false` while not connected. The frontend may overlay a "Paused" banner and ignore input.
+    pub fn is_paused(&self) -> bool {
+        self.core_connection
+            .as_ref()
+            .is_some_and(|connection| connection.is_paused())
+    }
+
+    /// Takes the reason, why the host ignored the last action of the local player, see
+    /// [`BackendCommand::RejectAction`]. Valid on the host as well as on the clients, `None` while not connected.
+    /// Only the latest rejection is kept, so the frontend may poll it once per frame and show it as a hint.
+    pub fn take_rejection(&mut self) -> Option<String> {
+        self.core_connection
+            .as_mut()
+            .and_then(|connection| connection.take_rejection())
+    }
+
+    /// The amount of spectators watching the room, as announced by the relay. Valid on the host as well as on
+    /// the clients, 0 while not connected. Only the number is known, not who the spectators are.
+    pub fn spectator_count(&self) -> u16 {
+        self.core_connection
+            .as_ref()
+            .map_or(0, |connection| connection.spectator_count())
+    }
+
+    /// Sets the display name of the local player, so games do not have to carry names in their own RPCs.
+    ///
+    /// On a client the name goes to the host, which stores it and announces it to everyone. On the host the name
+    /// gets stored and announced right away. All frontends read the names with [`display_name()`](Self::display_name),
+    /// joining clients learn all names from the host. An empty name removes the name again.
+    /// Returns `false` and sends nothing if not connected or if the name is longer than [`MAX_DISPLAY_NAME_SIZE`] bytes.
+    pub fn set_display_name(&mut self, name: &str) -> bool {
+        if name.len() > MAX_DISPLAY_NAME_SIZE
+            || !matches!(self.connection_state, ConnectionState::Connected { .. })
+        {
+            return false;
+        }
+        let Some(communicator) = self.core_connection.as_mut() else {
+            return false;
+        };
+        match self.server_context.as_ref() {
+            Some(server_context) => {
+                communicator.set_player_name(0, name);
+                if server_context.remote_players() > 0 {
+                    communicator.server_send_player_name(0);
+                }
+            }
+            None => communicator.client_send_name(name),
+        }
+        true
+    }
+
+    /// Asks the host for a fresh full state, e.g. when the user clicks "refresh" because the view looks stale.
+    ///
+    /// The state arrives as a [`ViewStateUpdate::Full`] with one of the next updates, deltas the host sent before
+    /// get discarded if they arrive afterward. Returns `false` and does nothing if not connected or if hosting,
+    /// as the host always has the authoritative state.
+    pub fn request_full_sync(&mut self) -> bool {
+        if !matches!(self.connection_state, ConnectionState::Connected { .. })
+            || self.server_context.is_some()
+        {
+            return false;
+        }
+        match self.core_connection.as_mut() {
+            Some(communicator) => {
+                communicator.client_request_sync();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Flags whether the local player is ready to start the game, for a common "Ready" button before the game starts.
+    ///
+    /// A client tells the relay, which aggregates the readiness of all clients for the host. Once the host and all
+    /// clients are ready, the backend gets [`BackEndArchitecture::all_players_ready`]. A client that joins starts as
+    /// not ready. Returns `false` and does nothing if not connected.
+    pub fn set_ready(&mut self, ready: bool) -> bool {
+        if !matches!(self.connection_state, ConnectionState::Connected { .. }) {
+            return false;
+        }
+        match (self.server_context.as_mut(), self.core_connection.as_mut()) {
+            (Some(server_context), _) => server_context.host_ready = ready,
+            (None, Some(communicator)) => communicator.client_send_ready(ready),
+            (None, None) => return false,
+        }
+        true
+    }
+
+    /// Returns the amount of ready players and of all players in the hosted room, both including the host,
+    /// see [`set_ready()`](Self::set_ready). Only the host learns about the readiness, so this is `None` if we are not hosting.
+    pub fn ready_count(&self) -> Option<(u16, u16)> {
+        self.server_context.as_ref().map(|server_context| {
+            let (ready_clients, total_clients) = server_context.client_readiness();
+            (
+                ready_clients + server_context.host_ready as u16,
+                total_clients + 1,
+            )
+        })
+    }
+
+    /// The display name of a player as set with [`set_display_name()`](Self::set_display_name), if the player has one.
+    /// A client sees its own name only once the host announced it.
+    pub fn display_name(&self, player_id: u16) -> Option<&str> {
+        self.core_connection
+            .as_ref()
+            .and_then(|connection| connection.player_name(player_id))
+    }
+
+    /// Defers a [`BackendCommand::TerminateRoom`] of the backend by the indicated seconds, so a brief disconnect
+    /// does not end the game for everyone. Only has an effect on the host.
+    ///
+    /// During the grace period the game goes on and [`is_termination_pending()`](Self::is_termination_pending)
+    /// reports it, e.g. for a "Waiting for player" overlay. The relay hands out a new player id on every join, spectators
+    /// included, so the backend learns about the returning player with [`BackEndArchitecture::player_arrival`] and
+    /// cancels the termination with [`BackendCommand::CancelTermination`], if the newcomer takes over the seat.
+    /// The grace period runs with the delta times of [`update()`](Self::update) and stands still during a pause
+    /// like the backend timers, see [`set_pause_timers()`](Self::set_pause_timers).
+    /// Defaults to 0, which terminates the room right away.
+    pub fn set_termination_grace(&mut self, seconds: f32) {
+        self.termination_grace_secs = seconds.max(0.0);
+    }
+
+    /// Flags, if the backend asked to terminate the room and the grace period is still running.
+    pub fn is_termination_pending(&self) -> bool {
+        self.server_context
+            .as_ref()
+            .is_some_and(|server_context| server_context.pending_termination.is_some())
+    }
+
+    /// Lets the backend timers stand still while the game is paused. Only has an effect on the host.
+    /// Disabled by default, so timers keep running during a pause.
+    pub fn set_pause_timers(&mut self, enabled: bool) {
+        self.pause_timers = enabled;
+    }
+
+    /// Returns the name of the game as given on construction, e.g. for a launcher or a debugging overlay.
+    pub fn game_name(&self) -> &str {
+        self.game_name.as_str()
+    }
+
+    /// Returns the web socket URL of the relay server as given on construction.
+    pub fn connection_string(&self) -> &str {
+        &self.connection_string
+    }
+
+    /// Tells if a host migration is going on. In games with host migration, the room survives its host leaving:
+    /// the client that joined first opens it again as the new host, with the backend created by
+    /// [`BackEndArchitecture::from_view_state`] from its last view state. The other clients join it again and get
+    /// new player ids. Meanwhile the connection state passes through `Disconnected`, which the frontend should not
+    /// take as the end of the game while this holds. [`disconnect()`](Self::disconnect) cancels the migration.
+    pub fn is_migrating(&self) -> bool {
+        self.migration.is_some()
+    }
+
+    /// Tells if the last join attempt got rejected because the room was full, and whether player or spectator
+    /// slots were missing, or because the host kicked this client recently.
+    /// Returns `None` while not disconnected or if the disconnection had another reason.
+    pub fn join_rejection(&self) -> Option<JoinRejection> {
+        match &self.connection_state {
+            ConnectionState::Disconnected {
+                error_string: Some(error),
+            } => JoinRejection::from_message(error),
+            _ => None,
+        }
+    }
+
+    /// Tells on a client, why the room ended because the host is gone: the host ended or aborted the game,
+    /// the connection to it broke down or the relay closed the room.
+    /// Returns `None` while not disconnected or if the disconnection had another reason.
+    pub fn host_departure(&self) -> Option<HostDeparture> {
+        match &self.connection_state {
+            ConnectionState::Disconnected {
+                error_string: Some(error),
+            } => HostDeparture::from_message(error),
+            _ => None,
+        }
+    }
+
+    /// Asks the local backend to restart the game, e.g. for a rematch button in the host UI.
+    ///
+    /// Calls [`BackEndArchitecture::on_host_reset_request`] directly, the resulting
+    /// commands get processed in the next [`update()`](Self::update).
+    /// Returns `false` and does nothing if we are not hosting.
+    pub fn request_reset(&mut self) -> bool {
+        match self.server_context.as_mut() {
+            Some(server_context) => {
+                server_context.back_end.on_host_reset_request();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the most messages any client of the hosted room has not picked up yet.
+    ///
+    /// The relay samples this about once a second. A growing value means clients fall behind,
+    /// so a game may slow down its delta emission. A client that lags too far gets disconnected by the relay.
+    /// Returns `0` if we are not hosting.
+    pub fn max_client_backlog(&self) -> u16 {
+        self.server_context
+            .as_ref()
+            .map_or(0, |server_context| server_context.max_client_backlog)
+    }
+
+    /// Checks if enough players are in the hosted room to start the game,
+    /// as required by [`BackEndArchitecture::min_players`]. The host and the adopted seats count as players.
+    ///
+    /// Only the host tracks who is present, so this returns `false` if we are not hosting.
+    pub fn enough_players(&self) -> bool {
+        self.server_context.as_ref().is_some_and(|server_context| {
+            server_context.remote_players() + 1 + server_context.adopted_players.len() as u16
+                >= server_context.back_end.min_players()
+        })
+    }
+
+    /// The ids of the departed players, whose seats the backend took over with [`BackendCommand::AdoptPlayer`],
+    /// in the order of the adoption. The frontend may label them, e.g. as "AI".
+    ///
+    /// Only the host tracks who is present, so this is empty if we are not hosting.
+    pub fn adopted_players(&self) -> &[u16] {
+        self.server_context
+            .as_ref()
+            .map_or(&[], |server_context| &server_context.adopted_players)
+    }
+
+    /// Dry-runs the legality check for the local player against the latest known view state.
+    /// Without a connection or view state there is nothing to check against.
+    fn is_rpc_legal(&self, payload: &ServerRpcPayload) -> bool {
+        let ConnectionState::Connected { player_id, .. } = self.connection_state else {
+            return true;
+        };
+        let view_state = match &self.server_context {
+            Some(server_context) => Some(server_context.back_end.get_view_state()),
+            None => self.shadow_view_state.as_ref(),
+        };
+        view_state.is_none_or(|state| state.is_action_legal(player_id, payload))
+    }
+
+    /// Predicts the delta for an RPC of a remote client on top of the pending predictions.
+    fn predict(&self, payload: &ServerRpcPayload) -> Option<DeltaInformation> {
+        if !self.predict_rpcs || self.server_context.is_some() {
+            return None;
+        }
+        let ConnectionState::Connected { player_id, .. } = self.connection_state else {
+            return None;
+        };
+        let mut state = self.shadow_view_state.clone()?;
+        for predicted in &self.pending_predictions {
+            state.try_apply(&predicted.delta).ok()?;
+        }
+        state.predict(player_id, payload)
+    }
+
+    /// Checks if the pending predictions still fit on top of the indicated state.
+    fn predictions_fit(&self, state: &ViewState) -> bool {
+        let mut state = state.clone();
+        self.pending_predictions
+            .iter()
+            .all(|predicted| state.try_apply(&predicted.delta).is_ok())
+    }
+
     /// Global function to mark error and drop the connection.
     fn mark_error(&mut self, error: String) {
         self.connection_state = ConnectionState::Disconnected {
             error_string: Some(error),
         };
         self.core_connection = None; // Drops sender + receiver, closes connection
+        self.shadow_view_state = None;
+        self.desynchronized = false;
+        self.connection_age_secs = 0.0;
+        self.room_id = None;
+        self.pending_predictions.clear();
+        self.buffered_delta_runs.clear();
+    }
+
+    /// Starts the host migration after the relay named the successor of the host that left.
+    /// The successor opens the room again, if its backend can continue from the last view state, the others join it again.
+    fn start_migration(&mut self, successor: u16, migration_token: Option<u64>, error: String) {
+        let ConnectionState::Connected {
+            player_id,
+            rule_set,
+            ..
+        } = self.connection_state
+        else {
+            self.mark_error(error);
+            return;
+        };
+        let view_state = self.shadow_view_state.take();
+        self.mark_error(error);
+        if player_id == successor {
+            // Without a backend to continue with, or the token to take the room over, the room simply ends for us.
+            let Some(back_end) = view_state.and_then(|view_state| {
+                BackendArchitecture::from_view_state(rule_set, &view_state, player_id)
+            }) else {
+                return;
+            };
+            let Some(migration_token) = migration_token else {
+                return;
+            };
+            self.migration = Some(Migration::Host {
+                back_end,
+                migration_token,
+            });
+            self.connection_initialize(self.connect_request.0.clone(), rule_set, true);
+        } else {
+            self.migration = Some(Migration::Client {
+                retry_in: MIGRATION_RETRY_SECS,
+                remaining: MIGRATION_TIMEOUT_SECS as f32,
+            });
+            self.connection_initialize(self.connect_request.0.clone(), 0, false);
+        }
+    }
+
+    /// Joins the room again as a client after a failed attempt during a host migration, until the time runs out.
+    fn update_migration(&mut self, delta_time: f32) {
+        if !matches!(self.connection_state, ConnectionState::Disconnected { .. }) {
+            return;
+        }
+        let retry = match self.migration.as_mut() {
+            Some(Migration::Client {
+                retry_in,
+                remaining,
+            }) => {
+                *remaining -= delta_time;
+                *retry_in -= delta_time;
+                if *remaining <= 0.0 {
+                    self.migration = None;
+                    false
+                } else if *retry_in <= 0.0 {
+                    *retry_in = MIGRATION_RETRY_SECS;
+                    true
+                } else {
+                    false
+                }
+            }
+            // The new host does not try again, the relay drops the room after a while.
+            Some(Migration::Host { .. }) => {
+                self.migration = None;
+                false
+            }
+            None => false,
+        };
+        if retry {
+            self.connection_initialize(self.connect_request.0.clone(), 0, false);
+        }
     }
 
     /// Helper function for connection initialization.
-    fn connection_initialize(&mut self, room_name: String, rule_variation: u16, is_server: bool) {
+    fn connection_initialize(&mut self, room_name: RoomId, rule_variation: u16, is_server: bool) {
         debug_assert!(
             self.server_context.is_none(),
             "We should have no server context at that point"
@@ -517,12 +1413,27 @@ where
             ),
             "Only in disconnected stata is a connect allowed."
         );
+        self.connect_request = (room_name, rule_variation, is_server);
+        self.connect_retry = 0;
+        self.open_connection();
+    }
+
+    /// Opens the web socket to the relay for the last connect request.
+    fn open_connection(&mut self) {
+        let (room_name, rule_variation, is_server) = self.connect_request.clone();
+        let migration_token = match &self.migration {
+            Some(Migration::Host {
+                migration_token, ..
+            }) => Some(*migration_token),
+            _ => None,
+        };
         let start = ConnectionInformation::start_connecting(
             self.connection_string.clone(),
             self.game_name.clone(),
             room_name,
             rule_variation,
             is_server,
+            migration_token,
         );
 
         match start {
@@ -531,11 +1442,26 @@ where
                 self.core_connection = Some(connection);
             }
             Err(e) => {
-                self.mark_error(e);
+                self.connect_failed(e);
             }
         }
     }
 
+    /// Tries to reach the relay again after the delay, while retries are left. Gives up with the error otherwise.
+    fn connect_failed(&mut self, error: String) {
+        if self.connect_retry < self.connect_retries {
+            self.connect_retry += 1;
+            self.core_connection = None;
+            self.connect_retry_in = self.connect_retry_delay_secs;
+            self.connection_state = ConnectionState::RetryingConnect {
+                attempt: self.connect_retry,
+                max_attempts: self.connect_retries,
+            };
+        } else {
+            self.mark_error(error);
+        }
+    }
+
     /// We are waiting for the base connection to be established.
     fn connection_update_awaiting(&mut self) {
         debug_assert!(matches!(
@@ -552,7 +1478,7 @@ where
                 self.connection_state = ConnectionState::ExecutingHandshake;
             }
             Err(e) => {
-                self.mark_error(e);
+                self.connect_failed(e);
             }
             _ => {} // Nothing to do here.
         }
@@ -578,13 +1504,33 @@ where
                     player_id: result.player_id,
                     rule_set: result.rule_variation,
                 };
+                self.room_id = Some(result.room_id);
+                self.connection_age_secs = 0.0;
+                // A new host of a migrated room continues with the backend it prepared.
+                let migrated_back_end = match self.migration.take() {
+                    Some(Migration::Host { back_end, .. }) => Some(back_end),
+                    _ => None,
+                };
                 if is_server {
-                    let mut server_context: ServerContext<BackendArchitecture> = ServerContext {
-                        back_end: BackEndArchitecture::new(result.rule_variation),
-                        timer: Timer::new(),
-                        amount_of_remote_players: 0,
-                    };
+                    let back_end = migrated_back_end
+                        .unwrap_or_else(|| BackEndArchitecture::new(result.rule_variation));
+                    let mut server_context: ServerContext<BackendArchitecture, ServerRpcPayload> =
+                        ServerContext {
+                            back_end,
+                            timer: Timer::new(),
+                            players: PlayerSet::default(),
+                            time_since_keepalive: 0.0,
+                            max_client_backlog: 0,
+                            host_command_que: VecDeque::new(),
+                            host_ready: false,
+                            client_readiness: None,
+                            all_ready: false,
+                            pending_termination: None,
+                            adopted_players: Vec::new(),
+                            rpc_acks: BTreeMap::new(),
+                        };
                     // We also flag ourselves that we arrived.
+                    server_context.players.insert(0);
                     server_context.back_end.player_arrival(0);
                     debug_assert_eq!(
                         result.player_id, 0,
@@ -611,35 +1557,99 @@ where
             .expect("No server context at that point");
         let communicator = self.core_connection.as_mut().unwrap();
 
-        // 1. Eventual timer run outs are send to the backend.
-        let running_out = server_context.timer.update_and_get_list(delta_time);
+        // 0. Keep the room alive on the relay, even if the game is idle.
+        server_context.time_since_keepalive += delta_time;
+        if server_context.time_since_keepalive >= KEEPALIVE_INTERVAL_SECS as f32 {
+            server_context.time_since_keepalive = 0.0;
+            communicator.server_send_keepalive();
+        }
+
+        // 1. Eventual timer run outs are send to the backend, the timers may stand still during a pause.
+        let timer_delta = if self.pause_timers && communicator.is_paused() {
+            0.0
+        } else {
+            delta_time
+        };
+        let running_out = server_context.timer.update_and_get_list(timer_delta);
         for timer_id in running_out {
-            server_context.back_end.timer_triggered(timer_id);
+            server_context
+                .back_end
+                .timer_triggered(timer_id, &server_context.players);
+        }
+        if let Some(remaining) = server_context.pending_termination.as_mut() {
+            *remaining -= timer_delta;
+            if *remaining <= 0.0 {
+                communicator.server_disconnect(HostDeparture::Aborted);
+                self.mark_error("Critical player left.".to_string());
+                self.server_context = None;
+                return;
+            }
         }
 
         // 2. Process rpc_que and send the data to the backend, on the server the local player is always player 0.
         while let Some(rpc) = self.rpc_que.pop_front() {
-            server_context.back_end.inform_rpc(0, rpc)
+            server_context
+                .back_end
+                .inform_rpc(0, rpc, &server_context.players)
+        }
+        while let Some(command) = server_context.host_command_que.pop_front() {
+            server_context.back_end.inform_host_command(command)
         }
 
         // 3. Collect data from ws_socket (RPC calls) and send the data to the backend.
-        let mut client_joined = false;
+        // The clients that joined with this update and are still present need the full sync,
+        // the ones that asked for it get it on their own.
+        let mut joined_clients: Vec<u16> = Vec::new();
+        let mut sync_requests: Vec<u16> = Vec::new();
         let vec = communicator.server_receive_commands_for();
         match vec {
             Ok(core) => {
                 for command in core {
                     match command {
                         ToServerCommands::ClientJoin(client) => {
-                            client_joined = true;
+                            joined_clients.push(client);
                             server_context.back_end.player_arrival(client);
-                            server_context.amount_of_remote_players += 1;
+                            server_context.players.insert(client);
                         }
                         ToServerCommands::ClientLeft(client) => {
-                            server_context.back_end.player_departure(client);
-                            server_context.amount_of_remote_players -= 1;
+                            // A client that joined and left within the same update has no subscription left to sync.
+                            joined_clients.retain(|&joined| joined != client);
+                            sync_requests.retain(|&requester| requester != client);
+                            server_context.players.remove(client);
+                            server_context.rpc_acks.remove(&client);
+                            if communicator.player_name(client).is_some() {
+                                communicator.set_player_name(client, "");
+                                if server_context.remote_players() > 0 {
+                                    communicator.server_send_player_name(client);
+                                }
+                            }
+                            let players_remaining = server_context.players.len() as u16;
+                            server_context
+                                .back_end
+                                .player_departure(client, players_remaining);
+                        }
+                        ToServerCommands::Rpc(client, sequence, payload) => {
+                            server_context.back_end.inform_rpc(
+                                client,
+                                payload,
+                                &server_context.players,
+                            );
+                            server_context.rpc_acks.insert(client, sequence);
+                        }
+                        ToServerCommands::ClientBacklog(backlog) => {
+                            server_context.max_client_backlog = backlog;
+                        }
+                        ToServerCommands::NameChange(client, name) => {
+                            communicator.set_player_name(client, &name);
+                            communicator.server_send_player_name(client);
+                        }
+                        ToServerCommands::ReadyState(ready, total) => {
+                            server_context.client_readiness = Some((ready, total));
                         }
-                        ToServerCommands::Rpc(client, payload) => {
-                            server_context.back_end.inform_rpc(client, payload)
+                        ToServerCommands::SyncRequest(client) => {
+                            if !sync_requests.contains(&client) {
+                                sync_requests.push(client);
+                            }
                         }
                     }
                 }
@@ -649,29 +1659,95 @@ where
                 return;
             }
         }
+        let client_joined = !joined_clients.is_empty();
+        let (ready_clients, total_clients) = server_context.client_readiness();
+        let all_ready = server_context.host_ready && ready_clients == total_clients;
+        if all_ready && !server_context.all_ready {
+            server_context.back_end.all_players_ready();
+        }
+        server_context.all_ready = all_ready;
 
         // 4. Collect the data from the backend.
         let status_updates = server_context.back_end.drain_commands();
+        // The commands reflect the changes of the view state, so a broken invariant shows up right after them.
+        #[cfg(debug_assertions)]
+        if !status_updates.is_empty() {
+            server_context
+                .back_end
+                .get_view_state()
+                .debug_assert_valid();
+        }
         let mut new_status = Vec::with_capacity(status_updates.len());
-        // 5. Process all timer and kicking commands.
+        let mut changed_regions: Vec<u16> = Vec::new();
+        let mut ticks = 0;
+        // 5. Process all timer, kicking, logging, result, pause and tick commands.
         for command in status_updates {
             match command {
+                TerminateRoom if self.termination_grace_secs > 0.0 => {
+                    // The first request starts the grace period, repeated ones do not prolong it.
+                    server_context
+                        .pending_termination
+                        .get_or_insert(self.termination_grace_secs);
+                }
                 TerminateRoom => {
-                    communicator.disconnect(true);
+                    communicator.server_disconnect(HostDeparture::Aborted);
                     self.mark_error("Critical player left.".to_string());
                     self.server_context = None;
                     // We are done here.
                     return;
                 }
+                CancelTermination => {
+                    server_context.pending_termination = None;
+                }
                 SetTimer { timer_id, duration } => {
                     server_context.timer.start_timer(timer_id, duration);
                 }
                 CancelTimer { timer_id } => {
                     server_context.timer.cancel_timer(timer_id);
                 }
+                Log(text) => {
+                    communicator.server_send_log(&text);
+                }
+                ReportResult(result) => {
+                    communicator.server_send_result(&result);
+                }
+                SetPaused(paused) => {
+                    communicator.set_paused(paused);
+                    if server_context.remote_players() > 0 {
+                        communicator.server_send_pause();
+                    }
+                }
+                AdoptPlayer { player } => {
+                    // The host never departs, so its seat can not be adopted.
+                    if player != 0 && !server_context.adopted_players.contains(&player) {
+                        server_context.adopted_players.push(player);
+                    }
+                }
+                ReleasePlayer { player } => {
+                    server_context
+                        .adopted_players
+                        .retain(|&adopted| adopted != player);
+                }
+                UpdateRegions(regions) => {
+                    // Without the codec the regions can not be encoded, the clients would never see the change.
+                    if self.region_codec.is_some() {
+                        changed_regions.extend(regions);
+                    } else {
+                        communicator.server_send_log(
+                            "UpdateRegions requires enable_regions on the transport layer, the regions got ignored.",
+                        );
+                    }
+                }
+                Tick => ticks += 1,
+                RejectAction { player: 0, reason } => communicator.set_rejection(reason),
+                RejectAction { player, reason } => {
+                    if server_context.remote_players() > 0 {
+                        communicator.server_send_rejection(player, &reason);
+                    }
+                }
                 KickPlayer { player } => {
                     // Safeguard for the case that a single player has already left.
-                    if server_context.amount_of_remote_players > 0 {
+                    if server_context.remote_players() > 0 {
                         communicator.server_kick_player(player);
                     }
                 }
@@ -680,68 +1756,296 @@ where
         }
         let status_updates = new_status;
 
-        // 6. Check if there is a reset view state included, if we so we simply broadcast the final result and can skip all the delta information.
+        // 6. Check if there is a reset view state included, if we so we simply broadcast the final result and can skip all the delta information
+        // and the changed regions.
         if status_updates
             .iter()
             .any(|x| matches!(x, BackendCommand::ResetViewState))
         {
             let view_state = (server_context.back_end.get_view_state()).clone();
 
-            // Reset the view state.
-            if server_context.amount_of_remote_players > 0 {
+            // Reset the view state, it supersedes the buffered deltas.
+            self.buffered_delta_runs.clear();
+            if server_context.remote_players() > 0 {
+                if client_joined {
+                    communicator.server_send_player_names();
+                }
                 communicator.server_send_reset(&view_state);
+                server_context.send_rpc_acks(communicator);
             }
             self.state_info_que
-                .push_back(ViewStateUpdate::Full(view_state));
+                .push_back(ViewStateUpdate::FullReset(view_state));
             // With the reset everyone is up to date anyway, because the queried view state is the situation right after the update.
             return;
         }
 
-        // 7. We collect all the remaining delta information.
-        let delta_collector: Vec<DeltaInformation> = status_updates
-            .into_iter()
-            .map(|command| match command {
-                BackendCommand::Delta(delta) => {
-                    self.state_info_que
-                        .push_back(ViewStateUpdate::Incremental(delta.clone()));
-                    delta
+        // 7. We collect all the remaining delta information, grouped into runs with the same remote audience,
+        // so that the order of the deltas is kept. The runs get appended to the ones still buffered.
+        if self.buffered_delta_runs.is_empty() {
+            self.buffered_delta_age = 0.0;
+        } else {
+            self.buffered_delta_age += delta_time;
+        }
+        for command in status_updates {
+            // The local player is always player 0 and does not go over the network.
+            let (local, remote, delta) = match command {
+                BackendCommand::Delta(delta) => (true, Some(RemoteAudience::All), delta),
+                BackendCommand::DeltaExcept { player: 0, delta } => {
+                    (false, Some(RemoteAudience::All), delta)
+                }
+                BackendCommand::DeltaExcept { player, delta } => {
+                    (true, Some(RemoteAudience::Except(player)), delta)
+                }
+                BackendCommand::DeltaTo { player: 0, delta } => (true, None, delta),
+                BackendCommand::DeltaTo { player, delta } => {
+                    (false, Some(RemoteAudience::To(player)), delta)
                 }
                 _ => panic!("Unknown command"),
-            })
-            .collect();
+            };
+            if local {
+                self.state_info_que
+                    .push_back(ViewStateUpdate::Incremental(delta.clone()));
+            }
+            let Some(remote) = remote else {
+                continue;
+            };
+            match self.buffered_delta_runs.last_mut() {
+                Some((run_audience, run)) if *run_audience == remote => run.push(delta),
+                _ => self.buffered_delta_runs.push((remote, vec![delta])),
+            }
+        }
+
+        // The local frontend gets the changed regions right after the deltas, like the remote players.
+        changed_regions.sort_unstable();
+        changed_regions.dedup();
+        if !changed_regions.is_empty() {
+            self.state_info_que.push_back(ViewStateUpdate::Full(
+                server_context.back_end.get_view_state().clone(),
+            ));
+        }
+        for _ in 0..ticks {
+            self.state_info_que.push_back(ViewStateUpdate::Tick);
+        }
 
         // If there are no remote players, we do not need to send update information.
-        if server_context.amount_of_remote_players == 0 {
+        if server_context.remote_players() == 0 {
+            self.buffered_delta_runs.clear();
+            server_context.rpc_acks.clear();
             return;
         }
 
-        // 6.  Now all is left are the status updates methods.
-        if !delta_collector.is_empty() {
-            communicator.server_send_delta_info(&delta_collector);
+        // 8. Now all is left are the status updates methods, once the flush interval passed.
+        // A joining or requesting client gets synchronized with the final view state, so everything drained so far
+        // has to go out before.
+        let flush_interval = self.delta_flush_interval_millis as f32 / 1000.0;
+        if client_joined
+            || !sync_requests.is_empty()
+            || !changed_regions.is_empty()
+            || ticks > 0
+            || self.buffered_delta_age >= flush_interval
+        {
+            for (audience, run) in self.buffered_delta_runs.drain(..) {
+                audience.send(communicator, &run);
+            }
+        }
+        // The changed regions follow the deltas, as they carry the final view state.
+        if let Some(codec) = &self.region_codec
+            && !changed_regions.is_empty()
+        {
+            let payload =
+                (codec.encode)(server_context.back_end.get_view_state(), &changed_regions)
+                    .expect("Could not serialize regions");
+            communicator.server_send_partial_update(&payload);
+        }
+        // The ticks go out after the updates, so that the clients see them after the state they belong to.
+        for _ in 0..ticks {
+            communicator.server_send_tick();
+        }
+        // The acknowledgements wait for the buffered deltas, which may answer the RPCs.
+        if self.buffered_delta_runs.is_empty() {
+            server_context.send_rpc_acks(communicator);
         }
 
         // If we have a client joined we sent a full state broadcast.
         // We do not have to send this information to the local player, as he has always been present.
         // We do the full sync right at the end, because the view state is the final state that is left by the backend.
+        // The early return without remote players above guarantees, that the joined clients are still subscribed.
         if client_joined {
+            // Names do not depend on the view state, the relay forwards them to unsynced clients as well.
+            communicator.server_send_player_names();
             communicator.server_send_full_sync(server_context.back_end.get_view_state());
+            // The new client also has to learn, that the game is paused.
+            if communicator.is_paused() {
+                communicator.server_send_pause();
+            }
+        }
+        // The joined clients got the full sync already.
+        for client in sync_requests {
+            if !joined_clients.contains(&client) {
+                communicator
+                    .server_send_full_sync_to(client, server_context.back_end.get_view_state());
+            }
         }
     }
 
     /// The update on the client side only communicates with the socket interface.
     fn update_client(&mut self) {
-        let communicator = self.core_connection.as_mut().unwrap();
         // 1. Send out data from rpc_que.
-        while let Some(rpc) = self.rpc_que.pop_front() {
-            communicator.client_send_rpc_from(rpc);
-        }
+        self.flush_rpcs();
+        let communicator = self.core_connection.as_mut().unwrap();
         // 2. Collect information from the socket and fill the data que.
         let update = communicator.client_receive_update();
+        // The state the frontend shows below the pending predictions, which the validation moves on.
+        let prediction_base = if self.pending_predictions.is_empty() {
+            None
+        } else {
+            self.shadow_view_state.clone()
+        };
         match update {
-            Ok(core) => self.state_info_que.extend(core),
-            Err(e) => {
-                self.mark_error(e);
+            Ok(core) => match self.validate_updates(core) {
+                Ok(updates) => self.forward_updates(updates, prediction_base),
+                Err(e) => self.mark_error(format!("Protocol error: {}", e)),
+            },
+            Err(e) => match communicator.migration_successor() {
+                Some(successor) => {
+                    let migration_token = communicator.migration_token();
+                    self.start_migration(successor, migration_token, e)
+                }
+                None => self.mark_error(e),
+            },
+        }
+    }
+
+    /// Hands validated updates to the frontend and reconciles them with the pending predictions.
+    ///
+    /// The `prediction_base` is the last synchronized state before the updates, as long as predictions are pending.
+    /// A delta confirming the oldest prediction is dropped, any other delta answers no prediction and reaches the
+    /// frontend, if the predictions still fit after it. A prediction fails, if it does not fit anymore or if the host
+    /// acknowledged its RPC without confirming it. After a failed prediction, the remaining deltas of the batch are
+    /// skipped, as the frontend gets the final authoritative state as a full update anyway.
+    fn forward_updates(
+        &mut self,
+        updates: Vec<ViewStateUpdate<ViewState, DeltaInformation>>,
+        mut prediction_base: Option<ViewState>,
+    ) {
+        let mut needs_rollback = false;
+        for update in updates {
+            match update {
+                ViewStateUpdate::Full(_) | ViewStateUpdate::FullReset(_) => {
+                    self.pending_predictions.clear();
+                    needs_rollback = false;
+                    self.state_info_que.push_back(update);
+                }
+                ViewStateUpdate::Tick => self.state_info_que.push_back(update),
+                ViewStateUpdate::Incremental(delta) => {
+                    if needs_rollback {
+                        continue;
+                    }
+                    let (Some(predicted), Some(base)) =
+                        (self.pending_predictions.front(), prediction_base.as_mut())
+                    else {
+                        self.state_info_que
+                            .push_back(ViewStateUpdate::Incremental(delta));
+                        continue;
+                    };
+                    // The delta passed the validation on the shadow view state, so it fits the base as well.
+                    let _ = base.try_apply(&delta);
+                    if ViewState::confirms_prediction(&predicted.delta, &delta) {
+                        self.pending_predictions.pop_front();
+                    } else if self.predictions_fit(base) {
+                        self.state_info_que
+                            .push_back(ViewStateUpdate::Incremental(delta));
+                    } else {
+                        self.pending_predictions.clear();
+                        needs_rollback = true;
+                    }
+                }
             }
         }
+        // The acknowledgement follows the updates answering the RPCs, a prediction still pending for them failed.
+        let acknowledged = self
+            .core_connection
+            .as_mut()
+            .and_then(|communicator| communicator.take_acknowledged_rpc());
+        if let (Some(acknowledged), Some(predicted)) =
+            (acknowledged, self.pending_predictions.front())
+            && (acknowledged.wrapping_sub(predicted.rpc_sequence) as i32) >= 0
+        {
+            self.pending_predictions.clear();
+            needs_rollback = true;
+        }
+        if needs_rollback && let Some(shadow) = &self.shadow_view_state {
+            self.state_info_que
+                .push_back(ViewStateUpdate::Full(shadow.clone()));
+        }
+    }
+
+    /// Runs the received updates through the shadow view state, so that deltas not fitting the
+    /// last synchronized state get rejected before the frontend sees them. The regions of a partial update
+    /// get set on the shadow view state, which the frontend then gets as a full update. Deltas that excluded this
+    /// client only move the shadow view state on, as the frontend holds the change already.
+    ///
+    /// A rejected or undecodable delta means the client missed an update. Instead of continuing with a corrupt view, the client
+    /// requests a full update and drops all deltas and regions until it arrives.
+    fn validate_updates(
+        &mut self,
+        received: Vec<ReceivedUpdate<ViewState, DeltaInformation>>,
+    ) -> Result<Vec<ViewStateUpdate<ViewState, DeltaInformation>>, String> {
+        let mut updates = Vec::with_capacity(received.len());
+        for update in received {
+            match update {
+                ReceivedUpdate::View(update) => {
+                    match &update {
+                        ViewStateUpdate::Full(state) | ViewStateUpdate::FullReset(state) => {
+                            self.shadow_view_state = Some(state.clone());
+                            self.desynchronized = false;
+                        }
+                        ViewStateUpdate::Incremental(_) if self.desynchronized => continue,
+                        ViewStateUpdate::Incremental(delta) => {
+                            let Some(shadow) = self.shadow_view_state.as_mut() else {
+                                return Err("Delta received before full update".to_string());
+                            };
+                            if shadow.try_apply(delta).is_err() {
+                                self.desynchronize();
+                                continue;
+                            }
+                        }
+                        ViewStateUpdate::Tick => {}
+                    }
+                    updates.push(update);
+                }
+                ReceivedUpdate::Shadow(_) if self.desynchronized => {}
+                ReceivedUpdate::Shadow(delta) => {
+                    let Some(shadow) = self.shadow_view_state.as_mut() else {
+                        return Err("Delta received before full update".to_string());
+                    };
+                    if shadow.try_apply(&delta).is_err() {
+                        self.desynchronize();
+                    }
+                }
+                ReceivedUpdate::Skipped if self.desynchronized => {}
+                ReceivedUpdate::Skipped => self.desynchronize(),
+                ReceivedUpdate::Regions(_) if self.desynchronized => {}
+                ReceivedUpdate::Regions(payload) => {
+                    let Some(codec) = &self.region_codec else {
+                        return Err("Partial update received without regions enabled".to_string());
+                    };
+                    let Some(shadow) = self.shadow_view_state.as_mut() else {
+                        return Err("Partial update received before full update".to_string());
+                    };
+                    (codec.apply)(shadow, &payload)?;
+                    updates.push(ViewStateUpdate::Full(shadow.clone()));
+                }
+            }
+        }
+        Ok(updates)
+    }
+
+    /// Requests a full update and drops all deltas and regions until it arrives.
+    fn desynchronize(&mut self) {
+        self.desynchronized = true;
+        if let Some(communicator) = self.core_connection.as_mut() {
+            communicator.client_request_sync();
+        }
     }
 }
@@ -0,0 +1,131 @@
+//! Reusable bookkeeping of whose turn it is.
+//!
+//! Turn based games map players to some kind of slot, like a stone color or a
+//! seat at the table, and rotate through those slots. The [`TurnTracker`]
+//! keeps the slots in turn order together with the id of the player that
+//! controls each slot, and answers "whose turn is it" and "who is next".
+//!
+//! Slots can be eliminated, so they get skipped from then on. For a player
+//! that can not act right now without being out of the game, use
+//! [`TurnTracker::advance_until`] with a predicate instead.
+//!
+//! The tracker is serializable, so it may live inside a view state.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut turns = TurnTracker::new([(Color::Red, 0), (Color::Green, 1), (Color::Blue, 2)]);
+//! assert!(turns.is_turn(0));
+//! turns.eliminate(&Color::Green);
+//! assert_eq!(turns.advance(), Some(&Color::Blue));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// One entry of the turn order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TurnSlot<Slot> {
+    /// The game specific slot, like a stone color.
+    slot: Slot,
+    /// The id of the player controlling the slot.
+    player_id: u16,
+    /// Eliminated slots get skipped when advancing.
+    eliminated: bool,
+}
+
+/// Tracks the turn order over game specific player slots.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TurnTracker<Slot> {
+    /// The slots in turn order.
+    slots: Vec<TurnSlot<Slot>>,
+    /// The index of the slot whose turn it is.
+    current: usize,
+}
+
+impl<Slot: PartialEq> TurnTracker<Slot> {
+    /// Creates the tracker from the slots in turn order, each with the id of the player controlling it.
+    /// The first slot has the first turn.
+    pub fn new(slots: impl IntoIterator<Item = (Slot, u16)>) -> Self {
+        TurnTracker {
+            slots: slots
+                .into_iter()
+                .map(|(slot, player_id)| TurnSlot {
+                    slot,
+                    player_id,
+                    eliminated: false,
+                })
+                .collect(),
+            current: 0,
+        }
+    }
+
+    /// The slot whose turn it is. `None` if there are no slots or the current one got eliminated.
+    pub fn current_turn(&self) -> Option<&Slot> {
+        self.slots
+            .get(self.current)
+            .filter(|entry| !entry.eliminated)
+            .map(|entry| &entry.slot)
+    }
+
+    /// The player whose turn it is, see [`current_turn`](Self::current_turn).
+    pub fn current_player(&self) -> Option<u16> {
+        self.slots
+            .get(self.current)
+            .filter(|entry| !entry.eliminated)
+            .map(|entry| entry.player_id)
+    }
+
+    /// Checks if it is the turn of the indicated player.
+    pub fn is_turn(&self, player_id: u16) -> bool {
+        self.current_player() == Some(player_id)
+    }
+
+    /// Hands the turn to the indicated slot. Returns `false` and changes nothing,
+    /// if the slot is unknown or eliminated.
+    pub fn set_turn(&mut self, slot: &Slot) -> bool {
+        match self
+            .slots
+            .iter()
+            .position(|entry| entry.slot == *slot && !entry.eliminated)
+        {
+            Some(index) => {
+                self.current = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Passes the turn to the next slot that is not eliminated and returns it.
+    /// If only the current slot remains, it keeps the turn. `None` if every slot is eliminated.
+    pub fn advance(&mut self) -> Option<&Slot> {
+        self.advance_until(|_| true)
+    }
+
+    /// Passes the turn to the next slot that is not eliminated and for which `can_act` holds.
+    /// The current slot is checked last, so it keeps the turn if nobody else can act.
+    /// Returns `None` and leaves the turn unchanged, if no slot can act at all.
+    pub fn advance_until(&mut self, mut can_act: impl FnMut(&Slot) -> bool) -> Option<&Slot> {
+        let len = self.slots.len();
+        let next = (1..=len)
+            .map(|offset| (self.current + offset) % len)
+            .find(|&index| {
+                let entry = &self.slots[index];
+                !entry.eliminated && can_act(&entry.slot)
+            })?;
+        self.current = next;
+        Some(&self.slots[next].slot)
+    }
+
+    /// Removes the slot from the rotation. It may still hold the turn until the next advance.
+    pub fn eliminate(&mut self, slot: &Slot) {
+        for entry in self.slots.iter_mut().filter(|entry| entry.slot == *slot) {
+            entry.eliminated = true;
+        }
+    }
+
+    /// The amount of slots that are not eliminated.
+    pub fn remaining(&self) -> usize {
+        self.slots.iter().filter(|entry| !entry.eliminated).count()
+    }
+}
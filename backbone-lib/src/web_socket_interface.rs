@@ -1,20 +1,38 @@
 //! Does all communication related stuff with the web sockets.
 //! Uses ewebsock for native builds and own implementation for WASM builds.
+//!
+//! Native builds support any number of simultaneous connections, one per [`ConnectionInformation`].
+//! The WASM plugin `quad_ws.js` only manages a single global socket, so in WASM only one
+//! connection may exist at a time. Starting a second one fails with an error instead of silently
+//! replacing the socket of the first.
+//!
+//! With the feature `json-debug`, payloads get serialized as JSON instead of postcard and all frames
+//! are sent as text (see [`protocol::debug_text_from_frame`]), so the traffic is readable in the browser
+//! devtools. The relay has to be built with the same feature. All payloads go through the codec of the module
+//! [`codec`](crate::codec).
 
+use crate::codec::{ActiveCodec, WireCodec};
 use crate::traits::SerializationCap;
 use crate::transport_layer::ViewStateUpdate;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use postcard::{from_bytes, take_from_bytes, to_stdvec};
 use protocol::{
-    CLIENT_DISCONNECTS, CLIENT_DISCONNECTS_SELF, CLIENT_GETS_KICKED, CLIENT_ID_SIZE, DELTA_UPDATE,
-    FULL_UPDATE, HAND_SHAKE_RESPONSE, JoinRequest, NEW_CLIENT, RESET, SERVER_DISCONNECTS,
-    SERVER_ERROR, SERVER_RPC,
+    CLIENT_BACKLOG, CLIENT_DISCONNECTS, CLIENT_DISCONNECTS_SELF, CLIENT_GETS_KICKED,
+    CLIENT_ID_SIZE, DELTA_UPDATE, DELTA_UPDATE_EXCEPT, DELTA_UPDATE_TO, FULL_UPDATE,
+    FULL_UPDATE_TO, GameId, HAND_SHAKE_RESPONSE, HOST_MIGRATION, HandshakeResponse, HostDeparture,
+    JoinRequest, KEEPALIVE, LOG, MIGRATION_TOKEN_SIZE, NEW_CLIENT, PARTIAL_UPDATE, PAUSE,
+    PAUSE_MSG_SIZE, PLAYER_NAME, READY, READY_MSG_SIZE, READY_STATE, REJECT_ACTION, REQUEST_SYNC,
+    REQUEST_SYNC_MSG_SIZE, RESET, RESULT, RPC_ACK, RPC_ACK_MSG_SIZE, RPC_SEQUENCE_SIZE, RoomId,
+    SEQUENCE_SIZE, SERVER_DISCONNECTS, SERVER_ERROR, SERVER_RPC, SET_NAME, SPECTATOR_COUNT,
+    SPECTATOR_COUNT_MSG_SIZE, TICK, debug_text_from_frame, frame_from_debug_text,
 };
+use std::collections::HashMap;
 
 #[cfg(not(target_arch = "wasm32"))]
-use ewebsock::WsEvent::{Closed, Error, Message};
+use ewebsock::WsEvent::{Closed, Error, Message, Opened};
 #[cfg(not(target_arch = "wasm32"))]
 use ewebsock::{WsMessage, WsReceiver, WsSender};
+#[cfg(target_arch = "wasm32")]
+use std::sync::atomic::{AtomicBool, Ordering};
 
 // ============================================================================
 // WASM FFI declarations
@@ -25,21 +43,107 @@ unsafe extern "C" {
     fn quad_ws_connect(url_ptr: *const u8, url_len: usize);
     fn quad_ws_connected() -> i32;
     fn quad_ws_send(data_ptr: *const u8, data_len: usize);
+    fn quad_ws_send_text(text_ptr: *const u8, text_len: usize);
     fn quad_ws_next_message_len() -> usize;
     fn quad_ws_recv(buffer_ptr: *mut u8, buffer_len: usize) -> usize;
+    fn quad_ws_page_origin(buffer_ptr: *mut u8, buffer_len: usize) -> usize;
+}
+
+/// Flags if the single global socket of the WASM plugin is owned by a connection.
+#[cfg(target_arch = "wasm32")]
+static WASM_SOCKET_IN_USE: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// Relay address
+// ============================================================================
+
+/// Builds the web socket url of the relay from the page the WASM module runs in, so a game does not hardcode it.
+///
+/// In WASM, the scheme follows the page: `https` pages connect with `wss`, `http` pages with `ws`, to the host of the
+/// page and the given path, e.g. `/ws` if the relay serves the page itself or `/api/ws` behind a reverse proxy.
+/// If the page has no usable origin, like a local file, and in native builds, the given fallback url gets returned.
+pub fn relay_url(path: &str, fallback_url: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(origin) = page_origin() {
+        let socket_origin = if let Some(host) = origin.strip_prefix("https://") {
+            Some(format!("wss://{}", host))
+        } else {
+            origin
+                .strip_prefix("http://")
+                .map(|host| format!("ws://{}", host))
+        };
+        if let Some(socket_origin) = socket_origin {
+            return format!("{}{}", socket_origin, path);
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = path;
+    fallback_url.to_string()
+}
+
+/// The origin of the page, like `https://example.org:8443`, as the browser reports it.
+#[cfg(target_arch = "wasm32")]
+fn page_origin() -> Option<String> {
+    unsafe {
+        let len = quad_ws_page_origin(std::ptr::null_mut(), 0);
+        if len == 0 {
+            return None;
+        }
+        let mut buffer = vec![0u8; len];
+        quad_ws_page_origin(buffer.as_mut_ptr(), buffer.len());
+        String::from_utf8(buffer).ok()
+    }
+}
+
+// ============================================================================
+// Payload encoding
+// ============================================================================
+
+/// Describes a payload that could not be decoded with the message type and the payload size,
+/// to tell protocol mismatches apart from corrupted frames.
+fn decode_error(message_name: &str, payload_size: usize, error: String) -> String {
+    format!(
+        "Failed to decode {} payload ({} bytes): {}",
+        message_name, payload_size, error
+    )
 }
 
 /// A local structure that gets completed by the synchronization.
 pub struct GameSetting {
     pub player_id: u16,
     pub rule_variation: u16,
+    /// The compound room id `room#game` as stored on the relay.
+    pub room_id: String,
+}
+
+/// An update a client received, before it gets validated by the transport layer.
+pub enum ReceivedUpdate<ViewState, DeltaInformation> {
+    /// An update as the frontend gets it.
+    View(ViewStateUpdate<ViewState, DeltaInformation>),
+    /// The encoded regions of a [`PARTIAL_UPDATE`], which only the transport layer can apply.
+    Regions(Vec<u8>),
+    /// A delta of a [`DELTA_UPDATE_EXCEPT`] that excluded us. The frontend holds the change already,
+    /// only the shadow view state of the transport layer needs it.
+    Shadow(DeltaInformation),
+    /// A delta of a run that could not be decoded. The view state missed its change, so the transport layer
+    /// treats it like a delta that does not fit and waits for the full update.
+    Skipped,
 }
 
 /// Contains the commands that go to the server.
 pub enum ToServerCommands<ServerRpcPayload> {
     ClientJoin(u16),
     ClientLeft(u16),
-    Rpc(u16, ServerRpcPayload),
+    /// An action of a client with the sequence number the client gave it.
+    Rpc(u16, u32, ServerRpcPayload),
+    /// The most messages any client has not picked up yet, as reported by the relay.
+    ClientBacklog(u16),
+    /// A client set its display name.
+    NameChange(u16, String),
+    /// The amount of ready clients and of all clients, as reported by the relay.
+    ReadyState(u16, u16),
+    /// A client asked for a fresh full state.
+    SyncRequest(u16),
 }
 
 /// This is a connection information setting that manages all receiving and sending
@@ -48,8 +152,36 @@ pub struct ConnectionInformation {
     sender: WsSender,
     #[cfg(not(target_arch = "wasm32"))]
     receiver: WsReceiver,
+    /// Flags, if the socket reported to be open and has not been closed or failed since.
+    #[cfg(not(target_arch = "wasm32"))]
+    socket_open: bool,
 
     pending_join_request: JoinRequest,
+    /// Flags, if the relay accepted the join request, so the relay interprets our disconnect message.
+    joined: bool,
+    /// Flags, if the disconnect message has been sent already.
+    departed: bool,
+
+    /// The sequence number the host attaches to its next view state update.
+    next_sequence: u32,
+    /// The sequence number of the last full update or reset the client applied.
+    last_sync_sequence: Option<u32>,
+    /// Flags, if the host paused the game, on the host as well as on the clients.
+    paused: bool,
+    /// The display names of the players by player id, on the host as well as on the clients.
+    player_names: HashMap<u16, String>,
+    /// The amount of spectators in the room as announced by the relay, on the host as well as on the clients.
+    spectators: u16,
+    /// The client that becomes the new host, once the relay announced a host migration.
+    migration_successor: Option<u16>,
+    /// The token to take the room over with, if the relay named us the new host.
+    migration_token: Option<u64>,
+    /// The reason of the last action of the local player the host rejected and nobody took yet.
+    rejection: Option<String>,
+    /// The sequence number of the next RPC the client sends.
+    next_rpc_sequence: u32,
+    /// The sequence number of the last RPC of the local player the host acknowledged and nobody took yet.
+    acknowledged_rpc: Option<u32>,
 }
 
 impl ConnectionInformation {
@@ -58,7 +190,20 @@ impl ConnectionInformation {
         ConnectionInformation {
             sender,
             receiver,
+            socket_open: false,
             pending_join_request: join_request,
+            joined: false,
+            departed: false,
+            next_sequence: 0,
+            last_sync_sequence: None,
+            paused: false,
+            player_names: HashMap::new(),
+            spectators: 0,
+            migration_successor: None,
+            migration_token: None,
+            rejection: None,
+            next_rpc_sequence: 0,
+            acknowledged_rpc: None,
         }
     }
 
@@ -66,6 +211,18 @@ impl ConnectionInformation {
     fn new(join_request: JoinRequest) -> Self {
         ConnectionInformation {
             pending_join_request: join_request,
+            joined: false,
+            departed: false,
+            next_sequence: 0,
+            last_sync_sequence: None,
+            paused: false,
+            player_names: HashMap::new(),
+            spectators: 0,
+            migration_successor: None,
+            migration_token: None,
+            rejection: None,
+            next_rpc_sequence: 0,
+            acknowledged_rpc: None,
         }
     }
 
@@ -74,6 +231,19 @@ impl ConnectionInformation {
         self.pending_join_request.create_room
     }
 
+    /// Checks if the socket is open. Natively this reflects the socket events seen so far,
+    /// so it turns false once a close or an error was received.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_connected(&self) -> bool {
+        self.socket_open
+    }
+
+    /// Checks if the socket is open. WASM version.
+    #[cfg(target_arch = "wasm32")]
+    pub fn is_connected(&self) -> bool {
+        unsafe { quad_ws_connected() != 0 }
+    }
+
     // ===================================================================
     // NATIVE (ewebsock) implementations
     // ===================================================================
@@ -84,12 +254,29 @@ impl ConnectionInformation {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    fn try_recv_binary(&mut self) -> Result<Option<Vec<u8>>, String> {
+    fn send_text(&mut self, text: String) {
+        self.sender.send(WsMessage::Text(text));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn try_recv_data(&mut self) -> Result<Option<Vec<u8>>, String> {
         loop {
             match self.receiver.try_recv() {
-                Some(Message(WsMessage::Binary(msg))) => return Ok(Some(msg)),
-                Some(Closed) => return Err("Connection closed by server".to_string()),
-                Some(Error(context)) => return Err(context),
+                Some(Message(WsMessage::Binary(msg))) if !ActiveCodec::TEXT_FRAMES => {
+                    return Ok(Some(msg));
+                }
+                Some(Message(WsMessage::Text(text))) if ActiveCodec::TEXT_FRAMES => {
+                    return Ok(Some(text.into_bytes()));
+                }
+                Some(Opened) => self.socket_open = true,
+                Some(Closed) => {
+                    self.socket_open = false;
+                    return Err("Connection closed by server".to_string());
+                }
+                Some(Error(context)) => {
+                    self.socket_open = false;
+                    return Err(context);
+                }
                 Some(_) => continue, // Ignore other message types, keep checking
                 None => return Ok(None),
             }
@@ -108,7 +295,15 @@ impl ConnectionInformation {
     }
 
     #[cfg(target_arch = "wasm32")]
-    fn try_recv_binary(&mut self) -> Result<Option<Vec<u8>>, String> {
+    fn send_text(&mut self, text: String) {
+        unsafe {
+            quad_ws_send_text(text.as_ptr(), text.len());
+        }
+    }
+
+    /// Receives binary frames, and text frames as UTF-8 bytes (the plugin does not tell them apart).
+    #[cfg(target_arch = "wasm32")]
+    fn try_recv_data(&mut self) -> Result<Option<Vec<u8>>, String> {
         unsafe {
             // First check for incoming messages.
             let len = quad_ws_next_message_len();
@@ -119,7 +314,7 @@ impl ConnectionInformation {
             }
 
             // No more messages, generate error.
-            if quad_ws_connected() == 0 {
+            if !self.is_connected() {
                 return Err("Connection lost".to_string());
             }
 
@@ -131,6 +326,44 @@ impl ConnectionInformation {
     // Shared implementation (platform-agnostic)
     // ===================================================================
 
+    /// Sends a frame, as text in the JSON debugging mode.
+    /// The `header_size` is the amount of leading bytes with message id and client ids.
+    fn send_frame(&mut self, data: &[u8], header_size: usize) {
+        if ActiveCodec::TEXT_FRAMES {
+            self.send_text(debug_text_from_frame(data, header_size));
+        } else {
+            self.send_binary(data);
+        }
+    }
+
+    /// Receives the next frame in the binary layout, text frames of the JSON debugging mode get converted back.
+    fn try_recv_frame(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let Some(data) = self.try_recv_data()? else {
+            return Ok(None);
+        };
+        if ActiveCodec::TEXT_FRAMES {
+            let text = String::from_utf8(data)
+                .map_err(|_| "Debug text frame is no valid UTF-8".to_string())?;
+            frame_from_debug_text(&text).map(Some)
+        } else {
+            Ok(Some(data))
+        }
+    }
+
+    /// Sends the join request, which has no header. In the JSON debugging mode it is sent as JSON text.
+    fn send_join_request(&mut self) -> Result<(), String> {
+        let msg = ActiveCodec::encode(&self.pending_join_request)
+            .map_err(|_| "Problem in serialization".to_string())?;
+        if ActiveCodec::TEXT_FRAMES {
+            let text =
+                String::from_utf8(msg).map_err(|_| "Join request is no UTF-8 text".to_string())?;
+            self.send_text(text);
+        } else {
+            self.send_binary(&msg);
+        }
+        Ok(())
+    }
+
     // -----------------------------------
     // All server related.
     // -----------------------------------
@@ -140,7 +373,14 @@ impl ConnectionInformation {
         let mut msg_builder = BytesMut::with_capacity(1 + CLIENT_ID_SIZE);
         msg_builder.put_u8(CLIENT_GETS_KICKED);
         msg_builder.put_u16(player_id);
-        self.send_binary(&msg_builder);
+        self.send_frame(&msg_builder, 1 + CLIENT_ID_SIZE);
+    }
+
+    /// Hands out the sequence number for the next view state update of the host.
+    fn take_sequence(&mut self) -> u32 {
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        sequence
     }
 
     /// Sends the sequence with the accumulated delta infos.
@@ -148,32 +388,230 @@ impl ConnectionInformation {
         &mut self,
         delta_vec: &[DeltaInformation],
     ) {
-        let serialized: Vec<_> = delta_vec
-            .iter()
-            .flat_map(|d| to_stdvec(d).expect("Could not serialize delta information."))
-            .collect();
-        let mut msg_builder = BytesMut::with_capacity(1 + serialized.len());
+        let serialized = ActiveCodec::encode_sequence(delta_vec)
+            .expect("Could not serialize delta information.");
+        let sequence = self.take_sequence();
+        let mut msg_builder = BytesMut::with_capacity(1 + SEQUENCE_SIZE + serialized.len());
         msg_builder.put_u8(DELTA_UPDATE);
+        msg_builder.put_u32(sequence);
+        msg_builder.put_slice(&serialized);
+        self.send_frame(&msg_builder, 1 + SEQUENCE_SIZE);
+    }
+
+    /// Sends the sequence with the accumulated delta infos to all clients except the indicated one.
+    pub fn server_send_delta_info_except<DeltaInformation: SerializationCap>(
+        &mut self,
+        excluded_player: u16,
+        delta_vec: &[DeltaInformation],
+    ) {
+        self.send_targeted_delta_info(DELTA_UPDATE_EXCEPT, excluded_player, delta_vec);
+    }
+
+    /// Sends the sequence with the accumulated delta infos to the indicated client only, never to spectators.
+    pub fn server_send_delta_info_to<DeltaInformation: SerializationCap>(
+        &mut self,
+        player: u16,
+        delta_vec: &[DeltaInformation],
+    ) {
+        self.send_targeted_delta_info(DELTA_UPDATE_TO, player, delta_vec);
+    }
+
+    /// Sends delta infos with a message type that carries a client id in front of the sequence number.
+    fn send_targeted_delta_info<DeltaInformation: SerializationCap>(
+        &mut self,
+        message_type: u8,
+        player: u16,
+        delta_vec: &[DeltaInformation],
+    ) {
+        let serialized = ActiveCodec::encode_sequence(delta_vec)
+            .expect("Could not serialize delta information.");
+        let sequence = self.take_sequence();
+        let mut msg_builder =
+            BytesMut::with_capacity(1 + CLIENT_ID_SIZE + SEQUENCE_SIZE + serialized.len());
+        msg_builder.put_u8(message_type);
+        msg_builder.put_u16(player);
+        msg_builder.put_u32(sequence);
         msg_builder.put_slice(&serialized);
-        self.send_binary(&msg_builder);
+        self.send_frame(&msg_builder, 1 + CLIENT_ID_SIZE + SEQUENCE_SIZE);
     }
 
     /// Sends a full synchronization command.
     pub fn server_send_full_sync<ViewState: SerializationCap>(&mut self, state: &ViewState) {
-        let serialized = to_stdvec(state).expect("Could not serialize state");
-        let mut msg_builder = BytesMut::with_capacity(1 + serialized.len());
+        let serialized = ActiveCodec::encode(state).expect("Could not serialize state");
+        let sequence = self.take_sequence();
+        let mut msg_builder = BytesMut::with_capacity(1 + SEQUENCE_SIZE + serialized.len());
         msg_builder.put_u8(FULL_UPDATE);
+        msg_builder.put_u32(sequence);
         msg_builder.put_slice(&serialized);
-        self.send_binary(&msg_builder);
+        self.send_frame(&msg_builder, 1 + SEQUENCE_SIZE);
+    }
+
+    /// Sends a full synchronization to the indicated client only, which gets it even if it is synchronized already.
+    pub fn server_send_full_sync_to<ViewState: SerializationCap>(
+        &mut self,
+        player: u16,
+        state: &ViewState,
+    ) {
+        let serialized = ActiveCodec::encode(state).expect("Could not serialize state");
+        let sequence = self.take_sequence();
+        let mut msg_builder =
+            BytesMut::with_capacity(1 + CLIENT_ID_SIZE + SEQUENCE_SIZE + serialized.len());
+        msg_builder.put_u8(FULL_UPDATE_TO);
+        msg_builder.put_u16(player);
+        msg_builder.put_u32(sequence);
+        msg_builder.put_slice(&serialized);
+        self.send_frame(&msg_builder, 1 + CLIENT_ID_SIZE + SEQUENCE_SIZE);
+    }
+
+    /// Sends the encoded regions of a view state to all synchronized clients, see [`PARTIAL_UPDATE`].
+    pub fn server_send_partial_update(&mut self, payload: &[u8]) {
+        let sequence = self.take_sequence();
+        let mut msg_builder = BytesMut::with_capacity(1 + SEQUENCE_SIZE + payload.len());
+        msg_builder.put_u8(PARTIAL_UPDATE);
+        msg_builder.put_u32(sequence);
+        msg_builder.put_slice(payload);
+        self.send_frame(&msg_builder, 1 + SEQUENCE_SIZE);
     }
 
     /// Same as full_sync only that it gets interpreted by all clients.
     pub fn server_send_reset<ViewState: SerializationCap>(&mut self, state: &ViewState) {
-        let serialized = to_stdvec(state).expect("Could not serialize state");
-        let mut msg_builder = BytesMut::with_capacity(1 + serialized.len());
+        let serialized = ActiveCodec::encode(state).expect("Could not serialize state");
+        let sequence = self.take_sequence();
+        let mut msg_builder = BytesMut::with_capacity(1 + SEQUENCE_SIZE + serialized.len());
         msg_builder.put_u8(RESET);
+        msg_builder.put_u32(sequence);
         msg_builder.put_slice(&serialized);
-        self.send_binary(&msg_builder);
+        self.send_frame(&msg_builder, 1 + SEQUENCE_SIZE);
+    }
+
+    /// Records on the host, whether the game is paused, see [`server_send_pause`](Self::server_send_pause).
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Sends the current pause state to all clients.
+    pub fn server_send_pause(&mut self) {
+        self.send_frame(&[PAUSE, self.paused as u8], PAUSE_MSG_SIZE);
+    }
+
+    /// Flags, if the host paused the game.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The amount of spectators in the room, as last announced by the relay.
+    pub fn spectator_count(&self) -> u16 {
+        self.spectators
+    }
+
+    /// The client that becomes the new host, if the relay announced a host migration, see [`HOST_MIGRATION`].
+    pub fn migration_successor(&self) -> Option<u16> {
+        self.migration_successor
+    }
+
+    /// The token to take the room over with, if the relay named us the new host in a host migration.
+    pub fn migration_token(&self) -> Option<u64> {
+        self.migration_token
+    }
+
+    /// Takes over the spectator count announced by the relay, see [`SPECTATOR_COUNT`].
+    fn read_spectator_count(&mut self, bytes: &mut Bytes) -> Result<(), String> {
+        if bytes.remaining() < SPECTATOR_COUNT_MSG_SIZE - 1 {
+            return Err("Malformed spectator count message".to_string());
+        }
+        self.spectators = bytes.get_u16();
+        Ok(())
+    }
+
+    /// Records the display name of a player, an empty name removes it.
+    /// On the host this gets followed by [`server_send_player_name`](Self::server_send_player_name).
+    pub fn set_player_name(&mut self, player_id: u16, name: &str) {
+        if name.is_empty() {
+            self.player_names.remove(&player_id);
+        } else {
+            self.player_names.insert(player_id, name.to_string());
+        }
+    }
+
+    /// The display name of a player, if known.
+    pub fn player_name(&self, player_id: u16) -> Option<&str> {
+        self.player_names.get(&player_id).map(String::as_str)
+    }
+
+    /// Sends the current display name of a player to all clients, an empty one if the player has none.
+    pub fn server_send_player_name(&mut self, player_id: u16) {
+        let name = self.player_name(player_id).unwrap_or_default().as_bytes();
+        let mut msg_builder = BytesMut::with_capacity(1 + CLIENT_ID_SIZE + name.len());
+        msg_builder.put_u8(PLAYER_NAME);
+        msg_builder.put_u16(player_id);
+        msg_builder.put_slice(name);
+        self.send_frame(&msg_builder, 1 + CLIENT_ID_SIZE);
+    }
+
+    /// Sends all known display names to all clients, e.g. for a client that just joined.
+    pub fn server_send_player_names(&mut self) {
+        let mut players: Vec<u16> = self.player_names.keys().copied().collect();
+        players.sort_unstable();
+        for player_id in players {
+            self.server_send_player_name(player_id);
+        }
+    }
+
+    /// Sends a diagnostic log line to the relay, that does not get forwarded to the clients.
+    pub fn server_send_log(&mut self, text: &str) {
+        let raw_data = text.as_bytes();
+        let mut msg_builder = BytesMut::with_capacity(1 + raw_data.len());
+        msg_builder.put_u8(LOG);
+        msg_builder.put_slice(raw_data);
+        self.send_frame(&msg_builder, 1);
+    }
+
+    /// Sends the opaque result blob of a finished game to the relay, that does not get forwarded to the clients.
+    pub fn server_send_result(&mut self, result: &[u8]) {
+        let mut msg_builder = BytesMut::with_capacity(1 + result.len());
+        msg_builder.put_u8(RESULT);
+        msg_builder.put_slice(result);
+        self.send_frame(&msg_builder, 1);
+    }
+
+    /// Sends a tick without a change of the view state to all synchronized clients.
+    pub fn server_send_tick(&mut self) {
+        self.send_frame(&[TICK], 1);
+    }
+
+    /// Tells a single player why the host ignored its action, the relay forwards it to that player only.
+    pub fn server_send_rejection(&mut self, player_id: u16, reason: &str) {
+        let raw_data = reason.as_bytes();
+        let mut msg_builder = BytesMut::with_capacity(1 + CLIENT_ID_SIZE + raw_data.len());
+        msg_builder.put_u8(REJECT_ACTION);
+        msg_builder.put_u16(player_id);
+        msg_builder.put_slice(raw_data);
+        self.send_frame(&msg_builder, 1 + CLIENT_ID_SIZE);
+    }
+
+    /// Records the reason of a rejected action of the local player, replacing one nobody took yet.
+    /// On the host this is the way a rejection reaches its own player.
+    pub fn set_rejection(&mut self, reason: String) {
+        self.rejection = Some(reason);
+    }
+
+    /// Takes the reason of the last rejected action of the local player, if there is one.
+    pub fn take_rejection(&mut self) -> Option<String> {
+        self.rejection.take()
+    }
+
+    /// Tells a single player the sequence number of its last RPC the host processed, see [`RPC_ACK`].
+    pub fn server_send_rpc_ack(&mut self, player_id: u16, sequence: u32) {
+        let mut msg_builder = BytesMut::with_capacity(RPC_ACK_MSG_SIZE);
+        msg_builder.put_u8(RPC_ACK);
+        msg_builder.put_u16(player_id);
+        msg_builder.put_u32(sequence);
+        self.send_frame(&msg_builder, RPC_ACK_MSG_SIZE);
+    }
+
+    /// Tells the relay that the host is still alive, nothing gets forwarded to the clients.
+    pub fn server_send_keepalive(&mut self) {
+        self.send_frame(&[KEEPALIVE], 1);
     }
 
     /// Reads in all the commands that come from the diverse clients to the server.
@@ -182,7 +620,7 @@ impl ConnectionInformation {
     ) -> Result<Vec<ToServerCommands<ServerRpcPayload>>, String> {
         let mut result: Vec<ToServerCommands<ServerRpcPayload>> = Vec::new();
 
-        while let Some(data) = self.try_recv_binary()? {
+        while let Some(data) = self.try_recv_frame()? {
             let mut bytes = Bytes::from(data);
             let msg = bytes.get_u8();
 
@@ -199,12 +637,35 @@ impl ConnectionInformation {
                     let client_id = bytes.get_u16();
                     result.push(ToServerCommands::ClientLeft(client_id));
                 }
+                CLIENT_BACKLOG => {
+                    let backlog = bytes.get_u16();
+                    result.push(ToServerCommands::ClientBacklog(backlog));
+                }
                 SERVER_RPC => {
+                    if bytes.remaining() < CLIENT_ID_SIZE + RPC_SEQUENCE_SIZE {
+                        return Err("Malformed SERVER_RPC message".to_string());
+                    }
                     let client_id = bytes.get_u16();
-                    let payload: ServerRpcPayload = from_bytes(bytes.chunk())
-                        .expect("Failed to deserialize server rpc payload");
-                    result.push(ToServerCommands::Rpc(client_id, payload));
+                    let sequence = bytes.get_u32();
+                    let payload: ServerRpcPayload = ActiveCodec::decode(bytes.chunk())
+                        .map_err(|error| decode_error("SERVER_RPC", bytes.len(), error))?;
+                    result.push(ToServerCommands::Rpc(client_id, sequence, payload));
                 }
+                SET_NAME => {
+                    let client_id = bytes.get_u16();
+                    let name = String::from_utf8_lossy(&bytes).to_string();
+                    result.push(ToServerCommands::NameChange(client_id, name));
+                }
+                READY_STATE => {
+                    let ready = bytes.get_u16();
+                    let total = bytes.get_u16();
+                    result.push(ToServerCommands::ReadyState(ready, total));
+                }
+                REQUEST_SYNC => {
+                    let client_id = bytes.get_u16();
+                    result.push(ToServerCommands::SyncRequest(client_id));
+                }
+                SPECTATOR_COUNT => self.read_spectator_count(&mut bytes)?,
                 _ => return Err(format!("Unknown message received: {:?}", msg)),
             }
         }
@@ -215,28 +676,81 @@ impl ConnectionInformation {
     // All client related.
     // -----------------------------------
 
-    /// Sends an rpc server over the next.
+    /// Sends an rpc server over the next, tagged with the next RPC sequence number.
     pub fn client_send_rpc_from<ServerRpcPayload: SerializationCap>(
         &mut self,
         server_payload: ServerRpcPayload,
     ) {
-        let raw_bytes = to_stdvec(&server_payload).expect("Failed to serialize server rpc payload");
-        let mut msg_builder = BytesMut::with_capacity(1 + raw_bytes.len());
+        let raw_bytes =
+            ActiveCodec::encode(&server_payload).expect("Failed to serialize server rpc payload");
+        let sequence = self.next_rpc_sequence;
+        self.next_rpc_sequence = self.next_rpc_sequence.wrapping_add(1);
+        let mut msg_builder = BytesMut::with_capacity(1 + RPC_SEQUENCE_SIZE + raw_bytes.len());
         msg_builder.put_u8(SERVER_RPC);
+        msg_builder.put_u32(sequence);
         msg_builder.put_slice(&raw_bytes);
-        self.send_binary(&msg_builder);
+        self.send_frame(&msg_builder, 1 + RPC_SEQUENCE_SIZE);
+    }
+
+    /// The sequence number the next RPC of [`client_send_rpc_from`](Self::client_send_rpc_from) gets.
+    pub fn next_rpc_sequence(&self) -> u32 {
+        self.next_rpc_sequence
+    }
+
+    /// Takes the sequence number of the last RPC the host acknowledged, if one arrived since the last call.
+    pub fn take_acknowledged_rpc(&mut self) -> Option<u32> {
+        self.acknowledged_rpc.take()
+    }
+
+    /// Asks the host to set the display name of this client.
+    pub fn client_send_name(&mut self, name: &str) {
+        let mut msg_builder = BytesMut::with_capacity(1 + name.len());
+        msg_builder.put_u8(SET_NAME);
+        msg_builder.put_slice(name.as_bytes());
+        self.send_frame(&msg_builder, 1);
+    }
+
+    /// Tells the relay whether this client is ready to start the game.
+    pub fn client_send_ready(&mut self, ready: bool) {
+        self.send_frame(&[READY, ready as u8], READY_MSG_SIZE);
+    }
+
+    /// Asks the host for a fresh full state.
+    pub fn client_request_sync(&mut self) {
+        self.send_frame(&[REQUEST_SYNC], REQUEST_SYNC_MSG_SIZE);
+    }
+
+    /// Reads the sequence number of a view state update.
+    /// Returns `None` for updates the last full synchronization already reflects, which must be discarded:
+    /// everything up to its sequence number and deltas that arrive before the first synchronization.
+    /// This way a delta interleaving with the full update of a client that joined mid-burst never gets applied twice.
+    fn read_sequence(&self, bytes: &mut Bytes, is_delta: bool) -> Result<Option<u32>, String> {
+        if bytes.remaining() < SEQUENCE_SIZE {
+            return Err("View state update without sequence number".to_string());
+        }
+        let sequence = bytes.get_u32();
+        let reflected = match self.last_sync_sequence {
+            // The sequence number wraps around, so only a positive distance counts as newer.
+            Some(last_sync) => (sequence.wrapping_sub(last_sync) as i32) <= 0,
+            None => is_delta,
+        };
+        if reflected {
+            return Ok(None);
+        }
+        Ok(Some(sequence))
     }
 
     /// Gets all the updates that were sent from the server to the client side.
+    /// Updates the last full synchronization already reflects get discarded.
     pub fn client_receive_update<
         ViewState: SerializationCap,
         DeltaInformation: SerializationCap,
     >(
         &mut self,
-    ) -> Result<Vec<ViewStateUpdate<ViewState, DeltaInformation>>, String> {
-        let mut result: Vec<ViewStateUpdate<ViewState, DeltaInformation>> = Vec::new();
+    ) -> Result<Vec<ReceivedUpdate<ViewState, DeltaInformation>>, String> {
+        let mut result: Vec<ReceivedUpdate<ViewState, DeltaInformation>> = Vec::new();
 
-        while let Some(data) = self.try_recv_binary()? {
+        while let Some(data) = self.try_recv_frame()? {
             let mut bytes = Bytes::from(data);
             let msg = bytes.get_u8();
 
@@ -245,20 +759,100 @@ impl ConnectionInformation {
                     let error_text = String::from_utf8_lossy(&bytes).to_string();
                     return Err(error_text);
                 }
-                DELTA_UPDATE => {
-                    let mut remaining: &[u8] = &bytes;
-                    while !remaining.is_empty() {
-                        let (delta, rest): (DeltaInformation, &[u8]) =
-                            take_from_bytes(remaining).expect("Failed to decode delta payload");
-                        remaining = rest;
-
-                        result.push(ViewStateUpdate::Incremental(delta));
+                DELTA_UPDATE | DELTA_UPDATE_EXCEPT => {
+                    // The relay only forwards the deltas that excluded us unchanged.
+                    let excluded = msg == DELTA_UPDATE_EXCEPT;
+                    if excluded {
+                        if bytes.remaining() < CLIENT_ID_SIZE {
+                            return Err("Malformed delta update message".to_string());
+                        }
+                        bytes.get_u16();
+                    }
+                    if self.read_sequence(&mut bytes, true)?.is_none() {
+                        continue;
+                    }
+                    let message_name = if excluded {
+                        "DELTA_UPDATE_EXCEPT"
+                    } else {
+                        "DELTA_UPDATE"
+                    };
+                    let deltas: Vec<Result<DeltaInformation, String>> =
+                        ActiveCodec::decode_sequence(&bytes)
+                            .map_err(|error| decode_error(message_name, bytes.len(), error))?;
+                    for delta in deltas {
+                        match delta {
+                            Ok(delta) if excluded => result.push(ReceivedUpdate::Shadow(delta)),
+                            Ok(delta) => result
+                                .push(ReceivedUpdate::View(ViewStateUpdate::Incremental(delta))),
+                            // The following deltas build on the missing change.
+                            Err(_) => {
+                                result.push(ReceivedUpdate::Skipped);
+                                break;
+                            }
+                        }
                     }
                 }
                 FULL_UPDATE | RESET => {
-                    let message: ViewState =
-                        from_bytes(&bytes).expect("Failed to decode full payload");
-                    result.push(ViewStateUpdate::Full(message));
+                    let Some(sequence) = self.read_sequence(&mut bytes, false)? else {
+                        continue;
+                    };
+                    self.last_sync_sequence = Some(sequence);
+                    let message_name = if msg == RESET { "RESET" } else { "FULL_UPDATE" };
+                    let message: ViewState = ActiveCodec::decode(&bytes)
+                        .map_err(|error| decode_error(message_name, bytes.len(), error))?;
+                    if msg == RESET {
+                        result.push(ReceivedUpdate::View(ViewStateUpdate::FullReset(message)));
+                    } else {
+                        result.push(ReceivedUpdate::View(ViewStateUpdate::Full(message)));
+                    }
+                }
+                PARTIAL_UPDATE => {
+                    if self.read_sequence(&mut bytes, true)?.is_none() {
+                        continue;
+                    }
+                    result.push(ReceivedUpdate::Regions(bytes.to_vec()));
+                }
+                TICK => result.push(ReceivedUpdate::View(ViewStateUpdate::Tick)),
+                PAUSE => {
+                    if bytes.is_empty() {
+                        return Err("Malformed pause message".to_string());
+                    }
+                    self.paused = bytes.get_u8() != 0;
+                }
+                PLAYER_NAME => {
+                    if bytes.remaining() < CLIENT_ID_SIZE {
+                        return Err("Malformed player name message".to_string());
+                    }
+                    let player_id = bytes.get_u16();
+                    let name = String::from_utf8_lossy(&bytes).to_string();
+                    self.set_player_name(player_id, &name);
+                }
+                SPECTATOR_COUNT => self.read_spectator_count(&mut bytes)?,
+                HOST_MIGRATION => {
+                    if bytes.remaining() < CLIENT_ID_SIZE {
+                        return Err("Malformed host migration message".to_string());
+                    }
+                    self.migration_successor = Some(bytes.get_u16());
+                    // Only the new host gets the token.
+                    if bytes.remaining() >= MIGRATION_TOKEN_SIZE {
+                        self.migration_token = Some(bytes.get_u64());
+                    }
+                }
+                REJECT_ACTION => {
+                    if bytes.remaining() < CLIENT_ID_SIZE {
+                        return Err("Malformed reject action message".to_string());
+                    }
+                    // The relay only forwards the rejections meant for us.
+                    bytes.get_u16();
+                    self.set_rejection(String::from_utf8_lossy(&bytes).to_string());
+                }
+                RPC_ACK => {
+                    if bytes.remaining() < RPC_ACK_MSG_SIZE - 1 {
+                        return Err("Malformed rpc acknowledgement message".to_string());
+                    }
+                    // The relay only forwards the acknowledgements meant for us.
+                    bytes.get_u16();
+                    self.acknowledged_rpc = Some(bytes.get_u32());
                 }
                 _ => return Err(format!("Unknown message received: {:?}", msg)),
             }
@@ -270,24 +864,31 @@ impl ConnectionInformation {
     // All connection logic related.
     // -----------------------------------
 
-    /// Sends the disconnect message
+    /// Sends the disconnect message, the host tells the clients that it ended the game.
     pub fn disconnect(&mut self, as_server: bool) {
-        let msg = if as_server {
-            vec![SERVER_DISCONNECTS]
+        if as_server {
+            self.server_disconnect(HostDeparture::Ended);
         } else {
-            vec![CLIENT_DISCONNECTS_SELF]
-        };
-        self.send_binary(&msg);
+            self.departed = true;
+            self.send_frame(&[CLIENT_DISCONNECTS_SELF], 1);
+        }
+    }
+
+    /// Sends the disconnect message of the host with the reason the clients get to see.
+    pub fn server_disconnect(&mut self, departure: HostDeparture) {
+        self.departed = true;
+        self.send_frame(&[SERVER_DISCONNECTS, departure.code()], 1);
     }
 
     /// Initiates the connection phase (native version).
     #[cfg(not(target_arch = "wasm32"))]
     pub fn start_connecting(
         base_url: String,
-        game_id: String,
-        room_id: String,
+        game_id: GameId,
+        room_id: RoomId,
         rule_variation: u16,
         is_server: bool,
+        migration_token: Option<u64>,
     ) -> Result<ConnectionInformation, String> {
         let options = ewebsock::Options::default();
         let (sender, receiver) = ewebsock::connect(&base_url, options)
@@ -298,6 +899,8 @@ impl ConnectionInformation {
             room_id,
             rule_variation,
             create_room: is_server,
+            codec: ActiveCodec::ID,
+            migration_token,
         };
 
         Ok(ConnectionInformation::new(sender, receiver, req))
@@ -307,11 +910,15 @@ impl ConnectionInformation {
     #[cfg(target_arch = "wasm32")]
     pub fn start_connecting(
         base_url: String,
-        game_id: String,
-        room_id: String,
+        game_id: GameId,
+        room_id: RoomId,
         rule_variation: u16,
         is_server: bool,
+        migration_token: Option<u64>,
     ) -> Result<ConnectionInformation, String> {
+        if WASM_SOCKET_IN_USE.swap(true, Ordering::SeqCst) {
+            return Err("Only one connection at a time is supported in WASM".to_string());
+        }
         unsafe {
             quad_ws_connect(base_url.as_ptr(), base_url.len());
         }
@@ -321,6 +928,8 @@ impl ConnectionInformation {
             room_id,
             rule_variation,
             create_room: is_server,
+            codec: ActiveCodec::ID,
+            migration_token,
         };
 
         Ok(ConnectionInformation::new(req))
@@ -331,9 +940,14 @@ impl ConnectionInformation {
     pub fn update_awaiting_readiness(
         connection: &mut ConnectionInformation,
     ) -> Result<bool, String> {
-        let msg = to_stdvec(&connection.pending_join_request)
-            .map_err(|_| "Problem in serialization".to_string())?;
-        connection.sender.send(WsMessage::Binary(msg));
+        // Picks up the opened event. The relay sends nothing before it got the join request.
+        if connection.try_recv_data()?.is_some() {
+            return Err("Received a message before the join request".to_string());
+        }
+        if !connection.is_connected() {
+            return Ok(false);
+        }
+        connection.send_join_request()?;
         Ok(true)
     }
 
@@ -342,14 +956,10 @@ impl ConnectionInformation {
     pub fn update_awaiting_readiness(
         connection: &mut ConnectionInformation,
     ) -> Result<bool, String> {
-        unsafe {
-            if quad_ws_connected() == 0 {
-                return Ok(false);
-            }
-            let msg = to_stdvec(&connection.pending_join_request)
-                .map_err(|_| "Problem in serialization".to_string())?;
-            quad_ws_send(msg.as_ptr(), msg.len());
+        if !connection.is_connected() {
+            return Ok(false);
         }
+        connection.send_join_request()?;
         Ok(true)
     }
 
@@ -357,13 +967,17 @@ impl ConnectionInformation {
     pub fn update_connecting(
         connection_info: &mut ConnectionInformation,
     ) -> Option<Result<GameSetting, String>> {
-        let data = match connection_info.try_recv_binary() {
+        let data = match connection_info.try_recv_frame() {
             Ok(Some(data)) => data,
             Ok(None) => return None,
             Err(e) => return Some(Err(e)),
         };
 
+        if data.is_empty() {
+            return Some(Err("Empty message received in handshake".to_string()));
+        }
         let mut bytes = Bytes::from(data);
+        let frame = bytes.clone();
         let msg = bytes.get_u8();
 
         match msg {
@@ -372,12 +986,17 @@ impl ConnectionInformation {
                 Some(Err(error_text))
             }
             HAND_SHAKE_RESPONSE => {
-                let player_id = bytes.get_u16();
-                let rule_variation = bytes.get_u16();
+                // Parsed from the complete frame, so fields a newer relay appends get skipped.
+                let response = match HandshakeResponse::from_frame(&frame) {
+                    Ok(response) => response,
+                    Err(error) => return Some(Err(error)),
+                };
+                connection_info.joined = true;
 
                 Some(Ok(GameSetting {
-                    player_id,
-                    rule_variation,
+                    player_id: response.player_id,
+                    rule_variation: response.rule_variation,
+                    room_id: response.room_id,
                 }))
             }
             _ => Some(Err(format!(
@@ -387,3 +1006,19 @@ impl ConnectionInformation {
         }
     }
 }
+
+/// Departs cleanly from the room and releases the single global socket of the WASM plugin for the next connection.
+///
+/// If we joined a room and have not said goodbye yet, the matching disconnect message gets sent, so the relay
+/// and the other side learn about the departure right away instead of waiting for the socket to time out.
+/// This is best-effort only: the message is queued on the socket, but nothing waits for it to go out, so it may
+/// get lost if the process ends right after.
+impl Drop for ConnectionInformation {
+    fn drop(&mut self) {
+        if self.joined && !self.departed && self.is_connected() {
+            self.disconnect(self.is_server());
+        }
+        #[cfg(target_arch = "wasm32")]
+        WASM_SOCKET_IN_USE.store(false, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,77 @@
+//! The active codec reads back what it wrote, single payloads as well as runs of them.
+//! Within a run, a corrupt payload does not affect the others.
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Move {
+    Place { row: u8, column: u8 },
+    Pass,
+}
+
+#[test]
+fn single_payload_round_trip() {
+    let value = Move::Place { row: 1, column: 2 };
+    let bytes = ActiveCodec::encode(&value).unwrap();
+    assert_eq!(ActiveCodec::decode::<Move>(&bytes).unwrap(), value);
+}
+
+#[test]
+fn run_of_payloads_round_trip() {
+    let values = vec![Move::Pass, Move::Place { row: 0, column: 3 }, Move::Pass];
+    let bytes = ActiveCodec::encode_sequence(&values).unwrap();
+    let decoded: Vec<Move> = ActiveCodec::decode_sequence(&bytes)
+        .unwrap()
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(decoded, values);
+    assert!(
+        ActiveCodec::decode_sequence::<Move>(&[])
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn garbage_is_an_error() {
+    assert!(ActiveCodec::decode::<Move>(&[0xff, 0xff, 0xff]).is_err());
+}
+
+#[cfg(not(feature = "json-debug"))]
+#[test]
+fn corrupt_payload_in_run_leaves_the_others_intact() {
+    let mut bytes = ActiveCodec::encode_sequence(&[Move::Pass]).unwrap();
+    // A variant index, that does not exist.
+    bytes.extend([0, 0, 0, 1, 7]);
+    bytes.extend(ActiveCodec::encode_sequence(&[Move::Place { row: 4, column: 5 }]).unwrap());
+
+    let decoded = ActiveCodec::decode_sequence::<Move>(&bytes).unwrap();
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded[0], Ok(Move::Pass));
+    assert!(decoded[1].is_err());
+    assert_eq!(decoded[2], Ok(Move::Place { row: 4, column: 5 }));
+}
+
+#[cfg(not(feature = "json-debug"))]
+#[test]
+fn length_prefix_beyond_the_run_is_an_error() {
+    let mut bytes = ActiveCodec::encode_sequence(&[Move::Pass]).unwrap();
+    bytes.extend([0, 0, 0, 9, 1]);
+    assert!(ActiveCodec::decode_sequence::<Move>(&bytes).is_err());
+    // A cut off length prefix.
+    assert!(ActiveCodec::decode_sequence::<Move>(&[0, 0, 0]).is_err());
+}
+
+#[test]
+fn payload_beyond_u16_fits_into_a_run() {
+    let values = vec![vec![7u8; 70_000], vec![1u8; 3]];
+    let bytes = ActiveCodec::encode_sequence(&values).unwrap();
+    let decoded: Vec<Vec<u8>> = ActiveCodec::decode_sequence(&bytes)
+        .unwrap()
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(decoded, values);
+}
@@ -0,0 +1,115 @@
+//! A backend in the functional style can be tested by its return values and runs through the adapter.
+
+use backbone_lib::traits::{
+    BackEndArchitecture, BackendCommand, FunctionalAdapter, FunctionalBackEnd, PlayerSet,
+};
+use serde::{Deserialize, Serialize};
+
+/// The timer that ends a turn.
+const TURN_TIMER: u16 = 1;
+
+/// The view state of a game in which the players add numbers to a common total.
+#[derive(Clone, Serialize, Deserialize, Default, PartialEq, Debug)]
+struct Total(u32);
+
+/// The delta tells who added how much.
+type Added = (u16, u32);
+
+/// Adds the number of every RPC, the host ends the game by resetting it.
+struct AddingBackend {
+    view_state: Total,
+}
+
+impl FunctionalBackEnd<u32, Added, Total> for AddingBackend {
+    fn new(_: u16) -> Self {
+        AddingBackend {
+            view_state: Total::default(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) -> Vec<BackendCommand<Added>> {
+        vec![BackendCommand::SetTimer {
+            timer_id: TURN_TIMER,
+            duration: 10.0,
+        }]
+    }
+    fn player_departure(&mut self, _: u16, players_remaining: u16) -> Vec<BackendCommand<Added>> {
+        if players_remaining < 2 {
+            vec![BackendCommand::TerminateRoom]
+        } else {
+            Vec::new()
+        }
+    }
+    fn inform_rpc(
+        &mut self,
+        player: u16,
+        payload: u32,
+        _: &PlayerSet,
+    ) -> Vec<BackendCommand<Added>> {
+        self.view_state.0 += payload;
+        vec![
+            BackendCommand::CancelTimer {
+                timer_id: TURN_TIMER,
+            },
+            BackendCommand::Delta((player, payload)),
+        ]
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) -> Vec<BackendCommand<Added>> {
+        vec![BackendCommand::Log("Turn timed out.".to_string())]
+    }
+    fn on_host_reset_request(&mut self) -> Vec<BackendCommand<Added>> {
+        self.view_state = Total::default();
+        vec![BackendCommand::ResetViewState]
+    }
+    fn get_view_state(&self) -> &Total {
+        &self.view_state
+    }
+}
+
+#[test]
+fn handlers_are_tested_by_their_return_values() {
+    let mut backend = AddingBackend::new(0);
+    let players: PlayerSet = [0, 2].into_iter().collect();
+    assert!(matches!(
+        backend.inform_rpc(2, 5, &players).as_slice(),
+        [
+            BackendCommand::CancelTimer {
+                timer_id: TURN_TIMER
+            },
+            BackendCommand::Delta((2, 5))
+        ]
+    ));
+    assert_eq!(backend.view_state, Total(5));
+    assert!(matches!(
+        backend.player_departure(2, 1).as_slice(),
+        [BackendCommand::TerminateRoom]
+    ));
+    // Handlers without an override return nothing.
+    assert!(backend.all_players_ready().is_empty());
+}
+
+#[test]
+fn adapter_collects_the_commands_until_drained() {
+    let mut adapter: FunctionalAdapter<AddingBackend, Added> =
+        BackEndArchitecture::<u32, Added, Total>::new(0);
+    let players: PlayerSet = [0, 1].into_iter().collect();
+    adapter.player_arrival(1);
+    adapter.inform_rpc(1, 3, &players);
+    adapter.timer_triggered(TURN_TIMER, &players);
+    assert_eq!(adapter.get_view_state(), &Total(3));
+
+    let commands = adapter.drain_commands();
+    assert!(matches!(
+        commands.as_slice(),
+        [
+            BackendCommand::SetTimer { .. },
+            BackendCommand::CancelTimer { .. },
+            BackendCommand::Delta((1, 3)),
+            BackendCommand::Log(_)
+        ]
+    ));
+    assert!(adapter.drain_commands().is_empty());
+
+    adapter.on_host_reset_request();
+    assert_eq!(adapter.backend().view_state, Total(0));
+    assert_eq!(adapter.min_players(), 1);
+}
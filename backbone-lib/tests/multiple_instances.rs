@@ -0,0 +1,103 @@
+//! Several transport layers in one process share nothing and progress independently.
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer};
+use serde::{Deserialize, Serialize};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Nothing listens here, so every connection attempt runs into its own error.
+const UNREACHABLE_RELAY: &str = "ws://127.0.0.1:9/ws";
+
+/// A view state without any content.
+#[derive(Clone, Serialize, Deserialize)]
+struct EmptyState;
+
+impl ViewStateArchitecture<u8, u8> for EmptyState {
+    fn try_apply(&mut self, _: &u8) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// A backend that ignores every event.
+struct IdleBackend {
+    view_state: EmptyState,
+}
+
+impl BackEndArchitecture<u8, u8, EmptyState> for IdleBackend {
+    fn new(_: u16) -> Self {
+        IdleBackend {
+            view_state: EmptyState,
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, _: u8, _: &PlayerSet) {}
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &EmptyState {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u8>> {
+        Vec::new()
+    }
+}
+
+type Layer = TransportLayer<u8, u8, IdleBackend, EmptyState>;
+
+fn is_disconnected(layer: &Layer) -> bool {
+    matches!(
+        layer.connection_state(),
+        ConnectionState::Disconnected { .. }
+    )
+}
+
+/// Heartbeats all layers until every one of them has dropped its connection.
+fn drive_until_disconnected(layers: &mut [Layer]) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !layers.iter().all(is_disconnected) {
+        assert!(
+            Instant::now() < deadline,
+            "Connections did not fail in time"
+        );
+        for layer in layers.iter_mut() {
+            layer.update(0.01);
+        }
+        sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn instances_connect_and_fail_independently() {
+    let mut layers: Vec<Layer> = (0..2)
+        .map(|_| Layer::generate_transport_layer(UNREACHABLE_RELAY.to_string(), "test".to_string()))
+        .collect();
+
+    layers[0].start_game_server("room-a".to_string(), 0);
+    layers[1].start_game_client("room-b".to_string());
+    assert_eq!(
+        layers[0].connection_state(),
+        &ConnectionState::AwaitingHandshake
+    );
+    assert_eq!(
+        layers[1].connection_state(),
+        &ConnectionState::AwaitingHandshake
+    );
+
+    drive_until_disconnected(&mut layers);
+    for layer in layers.iter() {
+        assert!(matches!(
+            layer.connection_state(),
+            ConnectionState::Disconnected {
+                error_string: Some(_)
+            }
+        ));
+    }
+
+    // Restarting one instance leaves the other one untouched.
+    layers[0].start_game_server("room-a".to_string(), 0);
+    assert_eq!(
+        layers[0].connection_state(),
+        &ConnectionState::AwaitingHandshake
+    );
+    assert!(is_disconnected(&layers[1]));
+}
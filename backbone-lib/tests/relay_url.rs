@@ -0,0 +1,11 @@
+//! Native builds have no page to derive the relay address from.
+
+use backbone_lib::transport_layer::relay_url;
+
+#[test]
+fn native_builds_use_the_fallback() {
+    assert_eq!(
+        relay_url("/api/ws", "ws://127.0.0.1:8080/ws"),
+        "ws://127.0.0.1:8080/ws"
+    );
+}
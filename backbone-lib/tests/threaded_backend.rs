@@ -0,0 +1,109 @@
+//! A backend on the worker thread keeps the calls fast and hands back its commands in order.
+
+use backbone_lib::threaded_backend::ThreadedBackend;
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet};
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// The time the backend thinks about every move.
+const THINKING_TIME: Duration = Duration::from_millis(100);
+
+/// The RPC that makes the backend panic.
+const CRASH: u32 = 0;
+
+/// The view state sums up all moves.
+#[derive(Clone, Serialize, Deserialize, Default, PartialEq, Debug)]
+struct Total(u32);
+
+/// A slow backend that is not `Send`, as it holds an `Rc`.
+struct SlowBackend {
+    view_state: Total,
+    command_list: Vec<BackendCommand<u32>>,
+    _not_send: Rc<()>,
+}
+
+impl BackEndArchitecture<u32, u32, Total> for SlowBackend {
+    fn new(rule_variation: u16) -> Self {
+        SlowBackend {
+            view_state: Total(rule_variation as u32),
+            command_list: Vec::new(),
+            _not_send: Rc::new(()),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, payload: u32, _: &PlayerSet) {
+        assert_ne!(payload, CRASH, "Backend crashed");
+        std::thread::sleep(THINKING_TIME);
+        self.view_state.0 += payload;
+        self.command_list.push(BackendCommand::Delta(payload));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn min_players(&self) -> u16 {
+        2
+    }
+    fn get_view_state(&self) -> &Total {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u32>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Threaded = ThreadedBackend<SlowBackend, u32, u32, Total>;
+
+/// Drains the backend until the amount of commands arrived.
+fn drain_until(backend: &mut Threaded, amount: usize) -> Vec<BackendCommand<u32>> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut commands = Vec::new();
+    while commands.len() < amount {
+        assert!(Instant::now() < deadline, "Commands did not arrive in time");
+        commands.extend(backend.drain_commands());
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    commands
+}
+
+#[test]
+fn calls_return_before_the_backend_finished() {
+    let mut backend = Threaded::new(10);
+    assert_eq!(backend.get_view_state(), &Total(10));
+    assert_eq!(backend.min_players(), 2);
+
+    let players: PlayerSet = [0, 1, 2].into_iter().collect();
+    let start = Instant::now();
+    backend.inform_rpc(1, 1, &players);
+    backend.inform_rpc(2, 2, &players);
+    backend.inform_rpc(1, 3, &players);
+    assert!(start.elapsed() < THINKING_TIME);
+    assert!(backend.drain_commands().is_empty());
+    assert_eq!(backend.get_view_state(), &Total(10));
+
+    let commands = drain_until(&mut backend, 3);
+    assert!(matches!(
+        commands.as_slice(),
+        [
+            BackendCommand::Delta(1),
+            BackendCommand::Delta(2),
+            BackendCommand::Delta(3)
+        ]
+    ));
+    assert_eq!(backend.get_view_state(), &Total(16));
+}
+
+#[test]
+fn crashed_worker_terminates_the_room_once() {
+    let mut backend = Threaded::new(0);
+    let players: PlayerSet = [0, 1].into_iter().collect();
+    backend.inform_rpc(1, CRASH, &players);
+    let commands = drain_until(&mut backend, 1);
+    assert!(matches!(
+        commands.as_slice(),
+        [BackendCommand::TerminateRoom]
+    ));
+    // Further calls go nowhere.
+    backend.inform_rpc(1, 1, &players);
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(backend.drain_commands().is_empty());
+}
@@ -0,0 +1,72 @@
+//! The timeline interpolates its keyframes independent of how the time gets split into frames.
+
+use backbone_lib::timeline::{Timeline, ease_in, ease_out, linear, smoothstep};
+
+/// The animation of a placed stone in Ternio: materializing within 0.25 seconds, flipping within 0.75 seconds.
+fn stone_animation() -> Timeline {
+    Timeline::new(0.0)
+        .then(0.25, 1.0, smoothstep)
+        .then(0.75, 2.0, linear)
+}
+
+#[test]
+fn easing_functions_keep_their_end_points() {
+    for easing in [linear, smoothstep, ease_in, ease_out] {
+        assert_eq!(easing(0.0), 0.0);
+        assert_eq!(easing(1.0), 1.0);
+        assert_eq!(easing(-1.0), 0.0);
+        assert_eq!(easing(2.0), 1.0);
+    }
+    assert_eq!(smoothstep(0.5), 0.5);
+    assert!(ease_in(0.5) < 0.5);
+    assert!(ease_out(0.5) > 0.5);
+}
+
+#[test]
+fn keyframes_get_interpolated_with_their_easing() {
+    let mut timeline = stone_animation();
+    assert_eq!(timeline.duration(), 1.0);
+    assert_eq!(timeline.value(), 0.0);
+
+    timeline.advance(0.125);
+    assert_eq!(timeline.value(), smoothstep(0.5));
+    timeline.advance(0.125);
+    assert_eq!(timeline.value(), 1.0);
+    timeline.advance(0.375);
+    assert_eq!(timeline.value(), 1.5);
+    assert_eq!(timeline.progress(), 0.625);
+}
+
+#[test]
+fn frame_rate_does_not_change_the_outcome() {
+    let mut coarse = stone_animation();
+    let mut fine = stone_animation();
+    coarse.advance(0.5);
+    for _ in 0..50 {
+        fine.advance(0.01);
+    }
+    assert!((coarse.value() - fine.value()).abs() < 1e-4);
+}
+
+#[test]
+fn timeline_finishes_and_holds_the_last_value() {
+    let mut timeline = stone_animation();
+    assert!(!timeline.advance(0.9));
+    assert!(timeline.advance(0.2));
+    assert!(timeline.is_finished());
+    assert_eq!(timeline.value(), 2.0);
+    assert_eq!(timeline.progress(), 1.0);
+
+    timeline.restart();
+    assert!(!timeline.is_finished());
+    assert_eq!(timeline.value(), 0.0);
+}
+
+#[test]
+fn zero_duration_keyframe_jumps() {
+    let mut timeline = Timeline::new(0.0).then(0.0, 5.0, linear);
+    assert!(timeline.is_finished());
+    assert_eq!(timeline.value(), 5.0);
+    assert!(timeline.advance(0.1));
+    assert_eq!(timeline.progress(), 1.0);
+}
@@ -0,0 +1,70 @@
+//! Rotation of the turn tracker, including eliminated slots.
+
+use backbone_lib::turn_tracker::TurnTracker;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Seat {
+    North,
+    East,
+    South,
+}
+
+fn three_seats() -> TurnTracker<Seat> {
+    TurnTracker::new([(Seat::North, 0), (Seat::East, 1), (Seat::South, 2)])
+}
+
+#[test]
+fn rotates_in_order_and_wraps() {
+    let mut turns = three_seats();
+    assert_eq!(turns.current_turn(), Some(&Seat::North));
+    assert!(turns.is_turn(0));
+    assert_eq!(turns.advance(), Some(&Seat::East));
+    assert!(turns.is_turn(1));
+    assert_eq!(turns.advance(), Some(&Seat::South));
+    assert_eq!(turns.advance(), Some(&Seat::North));
+    assert!(!turns.is_turn(2));
+}
+
+#[test]
+fn skips_eliminated_slots() {
+    let mut turns = three_seats();
+    turns.eliminate(&Seat::East);
+    assert_eq!(turns.remaining(), 2);
+    assert_eq!(turns.advance(), Some(&Seat::South));
+    assert_eq!(turns.advance(), Some(&Seat::North));
+
+    turns.eliminate(&Seat::North);
+    assert_eq!(turns.current_turn(), None);
+    assert_eq!(turns.advance(), Some(&Seat::South));
+    // The last remaining slot keeps the turn.
+    assert_eq!(turns.advance(), Some(&Seat::South));
+
+    turns.eliminate(&Seat::South);
+    assert_eq!(turns.advance(), None);
+    assert_eq!(turns.current_player(), None);
+}
+
+#[test]
+fn advance_until_checks_current_slot_last() {
+    let mut turns = three_seats();
+    assert_eq!(
+        turns.advance_until(|seat| *seat == Seat::North),
+        Some(&Seat::North)
+    );
+    assert_eq!(
+        turns.advance_until(|seat| *seat != Seat::East),
+        Some(&Seat::South)
+    );
+    assert_eq!(turns.advance_until(|_| false), None);
+    assert_eq!(turns.current_turn(), Some(&Seat::South));
+}
+
+#[test]
+fn set_turn_ignores_eliminated_slots() {
+    let mut turns = three_seats();
+    assert!(turns.set_turn(&Seat::South));
+    assert!(turns.is_turn(2));
+    turns.eliminate(&Seat::East);
+    assert!(!turns.set_turn(&Seat::East));
+    assert_eq!(turns.current_turn(), Some(&Seat::South));
+}
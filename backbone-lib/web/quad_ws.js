@@ -1,5 +1,5 @@
 // quad_ws.js - Minimal WebSocket plugin for Miniquad (quad-net replacement)
-// Binary messages only, polling-based
+// Binary messages, and text messages for the JSON debugging mode, polling-based
 
 
 "use strict";
@@ -48,6 +48,9 @@ miniquad_add_plugin({
                 ws.onmessage = function(event) {
                     if (event.data instanceof ArrayBuffer) {
                         incoming_queue.push(new Uint8Array(event.data));
+                    } else if (typeof event.data === "string") {
+                        // Text frames are handed over as UTF-8 bytes.
+                        incoming_queue.push(new TextEncoder().encode(event.data));
                     }
                 };
             } catch (e) {
@@ -69,6 +72,14 @@ miniquad_add_plugin({
             }
         };
         
+        // Send UTF-8 text as text frame
+        importObject.env.quad_ws_send_text = function(text_ptr, text_len) {
+            if (ws !== null && ws_connected) {
+                const bytes = new Uint8Array(wasm_memory.buffer, text_ptr, text_len);
+                ws.send(new TextDecoder().decode(bytes));
+            }
+        };
+
         // Check if message is available
         importObject.env.quad_ws_has_message = function() {
             return incoming_queue.length > 0 ? 1 : 0;
@@ -92,6 +103,19 @@ miniquad_add_plugin({
             
             return msg.length; // Return actual length (caller can detect truncation)
         };
+
+        // Write the origin of the page as UTF-8 into the buffer, returns the actual length (0 if there is none)
+        importObject.env.quad_ws_page_origin = function(buffer_ptr, buffer_len) {
+            const origin = window.location.origin;
+            if (!origin || origin === "null") return 0;
+
+            const bytes = new TextEncoder().encode(origin);
+            const copy_len = Math.min(bytes.length, buffer_len);
+            if (copy_len > 0) {
+                new Uint8Array(wasm_memory.buffer, buffer_ptr, copy_len).set(bytes.subarray(0, copy_len));
+            }
+            return bytes.length;
+        };
     }
     
 });
@@ -0,0 +1,155 @@
+//! Feeds arbitrary frames into the routing logic of the relay, to find panics on malformed input.
+//!
+//! A single run plays through a room without any network:
+//! 1. A client sends its frames, the messages with the injected player id get collected.
+//! 2. The host sends its frames, while the collected client messages wait for it in the channel to the host.
+//! 3. A player and a spectator pick up whatever the host broadcast, and the admin tap renders every mirrored message.
+//!
+//! Run it with `cargo fuzz run relay_messages` from the repository root. Panics inside the connection tasks
+//! abort the process as well, as libFuzzer installs a panic hook that does so.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use axum::extract::ws::Message;
+use bytes::Bytes;
+use futures_util::{SinkExt, stream};
+use libfuzzer_sys::fuzz_target;
+use protocol::CompoundRoomId;
+use relay_server::lobby::{ActivityTracker, RoomBroadcast};
+use relay_server::message_relay::{
+    ConnectionOptions, HostRoom, MessageSink, MessageStream, describe_tapped_frame,
+    handle_client_logic, handle_server_logic,
+};
+use relay_server::results::ResultsSink;
+use std::num::NonZeroU32;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::{Mutex, mpsc};
+
+/// A web socket frame as an endpoint may send it.
+#[derive(Arbitrary, Debug)]
+enum Frame {
+    Binary(Vec<u8>),
+    Text(String),
+    Ping(Vec<u8>),
+    Close,
+}
+
+impl From<Frame> for Message {
+    fn from(frame: Frame) -> Self {
+        match frame {
+            Frame::Binary(bytes) => Message::Binary(bytes.into()),
+            Frame::Text(text) => Message::Text(text.into()),
+            Frame::Ping(bytes) => Message::Ping(bytes.into()),
+            Frame::Close => Message::Close(None),
+        }
+    }
+}
+
+/// The traffic of a room.
+#[derive(Arbitrary, Debug)]
+struct Input {
+    /// Whether the endpoints joined in the JSON debugging mode.
+    text_frames: bool,
+    /// The id the relay assigned to the client.
+    player_id: u16,
+    /// The RPC rate limit of the game, the configuration never allows a rate of zero.
+    max_rpcs_per_sec: Option<NonZeroU32>,
+    /// The frames the client sends.
+    client_frames: Vec<Frame>,
+    /// The frames the host sends.
+    host_frames: Vec<Frame>,
+}
+
+/// A sink that swallows every frame, like an endpoint that reads everything.
+fn swallowing_sink() -> Arc<Mutex<impl MessageSink>> {
+    Arc::new(Mutex::new(
+        futures_util::sink::drain().sink_map_err(|never| match never {}),
+    ))
+}
+
+/// A stream that delivers the frames and then ends, like an endpoint that leaves.
+fn frame_stream(frames: Vec<Frame>) -> impl MessageStream {
+    stream::iter(frames.into_iter().map(|frame| Ok(Message::from(frame))))
+}
+
+/// The runtime is shared among the runs, creating one per run would dominate the time.
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("Could not create the runtime")
+    })
+}
+
+fuzz_target!(|input: Input| {
+    let options = ConnectionOptions {
+        text_frames: input.text_frames,
+        send_timeout: Duration::from_secs(1),
+        max_rpcs_per_sec: input.max_rpcs_per_sec.map(NonZeroU32::get),
+    };
+    runtime().block_on(async move {
+        // The channels never fill up, so no run waits for the overload timeouts of the relay.
+        let capacity = input.client_frames.len().max(input.host_frames.len()) + 1;
+        let (to_host, host_inbox) = mpsc::channel(capacity);
+        // The client keeps listening to a silent host, until it sent all of its frames.
+        let silent_host = RoomBroadcast::new(1);
+        handle_client_logic(
+            swallowing_sink(),
+            frame_stream(input.client_frames),
+            silent_host.subscribe(false),
+            to_host.clone(),
+            input.player_id,
+            ActivityTracker::new(),
+            options,
+        )
+        .await;
+
+        let broadcast = RoomBroadcast::new(capacity);
+        let player = broadcast.subscribe(false);
+        let spectator = broadcast.subscribe(true);
+        let mut tap = broadcast.subscribe_tap();
+        handle_server_logic(
+            swallowing_sink(),
+            frame_stream(input.host_frames),
+            host_inbox,
+            broadcast,
+            HostRoom {
+                room_id: CompoundRoomId::new("fuzz#fuzz"),
+                results: ResultsSink::default(),
+            },
+            ActivityTracker::new(),
+            options,
+        )
+        .await;
+        drop(to_host);
+
+        // The host is gone, so the clients end once they picked up the backlog.
+        for receiver in [player, spectator] {
+            handle_client_logic(
+                swallowing_sink(),
+                stream::pending(),
+                receiver,
+                mpsc::channel::<Bytes>(1).0,
+                input.player_id,
+                ActivityTracker::new(),
+                options,
+            )
+            .await;
+        }
+        loop {
+            match tap.try_recv() {
+                Ok(tapped) => {
+                    describe_tapped_frame(&tapped, options.text_frames);
+                }
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+    });
+});
@@ -19,18 +19,16 @@ pub struct TransitionBoard {
 }
 
 impl TransitionBoard {
-    /// Creates a new transition module. The  board as is and the move command in form of the delta information
-    /// to extract the information for stone flipping.
+    /// Creates a new transition module. The  board as is and the move command in form of the delta information,
+    /// which carries the stones the host flipped.
     ///
     /// # Panic
     /// The delta information handed over has to be a move command.
     pub fn new(move_command: DeltaInformation, game_board: &GameBoard) -> TransitionBoard {
-        let DeltaInformation::MakeMove(turn) = &move_command else {
+        let DeltaInformation::MakeMove(turn, flipped_stones) = &move_command else {
             panic! {"Wrong delta information in new."};
         };
 
-        let flipped_stones =
-            game_board.get_all_flipped_stones(turn.field_position.clone(), turn.stone_color);
         let buffered_positions = game_board.get_stone_placement();
         let filtered_positions: Vec<StonePlacement> = buffered_positions
             .iter()
@@ -45,7 +43,7 @@ impl TransitionBoard {
         let animator = Animator::new(
             buffered_positions,
             filtered_positions,
-            flipped_stones,
+            flipped_stones.clone(),
             turn.clone(),
         );
 
@@ -107,7 +105,7 @@ impl BufferedBoardForRendering {
                 panic!("Wrong game state!");
             }
         };
-        if view_state.player_colors[player_id as usize] == next_move {
+        if view_state.color_of_player(player_id) == next_move {
             next_player = String::from("YOU !");
         }
 
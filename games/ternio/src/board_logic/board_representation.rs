@@ -11,6 +11,8 @@ pub const NUM_OF_COLORS: usize = 3;
 pub const BOARD_DIM: usize = 9;
 /// The same as [`BOARD_DIM`] just in i8 as often needed.
 pub const BOARD_DIMS: i8 = BOARD_DIM as i8;
+/// The bytes of the compact board, with 2 bits per field.
+pub const COMPACT_BOARD_SIZE: usize = (BOARD_DIM * BOARD_DIM).div_ceil(4);
 
 /// Encodes a position on the game field. Origin is in the lower left point.
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -56,20 +58,8 @@ pub enum StoneColor {
     Blue,
 }
 
-impl StoneColor {
-    /// Gives the cycle of stone colors beginning from self. This is an aid function
-    /// to help determine what the next eligible player is.
-    pub fn cycle_from_next(&self) -> [StoneColor; NUM_OF_COLORS] {
-        use StoneColor::*;
-        match self {
-            Red => [Green, Blue, Red],
-            Green => [Blue, Red, Green],
-            Blue => [Red, Green, Blue],
-        }
-    }
-}
-
 /// Returns the information which stone at which position should be flipped from which to which color.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct FlipInformation {
     /// The position of the flipping stone.
     pub field_position: FieldPosition,
@@ -89,10 +79,40 @@ pub struct StonePlacement {
 }
 
 /// The game board that is also a part of the view state.
+/// It gets serialized in the compact form of [`GameBoard::serialize_compact`], which keeps the full updates small.
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "CompactBoard", try_from = "CompactBoard")]
 pub struct GameBoard {
     /// The contents of the field positions. Dimensions are [`BOARD_DIM`] × [`BOARD_DIM`].
-    pub fields: [[FieldContent; BOARD_DIM]; BOARD_DIM],
+    /// Only gets changed with [`GameBoard::place_single_stone`], which keeps the score up to date.
+    fields: [[FieldContent; BOARD_DIM]; BOARD_DIM],
+    /// The amount of stones for red, green, blue, kept up to date with every placed or flipped stone.
+    /// It is not part of the compact form, but gets recounted on load.
+    score: [i8; NUM_OF_COLORS],
+}
+
+/// The serialized form of the [`GameBoard`], see [`GameBoard::serialize_compact`].
+#[derive(Serialize, Deserialize)]
+struct CompactBoard([u8; COMPACT_BOARD_SIZE]);
+
+impl From<GameBoard> for CompactBoard {
+    fn from(board: GameBoard) -> Self {
+        let compact = board.serialize_compact();
+        debug_assert_eq!(
+            GameBoard::deserialize_compact(&compact).as_ref(),
+            Ok(&board),
+            "Compact board does not reconstruct the board"
+        );
+        CompactBoard(compact)
+    }
+}
+
+impl TryFrom<CompactBoard> for GameBoard {
+    type Error = String;
+
+    fn try_from(compact: CompactBoard) -> Result<Self, Self::Error> {
+        GameBoard::deserialize_compact(&compact.0)
+    }
 }
 
 /// Indicates a direction in which we want to walk from a local direction.
@@ -119,7 +139,10 @@ impl GameBoard {
     /// Creates a new game board with empty fields.
     pub fn new() -> Self {
         let fields = [[FieldContent::Empty; BOARD_DIM]; BOARD_DIM];
-        GameBoard { fields }
+        GameBoard {
+            fields,
+            score: [0; NUM_OF_COLORS],
+        }
     }
 
     /// Puts the board into a start configuration.
@@ -138,6 +161,43 @@ impl GameBoard {
         self.fields[3][5] = Stone(Blue);
         self.fields[5][5] = Stone(Blue);
         self.fields[4][3] = Stone(Blue);
+        self.score = self.count_stones();
+    }
+
+    /// Packs the fields with 2 bits each (empty, red, green, blue), column by column with four fields per byte.
+    /// The score is left out, as it follows from the fields.
+    pub fn serialize_compact(&self) -> [u8; COMPACT_BOARD_SIZE] {
+        let mut result = [0; COMPACT_BOARD_SIZE];
+        for (index, field) in self.fields.iter().flatten().enumerate() {
+            let code = match field {
+                FieldContent::Empty => 0,
+                FieldContent::Stone(color) => *color as u8 + 1,
+            };
+            result[index / 4] |= code << (index % 4 * 2);
+        }
+        result
+    }
+
+    /// Restores a board from the form of [`GameBoard::serialize_compact`] and recounts the score.
+    /// Fails on set bits behind the last field, which no board produces.
+    pub fn deserialize_compact(bytes: &[u8; COMPACT_BOARD_SIZE]) -> Result<Self, String> {
+        use StoneColor::*;
+        let mut board = GameBoard::new();
+        for (index, field) in board.fields.iter_mut().flatten().enumerate() {
+            *field = match (bytes[index / 4] >> (index % 4 * 2)) & 0b11 {
+                0 => FieldContent::Empty,
+                1 => FieldContent::Stone(Red),
+                2 => FieldContent::Stone(Green),
+                3 => FieldContent::Stone(Blue),
+                _ => unreachable!(),
+            };
+        }
+        let used_bits = BOARD_DIM * BOARD_DIM % 4 * 2;
+        if used_bits > 0 && bytes[COMPACT_BOARD_SIZE - 1] >> used_bits != 0 {
+            return Err("Bits set behind the last field of the compact board".to_string());
+        }
+        board.score = board.count_stones();
+        Ok(board)
     }
 
     /// Gets the stone color of an indicated field as an option.
@@ -167,8 +227,14 @@ impl GameBoard {
             .collect()
     }
 
-    /// Gets the current stone amount  for red, green, blue.
+    /// Gets the current stone amount for red, green, blue. This is the cached value, so it is cheap every frame.
     pub fn get_score(&self) -> [i8; NUM_OF_COLORS] {
+        self.score
+    }
+
+    /// Counts the stones for red, green, blue by scanning the whole board. [`GameBoard::get_score`] returns the same
+    /// without the scan, this is the reference to initialize and check the cache.
+    pub fn count_stones(&self) -> [i8; NUM_OF_COLORS] {
         let mut result: [i8; NUM_OF_COLORS] = [0; NUM_OF_COLORS];
 
         for i in 0..BOARD_DIM {
@@ -241,14 +307,30 @@ impl GameBoard {
     }
 
     /// Places an indicated stone with the indicated color at a specific position, without any flipping operations.
+    /// A stone that was there before gets replaced, the score follows both.
     fn place_single_stone(&mut self, test_position: &FieldPosition, test_stone: StoneColor) {
-        self.fields[test_position.x_coord as usize][test_position.y_coord as usize] =
-            FieldContent::Stone(test_stone);
+        let field =
+            &mut self.fields[test_position.x_coord as usize][test_position.y_coord as usize];
+        if let FieldContent::Stone(previous) = *field {
+            self.score[previous as usize] -= 1;
+        }
+        *field = FieldContent::Stone(test_stone);
+        self.score[test_stone as usize] += 1;
     }
 
-    /// Sets a stone at the indicated position, assumes the move is valid. It performs all necessary flipping operations.
-    pub fn set_stone(&mut self, test_position: &FieldPosition, test_stone: StoneColor) {
-        debug_assert!(self.is_legal_move(test_position.clone(), test_stone));
+    /// Sets a stone at the indicated position and performs all necessary flipping operations.
+    /// An illegal move, like one off the board or onto an occupied field, is an error and leaves the board as it is.
+    pub fn set_stone(
+        &mut self,
+        test_position: &FieldPosition,
+        test_stone: StoneColor,
+    ) -> Result<(), String> {
+        if !self.is_legal_move(test_position.clone(), test_stone) {
+            return Err(format!(
+                "Illegal move for {:?} at {}, {}",
+                test_stone, test_position.x_coord, test_position.y_coord
+            ));
+        }
         for dir in SCAN_DIRECTIONS.iter() {
             let amount_of_flipped_stones =
                 self.get_potentially_flipped_stones(test_position.clone(), test_stone, dir);
@@ -259,6 +341,8 @@ impl GameBoard {
             }
         }
         self.place_single_stone(test_position, test_stone);
+        debug_assert_eq!(self.score, self.count_stones(), "Score cache out of sync");
+        Ok(())
     }
 
     /// Checks the amount of potentially flipped stones, if we place a stone of the indicated color at the indicated position.
@@ -301,3 +385,114 @@ impl GameBoard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use StoneColor::*;
+
+    /// Sends the board through postcard and back.
+    fn round_trip(board: &GameBoard) -> Result<GameBoard, postcard::Error> {
+        postcard::from_bytes(&postcard::to_stdvec(board)?)
+    }
+
+    #[test]
+    fn illegal_moves_are_an_error_and_leave_the_board() {
+        let mut board = GameBoard::new();
+        board.reset_board();
+        let start = board.clone();
+        for (x_coord, y_coord) in [(-1, 3), (3, BOARD_DIMS), (4, 4), (0, 0)] {
+            assert!(
+                board
+                    .set_stone(&FieldPosition { x_coord, y_coord }, Red)
+                    .is_err()
+            );
+            assert_eq!(board, start);
+        }
+    }
+
+    #[test]
+    fn cached_score_matches_a_recount() {
+        let mut board = GameBoard::new();
+        board.reset_board();
+        assert_eq!(board.get_score(), board.count_stones());
+        // Moves with their flips, as long as the colors find one.
+        for color in [Red, Green, Blue].into_iter().cycle().take(30) {
+            let Some(position) = board.get_all_legal_moves(color).pop() else {
+                continue;
+            };
+            let flipped = board.get_all_flipped_stones(position.clone(), color).len();
+            let before = board.get_score();
+            board.set_stone(&position, color).unwrap();
+            assert_eq!(board.get_score(), board.count_stones());
+            assert_eq!(
+                board.get_score()[color as usize],
+                before[color as usize] + flipped as i8 + 1
+            );
+        }
+        // A single stone replaced by another color.
+        let stone = board.get_stone_placement().remove(0);
+        let other = [Red, Green, Blue]
+            .into_iter()
+            .find(|color| *color != stone.stone_color)
+            .unwrap();
+        board.place_single_stone(&stone.field_position, other);
+        assert_eq!(board.get_score(), board.count_stones());
+
+        board.reset_board();
+        assert_eq!(board.get_score(), [3, 3, 3]);
+        assert_eq!(board.get_score(), board.count_stones());
+    }
+
+    #[test]
+    fn empty_board_round_trip() {
+        let board = GameBoard::new();
+        assert_eq!(round_trip(&board).unwrap(), board);
+    }
+
+    #[test]
+    fn full_board_round_trip() {
+        let mut board = GameBoard::new();
+        for (index, (x_coord, y_coord)) in
+            (0..BOARD_DIMS).cartesian_product(0..BOARD_DIMS).enumerate()
+        {
+            let color = [Red, Green, Blue][index % NUM_OF_COLORS];
+            board.place_single_stone(&FieldPosition { x_coord, y_coord }, color);
+        }
+        let received = round_trip(&board).unwrap();
+        assert_eq!(received, board);
+        assert_eq!(received.get_score(), [27, 27, 27]);
+    }
+
+    #[test]
+    fn mixed_colors_round_trip() {
+        let mut board = GameBoard::new();
+        board.reset_board();
+        board.place_single_stone(
+            &FieldPosition {
+                x_coord: 0,
+                y_coord: 0,
+            },
+            Blue,
+        );
+        board.place_single_stone(
+            &FieldPosition {
+                x_coord: 8,
+                y_coord: 8,
+            },
+            Green,
+        );
+        let received = round_trip(&board).unwrap();
+        assert_eq!(received, board);
+        assert_eq!(received.get_score(), [3, 4, 4]);
+    }
+
+    #[test]
+    fn bits_behind_the_last_field_get_rejected() {
+        let mut compact = GameBoard::new().serialize_compact();
+        compact[COMPACT_BOARD_SIZE - 1] |= 0b100;
+        assert!(GameBoard::deserialize_compact(&compact).is_err());
+        let bytes = postcard::to_stdvec(&CompactBoard(compact)).unwrap();
+        assert!(postcard::from_bytes::<GameBoard>(&bytes).is_err());
+    }
+}
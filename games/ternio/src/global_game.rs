@@ -10,7 +10,7 @@ use crate::network_logic::view_state::ViewState;
 use crate::render_system::gui::{AssignmentResult, PlayerAssignmentGui, StartupGui, StartupResult};
 use crate::render_system::media::{CELL_SIZE, Media};
 use backbone_lib::transport_layer::TransportLayer;
-use backbone_lib::transport_layer::ViewStateUpdate::{Full, Incremental};
+use backbone_lib::transport_layer::ViewStateUpdate::{Full, FullReset, Incremental, Tick};
 use macroquad::camera::Camera2D;
 use macroquad::input::{MouseButton, is_mouse_button_pressed, mouse_position};
 use macroquad::math::Vec2;
@@ -97,7 +97,7 @@ impl GlobalData {
         // First analyze the incoming messages.
         while let Some(result) = self.net_architecture.get_next_update() {
             match result {
-                Full(state) => {
+                Full(state) | FullReset(state) => {
                     self.view_state = state;
                     return;
                 }
@@ -116,9 +116,10 @@ impl GlobalData {
                     }
                     break;
                 }
-                Incremental(DeltaInformation::MakeMove(_)) => {
+                Incremental(DeltaInformation::MakeMove(..)) => {
                     panic!("We should not get a make move update in the setup phase.")
                 }
+                Tick => {}
             }
         }
 
@@ -170,7 +171,7 @@ impl GlobalData {
                 buffer.render(&self.media);
 
                 // It is not our turn.
-                if color != self.view_state.player_colors[player_id as usize] {
+                if color != self.view_state.color_of_player(player_id) {
                     return;
                 }
 
@@ -212,7 +213,7 @@ impl GlobalData {
         let mut update_presentation_state = false;
         while let Some(result) = self.net_architecture.get_next_update() {
             match result {
-                Full(state) => {
+                Full(state) | FullReset(state) => {
                     self.view_state = state;
                     update_presentation_state = true;
                 }
@@ -224,7 +225,7 @@ impl GlobalData {
                         self.media.play_game_over_sound();
                     }
                 }
-                Incremental(command @ DeltaInformation::MakeMove(_)) => {
+                Incremental(command @ DeltaInformation::MakeMove(..)) => {
                     // Here we have to store the move and prepare the animation.
                     self.presentation_state = PresentationState::Animating(TransitionBoard::new(
                         command,
@@ -245,6 +246,7 @@ impl GlobalData {
                     );
                     self.view_state.apply_delta(&command);
                 }
+                Tick => {}
             }
         }
 
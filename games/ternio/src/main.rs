@@ -10,7 +10,7 @@ mod render_system;
 use crate::global_game::{GlobalData, TEXT_POINT_STATUS_INFO, TernioSystem};
 use crate::network_logic::basic_commands::{GameState, RpcPayload};
 use crate::render_system::gui::gui_setup;
-use backbone_lib::transport_layer::{ConnectionState, TransportLayer};
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer, relay_url};
 use board_logic::board_and_transition::PresentationState;
 use macroquad::prelude::{
     BLACK, Camera2D, Conf, Rect, clear_background, get_frame_time, next_frame, set_camera,
@@ -47,8 +47,8 @@ async fn main() {
     set_camera(&camera);
 
     let net_architecture: TernioSystem = TransportLayer::generate_transport_layer(
-        "ws://127.0.0.1:8080/ws".to_string(),
-        // "wss://board-game-hub.de/api/ws".to_string(),
+        // For a deployment behind the reverse proxy, use the path "/api/ws".
+        relay_url("/ws", "ws://127.0.0.1:8080/ws"),
         "Ternio".to_string(),
     );
 
@@ -66,7 +66,9 @@ async fn main() {
             ConnectionState::Disconnected { error_string } => {
                 global_data.handle_login_screen(&error_string);
             }
-            ConnectionState::AwaitingHandshake | ConnectionState::ExecutingHandshake => {
+            ConnectionState::AwaitingHandshake
+            | ConnectionState::RetryingConnect { .. }
+            | ConnectionState::ExecutingHandshake => {
                 global_data
                     .media
                     .print_text("Connecting...", TEXT_POINT_STATUS_INFO);
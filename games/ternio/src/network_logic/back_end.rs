@@ -1,12 +1,19 @@
 //! The backend logic for ternio. All relevant game logic is concentrated here.
 
-use crate::board_logic::board_representation::NUM_OF_COLORS;
 use crate::board_logic::board_representation::StoneColor::Red;
+use crate::board_logic::board_representation::{NUM_OF_COLORS, StonePlacement};
 use crate::network_logic::basic_commands::GameState;
 use crate::network_logic::basic_commands::{DeltaInformation, RpcPayload};
 use crate::network_logic::view_state::ViewState;
 use backbone_lib::traits::BackendCommand::{Delta, SetTimer};
-use backbone_lib::traits::{BackEndArchitecture, BackendCommand};
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet};
+
+/// The timer that restarts the game after it ended.
+const RESTART_TIMER: u16 = 0;
+/// The timer that lets the AI make its move for a player that left.
+const AI_MOVE_TIMER: u16 = 1;
+/// The time in seconds the AI pretends to think, so the other players can follow its moves.
+const AI_THINKING_TIME: f32 = 1.0;
 
 /// The backend module for the transport layer.
 pub struct TernioLogic {
@@ -16,6 +23,46 @@ pub struct TernioLogic {
     view_state: ViewState,
     /// The names of the three players if set. This is only done once, even if the game restarts.
     player_names: [Option<String>; NUM_OF_COLORS],
+    /// Flags the players, indexed by player id, that left during the game and whose color the AI plays on.
+    ai_players: [bool; NUM_OF_COLORS],
+}
+
+impl TernioLogic {
+    /// Lets the AI think about its move, if it plays the color that is to move now.
+    fn schedule_ai_move(&mut self) {
+        if let Some(color) = self.view_state.game_state.current_move_color()
+            && self.ai_players[self.view_state.player_of_color(color)]
+        {
+            self.command_list.push(SetTimer {
+                timer_id: AI_MOVE_TIMER,
+                duration: AI_THINKING_TIME,
+            });
+        }
+    }
+
+    /// The AI greedily picks the move that flips the most stones. The move takes the same path as the one
+    /// of a human player, so it gets checked and continues the game alike.
+    fn make_ai_move(&mut self, players: &PlayerSet) {
+        let Some(color) = self.view_state.game_state.current_move_color() else {
+            return;
+        };
+        let player = self.view_state.player_of_color(color);
+        if !self.ai_players[player] {
+            return;
+        }
+        let board = &self.view_state.game_board;
+        let best_move = board
+            .get_all_legal_moves(color)
+            .into_iter()
+            .max_by_key(|position| board.get_all_flipped_stones(position.clone(), color).len());
+        if let Some(field_position) = best_move {
+            let placement = StonePlacement {
+                field_position,
+                stone_color: color,
+            };
+            self.inform_rpc(player as u16, RpcPayload::MakeMove(placement), players);
+        }
+    }
 }
 
 impl BackEndArchitecture<RpcPayload, DeltaInformation, ViewState> for TernioLogic {
@@ -25,6 +72,7 @@ impl BackEndArchitecture<RpcPayload, DeltaInformation, ViewState> for TernioLogi
             command_list: Vec::new(),
             view_state: ViewState::new(),
             player_names: [None, None, None],
+            ai_players: [false; NUM_OF_COLORS],
         }
     }
 
@@ -37,20 +85,43 @@ impl BackEndArchitecture<RpcPayload, DeltaInformation, ViewState> for TernioLogi
         }
     }
 
-    /// As soon as a player leaves, we terminate the room as we can not continue the game.
-    fn player_departure(&mut self, player_id: u16) {
-        // If our partner leaves, we cancel the room.
-        if player_id < NUM_OF_COLORS as u16 {
+    /// Ternio needs all three colors, so the amount of remaining players does not matter.
+    /// If a player leaves before all names are known, we terminate the room as there is no game yet.
+    /// Later on, an AI takes over the color of the player. Colors are bound to the player ids, so a
+    /// returning player gets a new id and is kicked like any surplus player.
+    fn player_departure(&mut self, player_id: u16, _players_remaining: u16) {
+        if player_id >= NUM_OF_COLORS as u16 {
+            return;
+        }
+        if self.view_state.game_state == GameState::AwaitingPlayers {
             self.command_list.push(BackendCommand::TerminateRoom);
+            return;
         }
+        self.ai_players[player_id as usize] = true;
+        self.command_list
+            .push(BackendCommand::AdoptPlayer { player: player_id });
+        self.command_list.push(BackendCommand::Log(format!(
+            "Player {} left, the AI plays on.",
+            player_id
+        )));
+        self.schedule_ai_move();
+    }
+
+    /// Ternio needs a player for every color.
+    fn min_players(&self) -> u16 {
+        NUM_OF_COLORS as u16
     }
 
     /// The different RPCs from he players with the indicated id get processed here.
     /// These are **SetPlayerName** for the name of a single player, **SetPlayerColors** to set
     /// all colors of all players, **MakeMove** to place a stone. The legality of actions is checked upfront.
-    fn inform_rpc(&mut self, player: u16, payload: RpcPayload) {
+    fn inform_rpc(&mut self, player: u16, payload: RpcPayload, _: &PlayerSet) {
         // Here we need to do a validity check.
         if !self.view_state.check_legal_execution(player, &payload) {
+            self.command_list.push(BackendCommand::RejectAction {
+                player,
+                reason: "Illegal action.".to_string(),
+            });
             return;
         }
         match payload {
@@ -77,9 +148,14 @@ impl BackEndArchitecture<RpcPayload, DeltaInformation, ViewState> for TernioLogi
                 let delta = DeltaInformation::SetGameState(GameState::Move(Red));
                 self.view_state.apply_delta(&delta);
                 self.command_list.push(Delta(delta));
+                self.schedule_ai_move();
             }
             RpcPayload::MakeMove(move_command) => {
-                let delta = DeltaInformation::MakeMove(move_command);
+                let flipped_stones = self.view_state.game_board.get_all_flipped_stones(
+                    move_command.field_position.clone(),
+                    move_command.stone_color,
+                );
+                let delta = DeltaInformation::MakeMove(move_command, flipped_stones);
                 self.view_state.apply_delta(&delta);
                 self.command_list.push(Delta(delta));
                 // Now we have to see how to continue.
@@ -88,17 +164,17 @@ impl BackEndArchitecture<RpcPayload, DeltaInformation, ViewState> for TernioLogi
                     .game_state
                     .current_move_color()
                     .expect("Should have been checked before.");
-                let next_phase = current_color
-                    .cycle_from_next()
-                    .into_iter()
-                    .find(|color| {
+                let mut turns = self.view_state.turn_tracker();
+                turns.set_turn(&current_color);
+                let next_phase = turns
+                    .advance_until(|color| {
                         !self
                             .view_state
                             .game_board
                             .get_all_legal_moves(*color)
                             .is_empty()
                     })
-                    .map(GameState::Move)
+                    .map(|color| GameState::Move(*color))
                     .unwrap_or(GameState::GameOver);
 
                 let delta = DeltaInformation::SetGameState(next_phase);
@@ -107,16 +183,21 @@ impl BackEndArchitecture<RpcPayload, DeltaInformation, ViewState> for TernioLogi
                 // Set the timer for restart.
                 if next_phase == GameState::GameOver {
                     self.command_list.push(SetTimer {
-                        timer_id: 0,
+                        timer_id: RESTART_TIMER,
                         duration: 15.0,
                     })
                 }
+                self.schedule_ai_move();
             }
         }
     }
 
-    /// There is only one timer, and that is the one that restarts the game after a game ending.
-    fn timer_triggered(&mut self, _: u16) {
+    /// One timer restarts the game after a game ending, the other one lets the AI move.
+    fn timer_triggered(&mut self, timer_id: u16, players: &PlayerSet) {
+        if timer_id == AI_MOVE_TIMER {
+            self.make_ai_move(players);
+            return;
+        }
         // Simply reset the game.
         self.view_state.reset();
         self.command_list.push(BackendCommand::ResetViewState);
@@ -130,3 +211,99 @@ impl BackEndArchitecture<RpcPayload, DeltaInformation, ViewState> for TernioLogi
         std::mem::take(&mut self.command_list)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::board_representation::StoneColor::{Blue, Green};
+    use backbone_lib::traits::ViewStateArchitecture;
+
+    /// Starts a game with three players, red moves first. Returns the host and the view state of a client, which
+    /// took over every delta of the host.
+    fn started_game() -> (TernioLogic, ViewState) {
+        let players: PlayerSet = (0..NUM_OF_COLORS as u16).collect();
+        let mut host = TernioLogic::new(0);
+        for player in 0..NUM_OF_COLORS as u16 {
+            host.player_arrival(player);
+            host.inform_rpc(
+                player,
+                RpcPayload::SetPlayerName(player.to_string()),
+                &players,
+            );
+        }
+        host.inform_rpc(0, RpcPayload::SetPlayerColors([Red, Green, Blue]), &players);
+        let mut client = ViewState::new();
+        for command in host.drain_commands() {
+            if let Delta(delta) = command {
+                client.try_apply(&delta).unwrap();
+            }
+        }
+        (host, client)
+    }
+
+    /// Lets red make its first legal move and returns the delta of the host.
+    fn first_move(host: &mut TernioLogic) -> DeltaInformation {
+        let players: PlayerSet = (0..NUM_OF_COLORS as u16).collect();
+        let field_position = host.view_state.game_board.get_all_legal_moves(Red)[0].clone();
+        let placement = StonePlacement {
+            field_position,
+            stone_color: Red,
+        };
+        host.inform_rpc(0, RpcPayload::MakeMove(placement), &players);
+        host.drain_commands()
+            .into_iter()
+            .find_map(|command| match command {
+                Delta(delta @ DeltaInformation::MakeMove(..)) => Some(delta),
+                _ => None,
+            })
+            .expect("No move from the host")
+    }
+
+    #[test]
+    fn client_animates_the_stones_the_host_flipped() {
+        let (mut host, mut client) = started_game();
+        let delta = first_move(&mut host);
+        let DeltaInformation::MakeMove(placement, flipped_stones) = &delta else {
+            unreachable!()
+        };
+        assert!(!flipped_stones.is_empty());
+
+        let before = client.game_board.clone();
+        client.try_apply(&delta).unwrap();
+        assert_eq!(client.game_board, host.view_state.game_board);
+        // Every stone that changed is one the client animates, apart from the new one.
+        for stone in before.get_stone_placement() {
+            let flip = flipped_stones
+                .iter()
+                .find(|flip| flip.field_position == stone.field_position);
+            let expected = flip.map_or(stone.stone_color, |flip| {
+                assert_eq!(flip.source_color, stone.stone_color);
+                flip.destination_color
+            });
+            assert_eq!(
+                client.game_board.select_field(&stone.field_position),
+                expected
+            );
+        }
+        assert_eq!(
+            client.game_board.select_field(&placement.field_position),
+            Red
+        );
+        assert_eq!(
+            client.game_board.get_stone_placement().len(),
+            before.get_stone_placement().len() + 1
+        );
+    }
+
+    #[test]
+    fn client_rejects_a_move_with_other_flips() {
+        let (mut host, client) = started_game();
+        let DeltaInformation::MakeMove(placement, mut flipped_stones) = first_move(&mut host)
+        else {
+            unreachable!()
+        };
+        flipped_stones.pop();
+        let delta = DeltaInformation::MakeMove(placement, flipped_stones);
+        assert!(client.clone().try_apply(&delta).is_err());
+    }
+}
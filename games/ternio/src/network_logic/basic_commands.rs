@@ -1,6 +1,8 @@
 //! The view state, delta and rpc logic.
 
-use crate::board_logic::board_representation::{NUM_OF_COLORS, StoneColor, StonePlacement};
+use crate::board_logic::board_representation::{
+    FlipInformation, NUM_OF_COLORS, StoneColor, StonePlacement,
+};
 use crate::network_logic::basic_commands::GameState::Move;
 use serde::{Deserialize, Serialize};
 
@@ -47,6 +49,7 @@ pub enum DeltaInformation {
     SetPlayerNames([String; NUM_OF_COLORS]),
     /// Sets the colors of the players.
     SetPlayerColors([StoneColor; NUM_OF_COLORS]),
-    /// Makes a move command.
-    MakeMove(StonePlacement),
+    /// Makes a move command, together with the stones the host flipped with it. The clients animate
+    /// exactly these stones instead of working them out on their own.
+    MakeMove(StonePlacement, Vec<FlipInformation>),
 }
@@ -3,20 +3,62 @@
 use crate::board_logic::board_representation::{GameBoard, NUM_OF_COLORS, StoneColor};
 use crate::network_logic::basic_commands::GameState::{AssigningPlayers, AwaitingPlayers, Move};
 use crate::network_logic::basic_commands::{DeltaInformation, GameState, RpcPayload};
+use backbone_lib::traits::ViewStateArchitecture;
+use backbone_lib::turn_tracker::TurnTracker;
 use serde::{Deserialize, Serialize};
 
+/// Checks that every player gets a different color, so the colors can be mapped back to the players.
+fn is_color_permutation(colors: &[StoneColor; NUM_OF_COLORS]) -> bool {
+    [StoneColor::Red, StoneColor::Green, StoneColor::Blue]
+        .iter()
+        .all(|color| colors.contains(color))
+}
+
+/// The view state on the wire. The inverse of the player colors does not travel, it gets rebuilt from the colors.
+#[derive(Deserialize)]
+struct ReceivedViewState {
+    game_board: GameBoard,
+    player_names: [String; NUM_OF_COLORS],
+    player_colors: [StoneColor; NUM_OF_COLORS],
+    game_state: GameState,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(try_from = "ReceivedViewState")]
 pub struct ViewState {
     /// The central game board.
     pub game_board: GameBoard,
     /// The name of the three players.
     pub player_names: [String; NUM_OF_COLORS],
-    /// The colors the players have.
-    pub player_colors: [StoneColor; NUM_OF_COLORS],
+    /// The colors the players have, indexed by player id.
+    player_colors: [StoneColor; NUM_OF_COLORS],
+    /// The inverse of the player colors: the player id that has the color, indexed by the color.
+    #[serde(skip)]
+    player_of_color: [usize; NUM_OF_COLORS],
     /// The overall state we are currently in.
     pub game_state: GameState,
 }
 
+impl TryFrom<ReceivedViewState> for ViewState {
+    type Error = String;
+
+    /// Rebuilds the inverse of the player colors, which only works if the players have different colors.
+    fn try_from(received: ReceivedViewState) -> Result<Self, Self::Error> {
+        if !is_color_permutation(&received.player_colors) {
+            return Err("Players share a color".to_string());
+        }
+        let mut view_state = ViewState {
+            game_board: received.game_board,
+            player_names: received.player_names,
+            player_colors: received.player_colors,
+            player_of_color: [0; NUM_OF_COLORS],
+            game_state: received.game_state,
+        };
+        view_state.set_player_colors(received.player_colors);
+        Ok(view_state)
+    }
+}
+
 impl ViewState {
     /// We start by awaiting players.
     pub fn new() -> Self {
@@ -26,17 +68,42 @@ impl ViewState {
             game_board,
             player_names: [String::from(""), String::from(""), String::from("")],
             player_colors: [StoneColor::Red, StoneColor::Green, StoneColor::Blue],
+            player_of_color: [0, 1, 2],
             game_state: AwaitingPlayers,
         }
     }
 
+    /// The color of the indicated player.
+    pub fn color_of_player(&self, player_id: u16) -> StoneColor {
+        self.player_colors[player_id as usize]
+    }
+
+    /// The player that has the indicated color.
+    pub fn player_of_color(&self, color: StoneColor) -> usize {
+        self.player_of_color[color as usize]
+    }
+
+    /// Assigns the colors to the players, indexed by player id, and updates the inverse mapping.
+    /// The colors have to be a permutation of the stone colors.
+    fn set_player_colors(&mut self, colors: [StoneColor; NUM_OF_COLORS]) {
+        self.player_colors = colors;
+        for (player, color) in colors.iter().enumerate() {
+            self.player_of_color[*color as usize] = player;
+        }
+    }
+
     /// Asks for the player names in the sequence of the player colors rgb.
     pub fn get_player_names_in_rgb_sequence(&self) -> [String; NUM_OF_COLORS] {
         use StoneColor::*;
-        [Red, Green, Blue].map(|color| {
-            let idx = self.player_colors.iter().position(|c| *c == color).unwrap();
-            self.player_names[idx].clone()
-        })
+        [Red, Green, Blue].map(|color| self.player_names[self.player_of_color(color)].clone())
+    }
+
+    /// The turn order of the colors rgb, each with the player that has the color.
+    pub fn turn_tracker(&self) -> TurnTracker<StoneColor> {
+        use StoneColor::*;
+        TurnTracker::new(
+            [Red, Green, Blue].map(|color| (color, self.player_of_color(color) as u16)),
+        )
     }
 
     /// The reset recreates the game board but leaves nicknames intact. We start again by reassigning players.
@@ -54,11 +121,13 @@ impl ViewState {
         }
         match rpc_payload {
             RpcPayload::SetPlayerName(_) => self.game_state == AwaitingPlayers,
-            RpcPayload::SetPlayerColors(_) => {
-                player_id == 0 && (self.game_state == AssigningPlayers)
+            RpcPayload::SetPlayerColors(colors) => {
+                player_id == 0
+                    && (self.game_state == AssigningPlayers)
+                    && is_color_permutation(colors)
             }
             RpcPayload::MakeMove(move_command) => {
-                (self.player_colors[player_id as usize] == move_command.stone_color)
+                (self.color_of_player(player_id) == move_command.stone_color)
                     && (self.game_board.is_legal_move(
                         move_command.field_position.clone(),
                         move_command.stone_color,
@@ -69,7 +138,8 @@ impl ViewState {
     }
 
     /// Applies a known information coming from the server. This is game state changing, player names or
-    /// color changing or making a move.
+    /// color changing or making a move. An illegal move gets ignored, so a hostile or buggy host
+    /// can not crash the client.
     pub fn apply_delta(&mut self, delta: &DeltaInformation) {
         match delta {
             DeltaInformation::SetGameState(game_state) => {
@@ -79,11 +149,129 @@ impl ViewState {
                 self.player_names = names.clone();
             }
             DeltaInformation::SetPlayerColors(colors) => {
-                self.player_colors = *colors;
+                self.set_player_colors(*colors);
+            }
+            DeltaInformation::MakeMove(move_command, _) => {
+                // The board stays as it is on an illegal move.
+                let _ = self
+                    .game_board
+                    .set_stone(&move_command.field_position, move_command.stone_color);
+            }
+        }
+    }
+}
+
+/// Validation of the deltas the host sends, before they get applied on a remote client.
+impl ViewStateArchitecture<RpcPayload, DeltaInformation> for ViewState {
+    /// Names and colors may only be set in their setup phase, colors have to be a permutation
+    /// of the stone colors and moves have to be legal for the color that is currently moving. The flipped stones a
+    /// move carries have to be the ones the board flips, so the animation never shows something else.
+    fn try_apply(&mut self, delta: &DeltaInformation) -> Result<(), String> {
+        let is_legal = match delta {
+            DeltaInformation::SetGameState(_) => true,
+            DeltaInformation::SetPlayerNames(_) => self.game_state == AwaitingPlayers,
+            DeltaInformation::SetPlayerColors(colors) => {
+                self.game_state == AssigningPlayers && is_color_permutation(colors)
+            }
+            // The board does not simply take over the carried stones: the recount of the flips is what keeps the board
+            // of the client equal to the one of the host, and on a board this small it costs next to nothing.
+            DeltaInformation::MakeMove(move_command, flipped_stones) => {
+                matches!(self.game_state, Move(stone_color) if move_command.stone_color == stone_color)
+                    && self.game_board.is_legal_move(
+                        move_command.field_position.clone(),
+                        move_command.stone_color,
+                    )
+                    && *flipped_stones
+                        == self.game_board.get_all_flipped_stones(
+                            move_command.field_position.clone(),
+                            move_command.stone_color,
+                        )
             }
-            DeltaInformation::MakeMove(move_command) => self
-                .game_board
-                .set_stone(&move_command.field_position, move_command.stone_color),
+        };
+        if !is_legal {
+            return Err("Delta does not fit the current game state".to_string());
+        }
+        self.apply_delta(delta);
+        Ok(())
+    }
+
+    fn is_action_legal(&self, player: u16, payload: &RpcPayload) -> bool {
+        self.check_legal_execution(player, payload)
+    }
+
+    /// The cached score matches the stones on the board and the colors map to the players and back.
+    fn debug_assert_valid(&self) {
+        assert_eq!(
+            self.game_board.get_score(),
+            self.game_board.count_stones(),
+            "Score does not match the stones on the board"
+        );
+        assert!(
+            is_color_permutation(&self.player_colors),
+            "Players share a color"
+        );
+        for (player, color) in self.player_colors.iter().enumerate() {
+            assert_eq!(
+                self.player_of_color[*color as usize], player,
+                "Color mapping out of sync"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::board_representation::{BOARD_DIMS, FieldPosition, StonePlacement};
+    use StoneColor::*;
+    use backbone_lib::codec::{ActiveCodec, WireCodec};
+
+    /// Sends the view state over the wire and back.
+    fn round_trip(view_state: &ViewState) -> Result<ViewState, String> {
+        ActiveCodec::decode(&ActiveCodec::encode(view_state)?)
+    }
+
+    #[test]
+    fn reassigned_colors_map_back_after_a_round_trip() {
+        let mut view_state = ViewState::new();
+        view_state.player_names = ["Ann".into(), "Bob".into(), "Cid".into()];
+        view_state.game_state = AssigningPlayers;
+        view_state
+            .try_apply(&DeltaInformation::SetPlayerColors([Blue, Red, Green]))
+            .unwrap();
+
+        let received = round_trip(&view_state).unwrap();
+        received.debug_assert_valid();
+        assert_eq!(received.player_of_color(Red), 1);
+        assert_eq!(received.player_of_color(Green), 2);
+        assert_eq!(received.player_of_color(Blue), 0);
+        assert_eq!(
+            received.get_player_names_in_rgb_sequence(),
+            ["Bob", "Cid", "Ann"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn shared_colors_get_rejected_on_receive() {
+        let mut view_state = ViewState::new();
+        view_state.player_colors = [Red, Red, Blue];
+        assert!(round_trip(&view_state).is_err());
+    }
+
+    #[test]
+    fn moves_off_the_board_get_rejected() {
+        let mut view_state = ViewState::new();
+        view_state.game_state = Move(Red);
+        let start = view_state.game_board.clone();
+        for (x_coord, y_coord) in [(-1, 0), (0, BOARD_DIMS), (i8::MAX, i8::MIN)] {
+            let placement = StonePlacement {
+                field_position: FieldPosition { x_coord, y_coord },
+                stone_color: Red,
+            };
+            let delta = DeltaInformation::MakeMove(placement, Vec::new());
+            assert!(view_state.try_apply(&delta).is_err());
+            view_state.apply_delta(&delta);
+            assert_eq!(view_state.game_board, start);
         }
     }
 }
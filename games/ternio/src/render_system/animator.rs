@@ -2,6 +2,7 @@
 
 use crate::board_logic::board_representation::{FlipInformation, StonePlacement};
 use crate::render_system::media::{CELL_SIZE, STONE_RADIUS, draw_game_board, get_stone_color};
+use backbone_lib::timeline::{Timeline, linear, smoothstep};
 use macroquad::shapes::{draw_circle, draw_ellipse};
 use std::f32::consts::PI;
 
@@ -21,14 +22,8 @@ pub struct Animator {
     flipping_stones: Vec<FlipInformation>,
     /// The stone that gets newly placed
     materializing_place: StonePlacement,
-    /// The time passed in the animation.
-    time_passed: f32,
-}
-
-/// A smoothstep function in the range of 0..1 with vanishing derivatives at the extrema.
-fn smoothstep_normalized(t: f32) -> f32 {
-    let t = t.clamp(0.0, 1.0);
-    t * t * (3.0 - 2.0 * t)
+    /// Runs from 0 to 1 while the new stone materializes and from 1 to 2 while the enclosed stones flip.
+    timeline: Timeline,
 }
 
 impl Animator {
@@ -50,14 +45,15 @@ impl Animator {
             static_stones,
             flipping_stones,
             materializing_place,
-            time_passed: 0.0,
+            timeline: Timeline::new(0.0)
+                .then(TIME_FOR_SCALING, 1.0, smoothstep)
+                .then(TIME_FOR_FLIPPING, 2.0, linear),
         }
     }
 
     /// Does an update and returns if the animation is over.
     pub fn update(&mut self, delta_time: f32) -> bool {
-        self.time_passed += delta_time;
-        self.time_passed > (TIME_FOR_SCALING + TIME_FOR_FLIPPING)
+        self.timeline.advance(delta_time)
     }
 
     /// Draws the materializing stone with the indicated radius.
@@ -74,9 +70,10 @@ impl Animator {
     /// In pase a the newly placed stone materializes at its position and in phase 2 the
     /// flipping stones are animated into their new position.
     pub fn render(&self) {
+        let phase = self.timeline.value();
         // See if we are in materializing phase.
-        if self.time_passed < TIME_FOR_SCALING {
-            let size = smoothstep_normalized(self.time_passed / TIME_FOR_SCALING) * STONE_RADIUS;
+        if phase < 1.0 {
+            let size = phase * STONE_RADIUS;
             draw_game_board(&self.all_stones);
             self.draw_marked_stone_with_radius(size);
         } else {
@@ -84,7 +81,7 @@ impl Animator {
             // Draw the newly set stone.
             self.draw_marked_stone_with_radius(STONE_RADIUS);
 
-            let flipping_phase = (self.time_passed - TIME_FOR_SCALING) / TIME_FOR_FLIPPING;
+            let flipping_phase = phase - 1.0;
             let first_half = flipping_phase < 0.5;
 
             let x_scaling = STONE_RADIUS * (flipping_phase * PI).cos().abs();
@@ -20,10 +20,13 @@ use crate::tic_tac_toe_logic::backend::TicTacToeLogic;
 use crate::tic_tac_toe_logic::traits_implementation::{
     GameState, StonePlacement, ViewState, ViewStateDelta,
 };
-use backbone_lib::transport_layer::{ConnectionState, TransportLayer, ViewStateUpdate};
+use backbone_lib::traits::ViewStateArchitecture;
+use backbone_lib::transport_layer::{
+    ConnectionState, JoinRejection, TransportLayer, ViewStateUpdate, relay_url,
+};
 use macroquad::prelude::{
-    BLACK, Camera2D, Conf, MouseButton, Rect, Vec2, clear_background, get_frame_time,
-    is_mouse_button_pressed, mouse_position, next_frame, set_camera,
+    BLACK, Camera2D, Conf, KeyCode, MouseButton, Rect, Vec2, clear_background, get_frame_time,
+    is_key_pressed, is_mouse_button_pressed, mouse_position, next_frame, set_camera,
 };
 
 /// Configures window title and size.
@@ -52,8 +55,8 @@ async fn main() {
         TicTacToeLogic,
         ViewState,
     > = TransportLayer::generate_transport_layer(
-        "ws://127.0.0.1:8080/ws".to_string(),
-        // "wss://board-game-hub.de/api/ws".to_string(),
+        // For a deployment behind the reverse proxy, use the path "/api/ws".
+        relay_url("/ws", "ws://127.0.0.1:8080/ws"),
         "tic-tac-toe".to_string(),
     );
 
@@ -70,6 +73,16 @@ async fn main() {
         let state = net_architecture.connection_state().clone();
         match state {
             ConnectionState::Disconnected { error_string } => {
+                let error_string = match net_architecture.join_rejection() {
+                    Some(JoinRejection::SpectatorsFull) => {
+                        Some("All spectator slots of this room are taken.".to_string())
+                    }
+                    Some(JoinRejection::GameFull) => Some("This game is already full.".to_string()),
+                    Some(JoinRejection::Banned) => {
+                        Some("You were kicked from this room, try again later.".to_string())
+                    }
+                    None => error_string,
+                };
                 let start_up = start_up_gui.handle_start_up(&error_string);
 
                 match start_up {
@@ -84,9 +97,14 @@ async fn main() {
 
                 view_state = None;
             }
-            ConnectionState::AwaitingHandshake | ConnectionState::ExecutingHandshake => {
+            ConnectionState::AwaitingHandshake
+            | ConnectionState::RetryingConnect { .. }
+            | ConnectionState::ExecutingHandshake => {
                 graphics.print_text("Connecting", Vec2 { x: 200.0, y: 350.0 }, 24)
             }
+            ConnectionState::Connected { .. } if !net_architecture.has_initial_sync() => {
+                graphics.print_text("Syncing", Vec2 { x: 200.0, y: 350.0 }, 24)
+            }
             ConnectionState::Connected {
                 is_server: _,
                 player_id,
@@ -120,17 +138,21 @@ fn update_real_game(
     // We do not have any animations here, so we simply drain the commands.
     while let Some(update) = transport_layer.get_next_update() {
         match update {
-            ViewStateUpdate::Full(state) => {
+            ViewStateUpdate::Full(state) | ViewStateUpdate::FullReset(state) => {
                 *view_state = state;
             }
             ViewStateUpdate::Incremental(delta) => {
                 view_state.apply_delta(&delta);
             }
+            ViewStateUpdate::Tick => {}
         }
     }
 
-    let my_turn = ((local_player == 0) && view_state.next_move_host)
-        || ((local_player == 1) && (!view_state.next_move_host));
+    // Only the host knows who is present, it holds back its first stone until the opponent arrived.
+    let awaiting_opponent = (local_player == 0) && !transport_layer.enough_players();
+    let my_turn = (((local_player == 0) && view_state.next_move_host)
+        || ((local_player == 1) && (!view_state.next_move_host)))
+        && !awaiting_opponent;
 
     let text = match view_state.check_winning() {
         GameState::CrossWins => "Cross wins",
@@ -139,6 +161,8 @@ fn update_real_game(
         GameState::Pending => {
             if local_player > 1 {
                 "Spectator"
+            } else if awaiting_opponent {
+                "Awaiting opponent"
             } else if my_turn {
                 "Your turn"
             } else {
@@ -148,6 +172,13 @@ fn update_real_game(
     };
 
     graphics.print_text_centered(text, Vec2 { x: 200.0, y: 350.0 }, 24);
+    // The host may start the rematch right away instead of waiting for the restart timer.
+    if (local_player == 0) && (view_state.check_winning() != GameState::Pending) {
+        graphics.print_text_centered("Press R for a rematch", Vec2 { x: 200.0, y: 380.0 }, 16);
+        if is_key_pressed(KeyCode::R) {
+            transport_layer.request_reset();
+        }
+    }
     // Now we draw the board.
     graphics.draw_base_board();
     for x in 0..3 {
@@ -175,7 +206,7 @@ fn update_real_game(
                 column: x_pos as u8,
                 row: y_pos as u8,
             };
-            if view_state.check_legality(&command, local_player) {
+            if view_state.is_action_legal(local_player, &command) {
                 transport_layer.register_server_rpc(command);
             }
         }
@@ -4,7 +4,7 @@
 use crate::tic_tac_toe_logic::traits_implementation::{
     GameState, StonePlacement, ViewState, ViewStateDelta,
 };
-use backbone_lib::traits::{BackEndArchitecture, BackendCommand};
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet};
 
 /// The backend logic of tic-tac-toe is contained here,
 pub struct TicTacToeLogic {
@@ -53,19 +53,28 @@ impl BackEndArchitecture<StonePlacement, ViewStateDelta, ViewState> for TicTacTo
     }
 
     /// If player 1, the main playing partner left, the game ends,
-    fn player_departure(&mut self, player: u16) {
+    fn player_departure(&mut self, player: u16, _players_remaining: u16) {
         if player == 1 {
             self.command_list.push(BackendCommand::TerminateRoom);
         }
     }
 
+    /// The host and the playing partner.
+    fn min_players(&self) -> u16 {
+        2
+    }
+
     /// Check move for legality and if the game finished set the timer for restart.
-    fn inform_rpc(&mut self, player_id: u16, payload: StonePlacement) {
+    fn inform_rpc(&mut self, player_id: u16, payload: StonePlacement, _: &PlayerSet) {
         if self.view_state.game_state != GameState::Pending {
             return;
         }
-        // Returns illegal commands.
+        // Rejects illegal commands.
         if !self.view_state.check_legality(&payload, player_id) {
+            self.command_list.push(BackendCommand::RejectAction {
+                player: player_id,
+                reason: "Illegal move.".to_string(),
+            });
             return;
         }
         let delta = ViewStateDelta {
@@ -84,7 +93,15 @@ impl BackEndArchitecture<StonePlacement, ViewStateDelta, ViewState> for TicTacTo
     }
 
     /// The timers gets triggered when the game should restart.
-    fn timer_triggered(&mut self, _: u16) {
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {
+        self.is_host_starting = !self.is_host_starting;
+        self.reset_game();
+    }
+
+    /// The host wants a rematch right away, so we do not wait for the restart timer.
+    fn on_host_reset_request(&mut self) {
+        self.command_list
+            .push(BackendCommand::CancelTimer { timer_id: 0 });
         self.is_host_starting = !self.is_host_starting;
         self.reset_game();
     }
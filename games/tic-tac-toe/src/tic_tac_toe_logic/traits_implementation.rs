@@ -3,7 +3,10 @@
 //! - [`ViewState`]: The complete representation of the game board.
 //! - [`ViewStateDelta`]: The delta information to update the game board.
 //! - [`StonePlacement`]: The information of where a tone gets placed. The type of stone is extracted from the player id.
+//!
+//! The view state also implements [`Diffable`], as an alternative to the hand-authored deltas of the backend.
 
+use backbone_lib::traits::{Diffable, ViewStateArchitecture};
 use serde::{Deserialize, Serialize};
 
 /// The delta information for the view state.
@@ -63,14 +66,30 @@ impl ViewState {
         }
     }
 
-    /// Applies a change to the game board.
+    /// The field at the indicated position, if it is on the board.
+    fn field(&self, row: u8, column: u8) -> Option<u8> {
+        self.board
+            .get(row as usize)
+            .and_then(|row| row.get(column as usize))
+            .copied()
+    }
+
+    /// Applies a change to the game board. A delta for a field off the board gets ignored,
+    /// so a hostile or buggy host can not crash the client.
     pub fn apply_delta(&mut self, delta: &ViewStateDelta) {
-        self.board[delta.row as usize][delta.column as usize] = if delta.is_circle { 2 } else { 1 };
+        let Some(field) = self
+            .board
+            .get_mut(delta.row as usize)
+            .and_then(|row| row.get_mut(delta.column as usize))
+        else {
+            return;
+        };
+        *field = if delta.is_circle { 2 } else { 1 };
         self.next_move_host = !self.next_move_host;
         self.game_state = self.check_winning();
     }
 
-    /// Checks if the move is legal. This is if it is the correct players turn and the field is still free.
+    /// Checks if the move is legal. This is if it is the correct players turn and the field is on the board and still free.
     pub fn check_legality(&self, move_data: &StonePlacement, player_id: u16) -> bool {
         if player_id > 1 {
             return false;
@@ -78,10 +97,7 @@ impl ViewState {
         if (player_id == 0) != self.next_move_host {
             return false;
         }
-        if self.board[move_data.row as usize][move_data.column as usize] != 0 {
-            return false;
-        }
-        true
+        self.field(move_data.row, move_data.column) == Some(0)
     }
 
     /// Does a winning check with the player stone in probe handed over.
@@ -109,3 +125,182 @@ impl ViewState {
         GameState::Pending
     }
 }
+
+/// Validation of deltas coming from the host before they get applied on a remote client.
+impl ViewStateArchitecture<StonePlacement, ViewStateDelta> for ViewState {
+    /// A delta is only accepted while the game is pending, for the player whose turn it is
+    /// and on a free field on the board.
+    fn try_apply(&mut self, delta: &ViewStateDelta) -> Result<(), String> {
+        if self.game_state != GameState::Pending {
+            return Err("Move received after game over".to_string());
+        }
+        if delta.is_circle != self.next_move_host {
+            return Err("Move received for wrong player".to_string());
+        }
+        if self.field(delta.row, delta.column) != Some(0) {
+            return Err("Move received for illegal field".to_string());
+        }
+        self.apply_delta(delta);
+        Ok(())
+    }
+
+    fn is_action_legal(&self, player: u16, payload: &StonePlacement) -> bool {
+        self.check_legality(payload, player)
+    }
+
+    /// At most one player wins, the players alternate, so their stone counts differ by at most one,
+    /// and the stored game state matches the board.
+    fn debug_assert_valid(&self) {
+        assert!(
+            !(self.check_for(1) && self.check_for(2)),
+            "Cross and circle both won"
+        );
+        let count = |probe: u8| self.board.iter().flatten().filter(|x| **x == probe).count();
+        assert!(
+            count(1).abs_diff(count(2)) <= 1,
+            "Stone counts of the players drifted apart"
+        );
+        assert!(
+            self.game_state == self.check_winning(),
+            "Game state does not match the board"
+        );
+    }
+}
+
+/// Derives the moves between two boards. Stones never vanish during a game, so only new stones count.
+/// They are reported alternating between the players, starting with the one whose turn it is,
+/// so that applying them in order reproduces the other board.
+impl Diffable<ViewStateDelta> for ViewState {
+    fn diff(&self, other: &ViewState) -> Vec<ViewStateDelta> {
+        let (mut circles, mut crosses): (Vec<ViewStateDelta>, Vec<ViewStateDelta>) = (0..3)
+            .flat_map(|row| (0..3).map(move |column| (row, column)))
+            .filter(|&(row, column)| self.board[row][column] == 0 && other.board[row][column] != 0)
+            .map(|(row, column)| ViewStateDelta {
+                is_circle: other.board[row][column] == 2,
+                column: column as u8,
+                row: row as u8,
+            })
+            .partition(|delta| delta.is_circle);
+
+        // The host places circles.
+        let (mut next, mut after) = if self.next_move_host {
+            (circles.drain(..), crosses.drain(..))
+        } else {
+            (crosses.drain(..), circles.drain(..))
+        };
+        let mut result = Vec::new();
+        while let Some(delta) = next.next() {
+            result.push(delta);
+            std::mem::swap(&mut next, &mut after);
+        }
+        result.extend(after);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backbone_lib::traits::{BackendCommand, commit_view_state};
+
+    /// A move of the player whose turn it is on the start board.
+    fn delta(row: u8, column: u8) -> ViewStateDelta {
+        ViewStateDelta {
+            is_circle: true,
+            column,
+            row,
+        }
+    }
+
+    #[test]
+    fn delta_off_the_board_gets_rejected() {
+        for (row, column) in [(3, 0), (0, 3), (255, 255)] {
+            let mut view_state = ViewState::new(true);
+            assert!(view_state.try_apply(&delta(row, column)).is_err());
+            assert_eq!(view_state.board, ViewState::new(true).board);
+        }
+    }
+
+    #[test]
+    fn delta_off_the_board_leaves_the_view_state_as_it_is() {
+        let mut view_state = ViewState::new(true);
+        view_state.apply_delta(&delta(7, 1));
+        assert_eq!(view_state.board, ViewState::new(true).board);
+        assert!(view_state.next_move_host);
+        // The next regular move still works.
+        view_state.try_apply(&delta(2, 1)).unwrap();
+        assert_eq!(view_state.board[2][1], 2);
+    }
+
+    /// The hand-authored way of the backend: the player whose turn it is places a stone.
+    fn place(view_state: &mut ViewState, row: u8, column: u8) -> ViewStateDelta {
+        let delta = ViewStateDelta {
+            is_circle: view_state.next_move_host,
+            column,
+            row,
+        };
+        view_state.apply_delta(&delta);
+        delta
+    }
+
+    /// The delta as a comparable tuple of circle flag, row and column.
+    fn key(delta: &ViewStateDelta) -> (bool, u8, u8) {
+        (delta.is_circle, delta.row, delta.column)
+    }
+
+    #[test]
+    fn diffed_delta_matches_hand_authored_one() {
+        let mut view_state = ViewState::new(false);
+        for (row, column) in [(1, 1), (0, 2), (2, 0)] {
+            let before = view_state.clone();
+            let hand_authored = place(&mut view_state, row, column);
+            let diffed: Vec<_> = before.diff(&view_state).iter().map(key).collect();
+            assert_eq!(diffed, [key(&hand_authored)]);
+        }
+    }
+
+    #[test]
+    fn several_moves_replay_to_the_new_state() {
+        for is_host_starting in [true, false] {
+            let start = ViewState::new(is_host_starting);
+            let mut target = start.clone();
+            for (row, column) in [(0, 0), (1, 1), (2, 2), (0, 1)] {
+                place(&mut target, row, column);
+            }
+
+            let diffed = start.diff(&target);
+            assert_eq!(diffed.len(), 4);
+            let mut replayed = start.clone();
+            for delta in &diffed {
+                replayed.try_apply(delta).unwrap();
+            }
+            assert_eq!(replayed.board, target.board);
+            assert_eq!(replayed.next_move_host, target.next_move_host);
+        }
+    }
+
+    #[test]
+    fn commit_enqueues_deltas_and_takes_over_the_state() {
+        let mut view_state = ViewState::new(true);
+        let mut command_list: Vec<BackendCommand<ViewStateDelta>> = Vec::new();
+
+        let mut next = view_state.clone();
+        let hand_authored = place(&mut next, 0, 0);
+        assert_eq!(
+            commit_view_state(&mut view_state, next.clone(), &mut command_list),
+            1
+        );
+        assert_eq!(view_state.board, next.board);
+        assert!(matches!(
+            command_list.as_slice(),
+            [BackendCommand::Delta(delta)] if key(delta) == key(&hand_authored)
+        ));
+
+        // Nothing changed, nothing to send.
+        assert_eq!(
+            commit_view_state(&mut view_state, next, &mut command_list),
+            0
+        );
+        assert_eq!(command_list.len(), 1);
+    }
+}
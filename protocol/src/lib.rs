@@ -1,6 +1,21 @@
 //! The ids for messages that we use. They will be used consistent across the server and the client.
-//! Also contains the protocol structure for joining a game.
+//! Also contains the protocol structure for joining a game and the text form of frames for the JSON debugging mode.
+//!
+//! The crate is `no_std`. The constants need nothing at all, [`JoinRequest`], the ids [`GameId`], [`RoomId`] and
+//! [`CompoundRoomId`] and the debug text helpers need the default feature `alloc`, which also pulls in serde.
 
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::format;
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
 use serde::{Deserialize, Serialize};
 
 /// The buffer sizes for the channels for intra VPS communication.
@@ -18,29 +33,85 @@ pub const CLIENT_DISCONNECTS: u8 = 1;
 /// The disconnect client message size (Header + Client Id) (u8 + u16)
 pub const CLIENT_DISCONNECT_MSG_SIZE: usize = 3;
 
-/// Client -> Server RPC followed by u16 Clientid, followed by payload from postcard or other coding.  (Client->Server)
+/// Client -> Server RPC followed by u16 Clientid, the u32 RPC sequence number and the payload from postcard or other coding.
+/// (Client->Server) The client counts the sequence number up with every RPC it sends and sends the message without the
+/// client id, the relay injects it. The host acknowledges the processed RPCs with [`RPC_ACK`].
 pub const SERVER_RPC: u8 = 2;
 
+/// The size of the RPC sequence number of a [`SERVER_RPC`] and an [`RPC_ACK`]. (u32)
+pub const RPC_SEQUENCE_SIZE: usize = 4;
+
+/// The maximum size in bytes of the payload of a [`SERVER_RPC`] a client may send, not counting the command byte
+/// and the RPC sequence number.
+/// RPCs are meant to be small actions, the relay rejects larger ones before they reach the host.
+pub const MAX_RPC_PAYLOAD_SIZE: usize = 4096;
+
 /// The disconnection message that is used for disconnecting without any arguments, that gets passed through the web socket layer.
 pub const CLIENT_DISCONNECTS_SELF: u8 = 3;
 
+/// The relay reports the backlog of the room (Relay->Server), followed by u16 with the most messages
+/// any client has not picked up yet. Only sent when the value changed.
+pub const CLIENT_BACKLOG: u8 = 4;
+/// The client backlog message size (Header + Backlog) (u8 + u16)
+pub const CLIENT_BACKLOG_MSG_SIZE: usize = 3;
+
+/// The interval in milliseconds in which the relay samples the backlog of a room.
+pub const BACKLOG_REPORT_INTERVAL_MILLIS: u64 = 1000;
+
+/// A client sets its display name, followed by the name as UTF-8 of at most [`MAX_DISPLAY_NAME_SIZE`] bytes.
+/// Like for [`SERVER_RPC`], the relay injects the u16 client id after the command byte before forwarding it to the host.
+/// The host also receives [`SERVER_ERROR`] and [`HAND_SHAKE_RESPONSE`], so the id must not collide with those.
+pub const SET_NAME: u8 = 13;
+
+/// The maximum size in bytes of a display name, see [`SET_NAME`] and [`PLAYER_NAME`]. The relay ends a connection
+/// that sends a longer one.
+pub const MAX_DISPLAY_NAME_SIZE: usize = 64;
+
+/// The maximum size in bytes of a line in the lobby chat of the relay, which is a text frame outside of the rooms.
+pub const MAX_CHAT_MESSAGE_SIZE: usize = 512;
+
+/// A client flags whether it is ready to start the game, followed by a u8 that is 1 for ready and 0 for not ready.
+/// The relay keeps track of the readiness of all clients of the room and reports it to the host as [`READY_STATE`],
+/// the message itself is not forwarded.
+pub const READY: u8 = 14;
+/// The ready message size (Header + Flag) (u8 + u8)
+pub const READY_MSG_SIZE: usize = 2;
+
+/// The relay reports the readiness of the clients (Relay->Server), followed by a u16 with the amount of ready clients
+/// and a u16 with the amount of all clients in the room. The host is not included. Only sent when a value changed and
+/// not before the first client sent [`READY`], until then no client is ready.
+/// Clients that join start as not ready, clients that leave are no longer counted.
+pub const READY_STATE: u8 = 15;
+/// The ready state message size (Header + Ready + Total) (u8 + u16 + u16)
+pub const READY_STATE_MSG_SIZE: usize = 5;
+
+/// A client asks for a fresh full state, e.g. if it suspects its view to be stale. Like for [`SERVER_RPC`],
+/// the relay injects the u16 client id after the command byte before forwarding it to the host, which answers
+/// with [`FULL_UPDATE_TO`].
+pub const REQUEST_SYNC: u8 = 18;
+/// The request sync message size as sent by the client (Header) (u8)
+pub const REQUEST_SYNC_MSG_SIZE: usize = 1;
+
 // Server -> Client
 
-/// The server disconnects from the game and the room gets closed.
+/// The server disconnects from the game and the room gets closed. Optionally followed by a u8 with the
+/// [`HostDeparture`] code, a host that sends none ended the game. The relay forwards it to the clients always with the code.
 pub const SERVER_DISCONNECTS: u8 = 0;
-/// The disconnection message is just the byte itself.
-pub const SERVER_DISCONNECT_MSG_SIZE: usize = 1;
+/// The disconnection message size the relay sends (Header + Departure code) (u8 + u8)
+pub const SERVER_DISCONNECT_MSG_SIZE: usize = 2;
 
 /// A client gets kicked, meant for the situation, when no more clients should get accepted. followed by u16 client id. The receiving tokio task has to act on its own. (Server -> Client)
 pub const CLIENT_GETS_KICKED: u8 = 1;
 
-/// Delta update. Followed by payload for every delta update. May carry several delta messages in one pass.
+/// Delta update. Followed by the u32 sequence number and payload for every delta update. May carry several delta messages in one pass,
+/// each prefixed with its size as u32 in binary frames and separated by line breaks in JSON text frames,
+/// so a corrupt one does not spoil the others.
 pub const DELTA_UPDATE: u8 = 2;
 
-/// Flagging a full update. Followed by payload for full update.
+/// Flagging a full update. Followed by the u32 sequence number and payload for full update.
 pub const FULL_UPDATE: u8 = 3;
 
-/// The message to reset the game. This is also followed by a full update. Difference is, that every client will get the full update.
+/// The message to reset the game. This is also followed by the u32 sequence number and a full update. Difference is, that every client will get the full update.
 pub const RESET: u8 = 4;
 
 /// The error message we add.
@@ -49,22 +120,483 @@ pub const SERVER_ERROR: u8 = 5;
 /// The response message for the handshake.
 pub const HAND_SHAKE_RESPONSE: u8 = 6;
 
+/// A diagnostic log line from the host, followed by UTF-8 text. (Server -> Relay)
+/// The relay records it with the room id and never forwards it to clients.
+pub const LOG: u8 = 7;
+
+/// Delta update for everyone except one client. Followed by u16 client id of the excluded client, the sequence number
+/// and the payload as in [`DELTA_UPDATE`]. The relay forwards it as a plain [`DELTA_UPDATE`] to all other clients.
+/// The excluded client gets it unchanged and only applies it to its shadow view state, as its frontend holds the
+/// change already.
+pub const DELTA_UPDATE_EXCEPT: u8 = 8;
+
+/// Keeps the room of an idle host alive. The message is just the byte itself. (Server -> Relay)
+/// The relay counts it as activity of the room but forwards nothing to the clients.
+pub const KEEPALIVE: u8 = 9;
+
+/// The interval in seconds in which a connected host sends [`KEEPALIVE`].
+pub const KEEPALIVE_INTERVAL_SECS: u64 = 60;
+
+/// The result of a finished game from the host, followed by an opaque blob the game defines. (Server -> Relay)
+/// The relay hands it to its results sink without interpreting it and never forwards it to clients.
+pub const RESULT: u8 = 10;
+
+/// The host pauses or resumes the game, followed by a u8 that is 1 for paused and 0 for resumed. (Server -> Client)
+/// The relay forwards it to all clients, synchronized or not.
+pub const PAUSE: u8 = 11;
+/// The pause message size (Header + Flag) (u8 + u8)
+pub const PAUSE_MSG_SIZE: usize = 2;
+
+/// The display name of a player, followed by the u16 player id and the name as UTF-8. (Server -> Client)
+/// An empty name means the player has none (any more). The host sends it whenever a name changes and all names
+/// to a client that joins. The relay forwards it to all clients, synchronized or not.
+pub const PLAYER_NAME: u8 = 12;
+
+/// Delta update for a single client. Followed by u16 client id of the receiving client, the sequence number
+/// and the payload as in [`DELTA_UPDATE`], e.g. for the private hand of a player. The relay forwards it as a plain
+/// [`DELTA_UPDATE`] to that client only and never to spectators, not even when they are addressed.
+pub const DELTA_UPDATE_TO: u8 = 16;
+
+/// The amount of spectators in the room, followed by it as u16. (Relay -> Server and Client)
+/// The relay sends it to the host and all clients, synchronized or not, whenever a spectator joins or leaves and
+/// whenever a player joins while spectators are present. It never tells who the spectators are.
+pub const SPECTATOR_COUNT: u8 = 17;
+/// The spectator count message size (Header + Count) (u8 + u16)
+pub const SPECTATOR_COUNT_MSG_SIZE: usize = 3;
+
+/// Full update for a single client, the answer to [`REQUEST_SYNC`]. Followed by u16 client id of the receiving
+/// client, the sequence number and the payload as in [`FULL_UPDATE`]. The relay forwards it as a plain
+/// [`FULL_UPDATE`] to that client only, even if the client is synchronized already.
+pub const FULL_UPDATE_TO: u8 = 19;
+
+/// Partial update of a view state split into regions, like the rows of a large board. Followed by the u32 sequence
+/// number and the payload with the index and content of every changed region. The relay handles it like a
+/// [`DELTA_UPDATE`]: only synchronized clients get it, spectators included.
+pub const PARTIAL_UPDATE: u8 = 20;
+
+/// The relay hands the room over to a new host, followed by the u16 client id of the client that becomes it. (Relay -> Client)
+/// Only sent in games with host migration, right before [`SERVER_DISCONNECTS`] with [`HostDeparture::Migrated`]. The named
+/// client opens the room again as its host, the other clients join it again. Only the named client gets the u64 migration
+/// token behind the client id, which it has to present as [`JoinRequest::migration_token`] to take the room over.
+pub const HOST_MIGRATION: u8 = 21;
+/// The host migration message size (Header + Client id) (u8 + u16), as the clients not named get it.
+pub const HOST_MIGRATION_MSG_SIZE: usize = 3;
+/// The size of the migration token the new host gets behind a [`HOST_MIGRATION`]. (u64)
+pub const MIGRATION_TOKEN_SIZE: usize = 8;
+
+/// A tick of the host without any change of the view state, the message is just the byte itself. (Server -> Client)
+/// The relay handles it like a [`DELTA_UPDATE`]: only synchronized clients get it, spectators included.
+pub const TICK: u8 = 22;
+
+/// The host ignored an action of a player, followed by the u16 client id of that player and the reason as UTF-8.
+/// (Server -> Client) The relay forwards it unchanged to that client only, regardless of its sync state, and never to
+/// spectators.
+pub const REJECT_ACTION: u8 = 23;
+
+/// The host processed the RPCs of a client, followed by the u16 client id of that client and the u32 RPC sequence
+/// number of the last processed [`SERVER_RPC`]. (Server -> Client) The host sends it after the updates answering
+/// those RPCs, so a client that predicted an RPC and got no confirming delta by then knows that the prediction failed.
+/// The relay forwards it unchanged to that client only, like [`REJECT_ACTION`].
+pub const RPC_ACK: u8 = 24;
+/// The RPC acknowledgement message size (Header + Client id + RPC sequence number) (u8 + u16 + u32)
+pub const RPC_ACK_MSG_SIZE: usize = 7;
+
+/// The time in seconds the relay keeps a room for its new host after a host migration. The clients keep trying
+/// to join the room again for as long.
+pub const MIGRATION_TIMEOUT_SECS: u64 = 30;
+
 // Sizes of entries.
-/// For the handshake we respond with player id and rule variation. (u16 + u16)
-pub const HAND_SHAKE_RESPONSE_SIZE: usize = 5;
+/// The fixed part of the [`HAND_SHAKE_RESPONSE`]. (Header + Version + Body length) (u8 + u8 + u16)
+/// It is followed by the body of [`HandshakeResponse`], see there for the layout.
+pub const HAND_SHAKE_RESPONSE_HEADER_SIZE: usize = 4;
+
+/// The version of the [`HandshakeResponse`] layout the relay sends.
+pub const HAND_SHAKE_VERSION: u8 = 1;
+
+/// The codec id of postcard in the [`JoinRequest`], the default of the backbone.
+pub const CODEC_POSTCARD: u8 = 0;
+
+/// The codec id of JSON in the [`JoinRequest`], used in the JSON debugging mode.
+pub const CODEC_JSON: u8 = 1;
 
 /// The size of a new client. (u16)
 pub const CLIENT_ID_SIZE: usize = 2;
 
+/// The size of the sequence number of view state updates. (u32)
+/// The host counts it up with every [`DELTA_UPDATE`], [`PARTIAL_UPDATE`], [`FULL_UPDATE`] and [`RESET`] it sends, so a client can
+/// discard updates that are older than its last full synchronization.
+pub const SEQUENCE_SIZE: usize = 4;
+
+/// The maximum length of a room name in characters (not bytes), any Unicode character is allowed.
+pub const MAX_ROOM_NAME_LENGTH: usize = 64;
+
+/// The hard ceiling of players in a room including the host, regardless of the game configuration.
+/// Every player holds a subscription to the broadcast channel of the room, so this bounds the resources one room may take.
+pub const ABSOLUTE_MAX_CLIENTS: u16 = 128;
+
+/// The player limit of a room for the configured `max_players` (0 = no limit), capped at [`ABSOLUTE_MAX_CLIENTS`].
+pub fn effective_max_players(max_players: u16) -> u16 {
+    if max_players == 0 {
+        ABSOLUTE_MAX_CLIENTS
+    } else {
+        max_players.min(ABSOLUTE_MAX_CLIENTS)
+    }
+}
+
+/// The reasons the relay rejects a client that wants to join an existing room. They are sent as the text of
+/// [`SERVER_ERROR`], so a client can tell them apart from other errors with [`JoinRejection::from_message`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JoinRejection {
+    /// All player slots are taken and the game has no spectator slots.
+    GameFull,
+    /// All player slots and all spectator slots are taken.
+    SpectatorsFull,
+    /// The host kicked the client from the room a short while ago.
+    Banned,
+}
+
+impl JoinRejection {
+    /// The text that gets sent with [`SERVER_ERROR`].
+    pub fn message(self) -> &'static str {
+        match self {
+            JoinRejection::GameFull => "Game full.",
+            JoinRejection::SpectatorsFull => "Spectator slots full.",
+            JoinRejection::Banned => "Kicked from this room, try again later.",
+        }
+    }
+
+    /// Recognizes the text of a [`SERVER_ERROR`] as a rejection. Other errors give `None`.
+    pub fn from_message(message: &str) -> Option<Self> {
+        [
+            JoinRejection::GameFull,
+            JoinRejection::SpectatorsFull,
+            JoinRejection::Banned,
+        ]
+        .into_iter()
+        .find(|rejection| rejection.message() == message)
+    }
+}
+
+/// The reasons a room ends for its clients because the host is gone. The code follows [`SERVER_DISCONNECTS`], the relay
+/// closes the connections of the clients with the message as the text of [`SERVER_ERROR`], so a client can tell
+/// them apart from other errors with [`HostDeparture::from_message`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum HostDeparture {
+    /// The host ended the game intentionally.
+    Ended = 0,
+    /// The host left because the game could not go on, e.g. a critical player left.
+    Aborted = 1,
+    /// The connection to the host broke down.
+    Lost = 2,
+    /// The relay closed the room, e.g. by an administrator.
+    Closed = 3,
+    /// The host left and the room migrates to a new host, see [`HOST_MIGRATION`].
+    Migrated = 4,
+}
+
+impl HostDeparture {
+    /// All departures, in the order of their codes.
+    const ALL: [HostDeparture; 5] = [
+        HostDeparture::Ended,
+        HostDeparture::Aborted,
+        HostDeparture::Lost,
+        HostDeparture::Closed,
+        HostDeparture::Migrated,
+    ];
+
+    /// The code that follows [`SERVER_DISCONNECTS`].
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Reads the code that follows [`SERVER_DISCONNECTS`]. Unknown codes give `None`.
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+
+    /// The text that gets sent with [`SERVER_ERROR`].
+    pub fn message(self) -> &'static str {
+        match self {
+            HostDeparture::Ended => "Host ended the game.",
+            HostDeparture::Aborted => "Host aborted the game.",
+            HostDeparture::Lost => "Lost connection to host.",
+            HostDeparture::Closed => "Room was closed by the relay.",
+            HostDeparture::Migrated => "Host left, a new host takes over.",
+        }
+    }
+
+    /// Recognizes the text of a [`SERVER_ERROR`] as a departure of the host. Other errors give `None`.
+    pub fn from_message(message: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|departure| departure.message() == message)
+    }
+}
+
+/// The name of a game, as configured on the relay. Serializes as the plain string.
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct GameId(String);
+
+/// The name of a room within its game, as chosen by the host. Serializes as the plain string.
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct RoomId(String);
+
+/// The key of a room on the relay, combined from room and game name as `room#game`. Serializes as the plain string.
+///
+/// The `#` and `%` characters inside room and game names get percent-escaped, so names may contain
+/// any character and different room/game pairs never share a key.
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct CompoundRoomId(String);
+
+#[cfg(feature = "alloc")]
+impl GameId {
+    /// Wraps the name of a game.
+    pub fn new(name: impl Into<String>) -> Self {
+        GameId(name.into())
+    }
+
+    /// The name of the game.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl RoomId {
+    /// Wraps the name of a room.
+    pub fn new(name: impl Into<String>) -> Self {
+        RoomId(name.into())
+    }
+
+    /// The name of the room.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl CompoundRoomId {
+    /// Builds the key of the room of the game.
+    pub fn compound(room: &RoomId, game: &GameId) -> Self {
+        CompoundRoomId(format!(
+            "{}#{}",
+            escape_id_part(&room.0),
+            escape_id_part(&game.0)
+        ))
+    }
+
+    /// Wraps a key that is in the compound form already, e.g. from the path of an admin request.
+    /// It is not checked, a malformed key simply matches no room.
+    pub fn new(compound_id: impl Into<String>) -> Self {
+        CompoundRoomId(compound_id.into())
+    }
+
+    /// Splits the key back into room and game, `None` if it was not built by [`compound`](Self::compound).
+    pub fn split(&self) -> Option<(RoomId, GameId)> {
+        let (room, game) = self.0.split_once('#')?;
+        Some((
+            RoomId(unescape_id_part(room)),
+            GameId(unescape_id_part(game)),
+        ))
+    }
+
+    /// Checks if the room belongs to the game. The escaped room part never contains the separator,
+    /// so the suffix `#game` matches exactly the game.
+    pub fn is_of_game(&self, game: &GameId) -> bool {
+        self.0
+            .strip_suffix(escape_id_part(&game.0).as_str())
+            .is_some_and(|room| room.ends_with('#'))
+    }
+
+    /// The key in its compound form.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for GameId {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for RoomId {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for CompoundRoomId {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<GameId> for String {
+    fn from(id: GameId) -> Self {
+        id.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<RoomId> for String {
+    fn from(id: RoomId) -> Self {
+        id.0
+    }
+}
+
+/// Escapes the separator `#` and the escape character `%` itself.
+#[cfg(feature = "alloc")]
+fn escape_id_part(part: &str) -> String {
+    part.replace('%', "%25").replace('#', "%23")
+}
+
+/// Reverts [`escape_id_part`].
+#[cfg(feature = "alloc")]
+fn unescape_id_part(part: &str) -> String {
+    part.replace("%23", "#").replace("%25", "%")
+}
+
 /// The join request. This struct is used on the server and on the client.
+#[cfg(feature = "alloc")]
 #[derive(Deserialize, Serialize)]
 pub struct JoinRequest {
     /// Which game do we want to join.
-    pub game_id: String,
-    /// Which room do we want to join.
-    pub room_id: String,
+    pub game_id: GameId,
+    /// Which room do we want to join. Any Unicode text up to [`MAX_ROOM_NAME_LENGTH`] characters.
+    pub room_id: RoomId,
     /// The rule variation that is applied, this gets only interpreted if a room gets constructed.
     pub rule_variation: u16,
     /// Do we want to create a room and act as a server?
     pub create_room: bool,
+    /// The codec of the payloads, like [`CODEC_POSTCARD`]. The host decides it for the room, clients have to use the same.
+    pub codec: u8,
+    /// The token of a [`HOST_MIGRATION`] that named us the new host, to take over the room that waits for us.
+    pub migration_token: Option<u64>,
+}
+
+/// The answer of the relay to an accepted [`JoinRequest`], sent as [`HAND_SHAKE_RESPONSE`].
+///
+/// The frame is laid out as the message id, the u8 [`HAND_SHAKE_VERSION`] and the u16 length of the body, followed by
+/// the body. Version 1 of the body holds the u16 player id, the u16 rule variation and the compound room id
+/// `room#game` as UTF-8 with a u16 length prefix. Later versions only append fields to the body, so a reader takes the
+/// fields it knows and skips the rest of the body as well as anything behind it. This way the relay can extend the
+/// handshake without breaking older clients.
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HandshakeResponse {
+    /// The id of the player in the room, the host is always 0.
+    pub player_id: u16,
+    /// The rule variation of the room.
+    pub rule_variation: u16,
+    /// The compound room id `room#game` as stored on the relay.
+    pub room_id: String,
+}
+
+#[cfg(feature = "alloc")]
+impl HandshakeResponse {
+    /// Writes the complete frame including the message id.
+    pub fn to_frame(&self) -> Vec<u8> {
+        let room_id = self.room_id.as_bytes();
+        let body_length = 3 * 2 + room_id.len();
+        let mut frame = Vec::with_capacity(HAND_SHAKE_RESPONSE_HEADER_SIZE + body_length);
+        frame.push(HAND_SHAKE_RESPONSE);
+        frame.push(HAND_SHAKE_VERSION);
+        frame.extend_from_slice(&(body_length as u16).to_be_bytes());
+        frame.extend_from_slice(&self.player_id.to_be_bytes());
+        frame.extend_from_slice(&self.rule_variation.to_be_bytes());
+        frame.extend_from_slice(&(room_id.len() as u16).to_be_bytes());
+        frame.extend_from_slice(room_id);
+        frame
+    }
+
+    /// Reads a frame written with [`to_frame`](Self::to_frame) by this or a later version.
+    /// Unknown fields at the end of the body and bytes behind the body get ignored.
+    pub fn from_frame(frame: &[u8]) -> Result<Self, String> {
+        let truncated = || "Truncated handshake response".to_string();
+        if frame.first() != Some(&HAND_SHAKE_RESPONSE) {
+            return Err("Not a handshake response".to_string());
+        }
+        let header = frame
+            .get(..HAND_SHAKE_RESPONSE_HEADER_SIZE)
+            .ok_or_else(truncated)?;
+        if header[1] == 0 {
+            return Err("Handshake response without version".to_string());
+        }
+        let body_length = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let mut body = frame
+            .get(HAND_SHAKE_RESPONSE_HEADER_SIZE..HAND_SHAKE_RESPONSE_HEADER_SIZE + body_length)
+            .ok_or_else(truncated)?;
+
+        let player_id = take_u16(&mut body).ok_or_else(truncated)?;
+        let rule_variation = take_u16(&mut body).ok_or_else(truncated)?;
+        let room_id_length = take_u16(&mut body).ok_or_else(truncated)? as usize;
+        let room_id = body.get(..room_id_length).ok_or_else(truncated)?;
+        let room_id = core::str::from_utf8(room_id)
+            .map_err(|_| "Room id in handshake response is no UTF-8".to_string())?;
+        Ok(HandshakeResponse {
+            player_id,
+            rule_variation,
+            room_id: room_id.to_string(),
+        })
+    }
+}
+
+/// Reads a big endian u16 from the front of the bytes and advances them.
+#[cfg(feature = "alloc")]
+fn take_u16(bytes: &mut &[u8]) -> Option<u16> {
+    let (value, rest) = bytes.split_at_checked(2)?;
+    *bytes = rest;
+    Some(u16::from_be_bytes([value[0], value[1]]))
+}
+
+/// Renders a frame as text for the JSON debugging mode.
+///
+/// The fixed binary header of `header_size` bytes (message id and client ids) gets written as hex digits,
+/// followed by a space and the payload, which is JSON or plain text in that mode.
+/// For example a delta update reads as `02 [{"x":1}]`. A payload that is no UTF-8 text, like an opaque blob, gets written
+/// as hex digits together with the header and the text after the space stays empty, so it survives unchanged.
+#[cfg(feature = "alloc")]
+pub fn debug_text_from_frame(frame: &[u8], header_size: usize) -> String {
+    let header_size = header_size.min(frame.len());
+    let (header, payload) = match core::str::from_utf8(&frame[header_size..]) {
+        Ok(payload) => (&frame[..header_size], payload),
+        Err(_) => (frame, ""),
+    };
+    let header: String = header.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{} {}", header, payload)
+}
+
+/// Converts a text frame of the JSON debugging mode back into the binary frame layout.
+/// This is the inverse of [`debug_text_from_frame`].
+#[cfg(feature = "alloc")]
+pub fn frame_from_debug_text(text: &str) -> Result<Vec<u8>, String> {
+    let (header, payload) = text
+        .split_once(' ')
+        .ok_or_else(|| "Debug text frame without header separator".to_string())?;
+    if header.is_empty() || header.len() % 2 != 0 {
+        return Err(format!("Malformed debug text header {:?}", header));
+    }
+
+    let mut frame = Vec::with_capacity(header.len() / 2 + payload.len());
+    for index in (0..header.len()).step_by(2) {
+        let byte = header
+            .get(index..index + 2)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| format!("Malformed debug text header {:?}", header))?;
+        frame.push(byte);
+    }
+    frame.extend_from_slice(payload.as_bytes());
+    Ok(frame)
 }
@@ -0,0 +1,20 @@
+//! The text frames of the JSON debugging mode convert back into the exact binary frames, text payload or not.
+
+use protocol::{DELTA_UPDATE, RESULT, debug_text_from_frame, frame_from_debug_text};
+
+#[test]
+fn text_payload_stays_readable() {
+    let mut frame = vec![DELTA_UPDATE, 0, 0, 0, 5];
+    frame.extend_from_slice(br#"{"x":1}"#);
+    let text = debug_text_from_frame(&frame, 5);
+    assert_eq!(text, r#"0200000005 {"x":1}"#);
+    assert_eq!(frame_from_debug_text(&text).unwrap(), frame);
+}
+
+#[test]
+fn binary_payload_survives_unchanged() {
+    let frame = vec![RESULT, 0xff, 0x00, 0x80, b'a'];
+    let text = debug_text_from_frame(&frame, 1);
+    assert_eq!(text, "0aff008061 ");
+    assert_eq!(frame_from_debug_text(&text).unwrap(), frame);
+}
@@ -0,0 +1,58 @@
+//! The handshake response can be extended by the relay without breaking older readers.
+
+use protocol::{
+    HAND_SHAKE_RESPONSE, HAND_SHAKE_RESPONSE_HEADER_SIZE, HAND_SHAKE_VERSION, HandshakeResponse,
+};
+
+fn response() -> HandshakeResponse {
+    HandshakeResponse {
+        player_id: 3,
+        rule_variation: 258,
+        room_id: "Zimmer #1 🎲#game".to_string(),
+    }
+}
+
+#[test]
+fn response_survives_the_round_trip() {
+    let frame = response().to_frame();
+    assert_eq!(frame[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(frame[1], HAND_SHAKE_VERSION);
+    let body_length = u16::from_be_bytes([frame[2], frame[3]]) as usize;
+    assert_eq!(frame.len(), HAND_SHAKE_RESPONSE_HEADER_SIZE + body_length);
+    assert_eq!(HandshakeResponse::from_frame(&frame), Ok(response()));
+}
+
+#[test]
+fn fields_of_a_newer_version_get_skipped() {
+    let mut frame = response().to_frame();
+    // A later version appends a seed to the body and something behind it.
+    frame[1] = HAND_SHAKE_VERSION + 1;
+    let body_length = u16::from_be_bytes([frame[2], frame[3]]) + 4;
+    frame[2..4].copy_from_slice(&body_length.to_be_bytes());
+    frame.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+    frame.extend_from_slice(b"trailing");
+    assert_eq!(HandshakeResponse::from_frame(&frame), Ok(response()));
+}
+
+#[test]
+fn malformed_responses_are_errors() {
+    let frame = response().to_frame();
+    for length in 0..frame.len() {
+        assert!(HandshakeResponse::from_frame(&frame[..length]).is_err());
+    }
+
+    let mut no_version = frame.clone();
+    no_version[1] = 0;
+    assert!(HandshakeResponse::from_frame(&no_version).is_err());
+
+    // The body claims less than the room id needs.
+    let mut short_body = frame.clone();
+    let body_length = u16::from_be_bytes([frame[2], frame[3]]) - 1;
+    short_body[2..4].copy_from_slice(&body_length.to_be_bytes());
+    assert!(HandshakeResponse::from_frame(&short_body).is_err());
+
+    let mut not_utf8 = frame;
+    let last = not_utf8.len() - 1;
+    not_utf8[last] = 0xff;
+    assert!(HandshakeResponse::from_frame(&not_utf8).is_err());
+}
@@ -1,36 +1,51 @@
 //! This module does the whole initialization and handshake thing.
 //! The general protocol of connecting is :
 //! WASM Client -> Websocket: postcard serialized join request.
-//! Websocket -> WASM Client: u16 player id, u16 rule variation.
+//! Websocket -> WASM Client: u16 player id, u16 rule variation, compound room id as UTF-8.
+//!
+//! With the feature `json-debug`, a join request may also arrive as a JSON text frame. The connection then
+//! runs in the JSON debugging mode and all further frames are text, see [`protocol::debug_text_from_frame`].
 
 use crate::hand_shake::ClientServerSpecificData::{Client, Server};
 use crate::hand_shake::DisconnectEndpointSpecification::{DisconnectClient, DisconnectServer};
-use crate::lobby::{AppState, Room};
-use axum::extract::ws::Message::Binary;
+use crate::lobby::{ActivityTracker, AppState, Room, RoomBroadcast, rooms_of_game};
+use crate::message_relay::{KICKED_BY_HOST, message_histogram, outgoing_frame, send_to_host};
+use axum::extract::ws::Message::{Binary, Close, Text};
 use axum::extract::ws::{Message, WebSocket};
 use bytes::{BufMut, Bytes, BytesMut};
 use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{sink::SinkExt, stream::StreamExt};
 use postcard::from_bytes;
 use protocol::{
-    CHANNEL_BUFFER_SIZE, CLIENT_DISCONNECT_MSG_SIZE, CLIENT_DISCONNECTS, HAND_SHAKE_RESPONSE,
-    HAND_SHAKE_RESPONSE_SIZE, JoinRequest, NEW_CLIENT, NEW_CLIENT_MSG_SIZE,
-    SERVER_DISCONNECT_MSG_SIZE, SERVER_DISCONNECTS, SERVER_ERROR,
+    CHANNEL_BUFFER_SIZE, CLIENT_DISCONNECT_MSG_SIZE, CLIENT_DISCONNECTS, CompoundRoomId, GameId,
+    HOST_MIGRATION, HOST_MIGRATION_MSG_SIZE, HandshakeResponse, HostDeparture, JoinRejection,
+    JoinRequest, MAX_ROOM_NAME_LENGTH, MIGRATION_TIMEOUT_SECS, MIGRATION_TOKEN_SIZE, NEW_CLIENT,
+    NEW_CLIENT_MSG_SIZE, RoomId, SERVER_DISCONNECT_MSG_SIZE, SERVER_DISCONNECTS, SERVER_ERROR,
+    effective_max_players,
 };
+use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{broadcast, mpsc};
 
 /// Is called on error, sends a text message because e-websocket can not interpret closing messages.
-/// This text message is encoded as a binary message.
-async fn send_closing_message(sender: &mut SplitSink<WebSocket, Message>, closing_message: String) {
+/// This text message is encoded as a binary message, unless the connection uses text frames.
+async fn send_closing_message(
+    sender: &mut SplitSink<WebSocket, Message>,
+    closing_message: String,
+    text_frames: bool,
+) {
     let raw_data = closing_message.as_bytes();
     let mut msg = BytesMut::with_capacity(1 + raw_data.len());
     msg.put_u8(SERVER_ERROR);
     msg.put_slice(raw_data);
 
-    let _ = sender.send(Message::Binary(msg.into())).await;
+    let _ = sender
+        .send(outgoing_frame(msg.into(), 1, text_frames))
+        .await;
     let _ = sender.send(Message::Close(None)).await;
 }
 
@@ -39,17 +54,25 @@ pub struct HandshakeResult {
     /// The id of the player we play.
     pub player_id: u16,
     /// The complete identifier of the room as stored in the hashmap.
-    pub room_id: String,
+    pub room_id: CompoundRoomId,
     /// The rule variation we apply.
     pub rule_variation: u16,
+    /// Flags, if the connection runs in the JSON debugging mode.
+    pub text_frames: bool,
+    /// The address the connection came from, see [`peer_address`](crate::peer_address).
+    pub peer: IpAddr,
+    /// Flags, if the client joined as a spectator, always false for the host.
+    pub is_spectator: bool,
+    /// The amount of RPCs the client may send per second, if the game limits it. Always `None` for the host.
+    pub max_rpcs_per_sec: Option<u32>,
     /// The internal connection information.
     pub specific_data: ClientServerSpecificData,
 }
 
 /// Contains all the channel information for internal communication.
 pub enum ClientServerSpecificData {
-    /// In this case we are servicing the server.
-    Server(Receiver<Bytes>, broadcast::Sender<Bytes>),
+    /// In this case we are servicing the server, the tracker records the activity of the host.
+    Server(Receiver<Bytes>, RoomBroadcast, ActivityTracker),
     /// In this case we are servicing a client.
     Client(broadcast::Receiver<Bytes>, Sender<Bytes>),
 }
@@ -59,7 +82,13 @@ pub struct DisconnectData {
     /// The id of the player we play.
     pub player_id: u16,
     /// The complete identifier of the room as stored in the hashmap.
-    pub room_id: String,
+    pub room_id: CompoundRoomId,
+    /// Flags, if the connection runs in the JSON debugging mode.
+    pub text_frames: bool,
+    /// The address the connection came from, gets banned from the room if the host kicked the client.
+    pub peer: IpAddr,
+    /// Flags, if the client joined as a spectator, so the room gets told when it leaves.
+    pub is_spectator: bool,
     /// The sender we use.
     pub sender: DisconnectEndpointSpecification,
 }
@@ -67,7 +96,7 @@ pub struct DisconnectData {
 /// Contains the information where to send error data to in case of disconnection.
 pub enum DisconnectEndpointSpecification {
     /// If we are servicing the server, we broadcast the info to all clients.
-    DisconnectServer(broadcast::Sender<Bytes>),
+    DisconnectServer(RoomBroadcast),
     /// If we are servicing the client, we send data to the server.
     DisconnectClient(Sender<Bytes>),
 }
@@ -76,14 +105,20 @@ pub enum DisconnectEndpointSpecification {
 impl From<&HandshakeResult> for DisconnectData {
     fn from(value: &HandshakeResult) -> Self {
         match &value.specific_data {
-            Server(_, internal_sender) => DisconnectData {
+            Server(_, internal_sender, _) => DisconnectData {
                 player_id: value.player_id,
                 room_id: value.room_id.clone(),
+                text_frames: value.text_frames,
+                peer: value.peer,
+                is_spectator: value.is_spectator,
                 sender: DisconnectServer(internal_sender.clone()),
             },
             Client(_, internal_sender) => DisconnectData {
                 player_id: value.player_id,
                 room_id: value.room_id.clone(),
+                text_frames: value.text_frames,
+                peer: value.peer,
+                is_spectator: value.is_spectator,
                 sender: DisconnectClient(internal_sender.clone()),
             },
         }
@@ -96,15 +131,33 @@ struct InitialConnectionResult {
     /// Flags, if we are a server.
     is_server: bool,
     /// The complete room we have for internal administration.
-    compound_room_id: String,
+    compound_room_id: CompoundRoomId,
     /// Which game do we want to join.
-    game_id: String,
+    game_id: GameId,
     /// Which room do we want to join.
-    room_id: String,
+    room_id: RoomId,
     /// The rule variation that is applied, this gets only interpreted if a room gets constructed.
     rule_variation: u16,
-    /// The maximum amount of players a room allows (0 = infinite).
+    /// Flags, if the game configuration allows the rule variation.
+    variation_allowed: bool,
+    /// The maximum amount of players and spectators a room allows, already capped at the protocol ceiling.
     max_players: u16,
+    /// Flags, if clients beyond the player slots join as spectators.
+    spectator_slots: bool,
+    /// The amount of player slots including the host, clients beyond are spectators if there are spectator slots.
+    player_slots: u16,
+    /// Flags, if the join request came as text and the connection runs in the JSON debugging mode.
+    text_frames: bool,
+    /// The amount of rooms the game may have open at the same time, if limited.
+    max_rooms: Option<u16>,
+    /// Flags, if a room of the game migrates to a new host when its host leaves.
+    host_migration: bool,
+    /// The amount of RPCs a client of the game may send per second, if limited.
+    max_rpcs_per_sec: Option<u32>,
+    /// The token of a host that takes over a room after a host migration.
+    migration_token: Option<u64>,
+    /// The codec of the payloads the joining peer uses.
+    codec: u8,
 }
 
 /// Reads in the join request from the web socket, verifies if game exists and generates the final room name.
@@ -113,62 +166,91 @@ async fn get_initial_query(
     receiver: &mut SplitStream<WebSocket>,
     state: Arc<AppState>,
 ) -> Option<InitialConnectionResult> {
-    // First we get a room opening and joining request. This is the first binary message we received.
-    let my_data = loop {
+    // First we get a room opening and joining request. This is the first binary or text message we received.
+    let (parse_result, text_frames) = loop {
         let Some(raw_data) = receiver.next().await else {
             tracing::warn!("WebSocket closed before handshake completed");
-            send_closing_message(sender, "Initial error during handshake.".into()).await;
+            send_closing_message(sender, "Initial error during handshake.".into(), false).await;
             return None;
         };
         match raw_data {
             Err(err) => {
                 tracing::error!(?err, "Initial error during handshake.");
-                send_closing_message(sender, "Initial error during handshake.".into()).await;
+                send_closing_message(sender, "Initial error during handshake.".into(), false).await;
                 return None;
             }
+            // Now we get some data and we try to convert it into the required format.
             Ok(Binary(data)) => {
-                break data;
+                break (
+                    from_bytes::<JoinRequest>(&data).map_err(|e| e.to_string()),
+                    false,
+                );
+            }
+            Ok(Text(text)) => {
+                if !cfg!(feature = "json-debug") {
+                    tracing::warn!("Rejected join request in JSON debugging mode.");
+                    send_closing_message(
+                        sender,
+                        "The JSON debugging mode is not enabled on this relay.".into(),
+                        true,
+                    )
+                    .await;
+                    return None;
+                }
+                break (
+                    serde_json::from_str::<JoinRequest>(text.as_str()).map_err(|e| e.to_string()),
+                    true,
+                );
+            }
+            Ok(Close(_)) => {
+                tracing::info!("WebSocket closed cleanly before handshake completed");
+                return None;
             }
             // We do not care about any other message like ping pong messages.
             Ok(_) => {}
         }
     };
 
-    // Now we get some data and we try to convert it into the required format.
-    let working_struct = match from_bytes::<JoinRequest>(&my_data) {
+    let working_struct = match parse_result {
         Ok(req) => req,
         Err(e) => {
-            tracing::error!(error = ?e, "Failed to parse join request");
-            send_closing_message(sender, "Failed to parse join request.".into()).await;
+            tracing::error!(error = e, "Failed to parse join request");
+            send_closing_message(sender, "Failed to parse join request.".into(), text_frames).await;
             return None;
         }
     };
 
     // Let us take a look, if the game exists.
     let games = state.configs.read().await;
-    let game_exists = games.contains_key(&working_struct.game_id);
-    let max_players = if game_exists {
-        games[&working_struct.game_id]
-    } else {
-        0
-    };
+    let game_entry = games.get(working_struct.game_id.as_str()).cloned();
     drop(games);
 
-    if !game_exists {
+    let Some(game_entry) = game_entry else {
         tracing::error!(
-            optional_game = working_struct.game_id,
+            optional_game = working_struct.game_id.as_str(),
             "Requested illegal game."
         );
-        send_closing_message(sender, format!("Unknown game {}.", &working_struct.game_id)).await;
+        send_closing_message(
+            sender,
+            format!("Unknown game {}.", &working_struct.game_id),
+            text_frames,
+        )
+        .await;
+        return None;
+    };
+
+    if working_struct.room_id.as_str().chars().count() > MAX_ROOM_NAME_LENGTH {
+        send_closing_message(
+            sender,
+            format!("Room name exceeds {} characters.", MAX_ROOM_NAME_LENGTH),
+            text_frames,
+        )
+        .await;
         return None;
     }
 
     // The final room id is the combination of game and room id.
-    let room_id = format!(
-        "{}#{}",
-        working_struct.room_id.as_str(),
-        working_struct.game_id.as_str()
-    );
+    let room_id = CompoundRoomId::compound(&working_struct.room_id, &working_struct.game_id);
     let is_server = working_struct.create_room;
 
     Some(InitialConnectionResult {
@@ -177,30 +259,41 @@ async fn get_initial_query(
         game_id: working_struct.game_id,
         room_id: working_struct.room_id,
         rule_variation: working_struct.rule_variation,
-        max_players,
+        variation_allowed: game_entry.allows_variation(working_struct.rule_variation),
+        max_players: effective_max_players(game_entry.room_capacity()),
+        spectator_slots: game_entry.has_spectator_slots(),
+        player_slots: effective_max_players(game_entry.max_players),
+        text_frames,
+        max_rooms: game_entry.max_rooms,
+        host_migration: game_entry.host_migration,
+        max_rpcs_per_sec: game_entry.max_rpcs_per_sec,
+        migration_token: working_struct.migration_token,
+        codec: working_struct.codec,
     })
 }
 
-/// Connects and eventually establishes a room.
+/// Connects and eventually establishes a room. The peer is the address the connection came from.
 pub async fn init_and_connect(
     sender: &mut SplitSink<WebSocket, Message>,
     receiver: &mut SplitStream<WebSocket>,
     state: Arc<AppState>,
+    peer: IpAddr,
 ) -> Option<HandshakeResult> {
     let start_result = get_initial_query(sender, receiver, state.clone()).await?;
 
     if start_result.is_server {
-        process_handshake_server(sender, state, start_result).await
+        process_handshake_server(sender, state, start_result, peer).await
     } else {
-        process_handshake_client(sender, state, start_result).await
+        process_handshake_client(sender, state, start_result, peer).await
     }
 }
 
-/// Does the handshake, if we are connected to a client.
+/// Does the handshake, if we are connected to a client. Peers the host kicked recently get rejected.
 async fn process_handshake_client(
     sender: &mut SplitSink<WebSocket, Message>,
     state: Arc<AppState>,
     initial_result: InitialConnectionResult,
+    peer: IpAddr,
 ) -> Option<HandshakeResult> {
     let mut rooms = state.rooms.lock().await;
     let Some(local_room) = rooms.get_mut(&initial_result.compound_room_id) else {
@@ -211,22 +304,81 @@ async fn process_handshake_client(
                 "Room {} does not exist for game {}.",
                 &initial_result.room_id, &initial_result.game_id
             ),
+            initial_result.text_frames,
         )
         .await;
         return None;
     };
 
-    // Do we fit in? max_players == 0 means "infinite".
-    // Use >= so we reject if the room is already at/over capacity (defensive if state was inconsistent).
-    if initial_result.max_players != 0 && local_room.amount_of_players >= initial_result.max_players
-    {
+    if local_room.headless {
+        drop(rooms);
+        send_closing_message(
+            sender,
+            format!("Room {} waits for a host.", &initial_result.room_id),
+            initial_result.text_frames,
+        )
+        .await;
+        return None;
+    }
+
+    // Host and clients have to agree on the frame mode, as the payload is postcard or JSON accordingly.
+    if local_room.text_frames != initial_result.text_frames {
         drop(rooms);
         send_closing_message(
             sender,
             format!(
-                "Room  {} exceeded max amount of players {}.",
-                &initial_result.room_id, initial_result.max_players
+                "Room {} uses a different frame mode (JSON debugging).",
+                &initial_result.room_id
             ),
+            initial_result.text_frames,
+        )
+        .await;
+        return None;
+    }
+
+    // A codec the host does not know would make every payload undecodable for both.
+    if local_room.codec != initial_result.codec {
+        let message = format!(
+            "Room {} uses a different codec ({}).",
+            &initial_result.room_id, local_room.codec
+        );
+        drop(rooms);
+        send_closing_message(sender, message, initial_result.text_frames).await;
+        return None;
+    }
+
+    if local_room.is_banned(peer) {
+        drop(rooms);
+        tracing::info!(room_id = initial_result.room_id.as_str(), %peer, "Banned peer tried to rejoin.");
+        send_closing_message(
+            sender,
+            JoinRejection::Banned.message().to_string(),
+            initial_result.text_frames,
+        )
+        .await;
+        return None;
+    }
+
+    // Do we fit in? A configured limit of 0 has already been replaced by the protocol ceiling.
+    // Use >= so we reject if the room is already at/over capacity (defensive if state was inconsistent).
+    if local_room.amount_of_players >= initial_result.max_players {
+        drop(rooms);
+        // Player slots get filled first, so the joiner would have been a spectator if the game has spectator slots.
+        let rejection = if initial_result.spectator_slots {
+            JoinRejection::SpectatorsFull
+        } else {
+            JoinRejection::GameFull
+        };
+        tracing::info!(
+            room_id = initial_result.room_id.as_str(),
+            max_players = initial_result.max_players,
+            ?rejection,
+            "Room is full."
+        );
+        send_closing_message(
+            sender,
+            rejection.message().to_string(),
+            initial_result.text_frames,
         )
         .await;
         return None;
@@ -238,36 +390,70 @@ async fn process_handshake_client(
         send_closing_message(
             sender,
             format!("Room {} run out of client ids.", &initial_result.room_id),
+            initial_result.text_frames,
         )
         .await;
         tracing::error!("Server run out of client ids.");
         return None;
     }
 
+    // Once all player slots are taken, the client only gets to see the public messages of the host.
+    let is_spectator = initial_result.spectator_slots
+        && local_room.amount_of_players >= initial_result.player_slots;
     local_room.amount_of_players += 1;
     let player_id = local_room.next_client_id;
     local_room.next_client_id += 1;
 
     let to_server_sender = local_room.to_host_sender.clone();
-    let receiver = local_room.host_to_client_broadcaster.subscribe();
+    let receiver = local_room
+        .host_to_client_broadcaster
+        .subscribe(is_spectator);
+    // The new client is subscribed already, so it learns the count as well.
+    if is_spectator {
+        local_room.spectators += 1;
+    }
+    if local_room.spectators > 0 {
+        local_room.announce_spectators();
+    }
+    if !is_spectator {
+        local_room.players.push(player_id);
+    }
     let rule_variation = local_room.rule_variation;
     drop(rooms);
 
-    // Here we send a message to the server, that a new client has joined.
+    // Here we send a message to the server, that a new client has joined. The join goes into the same queue as
+    // the RPCs of the client, and its receive task only starts after this send completed, so the host always sees
+    // the join of a client before anything the client sends, even if the client sends before getting the response.
     let mut msg = BytesMut::with_capacity(NEW_CLIENT_MSG_SIZE);
     msg.put_u8(NEW_CLIENT); // Message-Type
     msg.put_u16(player_id); // player id.
 
-    let result = to_server_sender.send(msg.into()).await;
-    if let Err(error) = result {
+    let result = send_to_host(&to_server_sender, msg.into()).await;
+    if let Err(reason) = result {
         // We have to leave the room again.
         let mut rooms = state.rooms.lock().await;
-        if let Some(room) = rooms.get_mut(&initial_result.compound_room_id) {
+        if let Some(room) = rooms
+            .get_mut(&initial_result.compound_room_id)
+            .filter(|room| room.to_host_sender.same_channel(&to_server_sender))
+        {
             room.amount_of_players -= 1;
+            room.players.retain(|&player| player != player_id);
+            if is_spectator {
+                room.spectators -= 1;
+                room.announce_spectators();
+            }
         }
         drop(rooms);
-        tracing::error!(?error, "Server unexpectedly left during handshake");
-        send_closing_message(sender, "Server unexpectedly left during handshake".into()).await;
+        tracing::error!(
+            reason,
+            "Could not announce the client to the host during handshake"
+        );
+        send_closing_message(
+            sender,
+            format!("Server unavailable during handshake: {}", reason),
+            initial_result.text_frames,
+        )
+        .await;
         return None;
     }
 
@@ -275,6 +461,10 @@ async fn process_handshake_client(
         room_id: initial_result.compound_room_id,
         player_id,
         rule_variation,
+        text_frames: initial_result.text_frames,
+        peer,
+        is_spectator,
+        max_rpcs_per_sec: initial_result.max_rpcs_per_sec,
         specific_data: Client(receiver, to_server_sender),
     };
 
@@ -286,9 +476,14 @@ async fn process_handshake_server(
     sender: &mut SplitSink<WebSocket, Message>,
     state: Arc<AppState>,
     initial_result: InitialConnectionResult,
+    peer: IpAddr,
 ) -> Option<HandshakeResult> {
     let mut rooms = state.rooms.lock().await;
-    if rooms.contains_key(&initial_result.compound_room_id) {
+    // A persistent room held by the headless host of the relay gets taken over.
+    if rooms
+        .get(&initial_result.compound_room_id)
+        .is_some_and(|room| !room.headless)
+    {
         drop(rooms);
         send_closing_message(
             sender,
@@ -296,28 +491,110 @@ async fn process_handshake_server(
                 "Room {} already exists for game {}.",
                 &initial_result.room_id, &initial_result.game_id
             ),
+            initial_result.text_frames,
         )
         .await;
         // User error no need for error tracing.
         return None;
     }
+    // After a host migration, only the client chosen as the new host may take the room over.
+    if rooms
+        .get(&initial_result.compound_room_id)
+        .and_then(|room| room.migration_token)
+        .is_some_and(|token| Some(token) != initial_result.migration_token)
+    {
+        drop(rooms);
+        send_closing_message(
+            sender,
+            format!(
+                "Room {} is reserved for its new host.",
+                &initial_result.room_id
+            ),
+            initial_result.text_frames,
+        )
+        .await;
+        return None;
+    }
+    if !initial_result.variation_allowed {
+        drop(rooms);
+        send_closing_message(
+            sender,
+            format!(
+                "Rule variation {} is not allowed for game {}.",
+                initial_result.rule_variation, &initial_result.game_id
+            ),
+            initial_result.text_frames,
+        )
+        .await;
+        return None;
+    }
+    // Taking over a persistent room does not add a room.
+    if let Some(max_rooms) = initial_result.max_rooms
+        && !rooms.contains_key(&initial_result.compound_room_id)
+        && rooms_of_game(&rooms, &initial_result.game_id) >= max_rooms as usize
+    {
+        drop(rooms);
+        tracing::info!(
+            game_id = initial_result.game_id.as_str(),
+            max_rooms,
+            "Room limit of the game reached."
+        );
+        send_closing_message(
+            sender,
+            format!(
+                "Game {} has reached its limit of {} rooms.",
+                &initial_result.game_id, max_rooms
+            ),
+            initial_result.text_frames,
+        )
+        .await;
+        return None;
+    }
     // Here we create a new room.
     let (to_server_sender, to_server_receiver) = mpsc::channel(CHANNEL_BUFFER_SIZE);
-    let (to_client_sender, _) = broadcast::channel(CHANNEL_BUFFER_SIZE);
+    let to_client_sender = RoomBroadcast::new(CHANNEL_BUFFER_SIZE);
+    let activity_tracker = ActivityTracker::new();
     let new_room = Room {
         next_client_id: 1,
         amount_of_players: 1,
         rule_variation: initial_result.rule_variation,
         to_host_sender: to_server_sender,
         host_to_client_broadcaster: to_client_sender.clone(),
+        last_activity: activity_tracker.clone(),
+        text_frames: initial_result.text_frames,
+        codec: initial_result.codec,
+        banned: HashMap::new(),
+        spectators: 0,
+        headless: false,
+        players: Vec::new(),
+        host_migration: initial_result.host_migration,
+        migration_token: None,
     };
-    rooms.insert(initial_result.compound_room_id.clone(), new_room);
+    match rooms.insert(initial_result.compound_room_id.clone(), new_room) {
+        Some(replaced) if replaced.migration_token.is_some() => {
+            tracing::info!(
+                room_id = initial_result.room_id.as_str(),
+                "New host takes over the room after a host migration."
+            );
+        }
+        Some(_) => {
+            tracing::info!(
+                room_id = initial_result.room_id.as_str(),
+                "Host takes over persistent room."
+            );
+        }
+        None => {}
+    }
     drop(rooms);
     let hand_shake_result = HandshakeResult {
         room_id: initial_result.compound_room_id,
         player_id: 0,
         rule_variation: initial_result.rule_variation,
-        specific_data: Server(to_server_receiver, to_client_sender),
+        text_frames: initial_result.text_frames,
+        peer,
+        is_spectator: false,
+        max_rpcs_per_sec: None,
+        specific_data: Server(to_server_receiver, to_client_sender, activity_tracker),
     };
     Some(hand_shake_result)
 }
@@ -327,15 +604,59 @@ pub async fn inform_client_of_connection(
     sender: &mut SplitSink<WebSocket, Message>,
     status: &HandshakeResult,
 ) -> bool {
-    let mut msg = BytesMut::with_capacity(HAND_SHAKE_RESPONSE_SIZE);
-    msg.put_u8(HAND_SHAKE_RESPONSE);
-    msg.put_u16(status.player_id);
-    msg.put_u16(status.rule_variation);
-
-    let result = sender.send(Message::Binary(msg.into())).await;
+    let frame = HandshakeResponse {
+        player_id: status.player_id,
+        rule_variation: status.rule_variation,
+        room_id: status.room_id.to_string(),
+    }
+    .to_frame();
+    // The response is binary throughout, in the JSON debugging mode it gets written completely as hex digits.
+    let header_size = frame.len();
+    let result = sender
+        .send(outgoing_frame(
+            frame.into(),
+            header_size,
+            status.text_frames,
+        ))
+        .await;
     result.is_ok()
 }
 
+/// Tells all clients of the room, why the host is gone.
+fn send_departure(sender: &RoomBroadcast, departure: HostDeparture) {
+    let mut msg = BytesMut::with_capacity(SERVER_DISCONNECT_MSG_SIZE);
+    msg.put_u8(SERVER_DISCONNECTS);
+    msg.put_u8(departure.code());
+    sender.send_public(msg.into());
+}
+
+/// Draws the one-time token the new host of a migrated room takes it over with, from the randomness of the system.
+fn new_migration_token() -> u64 {
+    getrandom::u64().expect("No randomness available for the migration token")
+}
+
+/// Drops the room that waits for its new host after a host migration, if the new host did not take it over in time.
+async fn expire_migration(
+    app_state: Arc<AppState>,
+    room_id: CompoundRoomId,
+    placeholder: RoomBroadcast,
+) {
+    tokio::time::sleep(Duration::from_secs(MIGRATION_TIMEOUT_SECS)).await;
+    let mut rooms = app_state.rooms.lock().await;
+    if rooms
+        .get(&room_id)
+        .is_some_and(|room| room.host_to_client_broadcaster.same_channel(&placeholder))
+    {
+        rooms.remove(&room_id);
+        drop(rooms);
+        tracing::info!(
+            room_id = room_id.as_str(),
+            "New host did not take over the room in time."
+        );
+        app_state.open_persistent_rooms().await;
+    }
+}
+
 /// Performs the shutdown of the system and sends a last message.
 pub async fn shutdown_connection(
     wrapped_sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
@@ -345,33 +666,105 @@ pub async fn shutdown_connection(
 ) {
     match disconnect_data.sender {
         DisconnectServer(sender) => {
-            // Inform clients first.
-            let mut msg = BytesMut::with_capacity(SERVER_DISCONNECT_MSG_SIZE);
-            msg.put_u8(SERVER_DISCONNECTS);
-            let _ = sender.send(msg.into());
-            // Kill room.
+            // Inform clients first, why the host is gone. Any other reason than a departure the host announced
+            // means the connection to it broke down.
+            let departure =
+                HostDeparture::from_message(error_message).unwrap_or(HostDeparture::Lost);
+            tracing::info!(
+                room_id = disconnect_data.room_id.as_str(),
+                from_host = message_histogram(sender.counters(), false),
+                to_host = message_histogram(sender.counters(), true),
+                "Message histogram of the room."
+            );
             let mut rooms = app_state.rooms.lock().await;
-            rooms.remove(&disconnect_data.room_id);
-            drop(rooms);
+            // Nothing is left to do with the room, if it got closed already and the id was taken by a new room meanwhile.
+            let room = rooms
+                .get(&disconnect_data.room_id)
+                .filter(|room| room.host_to_client_broadcaster.same_channel(&sender));
+            let is_current = room.is_some();
+            // A host that aborted the game does not hand it over.
+            let successor = room
+                .filter(|room| room.host_migration && departure != HostDeparture::Aborted)
+                .and_then(|room| room.players.first().copied());
+            if let Some(successor) = successor {
+                // The room waits for the new host, before the clients learn about it and try to join again.
+                let migration_token = new_migration_token();
+                let placeholder =
+                    rooms[&disconnect_data.room_id].migration_placeholder(migration_token);
+                let placeholder_channel = placeholder.host_to_client_broadcaster.clone();
+                rooms.insert(disconnect_data.room_id.clone(), placeholder);
+                drop(rooms);
+                tracing::info!(
+                    room_id = disconnect_data.room_id.as_str(),
+                    successor,
+                    "Host migrates."
+                );
+                // The send task of every client but the new host strips the token.
+                let mut msg =
+                    BytesMut::with_capacity(HOST_MIGRATION_MSG_SIZE + MIGRATION_TOKEN_SIZE);
+                msg.put_u8(HOST_MIGRATION);
+                msg.put_u16(successor);
+                msg.put_u64(migration_token);
+                sender.send_public(msg.into());
+                send_departure(&sender, HostDeparture::Migrated);
+                tokio::spawn(expire_migration(
+                    app_state.clone(),
+                    disconnect_data.room_id.clone(),
+                    placeholder_channel,
+                ));
+            } else {
+                // Inform clients first, why the host is gone. Kill the room afterwards,
+                // a persistent room gets opened again with the headless host.
+                send_departure(&sender, departure);
+                if is_current {
+                    rooms.remove(&disconnect_data.room_id);
+                    drop(rooms);
+                    app_state.open_persistent_rooms().await;
+                }
+            }
         }
         DisconnectClient(sender) => {
             // Inform server first.
             let mut msg = BytesMut::with_capacity(CLIENT_DISCONNECT_MSG_SIZE);
             msg.put_u8(CLIENT_DISCONNECTS);
             msg.put_u16(disconnect_data.player_id);
-            let _ = sender.send(msg.into()).await;
-            // Subtract one client from the room.
+            let _ = send_to_host(&sender, msg.into()).await;
+            // Subtract one client from the room and keep a kicked client from rejoining right away.
             let mut rooms = app_state.rooms.lock().await;
-            // Check if the room still exists.
-            if let Some(room) = rooms.get_mut(&disconnect_data.room_id) {
+            // Check if the room still exists, and is not a new one of the same name, e.g. after a host migration.
+            if let Some(room) = rooms
+                .get_mut(&disconnect_data.room_id)
+                .filter(|room| room.to_host_sender.same_channel(&sender))
+            {
                 room.amount_of_players -= 1;
+                room.players
+                    .retain(|&player| player != disconnect_data.player_id);
+                if disconnect_data.is_spectator {
+                    room.spectators -= 1;
+                    room.announce_spectators();
+                }
+                if error_message == KICKED_BY_HOST {
+                    room.ban(disconnect_data.peer, app_state.ban_duration);
+                }
             }
             drop(rooms);
         }
     }
 
-    let mut sender = wrapped_sender.lock().await;
-
-    // Send the message to the WASM point.
-    send_closing_message(&mut sender, error_message.into()).await;
+    // Send the message to the WASM point. An endpoint that does not read any more must not keep the task alive.
+    let closing = async {
+        let mut sender = wrapped_sender.lock().await;
+        send_closing_message(
+            &mut sender,
+            error_message.into(),
+            disconnect_data.text_frames,
+        )
+        .await;
+    };
+    if tokio::time::timeout(app_state.send_timeout, closing)
+        .await
+        .is_err()
+    {
+        tracing::warn!("Closing message could not be sent in time.");
+    }
 }
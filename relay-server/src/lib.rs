@@ -0,0 +1,662 @@
+//! The relay server as a library, so that it can be started from the binary as well as from test code.
+//!
+//! - [`router`]: Sets up the routing system for the web sockets, the pages enlist and reload and the admin endpoints
+//! - [`maintenance_task`]: The periodic maintenance with the intervals of [`MaintenanceIntervals`]
+//! - [`cleanup_dead_rooms`]: The fallback cleanup the maintenance runs periodically
+//! - [`log_stale_connections`]: The periodic sweep for connection tasks that got stuck
+//! - [`log_metrics`]: The periodic log of the load of the relay, see [`collect_metrics`]
+//! - [`lobby`]: The rooms and the game configuration
+//! - [`results`]: The sink for the game results the hosts report
+//! - [`message_relay`]: The routing between host and clients, generic over the web socket halves for testing
+
+mod hand_shake;
+pub mod lobby;
+pub mod message_relay;
+pub mod results;
+
+use crate::hand_shake::{
+    ClientServerSpecificData, DisconnectData, inform_client_of_connection, init_and_connect,
+    shutdown_connection,
+};
+use crate::lobby::{ActivityTracker, AppState, CorsOrigins, GameEntry, RoomListing};
+use crate::message_relay::{
+    ConnectionOptions, HostRoom, handle_client_logic, handle_server_logic, run_admin_tap,
+    run_lobby_chat,
+};
+use axum::Json;
+use axum::Router;
+use axum::extract::ws::{Message, WebSocket};
+use axum::extract::{ConnectInfo, FromRequestParts, Path, Query, Request, State, WebSocketUpgrade};
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderName, Method, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use bytes::Bytes;
+use futures_util::SinkExt;
+use futures_util::stream::StreamExt;
+use protocol::{CompoundRoomId, MAX_CHAT_MESSAGE_SIZE};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Component;
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::sync::Mutex;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use tower::ServiceExt;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::services::{ServeDir, ServeFile};
+
+/// Sets up the routing system to serve the web sockets of the games and the lobby chat and listen for the pages
+/// enlist and reload and the admin endpoints. The frontend of every configured game gets served under `/games/<game>/` from its own
+/// directory, see [`game_frontend_handler`]. All other requests get served as static files from the static root,
+/// which holds the top level lobby.
+///
+/// With [`AppState::cors_origins`] set, the public HTTP endpoints enlist, reload and rooms answer browser pages of
+/// the allowed origins with CORS headers. The admin endpoints stay restricted to the own origin.
+///
+/// The web socket endpoint needs the peer address, so the router has to be served with
+/// `into_make_service_with_connect_info::<SocketAddr>()`.
+pub fn router(app_state: Arc<AppState>) -> Router {
+    let static_root = app_state.static_root.clone();
+    let mut public_endpoints = Router::new()
+        .route("/reload", get(reload_handler))
+        .route("/enlist", get(enlist_handler))
+        .route("/rooms", get(rooms_handler));
+    if let Some(origins) = &app_state.cors_origins {
+        public_endpoints = public_endpoints.layer(cors_layer(origins));
+    }
+    Router::new()
+        .merge(public_endpoints)
+        .route("/admin/close/{room_id}", post(admin_close_handler))
+        .route("/admin/games", post(admin_add_game_handler))
+        .route("/admin/dump", get(admin_dump_handler))
+        .route("/admin/tap/{room_id}", get(admin_tap_handler))
+        .route("/ws", get(websocket_handler))
+        .route("/lobby", get(lobby_chat_handler))
+        .route("/games/{game_id}", get(game_frontend_redirect))
+        .route("/games/{game_id}/", get(game_frontend_handler))
+        .route("/games/{game_id}/{*path}", get(game_frontend_handler))
+        .with_state(app_state)
+        .fallback_service(
+            ServeDir::new(&static_root)
+                .not_found_service(ServeFile::new(static_root.join("index.html"))),
+        )
+}
+
+/// The CORS middleware for the public endpoints, which all only get read.
+fn cors_layer(origins: &CorsOrigins) -> CorsLayer {
+    let allow_origin = match origins {
+        CorsOrigins::Any => AllowOrigin::any(),
+        CorsOrigins::Listed(origins) => AllowOrigin::list(origins.iter().cloned()),
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET])
+}
+
+/// The header in which the reverse proxy passes on the address of the client.
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// Runs over all rooms and checks if they are diconnected from the server.
+/// If so, it cleans them up. This is a fallback solution things should be handled internally otherwise.
+pub async fn cleanup_dead_rooms(state: &Arc<AppState>) {
+    let mut rooms = state.rooms.lock().await;
+    rooms.retain(|room_id, room| {
+        let is_alive = !room.to_host_sender.is_closed();
+        if !is_alive {
+            tracing::info!("Removing dead room: {}", room_id);
+        }
+        is_alive
+    });
+}
+
+/// Closes all rooms whose host was idle for longer than the threshold, like [`AppState::close_room`] does.
+/// Connected hosts send a keep alive every [`protocol::KEEPALIVE_INTERVAL_SECS`], so only rooms whose host stopped
+/// talking without leaving get closed. Rooms held by a headless host of the relay have no host to be idle and stay open.
+pub async fn close_idle_rooms(state: &Arc<AppState>, threshold: Duration) {
+    let idle_rooms: Vec<CompoundRoomId> = state
+        .rooms
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, room)| !room.headless && room.last_activity.idle_time() > threshold)
+        .map(|(room_id, _)| room_id.clone())
+        .collect();
+    for room_id in idle_rooms {
+        if state.close_room(&room_id).await {
+            tracing::info!(room_id = room_id.as_str(), "Closing idle room.");
+        }
+    }
+}
+
+/// The time a connection task may go without a heartbeat before it counts as stuck.
+/// Connections get pinged every 30 seconds, so a healthy task beats at least that often.
+pub const STALE_CONNECTION_THRESHOLD: Duration = Duration::from_secs(300);
+
+/// Logs all connection tasks that did not touch their heartbeat for longer than [`STALE_CONNECTION_THRESHOLD`].
+/// This only diagnoses stuck tasks, it does not end them.
+pub fn log_stale_connections(state: &Arc<AppState>) {
+    for (description, idle_time) in state
+        .connections
+        .stale_connections(STALE_CONNECTION_THRESHOLD)
+    {
+        tracing::warn!(
+            task = description,
+            idle_secs = idle_time.as_secs(),
+            "Connection task seems to be stuck."
+        );
+    }
+}
+
+/// A snapshot of the load of the relay, as logged by [`log_metrics`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct RelayMetrics {
+    /// The amount of rooms, including the dead ones.
+    pub rooms: usize,
+    /// The amount of rooms whose host is gone, waiting for [`cleanup_dead_rooms`].
+    pub dead_rooms: usize,
+    /// The amount of players and spectators over all rooms, hosts included.
+    pub players: u32,
+    /// The amount of connection tasks in the registry, two for every connection.
+    pub connection_tasks: usize,
+}
+
+/// Aggregates the load of the relay over all rooms.
+pub async fn collect_metrics(state: &Arc<AppState>) -> RelayMetrics {
+    let snapshots = state.snapshot_rooms().await;
+    RelayMetrics {
+        rooms: snapshots.len(),
+        dead_rooms: snapshots.iter().filter(|room| !room.is_alive).count(),
+        players: snapshots
+            .iter()
+            .map(|room| room.amount_of_players as u32)
+            .sum(),
+        connection_tasks: state.connections.task_count(),
+    }
+}
+
+/// Logs the metrics of [`collect_metrics`].
+pub async fn log_metrics(state: &Arc<AppState>) {
+    let metrics = collect_metrics(state).await;
+    tracing::info!(
+        rooms = metrics.rooms,
+        dead_rooms = metrics.dead_rooms,
+        players = metrics.players,
+        connection_tasks = metrics.connection_tasks,
+        "Relay metrics."
+    );
+}
+
+/// The intervals of the jobs of the [`maintenance_task`]. A zero interval disables its job.
+#[derive(Clone, Copy, Debug)]
+pub struct MaintenanceIntervals {
+    /// How often [`cleanup_dead_rooms`] runs.
+    pub dead_room_sweep: Duration,
+    /// How often [`close_idle_rooms`] runs.
+    pub idle_room_sweep: Duration,
+    /// The idle time after which [`close_idle_rooms`] closes a room.
+    pub idle_room_threshold: Duration,
+    /// How often [`log_stale_connections`] runs.
+    pub stale_connection_sweep: Duration,
+    /// How often [`log_metrics`] runs.
+    pub metrics: Duration,
+}
+
+impl Default for MaintenanceIntervals {
+    /// Sweeps dead rooms every 20 minutes, rooms idle for 30 minutes and stale connections every minute
+    /// and logs the metrics every 5 minutes.
+    fn default() -> Self {
+        MaintenanceIntervals {
+            dead_room_sweep: Duration::from_secs(1200),
+            idle_room_sweep: Duration::from_secs(60),
+            idle_room_threshold: Duration::from_secs(1800),
+            stale_connection_sweep: Duration::from_secs(60),
+            metrics: Duration::from_secs(300),
+        }
+    }
+}
+
+/// An interval that first fires after one period, or none for a zero period.
+fn periodic(period: Duration) -> Option<Interval> {
+    if period.is_zero() {
+        return None;
+    }
+    let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+    // A job that fell behind runs once, not in a burst.
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    Some(interval)
+}
+
+/// Waits for the next tick of the interval, forever if there is none.
+async fn next_tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Runs all periodic maintenance of the relay: the sweep for dead rooms, the sweep for idle rooms, the sweep for
+/// stuck connection tasks and the metrics. Spawn it once, it never returns. The jobs run one after the other, so they never overlap.
+pub async fn maintenance_task(state: Arc<AppState>, intervals: MaintenanceIntervals) {
+    let mut dead_room_sweep = periodic(intervals.dead_room_sweep);
+    let mut idle_room_sweep = periodic(intervals.idle_room_sweep);
+    let mut stale_connection_sweep = periodic(intervals.stale_connection_sweep);
+    let mut metrics = periodic(intervals.metrics);
+    loop {
+        tokio::select! {
+            _ = next_tick(&mut dead_room_sweep) => cleanup_dead_rooms(&state).await,
+            _ = next_tick(&mut idle_room_sweep) => {
+                close_idle_rooms(&state, intervals.idle_room_threshold).await
+            }
+            _ = next_tick(&mut stale_connection_sweep) => log_stale_connections(&state),
+            _ = next_tick(&mut metrics) => log_metrics(&state).await,
+        }
+    }
+}
+
+/// Generates a list with the current rooms, the amount of players and info if this is a dead room.
+async fn enlist_handler(State(state): State<Arc<AppState>>) -> String {
+    state
+        .snapshot_rooms()
+        .await
+        .iter()
+        .map(|room| {
+            let variation = match &room.variation_name {
+                Some(name) => format!("{:03} ({})", room.rule_variation, name),
+                None => format!("{:03}", room.rule_variation),
+            };
+            format!(
+                "Room: {:<30}  Variation: {} Players: {:03} is alive: {} awaits host: {} Idle: {}s",
+                room.room_id,
+                variation,
+                room.amount_of_players,
+                room.is_alive,
+                room.awaiting_host,
+                room.idle_time.as_secs()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The optional filter of the room listing.
+#[derive(Deserialize)]
+struct RoomQuery {
+    /// Only list the rooms of this game.
+    game: Option<String>,
+}
+
+/// Lists the open rooms as JSON with their rule variation, so a lobby UI can show them before joining.
+/// With `?game=<name>` only the rooms of that game get listed.
+async fn rooms_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RoomQuery>,
+) -> Json<Vec<RoomListing>> {
+    Json(state.list_rooms(query.game.as_deref()).await)
+}
+
+/// Lets `/games/<game>` point to the directory, so that relative links of the frontend resolve within it.
+async fn game_frontend_redirect(Path(game_id): Path<String>) -> Redirect {
+    Redirect::permanent(&format!("/games/{}/", game_id))
+}
+
+/// Serves the static files of a game frontend from the directory `games/<game>` below the static root.
+/// Missing files fall back to the `index.html` of the game. Only configured games get served, and the game name
+/// has to be a plain directory name, so a request can never leave the static root.
+async fn game_frontend_handler(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<HashMap<String, String>>,
+    request: Request,
+) -> Response {
+    let Some(game_id) = params.get("game_id") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let mut components = std::path::Path::new(game_id).components();
+    let plain_name = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    );
+    if !plain_name || !state.configs.read().await.contains_key(game_id) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    // The file path within the game directory is what follows the game segment, still percent encoded.
+    let (mut parts, body) = request.into_parts();
+    let full_path = parts.uri.path();
+    let after_games = &full_path["/games/".len()..];
+    let inner_path = &after_games[after_games.find('/').unwrap_or(after_games.len())..];
+    let inner_uri = match parts.uri.query() {
+        Some(query) => format!("{}?{}", inner_path, query),
+        None => inner_path.to_string(),
+    };
+    let Ok(inner_uri) = inner_uri.parse() else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    parts.uri = inner_uri;
+
+    let game_root = state.static_root.join("games").join(game_id);
+    let index = ServeFile::new(game_root.join("index.html"));
+    match ServeDir::new(game_root)
+        .not_found_service(index)
+        .oneshot(Request::from_parts(parts, body))
+        .await
+    {
+        Ok(response) => response.into_response(),
+        Err(never) => match never {},
+    }
+}
+
+/// Extractor that only succeeds, if the request carries the admin token as `Authorization: Bearer <token>`.
+struct AdminAuthorization;
+
+impl FromRequestParts<Arc<AppState>> for AdminAuthorization {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(expected) = state.admin_token.as_deref() else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+        let provided = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        // Constant time, so the response time does not tell how much of the token got guessed right.
+        if provided
+            .is_some_and(|provided| bool::from(provided.as_bytes().ct_eq(expected.as_bytes())))
+        {
+            Ok(AdminAuthorization)
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+/// Forcibly closes the room with the given compound id (see [`CompoundRoomId`]).
+/// The `#` has to be sent as `%23` in the path.
+async fn admin_close_handler(
+    _: AdminAuthorization,
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<CompoundRoomId>,
+) -> StatusCode {
+    if state.close_room(&room_id).await {
+        tracing::info!(room_id = room_id.as_str(), "Room closed by admin.");
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Registers a game from a JSON [`GameEntry`] in the live configuration, without touching the config file.
+async fn admin_add_game_handler(
+    _: AdminAuthorization,
+    State(state): State<Arc<AppState>>,
+    Json(entry): Json<GameEntry>,
+) -> (StatusCode, String) {
+    let name = entry.name.clone();
+    match state.add_game(entry).await {
+        Ok(()) => {
+            tracing::info!(name, "Game added by admin.");
+            (StatusCode::OK, format!("Game {} added.", name))
+        }
+        Err(error) => (StatusCode::BAD_REQUEST, error),
+    }
+}
+
+/// Dumps the snapshot of all rooms as pretty JSON, for diagnosing a stuck relay.
+async fn admin_dump_handler(
+    _: AdminAuthorization,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let rooms = state.snapshot_rooms().await;
+    match serde_json::to_string_pretty(&rooms) {
+        Ok(json) => (StatusCode::OK, [(CONTENT_TYPE, "application/json")], json),
+        Err(error) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(CONTENT_TYPE, "text/plain")],
+            format!("Failed to serialize rooms: {}", error),
+        ),
+    }
+}
+
+/// Streams the traffic of the room with the given compound id over a web socket, one text frame per message,
+/// see [`run_admin_tap`]. The tap only listens and never sends anything into the game.
+/// The `#` has to be sent as `%23` in the path.
+async fn admin_tap_handler(
+    _: AdminAuthorization,
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<CompoundRoomId>,
+) -> Response {
+    let (tap, text_frames) = {
+        let rooms = state.rooms.lock().await;
+        let Some(room) = rooms.get(&room_id) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        (
+            room.host_to_client_broadcaster.subscribe_tap(),
+            room.text_frames,
+        )
+    };
+    let options = ConnectionOptions {
+        text_frames,
+        send_timeout: state.send_timeout,
+        max_rpcs_per_sec: None,
+    };
+    tracing::info!(room_id = room_id.as_str(), "Admin tap started.");
+    ws.on_upgrade(move |socket| async move {
+        let (sender, receiver) = socket.split();
+        let reason = run_admin_tap(Arc::new(Mutex::new(sender)), receiver, tap, options).await;
+        tracing::info!(room_id = room_id.as_str(), reason, "Admin tap ended.");
+    })
+}
+
+/// Forces the reload of the config file and lists the content. This enables the adding of new games
+/// without restarting the service.
+async fn reload_handler(State(state): State<Arc<AppState>>) -> String {
+    let res = lobby::reload_config(&state).await;
+    match res {
+        Ok(_) => state
+            .configs
+            .read()
+            .await
+            .iter()
+            .map(|(key, entry)| {
+                format!(
+                    "Game: {:<40} Maximum Amount of Players: {} Rule Variations: {}",
+                    key,
+                    entry.max_players,
+                    entry
+                        .allowed_variations
+                        .as_ref()
+                        .map_or("all".to_string(), |allowed| format!("{:?}", allowed))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(e) => {
+            format!("Config reload failed: {}", e)
+        }
+    }
+}
+
+/// Identifies where a connection came from, used to ban kicked clients from a room for a while.
+///
+/// The relay is meant to run behind a local reverse proxy, so for a connection from a loopback address the
+/// client address gets taken from the last `X-Forwarded-For` entry, which the proxy appended. Otherwise, and if
+/// that header is missing or malformed, the address of the peer itself is used.
+pub fn peer_address(peer: SocketAddr, headers: &HeaderMap) -> IpAddr {
+    if !peer.ip().is_loopback() {
+        return peer.ip();
+    }
+    headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .next_back()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|address| address.trim().parse().ok())
+        .unwrap_or(peer.ip())
+}
+
+/// This function gets immediately called and upgrades the web response to a web socket.
+/// A peer that already holds [`AppState::max_connections_per_peer`] connections gets rejected
+/// with `429 Too Many Requests` instead.
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    let peer = peer_address(peer, &headers);
+    let Some(slot) = state
+        .peer_connections
+        .try_acquire(peer, state.max_connections_per_peer)
+    else {
+        tracing::warn!(%peer, "Too many connections from peer.");
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many connections from this address.",
+        )
+            .into_response();
+    };
+    ws.on_upgrade(move |socket| async move {
+        // The slot gets given back, once the connection is shut down.
+        let _slot = slot;
+        websocket(socket, state, peer).await
+    })
+}
+
+/// Upgrades the web response to a web socket for the lobby chat, see [`run_lobby_chat`].
+/// The chat connections count towards [`AppState::max_connections_per_peer`] like the game connections.
+async fn lobby_chat_handler(
+    ws: WebSocketUpgrade,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    let peer = peer_address(peer, &headers);
+    let Some(slot) = state
+        .peer_connections
+        .try_acquire(peer, state.max_connections_per_peer)
+    else {
+        tracing::warn!(%peer, "Too many connections from peer.");
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many connections from this address.",
+        )
+            .into_response();
+    };
+    // A bit beyond the limit of a chat line, so an oversized line still arrives and gets reported as such.
+    ws.max_message_size(2 * MAX_CHAT_MESSAGE_SIZE)
+        .on_upgrade(move |socket| async move {
+            let _slot = slot;
+            let (sender, receiver) = socket.split();
+            let reason = run_lobby_chat(
+                Arc::new(Mutex::new(sender)),
+                receiver,
+                state.lobby_chat.clone(),
+                state.send_timeout,
+            )
+            .await;
+            tracing::info!(%peer, reason, "Lobby chat ended.");
+        })
+}
+
+/// Does the whole handling from start to finish: Handshake -> Handling of logic depending on if we are connected to
+/// the server or client -> Shut down processing.
+async fn websocket(stream: WebSocket, state: Arc<AppState>, peer: IpAddr) {
+    // By splitting, we can send and receive at the same time.
+    let (mut sender, mut receiver) = stream.split();
+
+    let handshake_result = init_and_connect(&mut sender, &mut receiver, state.clone(), peer).await;
+    if handshake_result.is_none() {
+        // We quit here, as the handshake did not work out.
+        return;
+    }
+    let base_data = handshake_result.unwrap();
+
+    let disconnect_data = DisconnectData::from(&base_data);
+    let success = inform_client_of_connection(&mut sender, &base_data).await;
+    let wrapped_sender = Arc::new(Mutex::new(sender));
+
+    // The heartbeats of the tasks, to find stuck ones. They stay registered until the connection is shut down.
+    // The receive task of a host beats with the activity of its room, which includes the keep alive messages.
+    let connection_name = format!("{} player {}", base_data.room_id, base_data.player_id);
+    let ping_heartbeat = ActivityTracker::new();
+    let _ping_registration = state
+        .connections
+        .register(format!("{} ping", connection_name), ping_heartbeat.clone());
+    let receive_heartbeat = match &base_data.specific_data {
+        ClientServerSpecificData::Server(_, _, activity) => activity.clone(),
+        ClientServerSpecificData::Client(_, _) => ActivityTracker::new(),
+    };
+    let _receive_registration = state.connections.register(
+        format!("{} receive", connection_name),
+        receive_heartbeat.clone(),
+    );
+
+    // Ping-Task to keep alive. It shares the sender lock, so it also stalls if a send got stuck.
+    let ping_sender = wrapped_sender.clone();
+    let ping_task = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        interval.tick().await; // Skip first tick.
+        loop {
+            interval.tick().await;
+            let mut s = ping_sender.lock().await;
+            if s.send(Message::Ping(Bytes::new())).await.is_err() {
+                break;
+            }
+            ping_heartbeat.touch();
+        }
+    });
+
+    let options = ConnectionOptions {
+        text_frames: base_data.text_frames,
+        send_timeout: state.send_timeout,
+        max_rpcs_per_sec: base_data.max_rpcs_per_sec,
+    };
+    let mut error_message = "Connection to server lost";
+    if success {
+        match base_data.specific_data {
+            ClientServerSpecificData::Server(internal_receiver, internal_sender, activity) => {
+                error_message = handle_server_logic(
+                    wrapped_sender.clone(),
+                    receiver,
+                    internal_receiver,
+                    internal_sender,
+                    HostRoom {
+                        room_id: base_data.room_id,
+                        results: state.results.clone(),
+                    },
+                    activity,
+                    options,
+                )
+                .await;
+            }
+            ClientServerSpecificData::Client(internal_receiver, internal_sender) => {
+                error_message = handle_client_logic(
+                    wrapped_sender.clone(),
+                    receiver,
+                    internal_receiver,
+                    internal_sender,
+                    base_data.player_id,
+                    receive_heartbeat,
+                    options,
+                )
+                .await;
+            }
+        }
+    }
+
+    ping_task.abort();
+    shutdown_connection(wrapped_sender, disconnect_data, state, error_message).await;
+}
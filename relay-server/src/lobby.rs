@@ -1,28 +1,422 @@
 //! This module handles game rooms where players connect and exchange messages.
 //! It provides:
 //! - [`Room`]: A game session with host-to-client broadcast channels
+//! - [`PersistentRoom`]: A room the configuration keeps open with a headless host of the relay
+//! - [`RoomBroadcast`]: The broadcast channels of a room, separated into players and spectators, with a tap for admins
+//! - [`TappedFrame`]: A message of a room as mirrored to the admin tap
+//! - [`ActivityTracker`]: The time of the last activity of a room's host
+//! - [`MessageCounters`]: The amount of messages of a room per message type, logged when the room ends
+//! - [`ConnectionRegistry`]: Liveness time stamps of the connection tasks, to find tasks that got stuck
+//! - [`PeerConnections`]: The amount of open connections per peer address, to enforce a limit
 //! - [`AppState`]: Global state holding all active rooms and game configurations
-//! - [`reload_config`]: Hot-reloading of game settings from `GameConfig.json`
+//! - [`CorsOrigins`]: The origins that may call the HTTP endpoints from a browser page of another origin
+//! - [`RoomSnapshot`]: An owned copy of the state of a room for listings and admin tooling
+//! - [`RoomListing`]: The public view of a room for lobby UIs, including its rule variation
+//! - [`reload_config`]: Hot-reloading of game settings from `GameConfig.json`, validated by [`parse_config`]
+//! - [`rooms_of_game`]: The amount of rooms of a game, to enforce [`GameEntry::max_rooms`]
 
-use bytes::Bytes;
+use crate::results::ResultsSink;
+use axum::http::HeaderValue;
+use bytes::{BufMut, Bytes, BytesMut};
+use protocol::{
+    CHANNEL_BUFFER_SIZE, CODEC_POSTCARD, CompoundRoomId, GameId, HostDeparture,
+    MAX_ROOM_NAME_LENGTH, RoomId, SERVER_DISCONNECT_MSG_SIZE, SERVER_DISCONNECTS, SPECTATOR_COUNT,
+    SPECTATOR_COUNT_MSG_SIZE,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, Instant};
 use tokio::fs;
 use tokio::sync::{Mutex, RwLock};
 use tokio::sync::{broadcast, mpsc};
 
 /// The game entry we have for one game.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GameEntry {
     /// The name of the game.
     pub name: String,
     /// The maximum amount of players (0 = no limit)
     pub max_players: u16,
+    /// The rule variations a host may open a room with. If missing, all variations are allowed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_variations: Option<Vec<u16>>,
+    /// The amount of clients that may join as spectators once all player slots are taken.
+    /// Only applies if `max_players` is not 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_spectators: Option<u16>,
+    /// Human-readable names of the rule variations, like "Blitz", for listings. The relay does not interpret them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variation_names: Option<HashMap<u16, String>>,
+    /// The amount of rooms of the game that may be open at the same time. If missing, there is no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rooms: Option<u16>,
+    /// Rooms the relay opens on its own with a headless host, like a public table that exists right from the start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistent_rooms: Option<Vec<PersistentRoom>>,
+    /// Flags, if a room survives its host leaving: the client that joined first becomes the new host.
+    /// The game has to be able to continue from the view state of that client.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub host_migration: bool,
+    /// The amount of RPCs a single client may send per second, with bursts of up to a second's worth.
+    /// RPCs beyond get dropped by the relay. If missing, there is no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rpcs_per_sec: Option<u32>,
+}
+
+/// A room of a game the relay keeps open, see [`GameEntry::persistent_rooms`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PersistentRoom {
+    /// The name of the room as the players see it.
+    pub room: String,
+    /// The rule variation the room gets opened with.
+    #[serde(default)]
+    pub rule_variation: u16,
+}
+
+impl GameEntry {
+    /// Checks if a room may be opened with the indicated rule variation.
+    pub fn allows_variation(&self, rule_variation: u16) -> bool {
+        self.allowed_variations
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(&rule_variation))
+    }
+
+    /// The name of the rule variation, if the configuration gives it one.
+    pub fn variation_name(&self, rule_variation: u16) -> Option<&str> {
+        self.variation_names
+            .as_ref()
+            .and_then(|names| names.get(&rule_variation))
+            .map(String::as_str)
+    }
+
+    /// Checks if clients may join as spectators once all player slots are taken.
+    pub fn has_spectator_slots(&self) -> bool {
+        self.max_players != 0 && self.max_spectators.is_some_and(|spectators| spectators > 0)
+    }
+
+    /// The amount of players and spectators a room may hold (0 = no limit by the game).
+    pub fn room_capacity(&self) -> u16 {
+        if self.max_players == 0 {
+            0
+        } else {
+            self.max_players
+                .saturating_add(self.max_spectators.unwrap_or(0))
+        }
+    }
 }
 
 type EntryList = Vec<GameEntry>;
 
+/// Tracks when the host of a room was last active. Clones share the same time stamp.
+#[derive(Clone)]
+pub struct ActivityTracker {
+    /// The point in time the tracker was created, the reference for the time stamp.
+    created: Instant,
+    /// Milliseconds since creation at the last activity.
+    last_activity_millis: Arc<AtomicU64>,
+}
+
+impl ActivityTracker {
+    /// Creates a tracker that counts the creation as activity.
+    pub fn new() -> Self {
+        ActivityTracker {
+            created: Instant::now(),
+            last_activity_millis: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Marks the current moment as activity.
+    pub fn touch(&self) {
+        let millis = self.created.elapsed().as_millis() as u64;
+        self.last_activity_millis.store(millis, Ordering::Relaxed);
+    }
+
+    /// The time passed since the last activity.
+    pub fn idle_time(&self) -> Duration {
+        let last = Duration::from_millis(self.last_activity_millis.load(Ordering::Relaxed));
+        self.created.elapsed().saturating_sub(last)
+    }
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The registered heartbeats, keyed by a consecutive id.
+#[derive(Default)]
+struct RegistryEntries {
+    /// The id the next registered heartbeat gets.
+    next_id: u64,
+    /// The description of every heartbeat with its time stamp.
+    heartbeats: HashMap<u64, (String, ActivityTracker)>,
+}
+
+/// Liveness time stamps of the tasks that serve the connections. Clones share the same registry.
+///
+/// Every task gets an [`ActivityTracker`] registered here and touches it in its loop. A periodic sweep lists the
+/// trackers that went stale, which points to tasks that are stuck, e.g. in a dead lock. This is purely diagnostic.
+#[derive(Default, Clone)]
+pub struct ConnectionRegistry {
+    /// The entries, behind a synchronous lock, so a registration can remove itself on drop.
+    entries: Arc<std::sync::Mutex<RegistryEntries>>,
+}
+
+impl ConnectionRegistry {
+    /// Registers the tracker of a task with a description for the log, until the returned registration gets dropped.
+    pub fn register(
+        &self,
+        description: String,
+        tracker: ActivityTracker,
+    ) -> ConnectionRegistration {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let id = entries.next_id;
+        entries.next_id += 1;
+        entries.heartbeats.insert(id, (description, tracker));
+        ConnectionRegistration {
+            id,
+            registry: self.clone(),
+        }
+    }
+
+    /// Lists the descriptions of all trackers that were not touched for longer than the threshold, with their idle time.
+    pub fn stale_connections(&self, threshold: Duration) -> Vec<(String, Duration)> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .heartbeats
+            .values()
+            .map(|(description, tracker)| (description.clone(), tracker.idle_time()))
+            .filter(|(_, idle_time)| *idle_time > threshold)
+            .collect()
+    }
+
+    /// The amount of tasks registered right now.
+    pub fn task_count(&self) -> usize {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.heartbeats.len()
+    }
+}
+
+/// The registration of one task in the [`ConnectionRegistry`], removed again when dropped.
+/// Keep it as long as the task runs: a task that never ends keeps its registration, which then goes stale.
+pub struct ConnectionRegistration {
+    /// The key in the registry.
+    id: u64,
+    /// The registry to remove us from.
+    registry: ConnectionRegistry,
+}
+
+impl Drop for ConnectionRegistration {
+    fn drop(&mut self) {
+        let mut entries = self
+            .registry
+            .entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        entries.heartbeats.remove(&self.id);
+    }
+}
+
+/// The amount of open web socket connections per peer address. Clones share the same counters.
+#[derive(Default, Clone)]
+pub struct PeerConnections {
+    /// The counters, behind a synchronous lock, so a slot can give itself back on drop.
+    counts: Arc<std::sync::Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl PeerConnections {
+    /// Takes a connection slot for the peer, unless it already holds `limit` of them (`None` = no limit).
+    /// The slot stays taken until the returned [`PeerConnectionSlot`] gets dropped.
+    pub fn try_acquire(&self, peer: IpAddr, limit: Option<usize>) -> Option<PeerConnectionSlot> {
+        let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        // Checked before the entry gets created, so rejected peers leave nothing behind.
+        let count = counts.get(&peer).copied().unwrap_or(0);
+        if limit.is_some_and(|limit| count >= limit) {
+            return None;
+        }
+        counts.insert(peer, count + 1);
+        Some(PeerConnectionSlot {
+            peer,
+            connections: self.clone(),
+        })
+    }
+
+    /// The amount of connections the peer currently holds.
+    pub fn count(&self, peer: IpAddr) -> usize {
+        let counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        counts.get(&peer).copied().unwrap_or(0)
+    }
+
+    /// The amount of peers that currently hold connections.
+    pub fn peers(&self) -> usize {
+        self.counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
+
+/// A connection slot of a peer in the [`PeerConnections`], given back when dropped.
+pub struct PeerConnectionSlot {
+    /// The peer that holds the slot.
+    peer: IpAddr,
+    /// The counters to give the slot back to.
+    connections: PeerConnections,
+}
+
+impl Drop for PeerConnectionSlot {
+    fn drop(&mut self) {
+        let mut counts = self
+            .connections
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = counts.get_mut(&self.peer) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.peer);
+            }
+        }
+    }
+}
+
+/// Counts the messages of a room per message type and direction, for a histogram in the log when the host leaves.
+/// Clones share the same counters.
+#[derive(Clone)]
+pub struct MessageCounters {
+    /// The counters of the messages to the host and from the host, indexed by the message type.
+    counts: Arc<[[AtomicU64; 256]; 2]>,
+}
+
+impl MessageCounters {
+    /// Creates counters that are all zero.
+    pub fn new() -> Self {
+        MessageCounters {
+            counts: Arc::new(std::array::from_fn(|_| {
+                std::array::from_fn(|_| AtomicU64::new(0))
+            })),
+        }
+    }
+
+    /// Counts a message of the indicated type.
+    pub fn count(&self, to_host: bool, message_type: u8) {
+        self.counts[to_host as usize][message_type as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The message types that occurred in the indicated direction with their amount, ordered by the message type.
+    pub fn occurred(&self, to_host: bool) -> Vec<(u8, u64)> {
+        self.counts[to_host as usize]
+            .iter()
+            .zip(0..=u8::MAX)
+            .map(|(count, message_type)| (message_type, count.load(Ordering::Relaxed)))
+            .filter(|&(_, count)| count > 0)
+            .collect()
+    }
+}
+
+impl Default for MessageCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The broadcast channels from the host to the clients of a room. Clones share the same channels.
+///
+/// Players subscribe to the channel that carries every message of the host. Spectators subscribe to the public channel,
+/// which lacks the private messages meant for single players, like [`DELTA_UPDATE_TO`](protocol::DELTA_UPDATE_TO).
+/// This way private information never even reaches the tasks serving the spectators.
+///
+/// Additionally, every message between host and clients gets mirrored to the tap channel, if an admin listens.
+/// The tap has a channel of its own, so a slow admin never counts into the backlog of the clients.
+#[derive(Clone)]
+pub struct RoomBroadcast {
+    /// All messages of the host, subscribed by the players.
+    players: broadcast::Sender<Bytes>,
+    /// The public messages of the host, subscribed by the spectators.
+    spectators: broadcast::Sender<Bytes>,
+    /// The traffic in both directions, subscribed by admin taps.
+    tap: broadcast::Sender<TappedFrame>,
+    /// The amount of messages per type, counted by the tasks serving the host.
+    counters: MessageCounters,
+}
+
+/// A message between host and clients, as mirrored to the admin tap.
+#[derive(Clone, Debug)]
+pub struct TappedFrame {
+    /// Flags, if the message went from the clients (or the relay) to the host.
+    pub to_host: bool,
+    /// The message in its binary layout, as the relay handles it internally.
+    pub frame: Bytes,
+}
+
+impl RoomBroadcast {
+    /// Creates all channels with the indicated capacity.
+    pub fn new(capacity: usize) -> Self {
+        RoomBroadcast {
+            players: broadcast::channel(capacity).0,
+            spectators: broadcast::channel(capacity).0,
+            tap: broadcast::channel(capacity).0,
+            counters: MessageCounters::new(),
+        }
+    }
+
+    /// The amount of messages per type that passed the host's connection.
+    pub fn counters(&self) -> &MessageCounters {
+        &self.counters
+    }
+
+    /// Sends a message to players and spectators. Returns the amount of receivers, 0 if nobody listens.
+    pub fn send_public(&self, bytes: Bytes) -> usize {
+        self.mirror(false, &bytes);
+        self.players.send(bytes.clone()).unwrap_or(0) + self.spectators.send(bytes).unwrap_or(0)
+    }
+
+    /// Sends a message to the players only. Returns the amount of receivers, 0 if nobody listens.
+    pub fn send_private(&self, bytes: Bytes) -> usize {
+        self.mirror(false, &bytes);
+        self.players.send(bytes).unwrap_or(0)
+    }
+
+    /// Hands a copy of a message to the admin taps, if any listens.
+    pub fn mirror(&self, to_host: bool, frame: &Bytes) {
+        if self.tap.receiver_count() > 0 {
+            let _ = self.tap.send(TappedFrame {
+                to_host,
+                frame: frame.clone(),
+            });
+        }
+    }
+
+    /// Subscribes an admin tap to the traffic of the room in both directions. It can not send anything itself.
+    pub fn subscribe_tap(&self) -> broadcast::Receiver<TappedFrame> {
+        self.tap.subscribe()
+    }
+
+    /// Subscribes a client to the channel for its role.
+    pub fn subscribe(&self, is_spectator: bool) -> broadcast::Receiver<Bytes> {
+        if is_spectator {
+            self.spectators.subscribe()
+        } else {
+            self.players.subscribe()
+        }
+    }
+
+    /// The most messages any client of either channel has not picked up yet.
+    pub fn backlog(&self) -> usize {
+        self.players.len().max(self.spectators.len())
+    }
+
+    /// Checks if both refer to the channels of the same room.
+    pub fn same_channel(&self, other: &RoomBroadcast) -> bool {
+        self.players.same_channel(&other.players)
+    }
+}
+
 /// The description of the room, the players play in
 pub struct Room {
     /// The next id a client gets, this is consecutively counted.
@@ -34,33 +428,435 @@ pub struct Room {
     /// The sender to send messages to the host.
     pub to_host_sender: mpsc::Sender<Bytes>, // Clone-able no Mutex!
     /// The broad case sender needed to subscribe for the clients.
-    pub host_to_client_broadcaster: broadcast::Sender<Bytes>, // Clone-able -> no Mutex!
+    pub host_to_client_broadcaster: RoomBroadcast, // Clone-able -> no Mutex!
+    /// The last activity of the host, including keep alive messages.
+    pub last_activity: ActivityTracker, // Clone-able -> no Mutex!
+    /// Flags, if the host joined in the JSON debugging mode. All clients have to use the same mode.
+    pub text_frames: bool,
+    /// The codec of the payloads the host announced in its join request. All clients have to use the same codec.
+    pub codec: u8,
+    /// The peers the host kicked, with the time their ban ends.
+    pub banned: HashMap<IpAddr, Instant>,
+    /// The amount of clients in the room that joined as spectators, they are included in the amount of players.
+    pub spectators: u16,
+    /// Flags, if the headless host of the relay holds the room, see [`Room::headless`].
+    pub headless: bool,
+    /// The clients that joined as players, in the order they joined. The first one becomes the new host
+    /// on a host migration.
+    pub players: Vec<u16>,
+    /// Flags, if the room migrates to a new host when its host leaves, see [`GameEntry::host_migration`].
+    pub host_migration: bool,
+    /// The token the new host has to present to take the room over, set while the room waits for it after a host
+    /// migration. Only the new host got it, see [`HOST_MIGRATION`](protocol::HOST_MIGRATION).
+    pub migration_token: Option<u64>,
+}
+
+impl Room {
+    /// Creates a persistent room held by a headless host of the relay. The headless host only keeps the channel
+    /// to it open, so the room stays alive and gets listed. Clients can not join, until a real host takes the room over
+    /// by opening a room of the same name. The headless host ends, once the room gets dropped.
+    pub fn headless(rule_variation: u16) -> Room {
+        let (to_host_sender, mut to_host_receiver) = mpsc::channel::<Bytes>(CHANNEL_BUFFER_SIZE);
+        tokio::spawn(async move { while to_host_receiver.recv().await.is_some() {} });
+        Room {
+            next_client_id: 1,
+            amount_of_players: 1,
+            rule_variation,
+            to_host_sender,
+            host_to_client_broadcaster: RoomBroadcast::new(CHANNEL_BUFFER_SIZE),
+            last_activity: ActivityTracker::new(),
+            text_frames: false,
+            codec: CODEC_POSTCARD,
+            banned: HashMap::new(),
+            spectators: 0,
+            headless: true,
+            players: Vec::new(),
+            host_migration: false,
+            migration_token: None,
+        }
+    }
+
+    /// Creates the room that waits for the new host after a host migration. It is held by a headless host like a
+    /// persistent room, but only a host presenting the indicated token may take it over.
+    pub fn migration_placeholder(&self, migration_token: u64) -> Room {
+        Room {
+            text_frames: self.text_frames,
+            codec: self.codec,
+            banned: self.banned.clone(),
+            migration_token: Some(migration_token),
+            ..Room::headless(self.rule_variation)
+        }
+    }
+
+    /// Tells the host and all clients the amount of spectators, see [`SPECTATOR_COUNT`].
+    /// Gets called with the room map locked, so the announcements go out in the order of the changes.
+    /// If the channel to the host is full, the host misses this announcement.
+    pub fn announce_spectators(&self) {
+        let mut msg = BytesMut::with_capacity(SPECTATOR_COUNT_MSG_SIZE);
+        msg.put_u8(SPECTATOR_COUNT);
+        msg.put_u16(self.spectators);
+        let msg: Bytes = msg.into();
+        if self.to_host_sender.try_send(msg.clone()).is_err() {
+            tracing::warn!("Could not announce the spectator count to the host.");
+        }
+        self.host_to_client_broadcaster.send_public(msg);
+    }
+
+    /// Keeps the peer from joining the room again for the indicated duration.
+    pub fn ban(&mut self, peer: IpAddr, duration: Duration) {
+        self.banned.insert(peer, Instant::now() + duration);
+    }
+
+    /// Checks if the peer is still banned and forgets all bans that ran out.
+    pub fn is_banned(&mut self, peer: IpAddr) -> bool {
+        let now = Instant::now();
+        self.banned.retain(|_, end| *end > now);
+        self.banned.contains_key(&peer)
+    }
+}
+
+/// Counts the rooms of the game in the room map.
+pub fn rooms_of_game(rooms: &HashMap<CompoundRoomId, Room>, game: &GameId) -> usize {
+    rooms
+        .keys()
+        .filter(|room_id| room_id.is_of_game(game))
+        .count()
+}
+
+/// An owned copy of the relevant state of a room at one moment, as handed out by [`AppState::snapshot_rooms`].
+/// Serializes without any channel handles, the idle time as whole seconds.
+#[derive(Clone, Debug, Serialize)]
+pub struct RoomSnapshot {
+    /// The key of the room in the room map.
+    pub room_id: CompoundRoomId,
+    /// The rule variation the room was opened with.
+    pub rule_variation: u16,
+    /// The name of the rule variation, if the configuration of the game gives it one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variation_name: Option<String>,
+    /// The amount of players currently in the room.
+    pub amount_of_players: u16,
+    /// Flags, if the host is still connected.
+    pub is_alive: bool,
+    /// Flags, if a headless host of the relay holds the room until a real host takes it over, see [`Room::headless`].
+    /// Clients can not join the room meanwhile.
+    pub awaiting_host: bool,
+    /// The time passed since the last activity of the host.
+    #[serde(rename = "idle_secs", serialize_with = "serialize_secs")]
+    pub idle_time: Duration,
+}
+
+/// Writes a duration as whole seconds.
+fn serialize_secs<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_secs())
+}
+
+/// The public view of a room for lobby UIs, as handed out by [`AppState::list_rooms`].
+#[derive(Clone, Debug, Serialize)]
+pub struct RoomListing {
+    /// The name of the room as the players entered it.
+    pub room: String,
+    /// The name of the game played in the room.
+    pub game: String,
+    /// The rule variation the room was opened with, so a client knows it before joining.
+    pub rule_variation: u16,
+    /// The name of the rule variation, if the configuration of the game gives it one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variation_name: Option<String>,
+    /// The amount of players currently in the room.
+    pub amount_of_players: u16,
+}
+
+/// The default for [`AppState::send_timeout`].
+pub const DEFAULT_SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The amount of chat lines the lobby chat buffers for a connection that falls behind.
+pub const LOBBY_CHAT_BUFFER_SIZE: usize = 64;
+
+/// The default for [`AppState::ban_duration`].
+pub const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(60);
+
+/// The origins that may call the HTTP endpoints of the relay from a browser page of another origin,
+/// e.g. a lobby page hosted elsewhere. The web sockets are not affected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CorsOrigins {
+    /// Every origin may call the endpoints.
+    Any,
+    /// Only the listed origins, like `https://board-game-hub.de`, may call the endpoints.
+    Listed(Vec<HeaderValue>),
+}
+
+impl CorsOrigins {
+    /// Parses a comma separated list of origins, where `*` allows every origin.
+    pub fn parse(list: &str) -> Result<CorsOrigins, String> {
+        let origins: Vec<&str> = list
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        if origins.is_empty() {
+            return Err("No origin given.".to_string());
+        }
+        if origins.contains(&"*") {
+            return Ok(CorsOrigins::Any);
+        }
+        origins
+            .into_iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin).map_err(|_| format!("Invalid origin: {}", origin))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(CorsOrigins::Listed)
+    }
 }
 
 /// The application state.
-#[derive(Default)]
 pub struct AppState {
     /// The rooms we associate with several sessions.
-    pub rooms: Mutex<HashMap<String, Room>>,
-    /// Contains a mapping from game name to the maximum amount of players allowed.
-    pub configs: RwLock<HashMap<String, u16>>,
+    pub rooms: Mutex<HashMap<CompoundRoomId, Room>>,
+    /// Contains a mapping from game name to its configuration entry.
+    pub configs: RwLock<HashMap<String, GameEntry>>,
+    /// The token the admin endpoints require. If missing, the admin endpoints reject every request.
+    pub admin_token: Option<String>,
+    /// The heartbeats of the tasks serving the connections.
+    pub connections: ConnectionRegistry,
+    /// Where the game results the hosts report go.
+    pub results: ResultsSink,
+    /// How long sending a single frame to a host or client may take, before the connection gets dropped.
+    pub send_timeout: Duration,
+    /// How long a client the host kicked may not join the same room again.
+    pub ban_duration: Duration,
+    /// The directory the static files get served from. The frontend of a game lives in its subdirectory `games/<game>`.
+    pub static_root: PathBuf,
+    /// The open connections of every peer address.
+    pub peer_connections: PeerConnections,
+    /// The amount of connections a single peer address may hold at the same time. If missing, there is no limit.
+    pub max_connections_per_peer: Option<usize>,
+    /// The chat of the lobby, shared by all connections to `/lobby` and independent of the rooms.
+    pub lobby_chat: broadcast::Sender<String>,
+    /// The origins that may call the HTTP endpoints from another origin. If missing, no CORS headers get sent.
+    pub cors_origins: Option<CorsOrigins>,
 }
 
-/// Reloads the configuration file, that lists the games with the maximum number of players per room.
+impl Default for AppState {
+    fn default() -> Self {
+        AppState {
+            rooms: Mutex::default(),
+            configs: RwLock::default(),
+            admin_token: None,
+            connections: ConnectionRegistry::default(),
+            results: ResultsSink::default(),
+            send_timeout: DEFAULT_SEND_TIMEOUT,
+            ban_duration: DEFAULT_BAN_DURATION,
+            static_root: PathBuf::from("."),
+            peer_connections: PeerConnections::default(),
+            max_connections_per_peer: None,
+            lobby_chat: broadcast::channel(LOBBY_CHAT_BUFFER_SIZE).0,
+            cors_origins: None,
+        }
+    }
+}
+
+impl AppState {
+    /// Takes the room lock once and returns a snapshot of every room.
+    pub async fn snapshot_rooms(&self) -> Vec<RoomSnapshot> {
+        // The names get copied first, so the configuration is not locked together with the rooms.
+        let variation_names: HashMap<String, HashMap<u16, String>> = self
+            .configs
+            .read()
+            .await
+            .values()
+            .filter_map(|entry| Some((entry.name.clone(), entry.variation_names.clone()?)))
+            .collect();
+        let rooms = self.rooms.lock().await;
+        rooms
+            .iter()
+            .map(|(room_id, room)| RoomSnapshot {
+                room_id: room_id.clone(),
+                rule_variation: room.rule_variation,
+                variation_name: room_id
+                    .split()
+                    .and_then(|(_, game)| variation_names.get(game.as_str()))
+                    .and_then(|names| names.get(&room.rule_variation))
+                    .cloned(),
+                amount_of_players: room.amount_of_players,
+                is_alive: !room.to_host_sender.is_closed(),
+                awaiting_host: room.headless,
+                idle_time: room.last_activity.idle_time(),
+            })
+            .collect()
+    }
+
+    /// Lists the rooms with a connected host for lobby UIs, optionally only those of one game.
+    /// Rooms awaiting their host are left out, as clients can not join them.
+    pub async fn list_rooms(&self, game: Option<&str>) -> Vec<RoomListing> {
+        self.snapshot_rooms()
+            .await
+            .into_iter()
+            .filter(|snapshot| snapshot.is_alive && !snapshot.awaiting_host)
+            .filter_map(|snapshot| {
+                let (room, room_game) = snapshot.room_id.split()?;
+                Some(RoomListing {
+                    room: room.into(),
+                    game: room_game.into(),
+                    rule_variation: snapshot.rule_variation,
+                    variation_name: snapshot.variation_name,
+                    amount_of_players: snapshot.amount_of_players,
+                })
+            })
+            .filter(|listing| game.is_none_or(|game| listing.game == game))
+            .collect()
+    }
+
+    /// Adds a game to the live configuration or replaces the entry of the same name.
+    /// The configuration file stays untouched, so the next reload drops the game again unless it is in the file.
+    pub async fn add_game(&self, entry: GameEntry) -> Result<(), String> {
+        validate_entry(&entry)?;
+        self.configs.write().await.insert(entry.name.clone(), entry);
+        Ok(())
+    }
+
+    /// Opens every configured persistent room that has no living host, each with a headless host of its own.
+    /// Rooms that got removed from the configuration stay open, until they get closed.
+    pub async fn open_persistent_rooms(&self) {
+        // The list gets copied first, so the configuration is not locked together with the rooms.
+        let persistent_rooms: Vec<(String, PersistentRoom)> = self
+            .configs
+            .read()
+            .await
+            .values()
+            .flat_map(|entry| {
+                entry
+                    .persistent_rooms
+                    .iter()
+                    .flatten()
+                    .map(|room| (entry.name.clone(), room.clone()))
+            })
+            .collect();
+        let mut rooms = self.rooms.lock().await;
+        for (game, persistent_room) in persistent_rooms {
+            let room_id =
+                CompoundRoomId::compound(&RoomId::new(persistent_room.room), &GameId::new(game));
+            if rooms
+                .get(&room_id)
+                .is_some_and(|room| !room.to_host_sender.is_closed())
+            {
+                continue;
+            }
+            tracing::info!(room_id = room_id.as_str(), "Opening persistent room.");
+            rooms.insert(room_id, Room::headless(persistent_room.rule_variation));
+        }
+    }
+
+    /// Forcibly closes a room: All clients get informed that the server left and the room gets removed.
+    /// The host connection ends, once the last client is gone. Returns false if there is no such room.
+    pub async fn close_room(&self, room_id: &CompoundRoomId) -> bool {
+        let Some(room) = self.rooms.lock().await.remove(room_id) else {
+            return false;
+        };
+        let mut msg = BytesMut::with_capacity(SERVER_DISCONNECT_MSG_SIZE);
+        msg.put_u8(SERVER_DISCONNECTS);
+        msg.put_u8(HostDeparture::Closed.code());
+        // Reaches nobody, if no client is connected. Then there is nobody to inform.
+        room.host_to_client_broadcaster.send_public(msg.into());
+        true
+    }
+}
+
+/// Checks a single game entry on its own.
+fn validate_entry(entry: &GameEntry) -> Result<(), String> {
+    if entry.name.trim().is_empty() {
+        return Err("Game entry with an empty name".to_string());
+    }
+    for (rule_variation, name) in entry.variation_names.iter().flatten() {
+        if name.trim().is_empty() {
+            return Err(format!(
+                "Empty name for rule variation {} of game {}",
+                rule_variation, entry.name
+            ));
+        }
+        if !entry.allows_variation(*rule_variation) {
+            return Err(format!(
+                "Named rule variation {} is not allowed for game {}",
+                rule_variation, entry.name
+            ));
+        }
+    }
+    if entry.max_rpcs_per_sec == Some(0) {
+        return Err(format!(
+            "Game {} must allow at least one RPC per second",
+            entry.name
+        ));
+    }
+    let persistent_rooms = entry.persistent_rooms.as_deref().unwrap_or_default();
+    if let Some(max_rooms) = entry.max_rooms
+        && persistent_rooms.len() > max_rooms as usize
+    {
+        return Err(format!(
+            "Game {} has more persistent rooms than its limit of {} rooms",
+            entry.name, max_rooms
+        ));
+    }
+    for (index, persistent_room) in persistent_rooms.iter().enumerate() {
+        if persistent_room.room.is_empty()
+            || persistent_room.room.chars().count() > MAX_ROOM_NAME_LENGTH
+        {
+            return Err(format!(
+                "Persistent room name of game {} must have 1 to {} characters",
+                entry.name, MAX_ROOM_NAME_LENGTH
+            ));
+        }
+        if !entry.allows_variation(persistent_room.rule_variation) {
+            return Err(format!(
+                "Rule variation {} of persistent room {} is not allowed for game {}",
+                persistent_room.rule_variation, persistent_room.room, entry.name
+            ));
+        }
+        if persistent_rooms[..index]
+            .iter()
+            .any(|other| other.room == persistent_room.room)
+        {
+            return Err(format!(
+                "Duplicate persistent room {} for game {}",
+                persistent_room.room, entry.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Parses and validates the content of a configuration file into the mapping from game name to entry.
+/// Empty and duplicate game names are rejected, instead of letting a later entry silently win.
+pub fn parse_config(json_content: &str) -> Result<HashMap<String, GameEntry>, String> {
+    let raw_data: EntryList =
+        serde_json::from_str(json_content).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    let mut new_configs = HashMap::with_capacity(raw_data.len());
+    for entry in raw_data {
+        validate_entry(&entry)?;
+        if new_configs.contains_key(&entry.name) {
+            return Err(format!("Duplicate game entry {}", entry.name));
+        }
+        new_configs.insert(entry.name.clone(), entry);
+    }
+    Ok(new_configs)
+}
+
+/// Reloads the configuration file, that lists the games with the maximum number of players per room
+/// and optionally the allowed rule variations. The live configuration only gets replaced,
+/// if the whole file is valid. Afterward, the persistent rooms of the configuration get opened.
 pub async fn reload_config(state: &Arc<AppState>) -> Result<(), String> {
     let json_content = fs::read_to_string("GameConfig.json")
         .await
         .map_err(|e| format!("Failed to read file: {}", e))?;
-    let raw_data: EntryList =
-        serde_json::from_str(&json_content).map_err(|e| format!("Failed to parse JSON: {}", e))?;
-    let new_configs: HashMap<String, u16> = raw_data
-        .into_iter()
-        .map(|entry| (entry.name, entry.max_players))
-        .collect();
+    let new_configs = parse_config(&json_content)?;
 
     {
         let mut configs = state.configs.write().await;
         *configs = new_configs; // Replace all.
     }
+    state.open_persistent_rooms().await;
     Ok(())
 }
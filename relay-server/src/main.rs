@@ -1,30 +1,27 @@
-mod hand_shake;
-mod lobby;
-mod message_relay;
-
-use crate::hand_shake::{
-    ClientServerSpecificData, DisconnectData, inform_client_of_connection, init_and_connect,
-    shutdown_connection,
+use relay_server::lobby::{
+    AppState, CorsOrigins, DEFAULT_BAN_DURATION, DEFAULT_SEND_TIMEOUT, reload_config,
 };
-use crate::lobby::{AppState, reload_config};
-use crate::message_relay::{handle_client_logic, handle_server_logic};
-use axum::Router;
-use axum::extract::ws::{Message, WebSocket};
-use axum::extract::{State, WebSocketUpgrade};
-use axum::response::IntoResponse;
-use axum::routing::get;
-use bytes::Bytes;
-use futures_util::SinkExt;
-use futures_util::stream::StreamExt;
+use relay_server::results::ResultsSink;
+use relay_server::{MaintenanceIntervals, maintenance_task};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tower_http::services::{ServeDir, ServeFile};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
-/// Activates error tracing, spawns a watch dog task to eliminate eventual  dead rooms, then it sets up the roting system to serve the
-/// web sockets and listen for the pages enlist and reload. The server listens on port 8080.
+/// Activates error tracing, spawns the maintenance task, then it sets up the roting system to serve the
+/// web sockets and listen for the pages enlist and reload. The admin endpoints use the token from the environment
+/// variable `RELAY_ADMIN_TOKEN`. Game results get posted to the URL in `RELAY_RESULTS_URL`, if set, and are
+/// logged in any case. The timeout for sending a frame can be set in seconds with `RELAY_SEND_TIMEOUT_SECS`,
+/// the time a kicked client may not rejoin the room with `RELAY_BAN_SECS`. Static files get served from the
+/// directory in `RELAY_STATIC_ROOT`, the working directory by default. The amount of simultaneous connections from
+/// one address can be limited with `RELAY_MAX_CONNECTIONS_PER_IP`. The maintenance eliminates eventual dead rooms every
+/// `RELAY_DEAD_ROOM_SWEEP_SECS`, closes the rooms idle for longer than `RELAY_IDLE_ROOM_SECS` every
+/// `RELAY_IDLE_SWEEP_SECS`, logs connection tasks that got stuck every `RELAY_STALE_SWEEP_SECS` and logs the metrics
+/// every `RELAY_METRICS_SECS`, where 0 disables the job. Browser pages of the comma separated origins in
+/// `RELAY_CORS_ORIGINS`, or of any origin with `*`, may call the endpoints enlist, reload and rooms.
+/// The server listens on port 8080.
 async fn main() {
     tracing_subscriber::registry()
         .with(
@@ -41,15 +38,52 @@ async fn main() {
         )
         .init();
 
-    let app_state = Arc::new(AppState::default());
-    let watchdog_state = app_state.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1200)); // 20 Min
-        loop {
-            interval.tick().await;
-            cleanup_dead_rooms(&watchdog_state).await;
-        }
+    let results = match std::env::var("RELAY_RESULTS_URL") {
+        Ok(url) => ResultsSink::posting_to(&url).unwrap_or_else(|message| {
+            tracing::error!(message, "Results sink error.");
+            panic!("Results sink error: {}", message);
+        }),
+        Err(_) => ResultsSink::default(),
+    };
+    let app_state = Arc::new(AppState {
+        admin_token: std::env::var("RELAY_ADMIN_TOKEN").ok(),
+        results,
+        send_timeout: duration_from_env("RELAY_SEND_TIMEOUT_SECS", DEFAULT_SEND_TIMEOUT),
+        ban_duration: duration_from_env("RELAY_BAN_SECS", DEFAULT_BAN_DURATION),
+        static_root: std::env::var("RELAY_STATIC_ROOT")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(".")),
+        max_connections_per_peer: std::env::var("RELAY_MAX_CONNECTIONS_PER_IP")
+            .ok()
+            .map(|limit| {
+                limit.parse().unwrap_or_else(|_| {
+                    tracing::error!(limit, "Not a number of connections.");
+                    panic!("RELAY_MAX_CONNECTIONS_PER_IP is not a number: {}", limit);
+                })
+            }),
+        cors_origins: std::env::var("RELAY_CORS_ORIGINS").ok().map(|origins| {
+            CorsOrigins::parse(&origins).unwrap_or_else(|message| {
+                tracing::error!(message, "CORS origin error.");
+                panic!("RELAY_CORS_ORIGINS error: {}", message);
+            })
+        }),
+        ..AppState::default()
     });
+    let defaults = MaintenanceIntervals::default();
+    let intervals = MaintenanceIntervals {
+        dead_room_sweep: duration_from_env("RELAY_DEAD_ROOM_SWEEP_SECS", defaults.dead_room_sweep),
+        idle_room_sweep: duration_from_env("RELAY_IDLE_SWEEP_SECS", defaults.idle_room_sweep),
+        idle_room_threshold: duration_from_env(
+            "RELAY_IDLE_ROOM_SECS",
+            defaults.idle_room_threshold,
+        ),
+        stale_connection_sweep: duration_from_env(
+            "RELAY_STALE_SWEEP_SECS",
+            defaults.stale_connection_sweep,
+        ),
+        metrics: duration_from_env("RELAY_METRICS_SECS", defaults.metrics),
+    };
+    tokio::spawn(maintenance_task(app_state.clone(), intervals));
 
     let initial = reload_config(&app_state).await;
     if let Err(message) = initial {
@@ -57,136 +91,27 @@ async fn main() {
         panic!("Initial load error: {}", message);
     }
 
-    let app = Router::new()
-        .route("/reload", get(reload_handler))
-        .route("/enlist", get(enlist_handler))
-        .route("/ws", get(websocket_handler))
-        .with_state(app_state)
-        .fallback_service(ServeDir::new(".").not_found_service(ServeFile::new("index.html")));
+    let app = relay_server::router(app_state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:8080")
         .await
         .unwrap();
 
-    axum::serve(listener, app).await.unwrap();
-}
-
-/// Runs over all rooms and checks if they are diconnected from the server.
-/// If so, it cleans them up. This is a fallback solution things should be handled internally otherwise.
-async fn cleanup_dead_rooms(state: &Arc<AppState>) {
-    let mut rooms = state.rooms.lock().await;
-    rooms.retain(|room_id, room| {
-        let is_alive = !room.to_host_sender.is_closed();
-        if !is_alive {
-            tracing::info!("Removing dead room: {}", room_id);
-        }
-        is_alive
-    });
-}
-
-/// Generates a list with the current rooms, the amount of players and info if this is a dead room.
-async fn enlist_handler(State(state): State<Arc<AppState>>) -> String {
-    let rooms = state.rooms.lock().await;
-    rooms
-        .iter()
-        .map(|(name, room)| {
-            format!(
-                "Room: {:<30}  Variation: {:03} Players: {:03} is alive: {}",
-                name,
-                room.rule_variation,
-                room.amount_of_players,
-                !room.to_host_sender.is_closed()
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-/// Forces the reload of the config file and lists the content. This enables the adding of new games
-/// without restarting the service.
-async fn reload_handler(State(state): State<Arc<AppState>>) -> String {
-    let res = reload_config(&state).await;
-    match res {
-        Ok(_) => state
-            .configs
-            .read()
-            .await
-            .iter()
-            .map(|(key, players)| {
-                format!("Game: {:<40} Maximum Amount of Players: {}", key, players)
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-        Err(e) => {
-            format!("Config reload failed: {}", e)
-        }
-    }
-}
-
-/// This function gets immediately called and upgrades the web response to a web socket.
-async fn websocket_handler(
-    ws: WebSocketUpgrade,
-    State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    ws.on_upgrade(|socket| websocket(socket, state))
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
 
-/// Does the whole handling from start to finish: Handshake -> Handling of logic depending on if we are connected to
-/// the server or client -> Shut down processing.
-async fn websocket(stream: WebSocket, state: Arc<AppState>) {
-    // By splitting, we can send and receive at the same time.
-    let (mut sender, mut receiver) = stream.split();
-
-    let handshake_result = init_and_connect(&mut sender, &mut receiver, state.clone()).await;
-    if handshake_result.is_none() {
-        // We quit here, as the handshake did not work out.
-        return;
-    }
-    let base_data = handshake_result.unwrap();
-
-    let disconnect_data = DisconnectData::from(&base_data);
-    let success = inform_client_of_connection(&mut sender, &base_data).await;
-    let wrapped_sender = Arc::new(Mutex::new(sender));
-
-    // Ping-Task to keep alive.
-    let ping_sender = wrapped_sender.clone();
-    let ping_task = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(30));
-        interval.tick().await; // Skip first tick.
-        loop {
-            interval.tick().await;
-            let mut s = ping_sender.lock().await;
-            if s.send(Message::Ping(Bytes::new())).await.is_err() {
-                break;
-            }
-        }
-    });
-
-    let mut error_message = "Connection to server lost";
-    if success {
-        match base_data.specific_data {
-            ClientServerSpecificData::Server(internal_receiver, internal_sender) => {
-                error_message = handle_server_logic(
-                    wrapped_sender.clone(),
-                    receiver,
-                    internal_receiver,
-                    internal_sender,
-                )
-                .await;
-            }
-            ClientServerSpecificData::Client(internal_receiver, internal_sender) => {
-                error_message = handle_client_logic(
-                    wrapped_sender.clone(),
-                    receiver,
-                    internal_receiver,
-                    internal_sender,
-                    base_data.player_id,
-                )
-                .await;
-            }
-        }
+/// Reads a duration in whole seconds from the environment variable, or takes the default if it is not set.
+fn duration_from_env(variable: &str, default: Duration) -> Duration {
+    match std::env::var(variable) {
+        Ok(seconds) => Duration::from_secs(seconds.parse().unwrap_or_else(|_| {
+            tracing::error!(variable, seconds, "Not a number of seconds.");
+            panic!("{} is not a number of seconds: {}", variable, seconds);
+        })),
+        Err(_) => default,
     }
-
-    ping_task.abort();
-    shutdown_connection(wrapped_sender, disconnect_data, state, error_message).await;
 }
@@ -5,45 +5,210 @@
 //! - Validate and filter messages by type (preventing illegal commands)
 //! - Route host broadcasts to subscribed clients
 //! - Forward client RPCs to the host with injected player IDs
+//! - Aggregate the readiness of the clients and report it to the host
 //! - Manage sync state so clients only receive deltas after a full update
+//! - Stream the traffic of a room to an admin tap as readable log lines, see [`run_admin_tap`]
+//! - Count the messages of a room per message type, see [`message_histogram`]
 //!
 //! The relay server never interprets game logic — it only validates message types
 //! and routes bytes between endpoints.
+//!
+//! Connections that joined with a text frame (JSON debugging mode) exchange all frames as text,
+//! see [`protocol::debug_text_from_frame`]. Internally, messages keep the binary layout in both modes.
+//!
+//! The routing does not depend on axum's concrete web socket, only on the [`MessageSink`] and [`MessageStream`]
+//! halves of it. Tests plug in in-memory endpoints there and drive the logic without any network.
 
-use axum::extract::ws::{Message, WebSocket};
+use crate::lobby::{ActivityTracker, MessageCounters, RoomBroadcast, TappedFrame};
+use crate::results::ResultsSink;
+use axum::extract::ws::Message;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use futures_util::stream::{SplitSink, SplitStream};
-use futures_util::{SinkExt, StreamExt};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
 use protocol::*;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use tokio::sync::broadcast;
-use tokio::sync::broadcast::Sender;
 use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
 use tokio::sync::mpsc::Receiver;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinError;
+
+/// The sending half of an endpoint connection, like the [`SplitSink`](futures_util::stream::SplitSink) of a web socket.
+pub trait MessageSink: Sink<Message, Error = axum::Error> + Unpin + Send + 'static {}
+
+impl<T> MessageSink for T where T: Sink<Message, Error = axum::Error> + Unpin + Send + 'static {}
+
+/// The receiving half of an endpoint connection, like the [`SplitStream`](futures_util::stream::SplitStream) of a web socket.
+pub trait MessageStream:
+    Stream<Item = Result<Message, axum::Error>> + Unpin + Send + 'static
+{
+}
+
+impl<T> MessageStream for T where
+    T: Stream<Item = Result<Message, axum::Error>> + Unpin + Send + 'static
+{
+}
+
+/// Wraps an internal message into a web socket frame, as text in the JSON debugging mode.
+///
+/// The `header_size` is the amount of leading bytes with message id and client ids, which only matters for text.
+pub(crate) fn outgoing_frame(bytes: Bytes, header_size: usize, text_frames: bool) -> Message {
+    if text_frames {
+        Message::Text(debug_text_from_frame(&bytes, header_size).into())
+    } else {
+        Message::Binary(bytes)
+    }
+}
+
+/// Extracts the internal message from a web socket frame.
+///
+/// Returns `None` for frames that carry no message (like ping and pong, handled by axum, and close frames, handled by the caller),
+/// and an error for text frames that are malformed or arrive outside the JSON debugging mode.
+fn incoming_message(message: Message, text_frames: bool) -> Option<Result<Bytes, &'static str>> {
+    match message {
+        Message::Binary(bytes) if !text_frames => Some(Ok(bytes)),
+        Message::Text(text) if text_frames => Some(
+            frame_from_debug_text(text.as_str())
+                .map(Bytes::from)
+                .map_err(|error| {
+                    tracing::error!(error, "Malformed debug text frame.");
+                    "Malformed debug text frame."
+                }),
+        ),
+        Message::Binary(_) | Message::Text(_) => {
+            tracing::error!(text_frames, "Frame type does not match the connection.");
+            Some(Err("Frame type does not match the connection."))
+        }
+        _ => None,
+    }
+}
+
+/// How frames get exchanged with the endpoint of a connection.
+#[derive(Clone, Copy)]
+pub struct ConnectionOptions {
+    /// Whether the endpoint joined in the JSON debugging mode.
+    pub text_frames: bool,
+    /// How long sending a single frame may take, before the endpoint counts as stuck and gets dropped.
+    pub send_timeout: Duration,
+    /// The amount of RPCs a client may send per second, if the game limits it, see [`RpcRateLimit`].
+    pub max_rpcs_per_sec: Option<u32>,
+}
+
+/// A token bucket limiting the RPCs of a single client to the rate configured for its game.
+///
+/// The bucket holds up to a second's worth of RPCs, so short bursts pass. It refills continuously,
+/// an RPC that finds the bucket empty gets dropped.
+pub struct RpcRateLimit {
+    /// The RPCs allowed per second, which is also the capacity of the bucket.
+    rate: f64,
+    /// The RPCs that may be sent right now.
+    tokens: f64,
+    /// The time of the last refill.
+    last_refill: Instant,
+}
+
+impl RpcRateLimit {
+    /// Creates a full bucket for the indicated rate.
+    pub fn new(max_rpcs_per_sec: u32) -> Self {
+        RpcRateLimit {
+            rate: max_rpcs_per_sec as f64,
+            tokens: max_rpcs_per_sec as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Refills the bucket for the time passed and takes a token for an RPC at the indicated time.
+    /// Returns `false`, if the RPC exceeds the rate.
+    pub fn allows(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Sends a frame to the endpoint, waiting at most `send_timeout` for the lock and the send itself.
+/// A peer that never reads fills up its socket buffer, which would otherwise pin the task forever.
+/// The `error_reason` is returned, if the send fails.
+async fn send_frame(
+    sender: &Mutex<impl MessageSink>,
+    message: Message,
+    send_timeout: Duration,
+    error_reason: &'static str,
+) -> Result<(), &'static str> {
+    match tokio::time::timeout(send_timeout, async {
+        sender.lock().await.send(message).await
+    })
+    .await
+    {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(error)) => {
+            tracing::error!(?error, error_reason);
+            Err(error_reason)
+        }
+        Err(_) => {
+            tracing::warn!(?send_timeout, "Endpoint did not take the frame in time.");
+            Err("Send timeout.")
+        }
+    }
+}
+
+/// The room a host serves, for tagging its log lines and reporting its results.
+pub struct HostRoom {
+    /// The compound room id.
+    pub room_id: CompoundRoomId,
+    /// Where the results of the host go.
+    pub results: ResultsSink,
+}
 
 /// Spawns bidirectional message handlers for a game host connection.
 ///
 /// Creates two concurrent tasks:
-/// - **Send task**: Forwards client messages (joins, disconnects, RPCs) to the host
+/// - **Send task**: Forwards client messages (joins, disconnects, RPCs) to the host and reports the client backlog
 /// - **Receive task**: Broadcasts host messages (updates, kicks) to all clients
 ///
 /// When either task completes (connection lost, protocol error, intentional disconnect),
 /// the other is aborted and the room should be cleaned up by the caller.
 ///
+/// # Arguments
+/// * `room` - The compound room id, used to tag the log lines of the host, and the results sink
+/// * `activity` - Gets touched on every message of the host
+/// * `options` - The frame format and send timeout of the connection
+///
 /// # Returns
 /// A static string describing why the connection ended (for logging/debugging).
 pub async fn handle_server_logic(
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
-    receiver: SplitStream<WebSocket>,
+    sender: Arc<Mutex<impl MessageSink>>,
+    receiver: impl MessageStream,
     internal_receiver: Receiver<Bytes>,
-    internal_sender: broadcast::Sender<Bytes>,
+    internal_sender: RoomBroadcast,
+    room: HostRoom,
+    activity: ActivityTracker,
+    options: ConnectionOptions,
 ) -> &'static str {
-    let mut send_task =
-        tokio::spawn(async move { send_logic_server(sender, internal_receiver).await });
+    let broadcaster = internal_sender.clone();
+    let mut send_task = tokio::spawn(async move {
+        send_logic_server(sender, internal_receiver, broadcaster, options).await
+    });
 
-    let mut receive_task =
-        tokio::spawn(async move { receive_logic_server(receiver, internal_sender).await });
+    let mut receive_task = tokio::spawn(async move {
+        receive_logic_server(
+            receiver,
+            internal_sender,
+            room,
+            activity,
+            options.text_frames,
+        )
+        .await
+    });
 
     // If any one of the tasks run to completion, we abort the other.
     let result = tokio::select! {
@@ -51,10 +216,7 @@ pub async fn handle_server_logic(
         res_b = &mut receive_task => {send_task.abort(); res_b},
     };
 
-    result.unwrap_or_else(|err| {
-        tracing::error!(?err, "Error while handling server logic.");
-        "Internal panic in server side logic."
-    })
+    task_outcome(result, "Internal panic in server side logic.")
 }
 
 /// Receives messages from the game host and broadcasts them to all clients.
@@ -62,55 +224,203 @@ pub async fn handle_server_logic(
 /// Allowed message types from host:
 /// - [`CLIENT_GETS_KICKED`]: Remove a specific player
 /// - [`DELTA_UPDATE`]: Incremental game state change
+/// - [`DELTA_UPDATE_EXCEPT`]: Incremental game state change for all but one client
+/// - [`DELTA_UPDATE_TO`]: Private game state change for a single client, only broadcast to the players
+/// - [`PARTIAL_UPDATE`]: Changed regions of a large game state
+/// - [`TICK`]: Tick without a change of the game state
+/// - [`REJECT_ACTION`]: Feedback for an ignored action of a single client, only broadcast to the players
+/// - [`RPC_ACK`]: The last processed RPC of a single client, only broadcast to the players
 /// - [`FULL_UPDATE`]: Complete game state (for new/desynced clients)
+/// - [`FULL_UPDATE_TO`]: Complete game state for a single client that requested it
 /// - [`RESET`]: Game restart signal
-/// - [`SERVER_DISCONNECTS`]: Graceful shutdown (triggers cleanup)
+/// - [`PAUSE`]: Game paused or resumed
+/// - [`PLAYER_NAME`]: Display name of a player, opaque to the relay apart from its limit of [`MAX_DISPLAY_NAME_SIZE`]
+/// - [`SERVER_DISCONNECTS`]: Graceful shutdown with an optional [`HostDeparture`] code (triggers cleanup)
+/// - [`LOG`]: Diagnostic text, recorded with the room id and not forwarded
+/// - [`RESULT`]: Opaque result of a finished game, handed to the results sink and not forwarded
+/// - [`KEEPALIVE`]: Only counts as activity, not forwarded
 ///
-/// Any other message type is rejected as a protocol violation.
+/// Every message of the host touches the room's activity tracker and gets counted in the room's message counters.
+/// Any other message type is rejected as a protocol violation, see [`role_mismatch`] for the reason given.
+/// A message shorter than its fixed header gets dropped here, before it reaches the send tasks of the clients.
+/// A close frame ends the connection right away, like [`SERVER_DISCONNECTS`] without a code.
+/// The connection ends with the message of the [`HostDeparture`] in both cases, so the clients learn why.
 async fn receive_logic_server(
-    mut receiver: SplitStream<WebSocket>,
-    internal_sender: Sender<Bytes>,
+    mut receiver: impl MessageStream,
+    internal_sender: RoomBroadcast,
+    room: HostRoom,
+    activity: ActivityTracker,
+    text_frames: bool,
 ) -> &'static str {
     while let Some(state) = receiver.next().await {
-        match state {
-            Ok(Message::Binary(bytes)) => {
-                if bytes.is_empty() {
-                    tracing::error!("Illegal empty message in receive logic server.");
-                    return "Illegal empty message received.";
-                }
+        let Ok(message) = state else {
+            return "Connection lost.";
+        };
+        if let Message::Close(frame) = message {
+            // A clean close of the host, treated like an intentional disconnect.
+            tracing::info!(?frame, "Host closed the web socket.");
+            return HostDeparture::Ended.message();
+        }
+        let bytes = match incoming_message(message, text_frames) {
+            Some(Ok(bytes)) => bytes,
+            Some(Err(reason)) => return reason,
+            None => continue, // Ignore other messages (ping/pong handled by axum)
+        };
+        if bytes.is_empty() {
+            tracing::error!("Illegal empty message in receive logic server.");
+            return "Illegal empty message received.";
+        }
+        activity.touch();
+        internal_sender.counters().count(false, bytes[0]);
 
-                if bytes[0] == SERVER_DISCONNECTS {
-                    // This something normal to be expected.
-                    return "Server disconnected intentionally";
-                }
+        if bytes[0] == KEEPALIVE {
+            continue;
+        }
 
-                if !matches!(
-                    bytes[0],
-                    CLIENT_GETS_KICKED | DELTA_UPDATE | FULL_UPDATE | RESET
-                ) {
-                    tracing::error!(
-                        message_type = bytes[0],
-                        "Illegal message type Server->Client."
-                    );
-                    return "Illegal Server -> Client command.";
-                }
+        if bytes[0] == SERVER_DISCONNECTS {
+            // This something normal to be expected. The departure tells the clients why.
+            let departure = bytes
+                .get(1)
+                .and_then(|code| HostDeparture::from_code(*code))
+                .unwrap_or(HostDeparture::Ended);
+            return departure.message();
+        }
 
-                // All messages are simply passed through.
-                let res = internal_sender.send(bytes);
-                // An error may occur, if there are no further clients available.
-                // As a rule of a thumb the server should not send any messages, if he does not know of any clients.
-                // Currently logged as a warning, as it is unclear, if this is strictly avoidable.
-                if let Err(error) = res {
-                    tracing::warn!(?error, "Sending to no clients.");
-                }
+        if bytes[0] == LOG {
+            let text = String::from_utf8_lossy(&bytes[1..]);
+            tracing::info!(room_id = room.room_id.as_str(), message = %text, "Host log.");
+            continue;
+        }
+
+        if bytes[0] == RESULT {
+            room.results.report(room.room_id.as_str(), bytes.slice(1..));
+            continue;
+        }
+
+        if !matches!(
+            bytes[0],
+            CLIENT_GETS_KICKED
+                | DELTA_UPDATE
+                | DELTA_UPDATE_EXCEPT
+                | DELTA_UPDATE_TO
+                | PARTIAL_UPDATE
+                | TICK
+                | REJECT_ACTION
+                | RPC_ACK
+                | FULL_UPDATE
+                | FULL_UPDATE_TO
+                | RESET
+                | PAUSE
+                | PLAYER_NAME
+        ) {
+            tracing::error!(
+                message_type = bytes[0],
+                "Illegal message type Server->Client."
+            );
+            return role_mismatch(bytes[0], true).unwrap_or("Illegal Server -> Client command.");
+        }
+        if bytes[0] == PLAYER_NAME && bytes.len() > 1 + CLIENT_ID_SIZE + MAX_DISPLAY_NAME_SIZE {
+            tracing::error!(
+                size = bytes.len() - 1 - CLIENT_ID_SIZE,
+                "Oversized display name from host."
+            );
+            return "Display name too long.";
+        }
+        // Checked once here, a malformed message would otherwise end the connections of all clients.
+        if bytes.len() < min_host_message_size(bytes[0]) {
+            tracing::error!(
+                message_type = bytes[0],
+                size = bytes.len(),
+                "Dropping malformed message from host."
+            );
+            continue;
+        }
+
+        // All messages are simply passed through, private ones never reach the spectators.
+        let receivers = if matches!(bytes[0], DELTA_UPDATE_TO | REJECT_ACTION | RPC_ACK) {
+            internal_sender.send_private(bytes)
+        } else {
+            internal_sender.send_public(bytes)
+        };
+        // Nobody may listen, if there are no further clients available.
+        // As a rule of a thumb the server should not send any messages, if he does not know of any clients.
+        // Currently logged as a warning, as it is unclear, if this is strictly avoidable.
+        if receivers == 0 {
+            tracing::warn!("Sending to no clients.");
+        }
+    }
+    "Connection lost."
+}
+
+/// The smallest size of a message the host sends to the clients, which is the size of its fixed header.
+fn min_host_message_size(message_type: u8) -> usize {
+    match message_type {
+        CLIENT_GETS_KICKED | REJECT_ACTION | PLAYER_NAME => 1 + CLIENT_ID_SIZE,
+        DELTA_UPDATE | PARTIAL_UPDATE | FULL_UPDATE | RESET => 1 + SEQUENCE_SIZE,
+        DELTA_UPDATE_EXCEPT | DELTA_UPDATE_TO | FULL_UPDATE_TO => {
+            1 + CLIENT_ID_SIZE + SEQUENCE_SIZE
+        }
+        PAUSE => PAUSE_MSG_SIZE,
+        RPC_ACK => RPC_ACK_MSG_SIZE,
+        _ => 1,
+    }
+}
+
+/// The readiness of the clients of a room, as tracked by the send task of the host.
+#[derive(Default)]
+struct ClientReadiness {
+    /// The amount of clients in the room.
+    total: u16,
+    /// The clients that flagged themselves as ready.
+    ready: HashSet<u16>,
+    /// Flags, if any client sent its readiness yet. Rooms of games without a ready check never get reports.
+    in_use: bool,
+    /// The amount of ready clients and of all clients as last reported to the host.
+    reported: (u16, u16),
+}
+
+impl ClientReadiness {
+    /// Follows the joins, departures and readiness flags among the internal messages to the host.
+    /// Other messages are ignored.
+    fn track(&mut self, bytes: &[u8]) {
+        let Some(client_id) = bytes
+            .get(1..1 + CLIENT_ID_SIZE)
+            .map(|id| u16::from_be_bytes([id[0], id[1]]))
+        else {
+            return;
+        };
+        match bytes[0] {
+            NEW_CLIENT => self.total = self.total.saturating_add(1),
+            CLIENT_DISCONNECTS => {
+                self.total = self.total.saturating_sub(1);
+                self.ready.remove(&client_id);
+            }
+            READY if bytes.get(1 + CLIENT_ID_SIZE) == Some(&1) => {
+                self.in_use = true;
+                self.ready.insert(client_id);
             }
-            Ok(_) => {} // Ignore other messages (ping/pong handled by axum)
-            Err(_) => {
-                return "Connection lost.";
+            READY => {
+                self.in_use = true;
+                self.ready.remove(&client_id);
             }
+            _ => {}
         }
     }
-    "Connection lost."
+
+    /// Builds the [`READY_STATE`] message for the host, if the readiness changed since the last report.
+    /// Nothing gets reported before the first client sent its readiness.
+    fn report(&mut self) -> Option<Bytes> {
+        let current = (self.ready.len() as u16, self.total);
+        if !self.in_use || current == self.reported {
+            return None;
+        }
+        self.reported = current;
+        let mut msg = BytesMut::with_capacity(READY_STATE_MSG_SIZE);
+        msg.put_u8(READY_STATE);
+        msg.put_u16(current.0);
+        msg.put_u16(current.1);
+        Some(msg.into())
+    }
 }
 
 /// Forwards aggregated client messages to the game host.
@@ -119,35 +429,123 @@ async fn receive_logic_server(
 /// - [`NEW_CLIENT`]: Player joined notification
 /// - [`CLIENT_DISCONNECTS`]: Player left notification
 /// - [`SERVER_RPC`]: Game action from a client (with player ID prepended)
+/// - [`SET_NAME`]: Display name of a client (with player ID prepended)
+/// - [`READY`]: Readiness of a client (with player ID prepended), not forwarded itself
+/// - [`REQUEST_SYNC`]: A client asks for a fresh full state (with player ID prepended)
+/// - [`SPECTATOR_COUNT`]: The amount of spectators, announced by the relay itself
+///
+/// Joins, departures and the readiness flags get aggregated into [`READY_STATE`], which is sent to the host
+/// whenever the amount of ready clients or of all clients changed, once the first client sent its readiness.
+/// Additionally, the backlog of the broadcast channel (the most messages any client has not picked up yet)
+/// gets sampled every [`BACKLOG_REPORT_INTERVAL_MILLIS`] and reported as [`CLIENT_BACKLOG`], whenever it changed.
 ///
 /// This task owns the WebSocket sender lock for its lifetime to ensure
 /// sequential message delivery to the host.
 async fn send_logic_server(
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
+    sender: Arc<Mutex<impl MessageSink>>,
     mut internal_receiver: Receiver<Bytes>,
+    broadcaster: RoomBroadcast,
+    options: ConnectionOptions,
 ) -> &'static str {
-    while let Some(bytes) = internal_receiver.recv().await {
-        if bytes.is_empty() {
-            tracing::error!("Illegal internal empty message in send logic server.");
-            return "Illegal empty message received.";
-        }
-        if !matches!(bytes[0], NEW_CLIENT | CLIENT_DISCONNECTS | SERVER_RPC) {
-            tracing::error!(
-                message_type = bytes[0],
-                "Unknown internal Client->Server command"
-            );
-            return "Unknown internal Client->Server command";
+    let mut report_interval =
+        tokio::time::interval(Duration::from_millis(BACKLOG_REPORT_INTERVAL_MILLIS));
+    let mut reported_backlog = 0;
+    let mut readiness = ClientReadiness::default();
+    loop {
+        let bytes = tokio::select! {
+            message = internal_receiver.recv() => {
+                let Some(bytes) = message else {
+                    break;
+                };
+                if bytes.is_empty() {
+                    tracing::error!("Illegal internal empty message in send logic server.");
+                    return "Illegal empty message received.";
+                }
+                if !matches!(
+                    bytes[0],
+                    NEW_CLIENT
+                        | CLIENT_DISCONNECTS
+                        | SERVER_RPC
+                        | SET_NAME
+                        | READY
+                        | REQUEST_SYNC
+                        | SPECTATOR_COUNT
+                ) {
+                    tracing::error!(
+                        message_type = bytes[0],
+                        "Unknown internal Client->Server command"
+                    );
+                    return "Unknown internal Client->Server command";
+                }
+                bytes
+            }
+            _ = report_interval.tick() => {
+                let backlog = broadcaster.backlog().min(u16::MAX as usize) as u16;
+                if backlog == reported_backlog {
+                    continue;
+                }
+                reported_backlog = backlog;
+                let mut msg = BytesMut::with_capacity(CLIENT_BACKLOG_MSG_SIZE);
+                msg.put_u8(CLIENT_BACKLOG);
+                msg.put_u16(backlog);
+                msg.into()
+            }
+        };
+        broadcaster.counters().count(true, bytes[0]);
+        readiness.track(&bytes);
+        // Simply pass on the message, all of them carry a client id or the backlog.
+        // The readiness of a single client only reaches the host aggregated.
+        let forwarded = (bytes[0] != READY).then_some(bytes);
+        for message in forwarded.into_iter().chain(readiness.report()) {
+            broadcaster.mirror(true, &message);
+            let header_size = match message[0] {
+                READY_STATE => READY_STATE_MSG_SIZE,
+                SPECTATOR_COUNT => SPECTATOR_COUNT_MSG_SIZE,
+                SERVER_RPC => 1 + CLIENT_ID_SIZE + RPC_SEQUENCE_SIZE,
+                _ => 1 + CLIENT_ID_SIZE,
+            };
+            if let Err(reason) = send_frame(
+                &sender,
+                outgoing_frame(message, header_size, options.text_frames),
+                options.send_timeout,
+                "Error in communication with server endpoint.",
+            )
+            .await
+            {
+                return reason;
+            }
         }
-        // Simply pass on the message.
-        let res = sender.lock().await.send(Message::Binary(bytes)).await;
-        if let Err(err) = res {
-            tracing::error!(?err, "Error in communication with server endpoint.");
-            return "Error in communication with server endpoint.";
+    }
+    // In normal shutdown procedure that only happens, if the room got closed by an admin and all clients are gone.
+    tracing::warn!("Internal channel on server was closed.");
+    "Room was closed."
+}
+
+/// The reason a client connection ends with, if the host kicked the client.
+pub(crate) const KICKED_BY_HOST: &str = "We got rejected by server.";
+
+/// The time a client message may wait for space in the channel to the host, before the host counts as overloaded.
+pub(crate) const HOST_OVERLOAD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Forwards a client message to the host. If the channel to the host is full, this waits at most
+/// [`HOST_OVERLOAD_TIMEOUT`] for space, so one slow host can not stall its clients indefinitely.
+pub(crate) async fn send_to_host(
+    sender: &mpsc::Sender<Bytes>,
+    bytes: Bytes,
+) -> Result<(), &'static str> {
+    let bytes = match sender.try_send(bytes) {
+        Ok(()) => return Ok(()),
+        Err(TrySendError::Closed(_)) => return Err("Error in internal broadcast."),
+        Err(TrySendError::Full(bytes)) => bytes,
+    };
+    match tokio::time::timeout(HOST_OVERLOAD_TIMEOUT, sender.send(bytes)).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(_)) => Err("Error in internal broadcast."),
+        Err(_) => {
+            tracing::warn!("Channel to the host stayed full, dropping the client.");
+            Err("Host overloaded.")
         }
     }
-    // In normal shutdown procedure that should not happen, because we are responsible for closing the channel.
-    tracing::error!("Internal channel on server was unexpectedly closed.");
-    "Internal channel closed."
 }
 
 /// Spawns bidirectional message handlers for a game client connection.
@@ -158,23 +556,35 @@ async fn send_logic_server(
 ///
 /// # Arguments
 /// * `player_id` - Unique identifier assigned to this client for the session
+/// * `heartbeat` - Gets touched by the receive task on every message, to detect a stuck task
+/// * `options` - The frame format and send timeout of the connection
 ///
 /// # Returns
 /// A static string describing why the connection ended.
 pub async fn handle_client_logic(
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
-    receiver: SplitStream<WebSocket>,
+    sender: Arc<Mutex<impl MessageSink>>,
+    receiver: impl MessageStream,
     internal_receiver: tokio::sync::broadcast::Receiver<Bytes>,
     internal_sender: tokio::sync::mpsc::Sender<Bytes>,
     player_id: u16,
+    heartbeat: ActivityTracker,
+    options: ConnectionOptions,
 ) -> &'static str {
-    let mut send_task =
-        tokio::spawn(async move { send_logic_client(sender, internal_receiver, player_id).await });
+    let mut send_task = tokio::spawn(async move {
+        send_logic_client(sender, internal_receiver, player_id, options).await
+    });
 
-    let mut receive_task =
-        tokio::spawn(
-            async move { receive_logic_client(receiver, internal_sender, player_id).await },
-        );
+    let mut receive_task = tokio::spawn(async move {
+        receive_logic_client(
+            receiver,
+            internal_sender,
+            player_id,
+            heartbeat,
+            options.text_frames,
+            options.max_rpcs_per_sec.map(RpcRateLimit::new),
+        )
+        .await
+    });
 
     // If any one of the tasks run to completion, we abort the other.
     let result = tokio::select! {
@@ -182,60 +592,164 @@ pub async fn handle_client_logic(
         res_b = &mut receive_task => {send_task.abort(); res_b},
     };
 
-    result.unwrap_or_else(|err| {
-        tracing::error!(?err, "Internal panic in client side logic.");
-        "Internal panic in client side logic."
-    })
+    task_outcome(result, "Internal panic in client side logic.")
+}
+
+/// Turns the result of the connection task that finished first into the reason the connection ended.
+///
+/// Only a panic gets logged as an error. A cancellation belongs to a regular shutdown, e.g. when the runtime
+/// shuts down, and is no reason for alarm.
+fn task_outcome(
+    result: Result<&'static str, JoinError>,
+    panic_reason: &'static str,
+) -> &'static str {
+    match result {
+        Ok(reason) => reason,
+        Err(error) if error.is_cancelled() => {
+            tracing::debug!(?error, "Connection task got cancelled.");
+            "Connection task cancelled."
+        }
+        Err(error) => {
+            tracing::error!(?error, panic_reason);
+            panic_reason
+        }
+    }
+}
+
+/// Tells a message type that belongs to another role apart from an unknown one, for clearer diagnostics when
+/// host and client confuse their roles. Returns the reason to end the connection with, if another role sends it.
+///
+/// The ids of both directions overlap, so a type gets looked up in the direction of the sender first:
+/// a host sending 3 sends a valid [`FULL_UPDATE`], not [`CLIENT_DISCONNECTS_SELF`], and never gets here.
+/// From a client, the ids the relay reports to the host, like [`CLIENT_BACKLOG`], count as relay messages.
+fn role_mismatch(message_type: u8, from_host: bool) -> Option<&'static str> {
+    match (message_type, from_host) {
+        (SET_NAME | READY | REQUEST_SYNC, true) => Some("Client message received from the host."),
+        (
+            SERVER_ERROR | HAND_SHAKE_RESPONSE | READY_STATE | SPECTATOR_COUNT | HOST_MIGRATION,
+            true,
+        ) => Some("Relay message received from the host."),
+        (
+            NEW_CLIENT | CLIENT_DISCONNECTS | CLIENT_BACKLOG | SERVER_ERROR | HAND_SHAKE_RESPONSE
+            | READY_STATE | SPECTATOR_COUNT | HOST_MIGRATION,
+            false,
+        ) => Some("Relay message received from a client."),
+        (
+            LOG | DELTA_UPDATE_EXCEPT | KEEPALIVE | RESULT | PAUSE | PLAYER_NAME | DELTA_UPDATE_TO
+            | FULL_UPDATE_TO | PARTIAL_UPDATE | TICK | REJECT_ACTION | RPC_ACK,
+            false,
+        ) => Some("Host message received from a client."),
+        _ => None,
+    }
 }
 
 /// Receives messages from a client and forwards them to the host.
 ///
 /// Allowed message types from client:
 /// - [`SERVER_RPC`]: Game action — gets player ID injected before forwarding
+/// - [`SET_NAME`]: Display name — gets player ID injected before forwarding, like an RPC
+/// - [`READY`]: Readiness flag — gets player ID injected before forwarding, like an RPC
 /// - [`CLIENT_DISCONNECTS_SELF`]: Graceful disconnect (triggers cleanup)
 ///
 /// # Player ID Injection
-/// RPC messages are transformed from `[SERVER_RPC, payload...]` to
-/// `[SERVER_RPC, player_id_high, player_id_low, payload...]` so the host
-/// knows which player sent the action.
+/// RPC messages are transformed from `[SERVER_RPC, sequence..., payload...]` to
+/// `[SERVER_RPC, player_id_high, player_id_low, sequence..., payload...]` so the host
+/// knows which player sent the action. RPCs without a sequence number and payloads beyond [`MAX_RPC_PAYLOAD_SIZE`]
+/// are a protocol violation and end the connection, as are names beyond [`MAX_DISPLAY_NAME_SIZE`]
+/// and readiness flags of the wrong size. With a rate limit, RPCs beyond the rate of the game get dropped
+/// without ending the connection.
+///
+/// A close frame ends the connection right away, like [`CLIENT_DISCONNECTS_SELF`].
+/// Any other message type is rejected as a protocol violation, see [`role_mismatch`] for the reason given.
+/// Every message, including the pongs to the regular pings, touches the heartbeat of the task.
 async fn receive_logic_client(
-    mut receiver: SplitStream<WebSocket>,
+    mut receiver: impl MessageStream,
     internal_sender: tokio::sync::mpsc::Sender<Bytes>,
     player_id: u16,
+    heartbeat: ActivityTracker,
+    text_frames: bool,
+    mut rate_limit: Option<RpcRateLimit>,
 ) -> &'static str {
+    let mut dropping_rpcs = false;
     while let Some(state) = receiver.next().await {
-        match state {
-            Ok(Message::Binary(bytes)) => {
-                if bytes.is_empty() {
-                    tracing::error!("Illegal empty message received in receive logic client.");
-                    return "Illegal empty message received.";
+        heartbeat.touch();
+        let Ok(message) = state else {
+            return "Connection lost.";
+        };
+        if let Message::Close(frame) = message {
+            // A clean close of the client, treated like an intentional disconnect.
+            tracing::info!(?frame, "Client closed the web socket.");
+            return "Client closed the connection.";
+        }
+        let bytes = match incoming_message(message, text_frames) {
+            Some(Ok(bytes)) => bytes,
+            Some(Err(reason)) => return reason,
+            None => continue, // Ignore other messages (ping/pong handled by axum)
+        };
+        if bytes.is_empty() {
+            tracing::error!("Illegal empty message received in receive logic client.");
+            return "Illegal empty message received.";
+        }
+        match bytes[0] {
+            command @ (SERVER_RPC | SET_NAME | READY | REQUEST_SYNC) => {
+                if command == SERVER_RPC && bytes.len() < 1 + RPC_SEQUENCE_SIZE {
+                    tracing::error!(size = bytes.len(), "Malformed SERVER_RPC from client.");
+                    return "Malformed message received.";
                 }
-                match bytes[0] {
-                    SERVER_RPC => {
-                        // Inject player ID after command byte
-                        let mut msg = BytesMut::with_capacity(bytes.len() + CLIENT_ID_SIZE);
-                        msg.put_u8(SERVER_RPC);
-                        msg.put_u16(player_id);
-                        msg.put_slice(&bytes[1..]);
-
-                        let res = internal_sender.send(msg.into()).await;
-                        if let Err(error) = res {
-                            tracing::error!(?error, "Error in internal broadcast.");
-                            return "Error in internal broadcast.";
+                if command == SERVER_RPC
+                    && bytes.len() - 1 - RPC_SEQUENCE_SIZE > MAX_RPC_PAYLOAD_SIZE
+                {
+                    tracing::error!(
+                        size = bytes.len() - 1 - RPC_SEQUENCE_SIZE,
+                        "Oversized RPC from client."
+                    );
+                    return "RPC payload too large.";
+                }
+                if command == SERVER_RPC
+                    && let Some(rate_limit) = rate_limit.as_mut()
+                {
+                    if !rate_limit.allows(Instant::now()) {
+                        // Only the first of a run gets logged, a flooding client would flood the log as well.
+                        if !dropping_rpcs {
+                            tracing::warn!(player_id, "Dropping RPCs beyond the rate limit.");
                         }
+                        dropping_rpcs = true;
+                        continue;
                     }
-                    CLIENT_DISCONNECTS_SELF => {
-                        return "Client disconnected intentionally";
-                    }
-                    _ => {
-                        tracing::error!(command = ?bytes[0], "Illegal command from client.");
-                        return "Illegal Command from client";
-                    }
+                    dropping_rpcs = false;
+                }
+                if command == SET_NAME && bytes.len() - 1 > MAX_DISPLAY_NAME_SIZE {
+                    tracing::error!(
+                        size = bytes.len() - 1,
+                        "Oversized display name from client."
+                    );
+                    return "Display name too long.";
+                }
+                if command == READY && bytes.len() != READY_MSG_SIZE {
+                    tracing::error!(size = bytes.len(), "Malformed READY from client.");
+                    return "Malformed message received.";
+                }
+                if command == REQUEST_SYNC && bytes.len() != REQUEST_SYNC_MSG_SIZE {
+                    tracing::error!(size = bytes.len(), "Malformed REQUEST_SYNC from client.");
+                    return "Malformed message received.";
+                }
+                // Inject player ID after command byte
+                let mut msg = BytesMut::with_capacity(bytes.len() + CLIENT_ID_SIZE);
+                msg.put_u8(command);
+                msg.put_u16(player_id);
+                msg.put_slice(&bytes[1..]);
+
+                if let Err(reason) = send_to_host(&internal_sender, msg.into()).await {
+                    tracing::error!(reason, "Could not forward RPC to the host.");
+                    return reason;
                 }
             }
-            Ok(_) => {} // Ignore other messages
-            Err(_) => {
-                return "Connection lost.";
+            CLIENT_DISCONNECTS_SELF => {
+                return "Client disconnected intentionally";
+            }
+            _ => {
+                tracing::error!(command = ?bytes[0], "Illegal command from client.");
+                return role_mismatch(bytes[0], false).unwrap_or("Illegal Command from client");
             }
         }
     }
@@ -256,17 +770,31 @@ async fn receive_logic_client(
 /// [Unsynced] --DELTA_UPDATE--> [Unsynced] (dropped)
 /// ```
 ///
+/// A [`PARTIAL_UPDATE`] and a [`TICK`] are handled like a [`DELTA_UPDATE`].
+///
 /// # Filtered Messages
 /// - [`CLIENT_GETS_KICKED`]: Only terminates if `player_id` matches
-/// - [`SERVER_DISCONNECTS`]: Always terminates
+/// - [`DELTA_UPDATE_EXCEPT`]: Forwarded as [`DELTA_UPDATE`] unless `player_id` is the excluded one, which gets it
+///   unchanged, so it moves its shadow view state on without showing the change a second time
+/// - [`DELTA_UPDATE_TO`]: Forwarded as [`DELTA_UPDATE`] only if `player_id` is the addressed one
+/// - [`FULL_UPDATE_TO`]: Forwarded as [`FULL_UPDATE`] only if `player_id` is the addressed one, even if synced
+/// - [`REJECT_ACTION`]: Forwarded unchanged only if `player_id` is the addressed one, regardless of the sync state
+/// - [`RPC_ACK`]: Forwarded unchanged only if `player_id` is the addressed one, regardless of the sync state
+/// - [`SERVER_DISCONNECTS`]: Always terminates, with the message of the [`HostDeparture`] it carries
+/// - [`PAUSE`]: Forwarded regardless of the sync state, the host repeats it after the full update of a new client
+/// - [`PLAYER_NAME`]: Forwarded regardless of the sync state, the host sends all names when a client joins
+/// - [`SPECTATOR_COUNT`]: Forwarded regardless of the sync state, announced by the relay itself
+/// - [`HOST_MIGRATION`]: Forwarded regardless of the sync state, announced by the relay itself; the migration token
+///   is only kept for the new host
 ///
 /// # Error Handling
 /// Returns immediately if the broadcast channel lags (buffer overflow),
 /// as the client cannot recover from missed messages.
 async fn send_logic_client(
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
+    sender: Arc<Mutex<impl MessageSink>>,
     mut internal_receiver: tokio::sync::broadcast::Receiver<Bytes>,
     player_id: u16,
+    options: ConnectionOptions,
 ) -> &'static str {
     let mut is_synced = false;
     loop {
@@ -290,54 +818,217 @@ async fn send_logic_client(
                 }
                 match bytes[0] {
                     SERVER_DISCONNECTS => {
-                        return "Server has left the game.";
+                        let departure = bytes
+                            .get(1)
+                            .and_then(|code| HostDeparture::from_code(*code))
+                            .unwrap_or(HostDeparture::Lost);
+                        return departure.message();
                     }
                     CLIENT_GETS_KICKED => {
-                        if bytes.len() < 3 {
-                            tracing::error!("Malformed CLIENT_GETS_KICKED message");
-                            return "Malformed message received.";
-                        }
                         bytes.get_u8(); // Skip command byte
                         let meant_client = bytes.get_u16();
                         // We have to see if  we are meant.
                         if meant_client == player_id {
-                            return "We got rejected by server.";
+                            return KICKED_BY_HOST;
+                        }
+                    }
+                    DELTA_UPDATE | PARTIAL_UPDATE => {
+                        // Silently drop deltas and partial updates for unsynced clients
+                        if is_synced
+                            && let Err(reason) = send_frame(
+                                &sender,
+                                outgoing_frame(bytes, 1 + SEQUENCE_SIZE, options.text_frames),
+                                options.send_timeout,
+                                "Error in communication with client endpoint.",
+                            )
+                            .await
+                        {
+                            return reason;
                         }
                     }
-                    DELTA_UPDATE => {
-                        if is_synced {
-                            let res = sender.lock().await.send(Message::Binary(bytes)).await;
-                            if let Err(error) = res {
-                                tracing::error!(
-                                    ?error,
-                                    "Error in communication with client endpoint."
-                                );
-                                return "Error in communication with client endpoint.";
+                    TICK => {
+                        if is_synced
+                            && let Err(reason) = send_frame(
+                                &sender,
+                                outgoing_frame(bytes, 1, options.text_frames),
+                                options.send_timeout,
+                                "Error in communication with client endpoint.",
+                            )
+                            .await
+                        {
+                            return reason;
+                        }
+                    }
+                    DELTA_UPDATE_EXCEPT if is_synced && bytes[1..3] == player_id.to_be_bytes() => {
+                        // The excluded client holds the change already, it only moves its shadow view state on.
+                        if let Err(reason) = send_frame(
+                            &sender,
+                            outgoing_frame(
+                                bytes,
+                                1 + CLIENT_ID_SIZE + SEQUENCE_SIZE,
+                                options.text_frames,
+                            ),
+                            options.send_timeout,
+                            "Error in communication with client endpoint.",
+                        )
+                        .await
+                        {
+                            return reason;
+                        }
+                    }
+                    command @ (DELTA_UPDATE_EXCEPT | DELTA_UPDATE_TO) => {
+                        bytes.get_u8(); // Skip command byte
+                        let client = bytes.get_u16();
+                        // The excluded client got its message above.
+                        let is_receiver = command == DELTA_UPDATE_EXCEPT || client == player_id;
+                        if is_synced && is_receiver {
+                            // The client only knows plain delta updates.
+                            let mut msg = BytesMut::with_capacity(1 + bytes.len());
+                            msg.put_u8(DELTA_UPDATE);
+                            msg.put_slice(&bytes);
+                            if let Err(reason) = send_frame(
+                                &sender,
+                                outgoing_frame(msg.into(), 1 + SEQUENCE_SIZE, options.text_frames),
+                                options.send_timeout,
+                                "Error in communication with client endpoint.",
+                            )
+                            .await
+                            {
+                                return reason;
                             }
                         }
-                        // Silently drop deltas for unsynced clients
                     }
                     FULL_UPDATE => {
                         if !is_synced {
                             is_synced = true;
-                            let res = sender.lock().await.send(Message::Binary(bytes)).await;
-                            if let Err(error) = res {
-                                tracing::error!(
-                                    ?error,
-                                    "Error in communication with client endpoint."
-                                );
-                                return "Error in communication with client endpoint.";
+                            if let Err(reason) = send_frame(
+                                &sender,
+                                outgoing_frame(bytes, 1 + SEQUENCE_SIZE, options.text_frames),
+                                options.send_timeout,
+                                "Error in communication with client endpoint.",
+                            )
+                            .await
+                            {
+                                return reason;
                             }
                         }
                         // Drop redundant full updates for already synced clients
                     }
+                    FULL_UPDATE_TO => {
+                        bytes.get_u8(); // Skip command byte
+                        if bytes.get_u16() == player_id {
+                            // The client asked for it, so it gets it in any sync state.
+                            is_synced = true;
+                            let mut msg = BytesMut::with_capacity(1 + bytes.len());
+                            msg.put_u8(FULL_UPDATE);
+                            msg.put_slice(&bytes);
+                            if let Err(reason) = send_frame(
+                                &sender,
+                                outgoing_frame(msg.into(), 1 + SEQUENCE_SIZE, options.text_frames),
+                                options.send_timeout,
+                                "Error in communication with client endpoint.",
+                            )
+                            .await
+                            {
+                                return reason;
+                            }
+                        }
+                    }
+                    message_type @ (REJECT_ACTION | RPC_ACK) => {
+                        let header_size = if message_type == RPC_ACK {
+                            RPC_ACK_MSG_SIZE
+                        } else {
+                            1 + CLIENT_ID_SIZE
+                        };
+                        let meant_client = u16::from_be_bytes([bytes[1], bytes[2]]);
+                        if meant_client == player_id
+                            && let Err(reason) = send_frame(
+                                &sender,
+                                outgoing_frame(bytes, header_size, options.text_frames),
+                                options.send_timeout,
+                                "Error in communication with client endpoint.",
+                            )
+                            .await
+                        {
+                            return reason;
+                        }
+                    }
+                    PAUSE => {
+                        if let Err(reason) = send_frame(
+                            &sender,
+                            outgoing_frame(bytes, PAUSE_MSG_SIZE, options.text_frames),
+                            options.send_timeout,
+                            "Error in communication with client endpoint.",
+                        )
+                        .await
+                        {
+                            return reason;
+                        }
+                    }
+                    PLAYER_NAME => {
+                        if let Err(reason) = send_frame(
+                            &sender,
+                            outgoing_frame(bytes, 1 + CLIENT_ID_SIZE, options.text_frames),
+                            options.send_timeout,
+                            "Error in communication with client endpoint.",
+                        )
+                        .await
+                        {
+                            return reason;
+                        }
+                    }
+                    SPECTATOR_COUNT => {
+                        if bytes.len() < SPECTATOR_COUNT_MSG_SIZE {
+                            tracing::error!("Malformed SPECTATOR_COUNT message");
+                            return "Malformed message received.";
+                        }
+                        if let Err(reason) = send_frame(
+                            &sender,
+                            outgoing_frame(bytes, SPECTATOR_COUNT_MSG_SIZE, options.text_frames),
+                            options.send_timeout,
+                            "Error in communication with client endpoint.",
+                        )
+                        .await
+                        {
+                            return reason;
+                        }
+                    }
+                    HOST_MIGRATION => {
+                        let full_size = HOST_MIGRATION_MSG_SIZE + MIGRATION_TOKEN_SIZE;
+                        if bytes.len() < full_size {
+                            tracing::error!("Malformed HOST_MIGRATION message");
+                            return "Malformed message received.";
+                        }
+                        let successor = u16::from_be_bytes([bytes[1], bytes[2]]);
+                        let bytes = if successor == player_id {
+                            bytes.slice(..full_size)
+                        } else {
+                            bytes.slice(..HOST_MIGRATION_MSG_SIZE)
+                        };
+                        let header_size = bytes.len();
+                        if let Err(reason) = send_frame(
+                            &sender,
+                            outgoing_frame(bytes, header_size, options.text_frames),
+                            options.send_timeout,
+                            "Error in communication with client endpoint.",
+                        )
+                        .await
+                        {
+                            return reason;
+                        }
+                    }
                     RESET => {
                         // We simply forward the message and are definitively synced here.
                         is_synced = true;
-                        let res = sender.lock().await.send(Message::Binary(bytes)).await;
-                        if let Err(error) = res {
-                            tracing::error!(?error, "Error in communication with client endpoint.");
-                            return "Error in communication with client endpoint.";
+                        if let Err(reason) = send_frame(
+                            &sender,
+                            outgoing_frame(bytes, 1 + SEQUENCE_SIZE, options.text_frames),
+                            options.send_timeout,
+                            "Error in communication with client endpoint.",
+                        )
+                        .await
+                        {
+                            return reason;
                         }
                     }
                     _ => {
@@ -352,3 +1043,184 @@ async fn send_logic_client(
         }
     }
 }
+
+/// The name and the size of the fixed header of a message, as it travels inside the relay.
+fn tapped_message_kind(tapped: &TappedFrame) -> (&'static str, usize) {
+    match tapped.frame.first() {
+        Some(&message_type) => message_kind(message_type, tapped.to_host),
+        None => ("EMPTY", 0),
+    }
+}
+
+/// The name and the size of the fixed header of a message type in the indicated direction.
+fn message_kind(message_type: u8, to_host: bool) -> (&'static str, usize) {
+    if to_host {
+        match message_type {
+            NEW_CLIENT => ("NEW_CLIENT", NEW_CLIENT_MSG_SIZE),
+            CLIENT_DISCONNECTS => ("CLIENT_DISCONNECTS", CLIENT_DISCONNECT_MSG_SIZE),
+            SERVER_RPC => ("SERVER_RPC", 1 + CLIENT_ID_SIZE + RPC_SEQUENCE_SIZE),
+            CLIENT_BACKLOG => ("CLIENT_BACKLOG", CLIENT_BACKLOG_MSG_SIZE),
+            SET_NAME => ("SET_NAME", 1 + CLIENT_ID_SIZE),
+            READY => ("READY", READY_MSG_SIZE),
+            READY_STATE => ("READY_STATE", READY_STATE_MSG_SIZE),
+            SPECTATOR_COUNT => ("SPECTATOR_COUNT", SPECTATOR_COUNT_MSG_SIZE),
+            REQUEST_SYNC => ("REQUEST_SYNC", 1 + CLIENT_ID_SIZE),
+            _ => ("UNKNOWN", 1),
+        }
+    } else {
+        match message_type {
+            SERVER_DISCONNECTS => ("SERVER_DISCONNECTS", SERVER_DISCONNECT_MSG_SIZE),
+            CLIENT_GETS_KICKED => ("CLIENT_GETS_KICKED", 1 + CLIENT_ID_SIZE),
+            DELTA_UPDATE => ("DELTA_UPDATE", 1 + SEQUENCE_SIZE),
+            FULL_UPDATE => ("FULL_UPDATE", 1 + SEQUENCE_SIZE),
+            RESET => ("RESET", 1 + SEQUENCE_SIZE),
+            DELTA_UPDATE_EXCEPT => ("DELTA_UPDATE_EXCEPT", 1 + CLIENT_ID_SIZE + SEQUENCE_SIZE),
+            PAUSE => ("PAUSE", PAUSE_MSG_SIZE),
+            PLAYER_NAME => ("PLAYER_NAME", 1 + CLIENT_ID_SIZE),
+            DELTA_UPDATE_TO => ("DELTA_UPDATE_TO", 1 + CLIENT_ID_SIZE + SEQUENCE_SIZE),
+            SPECTATOR_COUNT => ("SPECTATOR_COUNT", SPECTATOR_COUNT_MSG_SIZE),
+            FULL_UPDATE_TO => ("FULL_UPDATE_TO", 1 + CLIENT_ID_SIZE + SEQUENCE_SIZE),
+            PARTIAL_UPDATE => ("PARTIAL_UPDATE", 1 + SEQUENCE_SIZE),
+            HOST_MIGRATION => ("HOST_MIGRATION", HOST_MIGRATION_MSG_SIZE),
+            TICK => ("TICK", 1),
+            REJECT_ACTION => ("REJECT_ACTION", 1 + CLIENT_ID_SIZE),
+            RPC_ACK => ("RPC_ACK", RPC_ACK_MSG_SIZE),
+            _ => ("UNKNOWN", 1),
+        }
+    }
+}
+
+/// Renders the messages of a room in the indicated direction as a histogram like `DELTA_UPDATE=12 FULL_UPDATE=2`,
+/// ordered by the message type. Unknown types appear with their number, like `UNKNOWN(42)=1`.
+pub fn message_histogram(counters: &MessageCounters, to_host: bool) -> String {
+    counters
+        .occurred(to_host)
+        .into_iter()
+        .map(
+            |(message_type, count)| match message_kind(message_type, to_host).0 {
+                "UNKNOWN" => format!("UNKNOWN({})={}", message_type, count),
+                name => format!("{}={}", name, count),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Renders a tapped message as a log line with its direction, name and header in hex digits.
+///
+/// In the JSON debugging mode, the payload is readable and gets appended as text, like
+/// `host -> clients DELTA_UPDATE 0200000007 [{"x":1}]`. Otherwise only its size is given, as the
+/// relay can not decode the payloads of the games.
+pub fn describe_tapped_frame(tapped: &TappedFrame, text_frames: bool) -> String {
+    let direction = if tapped.to_host {
+        "clients -> host"
+    } else {
+        "host -> clients"
+    };
+    let (name, header_size) = tapped_message_kind(tapped);
+    let header_size = header_size.min(tapped.frame.len());
+    if text_frames {
+        format!(
+            "{} {} {}",
+            direction,
+            name,
+            debug_text_from_frame(&tapped.frame, header_size)
+        )
+    } else {
+        let header: String = tapped.frame[..header_size]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!(
+            "{} {} {} ({} payload bytes)",
+            direction,
+            name,
+            header,
+            tapped.frame.len() - header_size
+        )
+    }
+}
+
+/// Serves a connection to the lobby chat, that exists independent of the rooms and needs no handshake.
+///
+/// Every text frame the connection sends goes to everyone in the lobby, the sender included, so all see the same
+/// order. Other frames get ignored. Lines a connection fell behind on get skipped, the chat goes on.
+/// It ends when the connection leaves or sends a line beyond [`MAX_CHAT_MESSAGE_SIZE`] bytes.
+pub async fn run_lobby_chat(
+    sender: Arc<Mutex<impl MessageSink>>,
+    mut receiver: impl MessageStream,
+    chat: tokio::sync::broadcast::Sender<String>,
+    send_timeout: Duration,
+) -> &'static str {
+    let mut lines = chat.subscribe();
+    loop {
+        let line = tokio::select! {
+            line = lines.recv() => match line {
+                Ok(line) => line,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "Lobby chat lines skipped.");
+                    continue;
+                }
+                Err(RecvError::Closed) => return "Lobby chat closed.",
+            },
+            message = receiver.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if text.len() > MAX_CHAT_MESSAGE_SIZE {
+                        tracing::warn!(size = text.len(), "Oversized lobby chat line.");
+                        return "Chat line too long.";
+                    }
+                    // Our own subscription keeps the channel open, so the send always succeeds.
+                    let _ = chat.send(text.to_string());
+                    continue;
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return "Left the lobby chat.",
+                Some(Ok(_)) => continue,
+            },
+        };
+        if let Err(reason) = send_frame(
+            &sender,
+            Message::Text(line.into()),
+            send_timeout,
+            "Error in communication with the lobby chat.",
+        )
+        .await
+        {
+            return reason;
+        }
+    }
+}
+
+/// Streams the traffic of a room to an admin as text frames, one log line per message, see [`describe_tapped_frame`].
+///
+/// The tap only listens: anything the admin sends is ignored, nothing reaches the room. If the admin falls behind,
+/// the skipped messages get noted in a line instead of ending the tap. It ends when the admin leaves or the room closes.
+pub async fn run_admin_tap(
+    sender: Arc<Mutex<impl MessageSink>>,
+    mut receiver: impl MessageStream,
+    mut tap: tokio::sync::broadcast::Receiver<TappedFrame>,
+    options: ConnectionOptions,
+) -> &'static str {
+    loop {
+        let line = tokio::select! {
+            tapped = tap.recv() => match tapped {
+                Ok(tapped) => describe_tapped_frame(&tapped, options.text_frames),
+                Err(RecvError::Lagged(skipped)) => format!("... {} messages skipped", skipped),
+                Err(RecvError::Closed) => return "Room was closed.",
+            },
+            message = receiver.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return "Admin left the tap.",
+                Some(Ok(_)) => continue,
+            },
+        };
+        if let Err(reason) = send_frame(
+            &sender,
+            Message::Text(line.into()),
+            options.send_timeout,
+            "Error in communication with the admin tap.",
+        )
+        .await
+        {
+            return reason;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! The sink for the game results hosts report with [`protocol::RESULT`].
+//!
+//! The relay does not interpret a result, it is an opaque blob the game defines. Every result gets logged with
+//! the room id and its size. If a URL is configured, the blob additionally gets posted there as
+//! `application/octet-stream`, with the compound room id as UTF-8 in the `x-room-id` header, so a separate service can
+//! keep leaderboards or statistics. Posting happens in the background and failures are only logged,
+//! so a slow or missing results service never stalls a room. Every post has a timeout, and only a bounded amount of
+//! posts may be underway at the same time; results beyond get dropped with a warning instead of piling up.
+
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, Method, Request, Uri};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// The header that carries the compound room id when a result gets posted.
+pub const ROOM_ID_HEADER: &str = "x-room-id";
+
+/// The amount of posts that may be underway at the same time by default.
+const DEFAULT_MAX_PENDING_POSTS: usize = 64;
+
+/// The time a post may take by default, before it counts as failed.
+const DEFAULT_POST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The results service the results get posted to.
+#[derive(Clone)]
+struct PostTarget {
+    /// The URL the results get posted to.
+    uri: Uri,
+    /// The HTTP client shared by all posts.
+    client: Client<HttpConnector, Full<Bytes>>,
+    /// The permits for the posts underway, one per post.
+    pending_posts: Arc<Semaphore>,
+    /// The time a post may take, before it counts as failed.
+    timeout: Duration,
+}
+
+/// Where the results of the hosts go. The default only logs them. Clones share the same HTTP client and the same
+/// bound of posts underway.
+#[derive(Default, Clone)]
+pub struct ResultsSink {
+    /// The results service, if configured.
+    target: Option<PostTarget>,
+}
+
+impl ResultsSink {
+    /// Creates a sink that posts every result to the given URL in addition to logging it.
+    /// Only plain `http` URLs are supported, put a reverse proxy in between for anything else.
+    pub fn posting_to(url: &str) -> Result<Self, String> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|error| format!("Invalid results url {}: {}", url, error))?;
+        if uri.scheme_str() != Some("http") {
+            return Err(format!("Results url {} is not a plain http url.", url));
+        }
+        let client = Client::builder(TokioExecutor::new()).build_http();
+        Ok(ResultsSink {
+            target: Some(PostTarget {
+                uri,
+                client,
+                pending_posts: Arc::new(Semaphore::new(DEFAULT_MAX_PENDING_POSTS)),
+                timeout: DEFAULT_POST_TIMEOUT,
+            }),
+        })
+    }
+
+    /// Limits the posts to the indicated amount underway at the same time, each taking at most the indicated time.
+    /// Has no effect on a sink that only logs.
+    pub fn with_post_limits(mut self, max_pending_posts: usize, timeout: Duration) -> Self {
+        if let Some(target) = self.target.as_mut() {
+            target.pending_posts = Arc::new(Semaphore::new(max_pending_posts));
+            target.timeout = timeout;
+        }
+        self
+    }
+
+    /// Hands over the result of a room. Logs it right away and posts it in the background, if a URL is configured.
+    pub fn report(&self, room_id: &str, result: Bytes) {
+        tracing::info!(room_id, size = result.len(), "Game result.");
+        let Some(target) = self.target.clone() else {
+            return;
+        };
+        let Ok(permit) = target.pending_posts.try_acquire_owned() else {
+            tracing::warn!(room_id, "Too many results underway, dropped the result.");
+            return;
+        };
+        let room_id = room_id.to_string();
+        tokio::spawn(async move {
+            // The permit gets released, once the post is done.
+            let _permit = permit;
+            // Room names may be any Unicode text, which only the raw byte form of a header value accepts.
+            let request = HeaderValue::from_bytes(room_id.as_bytes())
+                .map_err(axum::http::Error::from)
+                .and_then(|room_header| {
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri(target.uri)
+                        .header(CONTENT_TYPE, "application/octet-stream")
+                        .header(ROOM_ID_HEADER, room_header)
+                        .body(Full::new(result))
+                });
+            let request = match request {
+                Ok(request) => request,
+                Err(error) => {
+                    tracing::warn!(room_id, ?error, "Could not build the result post.");
+                    return;
+                }
+            };
+            match tokio::time::timeout(target.timeout, target.client.request(request)).await {
+                Ok(Ok(response)) if response.status().is_success() => {}
+                Ok(Ok(response)) => {
+                    tracing::warn!(room_id, status = %response.status(), "Results sink refused the result.");
+                }
+                Ok(Err(error)) => {
+                    tracing::warn!(room_id, ?error, "Could not post the result.");
+                }
+                Err(_) => {
+                    tracing::warn!(room_id, "Posting the result timed out.");
+                }
+            }
+        });
+    }
+}
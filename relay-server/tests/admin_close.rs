@@ -0,0 +1,54 @@
+//! Closing a room through the admin endpoint.
+
+mod common;
+
+use common::{join, next_binary, start_relay_with_admin_token};
+use protocol::{HAND_SHAKE_RESPONSE, HostDeparture, SERVER_ERROR};
+
+const TOKEN: &str = "secret";
+
+/// Posts to the relay without a body and returns the status code.
+async fn post(ws_url: &str, path: &str, token: Option<&str>) -> u16 {
+    common::post(ws_url, path, token, "").await
+}
+
+#[tokio::test]
+async fn admin_close_requires_token() {
+    let url = start_relay_with_admin_token(&["game"], TOKEN).await;
+    let (_host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    assert_eq!(post(&url, "/admin/close/room%23game", None).await, 401);
+    assert_eq!(
+        post(&url, "/admin/close/room%23game", Some("wrong")).await,
+        401
+    );
+    assert_eq!(
+        post(&url, "/admin/close/other%23game", Some(TOKEN)).await,
+        404
+    );
+}
+
+#[tokio::test]
+async fn admin_close_disconnects_clients() {
+    let url = start_relay_with_admin_token(&["game"], TOKEN).await;
+    let (_host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    assert_eq!(
+        post(&url, "/admin/close/room%23game", Some(TOKEN)).await,
+        200
+    );
+    let closing = next_binary(&mut client).await;
+    assert_eq!(closing[0], SERVER_ERROR);
+    assert_eq!(
+        HostDeparture::from_message(&String::from_utf8_lossy(&closing[1..])),
+        Some(HostDeparture::Closed)
+    );
+    assert_eq!(
+        post(&url, "/admin/close/room%23game", Some(TOKEN)).await,
+        404
+    );
+}
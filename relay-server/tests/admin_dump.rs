@@ -0,0 +1,27 @@
+//! The admin dump lists the rooms as JSON.
+
+mod common;
+
+use common::{http_request, join, start_relay_with_admin_token};
+use protocol::HAND_SHAKE_RESPONSE;
+
+const TOKEN: &str = "secret";
+
+#[tokio::test]
+async fn dump_lists_rooms() {
+    let url = start_relay_with_admin_token(&["game"], TOKEN).await;
+    let (_host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (status, _) = http_request(&url, "GET", "/admin/dump", None, "").await;
+    assert_eq!(status, 401);
+
+    let (status, body) = http_request(&url, "GET", "/admin/dump", Some(TOKEN), "").await;
+    assert_eq!(status, 200);
+    let rooms: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let room = &rooms[0];
+    assert_eq!(room["room_id"], "room#game");
+    assert_eq!(room["amount_of_players"], 1);
+    assert_eq!(room["is_alive"], true);
+    assert!(room["idle_secs"].is_u64());
+}
@@ -0,0 +1,29 @@
+//! Registering games at runtime through the admin endpoint.
+
+mod common;
+
+use common::{join, post, start_relay_with_admin_token};
+use protocol::{HAND_SHAKE_RESPONSE, SERVER_ERROR};
+
+const TOKEN: &str = "secret";
+
+#[tokio::test]
+async fn posted_game_can_be_hosted() {
+    let url = start_relay_with_admin_token(&[], TOKEN).await;
+    let (_socket, answer) = join(&url, "new-game", "room", true).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+
+    let entry = r#"{"name": "new-game", "max_players": 4}"#;
+    assert_eq!(post(&url, "/admin/games", None, entry).await, 401);
+    assert_eq!(post(&url, "/admin/games", Some(TOKEN), entry).await, 200);
+
+    let (_host, answer) = join(&url, "new-game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+}
+
+#[tokio::test]
+async fn invalid_game_gets_rejected() {
+    let url = start_relay_with_admin_token(&[], TOKEN).await;
+    let entry = r#"{"name": " ", "max_players": 4}"#;
+    assert_eq!(post(&url, "/admin/games", Some(TOKEN), entry).await, 400);
+}
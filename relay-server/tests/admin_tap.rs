@@ -0,0 +1,116 @@
+//! The admin tap streams the traffic of a room in both directions as log lines.
+
+mod common;
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use common::{Socket, join, next_binary, raw_frame, start_relay_with_admin_token};
+use futures_util::{SinkExt, StreamExt};
+use protocol::{DELTA_UPDATE, HAND_SHAKE_RESPONSE, NEW_CLIENT, SERVER_RPC};
+use std::time::Duration;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+const TOKEN: &str = "secret";
+
+/// Opens the tap of the room `room#game`, optionally with the admin token.
+async fn open_tap(url: &str, token: Option<&str>) -> Result<Socket, u16> {
+    let mut request = url
+        .replace("/ws", "/admin/tap/room%23game")
+        .into_client_request()
+        .unwrap();
+    if let Some(token) = token {
+        request.headers_mut().insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+    }
+    match connect_async(request).await {
+        Ok((socket, _)) => Ok(socket),
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+            Err(response.status().as_u16())
+        }
+        Err(error) => panic!("Unexpected error: {}", error),
+    }
+}
+
+/// Waits for the next log line of the tap.
+async fn next_line(tap: &mut Socket) -> String {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), tap.next())
+            .await
+            .expect("No line in time")
+            .expect("Tap ended")
+            .expect("Tap error");
+        if let Message::Text(text) = message {
+            return text.to_string();
+        }
+    }
+}
+
+/// The log line of a message with its header, which shows the payload in the JSON debugging mode and only
+/// its size otherwise.
+fn described(header: &str, payload: &str) -> String {
+    if ActiveCodec::TEXT_FRAMES {
+        format!("{} {}", header, payload)
+    } else {
+        format!("{} ({} payload bytes)", header, payload.len())
+    }
+}
+
+#[tokio::test]
+async fn tap_requires_token_and_room() {
+    let url = start_relay_with_admin_token(&["game"], TOKEN).await;
+    assert_eq!(open_tap(&url, None).await.err(), Some(401));
+    assert_eq!(open_tap(&url, Some("wrong")).await.err(), Some(401));
+    assert_eq!(open_tap(&url, Some(TOKEN)).await.err(), Some(404));
+}
+
+#[tokio::test]
+async fn tap_mirrors_both_directions() {
+    let url = start_relay_with_admin_token(&["game"], TOKEN).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let mut tap = open_tap(&url, Some(TOKEN)).await.unwrap();
+
+    let (mut client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await, vec![NEW_CLIENT, 0, 1]);
+    assert_eq!(
+        next_line(&mut tap).await,
+        described("clients -> host NEW_CLIENT 000001", "")
+    );
+
+    client
+        .send(raw_frame(vec![SERVER_RPC, 0, 0, 0, 7, b'7', b'8']))
+        .await
+        .unwrap();
+    assert_eq!(
+        next_binary(&mut host).await,
+        vec![SERVER_RPC, 0, 1, 0, 0, 0, 7, b'7', b'8']
+    );
+    assert_eq!(
+        next_line(&mut tap).await,
+        described("clients -> host SERVER_RPC 02000100000007", "78")
+    );
+
+    host.send(raw_frame(vec![DELTA_UPDATE, 0, 0, 0, 1, b'9']))
+        .await
+        .unwrap();
+    assert_eq!(
+        next_line(&mut tap).await,
+        described("host -> clients DELTA_UPDATE 0200000001", "9")
+    );
+
+    // Anything the admin sends is ignored and never reaches the room.
+    tap.send(Message::Binary(vec![DELTA_UPDATE, 0, 0, 0, 2, 9].into()))
+        .await
+        .unwrap();
+    host.send(raw_frame(vec![DELTA_UPDATE, 0, 0, 0, 3, b'9']))
+        .await
+        .unwrap();
+    assert_eq!(
+        next_line(&mut tap).await,
+        described("host -> clients DELTA_UPDATE 0200000003", "9")
+    );
+}
@@ -0,0 +1,24 @@
+//! A client that closes its web socket cleanly gets reported to the host right away.
+
+mod common;
+
+use common::{join, next_binary};
+use protocol::{CLIENT_DISCONNECTS, HAND_SHAKE_RESPONSE, NEW_CLIENT};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "close-frame";
+
+#[tokio::test]
+async fn close_frame_disconnects_client_promptly() {
+    let url = common::start_relay(&[GAME]).await;
+    let (mut host, answer) = join(&url, GAME, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await, vec![NEW_CLIENT, 0, 1]);
+
+    client.close(None).await.unwrap();
+
+    // The client is still holding its tcp connection, only the close frame signals the disconnect.
+    assert_eq!(next_binary(&mut host).await, vec![CLIENT_DISCONNECTS, 0, 1]);
+}
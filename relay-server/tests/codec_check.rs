@@ -0,0 +1,33 @@
+//! Clients have to use the codec the host of the room announced in its join request.
+
+mod common;
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use common::{join, join_with_request};
+use protocol::{GameId, HAND_SHAKE_RESPONSE, JoinRequest, RoomId, SERVER_ERROR};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "codec";
+
+#[tokio::test]
+async fn client_with_another_codec_gets_rejected() {
+    let url = common::start_relay(&[GAME]).await;
+    let (_host, answer) = join(&url, GAME, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let request = JoinRequest {
+        game_id: GameId::new(GAME),
+        room_id: RoomId::new("room"),
+        rule_variation: 0,
+        create_room: false,
+        codec: ActiveCodec::ID + 7,
+        migration_token: None,
+    };
+    let (_client, answer) = join_with_request(&url, &request).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+    assert!(String::from_utf8_lossy(&answer[1..]).contains("different codec"));
+
+    // The same codec as the host is fine.
+    let (_client, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+}
@@ -0,0 +1,546 @@
+//! Shared setup for the relay integration tests.
+
+// Not every test uses every helper.
+#![allow(dead_code)]
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use backbone_lib::traits::{
+    BackEndArchitecture, BackendCommand, PlayerSet, SerializationCap, ViewStateArchitecture,
+};
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer, ViewStateUpdate};
+use futures_util::{SinkExt, StreamExt};
+use protocol::{GameId, JoinRequest, RoomId, debug_text_from_frame, frame_from_debug_text};
+use relay_server::lobby::{AppState, GameEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::RwLock;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+
+/// A raw web socket connection to the relay.
+pub type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Starts the relay on an ephemeral port with the given games registered and returns the web socket url.
+pub async fn start_relay(games: &[&str]) -> String {
+    start_relay_with_state(games, AppState::default()).await
+}
+
+/// Starts the relay like [`start_relay`] with the admin endpoints enabled for the given token.
+pub async fn start_relay_with_admin_token(games: &[&str], token: &str) -> String {
+    let state = AppState {
+        admin_token: Some(token.to_string()),
+        ..AppState::default()
+    };
+    start_relay_with_state(games, state).await
+}
+
+/// Starts the relay with the game entries registered and returns the web socket url.
+pub async fn start_relay_with_entries(entries: impl IntoIterator<Item = GameEntry>) -> String {
+    let state = AppState {
+        configs: RwLock::new(
+            entries
+                .into_iter()
+                .map(|entry| (entry.name.clone(), entry))
+                .collect(),
+        ),
+        ..AppState::default()
+    };
+    start_relay_with_state(&[], state).await
+}
+
+/// Starts the relay like [`start_relay`] with a prepared state, e.g. with custom game entries.
+pub async fn start_relay_with_state(games: &[&str], app_state: AppState) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    start_relay_on(listener, games, app_state).await
+}
+
+/// Starts the relay like [`start_relay_with_state`] on a listener bound already, e.g. to a known port.
+pub async fn start_relay_on(
+    listener: tokio::net::TcpListener,
+    games: &[&str],
+    app_state: AppState,
+) -> String {
+    let app_state = Arc::new(app_state);
+    for game in games {
+        app_state
+            .configs
+            .write()
+            .await
+            .insert(game.to_string(), GameEntryBuilder::new(game).build());
+    }
+
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            relay_server::router(app_state).into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+    format!("ws://{}/ws", address)
+}
+
+/// Builds a game entry for a test, starting from a game without any limits.
+pub struct GameEntryBuilder(GameEntry);
+
+impl GameEntryBuilder {
+    /// Starts the entry of the game with the indicated name.
+    pub fn new(name: &str) -> Self {
+        GameEntryBuilder(GameEntry {
+            name: name.to_string(),
+            max_players: 0,
+            allowed_variations: None,
+            max_spectators: None,
+            variation_names: None,
+            max_rooms: None,
+            persistent_rooms: None,
+            host_migration: false,
+            max_rpcs_per_sec: None,
+        })
+    }
+
+    /// Limits the amount of players.
+    pub fn max_players(mut self, max_players: u16) -> Self {
+        self.0.max_players = max_players;
+        self
+    }
+
+    /// Sets the spectator slots, missing ones are the same as none.
+    pub fn max_spectators(mut self, max_spectators: Option<u16>) -> Self {
+        self.0.max_spectators = max_spectators;
+        self
+    }
+
+    /// Names a rule variation for the listings.
+    pub fn variation_name(mut self, rule_variation: u16, name: &str) -> Self {
+        self.0
+            .variation_names
+            .get_or_insert_with(HashMap::new)
+            .insert(rule_variation, name.to_string());
+        self
+    }
+
+    /// Limits the amount of open rooms.
+    pub fn max_rooms(mut self, max_rooms: u16) -> Self {
+        self.0.max_rooms = Some(max_rooms);
+        self
+    }
+
+    /// Lets rooms survive their host leaving.
+    pub fn host_migration(mut self) -> Self {
+        self.0.host_migration = true;
+        self
+    }
+
+    /// Limits the RPCs a single client may send per second.
+    pub fn max_rpcs_per_sec(mut self, max_rpcs_per_sec: u32) -> Self {
+        self.0.max_rpcs_per_sec = Some(max_rpcs_per_sec);
+        self
+    }
+
+    /// Finishes the entry.
+    pub fn build(self) -> GameEntry {
+        self.0
+    }
+}
+
+/// Connects to the relay with a raw socket, sends the join request and returns the socket
+/// together with the first answer (handshake response or error).
+pub async fn join(url: &str, game: &str, room: &str, create_room: bool) -> (Socket, Vec<u8>) {
+    join_with_variation(url, game, room, create_room, 0).await
+}
+
+/// Joins like [`join`] with the indicated rule variation.
+pub async fn join_with_variation(
+    url: &str,
+    game: &str,
+    room: &str,
+    create_room: bool,
+    rule_variation: u16,
+) -> (Socket, Vec<u8>) {
+    let request = JoinRequest {
+        game_id: GameId::new(game),
+        room_id: RoomId::new(room),
+        rule_variation,
+        create_room,
+        codec: ActiveCodec::ID,
+        migration_token: None,
+    };
+    join_with_request(url, &request).await
+}
+
+/// Joins like [`join`] with the indicated join request.
+pub async fn join_with_request(url: &str, request: &JoinRequest) -> (Socket, Vec<u8>) {
+    let (mut socket, _) = connect_async(url).await.unwrap();
+    socket.send(join_message(request)).await.unwrap();
+    let answer = next_binary(&mut socket).await;
+    (socket, answer)
+}
+
+/// The join request as the transport layer sends it, a JSON text frame in the JSON debugging mode.
+pub fn join_message(request: &JoinRequest) -> Message {
+    if ActiveCodec::TEXT_FRAMES {
+        Message::Text(serde_json::to_string(request).unwrap().into())
+    } else {
+        Message::Binary(postcard::to_stdvec(request).unwrap().into())
+    }
+}
+
+/// Wraps a frame in the binary layout for a raw socket. In the JSON debugging mode it becomes a text frame
+/// with all bytes written as hex digits, which the relay turns back into the same frame.
+pub fn raw_frame(bytes: Vec<u8>) -> Message {
+    if ActiveCodec::TEXT_FRAMES {
+        Message::Text(debug_text_from_frame(&bytes, bytes.len()).into())
+    } else {
+        Message::Binary(bytes.into())
+    }
+}
+
+/// The frame in the binary layout a message carries, if it is a frame of the active mode.
+pub fn frame_bytes(message: Message) -> Option<Vec<u8>> {
+    match message {
+        Message::Binary(bytes) if !ActiveCodec::TEXT_FRAMES => Some(bytes.to_vec()),
+        Message::Text(text) if ActiveCodec::TEXT_FRAMES => {
+            Some(frame_from_debug_text(text.as_str()).unwrap())
+        }
+        _ => None,
+    }
+}
+
+/// Waits for the next frame in the binary layout, skipping pings. Text frames of the JSON debugging mode get
+/// converted back.
+pub async fn next_binary(socket: &mut Socket) -> Vec<u8> {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("No message in time")
+            .expect("Socket ended")
+            .expect("Socket error");
+        if let Some(bytes) = frame_bytes(message) {
+            return bytes;
+        }
+    }
+}
+
+/// Sends a bare HTTP post with a JSON body to the relay behind the web socket url and returns the status code.
+pub async fn post(ws_url: &str, path: &str, token: Option<&str>, body: &str) -> u16 {
+    http_request(ws_url, "POST", path, token, body).await.0
+}
+
+/// Sends a bare HTTP request to the relay behind the web socket url and returns the status code and the body.
+pub async fn http_request(
+    ws_url: &str,
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+    body: &str,
+) -> (u16, String) {
+    let address = ws_url
+        .trim_start_matches("ws://")
+        .trim_end_matches("/ws")
+        .to_string();
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    let authorization = token
+        .map(|token| format!("Authorization: Bearer {}\r\n", token))
+        .unwrap_or_default();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        address,
+        authorization,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let status = response[9..12].parse().unwrap();
+    let body = response
+        .split_once("\r\n\r\n")
+        .map_or(String::new(), |(_, body)| body.to_string());
+    (status, body)
+}
+
+/// Repeats the step every few milliseconds until it reports that the condition holds,
+/// failing the test if that takes longer than ten seconds.
+pub async fn poll_until(mut step: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !step() {
+        assert!(Instant::now() < deadline, "Condition not reached in time");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// Updates the layers until the condition holds, the view state updates stay queued.
+pub async fn drive_until<ServerRpcPayload, DeltaInformation, Backend, ViewState>(
+    layers: &mut [&mut TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>],
+    mut condition: impl FnMut(
+        &mut [&mut TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>],
+    ) -> bool,
+) where
+    ServerRpcPayload: SerializationCap,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>,
+    DeltaInformation: SerializationCap + Clone,
+    ViewState: SerializationCap + Clone + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>,
+{
+    poll_until(|| {
+        for layer in layers.iter_mut() {
+            layer.update(0.01);
+        }
+        condition(layers)
+    })
+    .await;
+}
+
+/// What a test keeps of the updates a transport layer hands to its frontend.
+pub trait Frontend<ViewState, DeltaInformation> {
+    /// Takes the next view state update.
+    fn apply(&mut self, update: ViewStateUpdate<ViewState, DeltaInformation>);
+
+    /// Takes the reason the host gave for rejecting an action, the default ignores it.
+    fn reject(&mut self, _reason: String) {}
+}
+
+/// Updates the layers like [`drive_until`], but hands their view state updates and rejections
+/// to the frontend of the same index.
+pub async fn drive_frontends_until<ServerRpcPayload, DeltaInformation, Backend, ViewState, F>(
+    layers: &mut [&mut TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>],
+    frontends: &mut [F],
+    condition: impl Fn(
+        &[&mut TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>],
+        &[F],
+    ) -> bool,
+) where
+    ServerRpcPayload: SerializationCap,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>,
+    DeltaInformation: SerializationCap + Clone,
+    ViewState: SerializationCap + Clone + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>,
+    F: Frontend<ViewState, DeltaInformation>,
+{
+    poll_until(|| {
+        for (layer, frontend) in layers.iter_mut().zip(frontends.iter_mut()) {
+            layer.update(0.01);
+            while let Some(update) = layer.get_next_update() {
+                frontend.apply(update);
+            }
+            if let Some(reason) = layer.take_rejection() {
+                frontend.reject(reason);
+            }
+        }
+        condition(layers, frontends)
+    })
+    .await;
+}
+
+/// Checks if the layer is connected to the relay, as host or as client.
+pub fn is_connected<ServerRpcPayload, DeltaInformation, Backend, ViewState>(
+    layer: &TransportLayer<ServerRpcPayload, DeltaInformation, Backend, ViewState>,
+) -> bool
+where
+    ServerRpcPayload: SerializationCap,
+    Backend: BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState>,
+    DeltaInformation: SerializationCap + Clone,
+    ViewState: SerializationCap + Clone + ViewStateArchitecture<ServerRpcPayload, DeltaInformation>,
+{
+    matches!(layer.connection_state(), ConnectionState::Connected { .. })
+}
+
+/// A view state without any content.
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct Empty;
+
+impl<ServerRpcPayload, DeltaInformation> ViewStateArchitecture<ServerRpcPayload, DeltaInformation>
+    for Empty
+{
+    fn try_apply(&mut self, _: &DeltaInformation) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The view state takes over the last delta.
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct Last<T>(pub T);
+
+impl<ServerRpcPayload, T: Clone> ViewStateArchitecture<ServerRpcPayload, T> for Last<T> {
+    fn try_apply(&mut self, delta: &T) -> Result<(), String> {
+        self.0 = delta.clone();
+        Ok(())
+    }
+}
+
+/// The sum of all numbers the players sent.
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct Sum(pub u32);
+
+impl ViewStateArchitecture<u32, u32> for Sum {
+    fn try_apply(&mut self, delta: &u32) -> Result<(), String> {
+        self.0 += delta;
+        Ok(())
+    }
+}
+
+/// A backend that never changes anything, for layers that only join as client or host an idle game.
+pub struct IdleBackend<ViewState> {
+    view_state: ViewState,
+}
+
+impl<ServerRpcPayload, DeltaInformation, ViewState>
+    BackEndArchitecture<ServerRpcPayload, DeltaInformation, ViewState> for IdleBackend<ViewState>
+where
+    ServerRpcPayload: SerializationCap,
+    DeltaInformation: SerializationCap,
+    ViewState: SerializationCap + Clone + Default,
+{
+    fn new(_: u16) -> Self {
+        IdleBackend {
+            view_state: ViewState::default(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, _: ServerRpcPayload, _: &PlayerSet) {}
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &ViewState {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<DeltaInformation>> {
+        Vec::new()
+    }
+}
+
+/// A backend that echoes every RPC as a delta.
+pub struct EchoBackend {
+    view_state: Last<u8>,
+    command_list: Vec<BackendCommand<u8>>,
+}
+
+impl BackEndArchitecture<u8, u8, Last<u8>> for EchoBackend {
+    fn new(_: u16) -> Self {
+        EchoBackend {
+            view_state: Last::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, payload: u8, _: &PlayerSet) {
+        self.view_state.0 = payload;
+        self.command_list.push(BackendCommand::Delta(payload));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Last<u8> {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u8>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+/// Adds every RPC to the sum, and a hundred every time its timer of half a second runs out.
+pub struct SumBackend {
+    view_state: Sum,
+    command_list: Vec<BackendCommand<u32>>,
+}
+
+impl BackEndArchitecture<u32, u32, Sum> for SumBackend {
+    fn new(_: u16) -> Self {
+        SumBackend {
+            view_state: Sum::default(),
+            command_list: vec![BackendCommand::SetTimer {
+                timer_id: 0,
+                duration: 0.5,
+            }],
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, amount: u32, _: &PlayerSet) {
+        self.view_state.0 += amount;
+        self.command_list.push(BackendCommand::Delta(amount));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {
+        self.view_state.0 += 100;
+        self.command_list.push(BackendCommand::Delta(100));
+    }
+    fn get_view_state(&self) -> &Sum {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u32>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+/// The in-memory stand-in for the sending half of a web socket, every frame ends up in the channel.
+pub struct MockSink(pub tokio::sync::mpsc::UnboundedSender<axum::extract::ws::Message>);
+
+impl futures_util::Sink<axum::extract::ws::Message> for MockSink {
+    type Error = axum::Error;
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn start_send(
+        self: std::pin::Pin<&mut Self>,
+        item: axum::extract::ws::Message,
+    ) -> Result<(), Self::Error> {
+        self.0.send(item).map_err(axum::Error::new)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+/// The in-memory stand-in for the receiving half of a web socket, fed through the channel.
+/// Dropping the sender of the channel looks like a lost connection.
+pub struct MockStream(pub tokio::sync::mpsc::UnboundedReceiver<axum::extract::ws::Message>);
+
+impl futures_util::Stream for MockStream {
+    type Item = Result<axum::extract::ws::Message, axum::Error>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        context: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.poll_recv(context).map(|message| message.map(Ok))
+    }
+}
+
+/// Creates a mocked endpoint: the sink and stream for the relay logic and the channel ends of the test,
+/// to feed frames into the relay and to read the frames it sent.
+pub fn mock_endpoint() -> (
+    MockSink,
+    MockStream,
+    tokio::sync::mpsc::UnboundedSender<axum::extract::ws::Message>,
+    tokio::sync::mpsc::UnboundedReceiver<axum::extract::ws::Message>,
+) {
+    let (outgoing_sender, outgoing_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (incoming_sender, incoming_receiver) = tokio::sync::mpsc::unbounded_channel();
+    (
+        MockSink(outgoing_sender),
+        MockStream(incoming_receiver),
+        incoming_sender,
+        outgoing_receiver,
+    )
+}
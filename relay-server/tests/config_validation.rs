@@ -0,0 +1,86 @@
+//! Validation of the game configuration before it replaces the live one.
+
+use relay_server::lobby::parse_config;
+
+#[test]
+fn valid_config_gets_mapped_by_name() {
+    let configs = parse_config(
+        r#"[{"name": "tic-tac-toe", "max_players": 2}, {"name": "Ternio", "max_players": 3}]"#,
+    )
+    .unwrap();
+    assert_eq!(configs.len(), 2);
+    assert_eq!(configs["Ternio"].max_players, 3);
+}
+
+#[test]
+fn duplicate_names_get_rejected() {
+    let result = parse_config(
+        r#"[{"name": "tic-tac-toe", "max_players": 2}, {"name": "tic-tac-toe", "max_players": 5}]"#,
+    );
+    assert!(result.unwrap_err().contains("tic-tac-toe"));
+}
+
+#[test]
+fn empty_names_get_rejected() {
+    assert!(parse_config(r#"[{"name": "", "max_players": 2}]"#).is_err());
+    assert!(parse_config(r#"[{"name": "  ", "max_players": 2}]"#).is_err());
+}
+
+#[test]
+fn variation_names_must_be_allowed_and_non_empty() {
+    let configs = parse_config(
+        r#"[{"name": "Ternio", "max_players": 3, "allowed_variations": [0, 1], "variation_names": {"1": "Blitz"}}]"#,
+    )
+    .unwrap();
+    assert_eq!(configs["Ternio"].variation_name(1), Some("Blitz"));
+    assert_eq!(configs["Ternio"].variation_name(0), None);
+
+    let result = parse_config(
+        r#"[{"name": "Ternio", "max_players": 3, "allowed_variations": [0], "variation_names": {"1": "Blitz"}}]"#,
+    );
+    assert!(result.unwrap_err().contains("variation 1"));
+    assert!(
+        parse_config(r#"[{"name": "Ternio", "max_players": 3, "variation_names": {"0": " "}}]"#)
+            .is_err()
+    );
+}
+
+#[test]
+fn persistent_rooms_must_be_valid_and_unique() {
+    let configs = parse_config(
+        r#"[{"name": "Ternio", "max_players": 3, "persistent_rooms": [{"room": "Public"}]}]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        configs["Ternio"].persistent_rooms.as_ref().unwrap()[0].rule_variation,
+        0
+    );
+
+    let result = parse_config(
+        r#"[{"name": "Ternio", "max_players": 3, "allowed_variations": [0], "persistent_rooms": [{"room": "Public", "rule_variation": 1}]}]"#,
+    );
+    assert!(result.unwrap_err().contains("variation 1"));
+    let result = parse_config(
+        r#"[{"name": "Ternio", "max_players": 3, "persistent_rooms": [{"room": "Public"}, {"room": "Public"}]}]"#,
+    );
+    assert!(result.unwrap_err().contains("Duplicate"));
+    assert!(
+        parse_config(
+            r#"[{"name": "Ternio", "max_players": 3, "persistent_rooms": [{"room": ""}]}]"#
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn persistent_rooms_must_fit_the_room_limit() {
+    let configs = parse_config(
+        r#"[{"name": "Ternio", "max_players": 3, "max_rooms": 1, "persistent_rooms": [{"room": "Public"}]}]"#,
+    )
+    .unwrap();
+    assert_eq!(configs["Ternio"].max_rooms, Some(1));
+    let result = parse_config(
+        r#"[{"name": "Ternio", "max_players": 3, "max_rooms": 1, "persistent_rooms": [{"room": "A"}, {"room": "B"}]}]"#,
+    );
+    assert!(result.unwrap_err().contains("limit of 1 rooms"));
+}
@@ -0,0 +1,69 @@
+//! A failed connect to the relay gets retried a few times, so a relay that comes up shortly after is still reached.
+
+mod common;
+
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer};
+use common::{Empty, IdleBackend, drive_until, is_connected, start_relay_on};
+use relay_server::lobby::AppState;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "retry";
+
+type Layer = TransportLayer<(), (), IdleBackend<Empty>, Empty>;
+
+/// An address nobody listens on right now.
+async fn free_address() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn relay_coming_up_after_a_failed_attempt_gets_reached() {
+    let address = free_address().await;
+    let mut layer = Layer::generate_transport_layer(format!("ws://{}/ws", address), GAME.into());
+    layer.set_connect_retries(5, 0.2);
+    layer.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut layer], |layers| {
+        matches!(
+            layers[0].connection_state(),
+            ConnectionState::RetryingConnect { .. }
+        )
+    })
+    .await;
+    assert_eq!(
+        layer.connection_state(),
+        &ConnectionState::RetryingConnect {
+            attempt: 1,
+            max_attempts: 5
+        }
+    );
+
+    let listener = TcpListener::bind(address).await.unwrap();
+    start_relay_on(listener, &[GAME], AppState::default()).await;
+    drive_until(&mut [&mut layer], |layers| is_connected(layers[0])).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connect_gives_up_after_the_last_retry() {
+    let address = free_address().await;
+    let mut layer = Layer::generate_transport_layer(format!("ws://{}/ws", address), GAME.into());
+    layer.set_connect_retries(2, 0.05);
+    layer.start_game_client("room".to_string());
+    let mut last_attempt = 0;
+    drive_until(&mut [&mut layer], |layers| {
+        let state = layers[0].connection_state();
+        if let ConnectionState::RetryingConnect { attempt, .. } = state {
+            last_attempt = *attempt;
+        }
+        matches!(
+            state,
+            ConnectionState::Disconnected {
+                error_string: Some(_)
+            }
+        )
+    })
+    .await;
+    assert_eq!(last_attempt, 2);
+}
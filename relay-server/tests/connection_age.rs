@@ -0,0 +1,62 @@
+//! The transport layer measures the time since the connection got established.
+
+mod common;
+
+use backbone_lib::transport_layer::TransportLayer;
+use common::{Empty, IdleBackend, is_connected, poll_until};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "age";
+
+type Layer = TransportLayer<(), (), IdleBackend<Empty>, Empty>;
+
+/// Updates the layer with the given delta time until it is connected.
+async fn connect(layer: &mut Layer, delta_time: f32) {
+    poll_until(|| {
+        layer.update(delta_time);
+        is_connected(layer)
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn age_counts_from_the_connection_on() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    assert_eq!(host.connection_age_secs(), 0.0);
+
+    // The time spent on the handshake does not count.
+    host.start_game_server("room".to_string(), 0);
+    connect(&mut host, 5.0).await;
+    assert_eq!(host.connection_age_secs(), 0.0);
+
+    host.update(0.5);
+    host.update(0.25);
+    assert_eq!(host.connection_age_secs(), 0.75);
+
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    client.start_game_client("room".to_string());
+    connect(&mut client, 0.0).await;
+    client.update(1.5);
+    assert_eq!(client.connection_age_secs(), 1.5);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn age_starts_anew_with_every_connection() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url, GAME.to_string());
+    host.start_game_server("first".to_string(), 0);
+    connect(&mut host, 0.0).await;
+    host.update(2.0);
+    assert_eq!(host.connection_age_secs(), 2.0);
+
+    host.disconnect();
+    assert_eq!(host.connection_age_secs(), 0.0);
+    host.update(1.0);
+    assert_eq!(host.connection_age_secs(), 0.0);
+
+    host.start_game_server("second".to_string(), 0);
+    connect(&mut host, 0.0).await;
+    host.update(0.5);
+    assert_eq!(host.connection_age_secs(), 0.5);
+}
@@ -0,0 +1,67 @@
+//! A single address can not hold more web socket connections than the configured limit.
+
+mod common;
+
+use common::{join, start_relay_with_state};
+use protocol::HAND_SHAKE_RESPONSE;
+use relay_server::lobby::{AppState, PeerConnections};
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Error;
+
+/// The limit of connections per address in this test.
+const LIMIT: usize = 3;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connections_beyond_the_limit_get_rejected() {
+    let state = AppState {
+        max_connections_per_peer: Some(LIMIT),
+        ..AppState::default()
+    };
+    let url = start_relay_with_state(&["game"], state).await;
+
+    let (host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let mut clients = Vec::new();
+    for _ in 1..LIMIT {
+        let (client, answer) = join(&url, "game", "room", false).await;
+        assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+        clients.push(client);
+    }
+
+    // A connection beyond the limit does not even get upgraded.
+    for _ in 0..2 {
+        match connect_async(&url).await {
+            Err(Error::Http(response)) => assert_eq!(response.status(), 429),
+            other => panic!("Expected a rejection, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    // Once a connection is gone, its slot is free again.
+    drop(clients.pop());
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if connect_async(&url).await.is_ok() {
+            break;
+        }
+        assert!(Instant::now() < deadline, "Slot was not given back in time");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    drop(host);
+}
+
+#[test]
+fn rejected_peers_leave_no_entry() {
+    let connections = PeerConnections::default();
+    let peer = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    assert!(connections.try_acquire(peer, Some(0)).is_none());
+    assert_eq!(connections.peers(), 0);
+
+    let slot = connections.try_acquire(peer, Some(1));
+    assert!(slot.is_some());
+    assert!(connections.try_acquire(peer, Some(1)).is_none());
+    assert_eq!(connections.count(peer), 1);
+    drop(slot);
+    assert_eq!(connections.peers(), 0);
+}
@@ -0,0 +1,95 @@
+//! The public HTTP endpoints answer browser pages of the configured origins with CORS headers, the admin endpoints and
+//! unconfigured relays do not.
+
+mod common;
+
+use common::start_relay_with_state;
+use relay_server::lobby::{AppState, CorsOrigins};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// The origin of the lobby page in these tests.
+const LOBBY_ORIGIN: &str = "https://lobby.example.org";
+
+/// Starts a relay with the indicated CORS origins and returns its web socket url.
+async fn relay_allowing(origins: Option<&str>) -> String {
+    let state = AppState {
+        cors_origins: origins.map(|origins| CorsOrigins::parse(origins).unwrap()),
+        admin_token: Some("secret".to_string()),
+        ..AppState::default()
+    };
+    start_relay_with_state(&["game"], state).await
+}
+
+/// Sends a bare HTTP request from a page of the origin and returns the status code and the lower cased
+/// allowed origin of the answer, if any.
+async fn request_from(
+    ws_url: &str,
+    method: &str,
+    path: &str,
+    origin: &str,
+    extra_headers: &str,
+) -> (u16, Option<String>) {
+    let address = ws_url.trim_start_matches("ws://").trim_end_matches("/ws");
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\nOrigin: {origin}\r\n{extra_headers}Content-Length: 0\r\nConnection: close\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let status = response[9..12].parse().unwrap();
+    let (head, _) = response.split_once("\r\n\r\n").unwrap();
+    let allowed_origin = head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("access-control-allow-origin")
+            .then(|| value.trim().to_string())
+    });
+    (status, allowed_origin)
+}
+
+#[tokio::test]
+async fn listed_origins_get_the_public_endpoints() {
+    let url = relay_allowing(Some("https://board-game-hub.de, https://lobby.example.org")).await;
+    for path in ["/enlist", "/rooms", "/reload"] {
+        let (status, allowed) = request_from(&url, "GET", path, LOBBY_ORIGIN, "").await;
+        assert_eq!(status, 200, "{path}");
+        assert_eq!(allowed.as_deref(), Some(LOBBY_ORIGIN), "{path}");
+    }
+    let (_, allowed) = request_from(&url, "GET", "/rooms", "https://evil.example.org", "").await;
+    assert_eq!(allowed, None);
+
+    let preflight = "Access-Control-Request-Method: GET\r\n";
+    let (status, allowed) = request_from(&url, "OPTIONS", "/rooms", LOBBY_ORIGIN, preflight).await;
+    assert_eq!(status, 200);
+    assert_eq!(allowed.as_deref(), Some(LOBBY_ORIGIN));
+}
+
+#[tokio::test]
+async fn admin_endpoints_and_unconfigured_relays_send_no_headers() {
+    let url = relay_allowing(Some("*")).await;
+    let (_, allowed) = request_from(&url, "GET", "/rooms", LOBBY_ORIGIN, "").await;
+    assert_eq!(allowed.as_deref(), Some("*"));
+    let authorization = "Authorization: Bearer secret\r\n";
+    let (status, allowed) =
+        request_from(&url, "GET", "/admin/dump", LOBBY_ORIGIN, authorization).await;
+    assert_eq!(status, 200);
+    assert_eq!(allowed, None);
+
+    let url = relay_allowing(None).await;
+    let (status, allowed) = request_from(&url, "GET", "/rooms", LOBBY_ORIGIN, "").await;
+    assert_eq!(status, 200);
+    assert_eq!(allowed, None);
+}
+
+#[test]
+fn origin_lists_get_parsed() {
+    assert_eq!(CorsOrigins::parse(" * "), Ok(CorsOrigins::Any));
+    let CorsOrigins::Listed(origins) = CorsOrigins::parse("https://a.de,https://b.de,").unwrap()
+    else {
+        panic!("Origins not listed");
+    };
+    assert_eq!(origins, ["https://a.de", "https://b.de"]);
+    assert!(CorsOrigins::parse(" , ").is_err());
+    assert!(CorsOrigins::parse("https://a.de\u{7f}").is_err());
+}
@@ -0,0 +1,139 @@
+//! A payload the client can not decode ends the connection with the message type and the size in the error.
+//! A single corrupt delta in a run keeps the connection, as the length prefixes keep the other deltas apart. The client
+//! missed its change though, so it drops the rest until a full update heals the gap.
+//! The corrupt payloads are hand built postcard bytes, so the JSON debugging mode has no part in this.
+#![cfg(not(feature = "json-debug"))]
+
+mod common;
+
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer, ViewStateUpdate};
+use common::{
+    IdleBackend, Last, Socket, drive_until, is_connected, join, next_binary, poll_until, raw_frame,
+};
+use futures_util::SinkExt;
+use protocol::{
+    DELTA_UPDATE, FULL_UPDATE, FULL_UPDATE_TO, HAND_SHAKE_RESPONSE, NEW_CLIENT, REQUEST_SYNC,
+};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "decode";
+
+type Layer = TransportLayer<u8, u16, IdleBackend<Last<u16>>, Last<u16>>;
+
+/// Connects a client to the room of a raw host and synchronizes it with a full update.
+async fn synchronized_client(url: &str, host: &mut Socket) -> Layer {
+    let mut client = Layer::generate_transport_layer(url.to_string(), GAME.to_string());
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut client], |layers| is_connected(layers[0])).await;
+    assert_eq!(next_binary(host).await[0], NEW_CLIENT);
+
+    let mut full_update = vec![FULL_UPDATE, 0, 0, 0, 0];
+    full_update.extend(postcard::to_stdvec(&Last(1u16)).unwrap());
+    host.send(raw_frame(full_update)).await.unwrap();
+    client
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn truncated_delta_names_type_and_size() {
+    let url = common::start_relay(&[GAME]).await;
+    let (mut host, answer) = join(&url, GAME, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let mut client = synchronized_client(&url, &mut host).await;
+
+    // The length prefix of the first delta is cut off, so the deltas can not be told apart anymore.
+    host.send(raw_frame(vec![DELTA_UPDATE, 0, 0, 0, 1, 0x80]))
+        .await
+        .unwrap();
+
+    let mut error = None;
+    poll_until(|| {
+        client.update(0.01);
+        while client.get_next_update().is_some() {}
+        if let ConnectionState::Disconnected { error_string } = client.connection_state() {
+            error = error_string.clone();
+        }
+        error.is_some()
+    })
+    .await;
+    let error = error.unwrap();
+    assert!(
+        error.starts_with("Failed to decode DELTA_UPDATE payload (1 bytes)"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn corrupt_delta_desynchronizes_until_a_full_update() {
+    let url = common::start_relay(&[GAME]).await;
+    let (mut host, answer) = join(&url, GAME, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let mut client = synchronized_client(&url, &mut host).await;
+
+    // The middle delta announces a further varint byte, that its length prefix does not cover.
+    let frame = [
+        DELTA_UPDATE,
+        0,
+        0,
+        0,
+        1,
+        0,
+        0,
+        0,
+        1,
+        5,
+        0,
+        0,
+        0,
+        1,
+        0x80,
+        0,
+        0,
+        0,
+        1,
+        7,
+    ];
+    host.send(raw_frame(frame.to_vec())).await.unwrap();
+
+    let mut deltas = Vec::new();
+    poll_until(|| {
+        client.update(0.01);
+        while let Some(update) = client.get_next_update() {
+            if let ViewStateUpdate::Incremental(delta) = update {
+                deltas.push(delta);
+            }
+        }
+        client.is_desynchronized()
+    })
+    .await;
+    // The delta after the corrupt one builds on the missing change.
+    assert_eq!(deltas, [5]);
+    assert!(is_connected(&client));
+    // The client asks for a fresh state, as it missed a delta.
+    let request = next_binary(&mut host).await;
+    assert_eq!(request[0], REQUEST_SYNC);
+
+    // Later deltas get dropped as well, until the full update arrives.
+    host.send(raw_frame(vec![DELTA_UPDATE, 0, 0, 0, 2, 0, 0, 0, 1, 8]))
+        .await
+        .unwrap();
+    let mut full_update = vec![FULL_UPDATE_TO, request[1], request[2], 0, 0, 0, 3];
+    full_update.extend(postcard::to_stdvec(&Last(9u16)).unwrap());
+    host.send(raw_frame(full_update)).await.unwrap();
+    let mut full = None;
+    poll_until(|| {
+        client.update(0.01);
+        while let Some(update) = client.get_next_update() {
+            match update {
+                ViewStateUpdate::Incremental(delta) => deltas.push(delta),
+                ViewStateUpdate::Full(Last(value)) => full = Some(value),
+                _ => {}
+            }
+        }
+        full.is_some()
+    })
+    .await;
+    assert_eq!(full, Some(9));
+    assert_eq!(deltas, [5]);
+    assert!(!client.is_desynchronized());
+}
@@ -0,0 +1,113 @@
+//! The client excluded from a delta keeps its shadow view state in step, so later deltas building on it still fit.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{Frontend, drive_frontends_until, is_connected};
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "except";
+
+/// A counter that only accepts the next number as delta.
+#[derive(Clone, Serialize, Deserialize, Default, PartialEq, Debug)]
+struct Counter(u32);
+
+impl ViewStateArchitecture<(), u32> for Counter {
+    fn try_apply(&mut self, delta: &u32) -> Result<(), String> {
+        if *delta != self.0 + 1 {
+            return Err(format!("Expected {}, got {}", self.0 + 1, delta));
+        }
+        self.0 = *delta;
+        Ok(())
+    }
+}
+
+/// Counts up for every RPC, the sender counted up on its own already and is left out.
+struct CountingBackend {
+    view_state: Counter,
+    command_list: Vec<BackendCommand<u32>>,
+}
+
+impl BackEndArchitecture<(), u32, Counter> for CountingBackend {
+    fn new(_: u16) -> Self {
+        CountingBackend {
+            view_state: Counter::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, player: u16, _: (), _: &PlayerSet) {
+        self.view_state.0 += 1;
+        self.command_list.push(BackendCommand::DeltaExcept {
+            player,
+            delta: self.view_state.0,
+        });
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Counter {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u32>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<(), u32, CountingBackend, Counter>;
+
+/// The counter a frontend sees, with the amount of full updates it got.
+impl Frontend<Counter, u32> for (Option<u32>, usize) {
+    fn apply(&mut self, update: ViewStateUpdate<Counter, u32>) {
+        match update {
+            ViewStateUpdate::Full(Counter(full)) | ViewStateUpdate::FullReset(Counter(full)) => {
+                self.0 = Some(full);
+                self.1 += 1;
+            }
+            ViewStateUpdate::Incremental(delta) => self.0 = Some(delta),
+            ViewStateUpdate::Tick => {}
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_excluded_client_stays_synchronized() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut first = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut second = Layer::generate_transport_layer(url, GAME.to_string());
+    let mut counters = [(None, 0), (None, 0), (None, 0)];
+
+    host.start_game_server("room".to_string(), 0);
+    drive_frontends_until(&mut [&mut host], &mut counters[..1], |layers, _| {
+        is_connected(layers[0])
+    })
+    .await;
+    first.start_game_client("room".to_string());
+    second.start_game_client("room".to_string());
+    let mut layers = [&mut host, &mut first, &mut second];
+    drive_frontends_until(&mut layers, &mut counters, |_, counters| {
+        counters[1..].iter().all(|(counter, _)| *counter == Some(0))
+    })
+    .await;
+
+    // The first client counts up on its own, the others learn it from the host.
+    counters[1].0 = Some(1);
+    layers[1].register_server_rpc(());
+    drive_frontends_until(&mut layers, &mut counters, |_, counters| {
+        counters[2].0 == Some(1)
+    })
+    .await;
+    // The delta of the second client builds on the one the first client was left out of.
+    counters[2].0 = Some(2);
+    layers[2].register_server_rpc(());
+    drive_frontends_until(&mut layers, &mut counters, |_, counters| {
+        counters[1].0 == Some(2)
+    })
+    .await;
+
+    assert!(!layers[1].is_desynchronized());
+    assert_eq!(counters[1].1, 1, "The excluded client needed a resync");
+    assert_eq!(counters[2].1, 1);
+}
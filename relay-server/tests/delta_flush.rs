@@ -0,0 +1,77 @@
+//! With a flush interval, the host collects the deltas of several updates and sends them in one frame.
+
+mod common;
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use backbone_lib::transport_layer::TransportLayer;
+use common::{EchoBackend, Last, Socket, frame_bytes, is_connected, join, next_binary, poll_until};
+use futures_util::StreamExt;
+use protocol::{DELTA_UPDATE, FULL_UPDATE, HAND_SHAKE_RESPONSE, PLAYER_NAME, SEQUENCE_SIZE};
+use std::time::{Duration, Instant};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "flush";
+
+type Layer = TransportLayer<u8, u8, EchoBackend, Last<u8>>;
+
+/// The next view state update of the raw client, skipping the player names.
+async fn next_update(client: &mut Socket) -> Vec<u8> {
+    loop {
+        let frame = next_binary(client).await;
+        if frame[0] != PLAYER_NAME {
+            return frame;
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deltas_get_collected_until_the_interval_passed() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    host.set_delta_flush_interval(200);
+    host.start_game_server("room".to_string(), 0);
+    poll_until(|| {
+        host.update(0.0);
+        is_connected(&host)
+    })
+    .await;
+
+    let (mut client, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    // The host sends the full update, once it learned about the client.
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        assert!(
+            Instant::now() < deadline,
+            "Full update did not arrive in time"
+        );
+        host.update(0.0);
+        if let Ok(Some(Ok(message))) =
+            tokio::time::timeout(Duration::from_millis(10), client.next()).await
+            && frame_bytes(message).is_some_and(|frame| frame[0] == FULL_UPDATE)
+        {
+            break;
+        }
+    }
+
+    // Three updates with a delta each, 0.05 seconds pass with every update, 0.15 in total.
+    for delta in 1..=3 {
+        host.register_server_rpc(delta);
+        host.update(0.05);
+    }
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), client.next())
+            .await
+            .is_err(),
+        "Deltas got sent before the interval passed"
+    );
+
+    host.update(0.05);
+    host.update(0.05);
+    let frame = next_update(&mut client).await;
+    assert_eq!(frame[0], DELTA_UPDATE);
+    assert_eq!(
+        frame[1 + SEQUENCE_SIZE..],
+        ActiveCodec::encode_sequence(&[1u8, 2, 3]).unwrap()
+    );
+}
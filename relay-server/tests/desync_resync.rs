@@ -0,0 +1,106 @@
+//! A client that missed an update can not apply the following delta, so it requests a full update and heals with it
+//! instead of continuing with a corrupt view state.
+
+mod common;
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use backbone_lib::traits::ViewStateArchitecture;
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{
+    Frontend, IdleBackend, drive_frontends_until, drive_until, is_connected, join, next_binary,
+    raw_frame,
+};
+use futures_util::SinkExt;
+use protocol::{
+    DELTA_UPDATE, FULL_UPDATE, FULL_UPDATE_TO, HAND_SHAKE_RESPONSE, NEW_CLIENT, REQUEST_SYNC,
+};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "desync";
+
+/// A counter that only accepts the next number, so a skipped delta shows up right away.
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct Counter(u16);
+
+impl ViewStateArchitecture<u8, u16> for Counter {
+    fn try_apply(&mut self, delta: &u16) -> Result<(), String> {
+        if *delta != self.0 + 1 {
+            return Err(format!("Expected {}, got {}", self.0 + 1, delta));
+        }
+        self.0 = *delta;
+        Ok(())
+    }
+}
+
+type Layer = TransportLayer<u8, u16, IdleBackend<Counter>, Counter>;
+
+/// A readable form of the updates, as the view state update itself can not be compared.
+#[derive(PartialEq, Debug)]
+enum Seen {
+    Full(u16),
+    Delta(u16),
+}
+
+/// Builds a delta update with a single delta.
+fn delta(sequence: u32, value: u16) -> Message {
+    let mut frame = vec![DELTA_UPDATE];
+    frame.extend(sequence.to_be_bytes());
+    frame.extend(ActiveCodec::encode_sequence(&[value]).unwrap());
+    raw_frame(frame)
+}
+
+/// Collects the updates of the client.
+impl Frontend<Counter, u16> for Vec<Seen> {
+    fn apply(&mut self, update: ViewStateUpdate<Counter, u16>) {
+        self.push(match update {
+            ViewStateUpdate::Full(Counter(value)) | ViewStateUpdate::FullReset(Counter(value)) => {
+                Seen::Full(value)
+            }
+            ViewStateUpdate::Incremental(value) => Seen::Delta(value),
+            ViewStateUpdate::Tick => return,
+        });
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unapplicable_delta_requests_a_resync() {
+    let url = common::start_relay(&[GAME]).await;
+    let (mut host, answer) = join(&url, GAME, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut client], |layers| is_connected(layers[0])).await;
+    let mut seen = [Vec::new()];
+    let joined = next_binary(&mut host).await;
+    assert_eq!(joined[0], NEW_CLIENT);
+
+    let mut full_update = vec![FULL_UPDATE, 0, 0, 0, 0];
+    full_update.extend(ActiveCodec::encode(&Counter(1)).unwrap());
+    host.send(raw_frame(full_update)).await.unwrap();
+    host.send(delta(1, 2)).await.unwrap();
+    drive_frontends_until(&mut [&mut client], &mut seen, |_, seen| seen[0].len() == 2).await;
+    assert_eq!(seen[0], [Seen::Full(1), Seen::Delta(2)]);
+
+    // The delta to 3 got lost, so neither the 4 nor the 5 fit anymore.
+    host.send(delta(3, 4)).await.unwrap();
+    host.send(delta(4, 5)).await.unwrap();
+    drive_until(&mut [&mut client], |layers| layers[0].is_desynchronized()).await;
+    let request = next_binary(&mut host).await;
+    assert_eq!(request[0], REQUEST_SYNC);
+    assert_eq!(request[1..3], joined[1..3]);
+
+    // The requested full update heals the client, later deltas apply again.
+    let mut full_update = vec![FULL_UPDATE_TO, joined[1], joined[2], 0, 0, 0, 4];
+    full_update.extend(ActiveCodec::encode(&Counter(5)).unwrap());
+    host.send(raw_frame(full_update)).await.unwrap();
+    host.send(delta(5, 6)).await.unwrap();
+    drive_frontends_until(&mut [&mut client], &mut seen, |_, seen| seen[0].len() == 4).await;
+    assert_eq!(
+        seen[0],
+        [Seen::Full(1), Seen::Delta(2), Seen::Full(5), Seen::Delta(6)]
+    );
+    assert!(!client.is_desynchronized());
+    assert!(is_connected(&client));
+}
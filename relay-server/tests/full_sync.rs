@@ -0,0 +1,76 @@
+//! The host sends a full sync only for joined clients that are still subscribed.
+
+mod common;
+
+use backbone_lib::transport_layer::TransportLayer;
+use common::{Empty, IdleBackend, Socket, drive_until, frame_bytes, is_connected, join};
+use futures_util::{SinkExt, StreamExt};
+use protocol::{FULL_UPDATE, HAND_SHAKE_RESPONSE};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "full-sync";
+
+type Layer = TransportLayer<(), (), IdleBackend<Empty>, Empty>;
+
+/// Creates a room with a connected host.
+async fn connected_host(url: &str) -> Layer {
+    let mut host = Layer::generate_transport_layer(url.to_string(), GAME.to_string());
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    host
+}
+
+/// Heartbeats the host for the given time and collects the full updates the client got meanwhile.
+async fn full_updates_within(
+    host: &mut Layer,
+    client: &mut Socket,
+    time: Duration,
+) -> Vec<Vec<u8>> {
+    let mut result = Vec::new();
+    let end = Instant::now() + time;
+    while Instant::now() < end {
+        host.update(0.01);
+        if let Ok(Some(Ok(message))) =
+            tokio::time::timeout(Duration::from_millis(10), client.next()).await
+            && let Some(frame) = frame_bytes(message)
+            && frame[0] == FULL_UPDATE
+        {
+            result.push(frame);
+        }
+    }
+    result
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn single_client_gets_exactly_one_full_sync() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = connected_host(&url).await;
+    let (mut client, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let full_updates =
+        full_updates_within(&mut host, &mut client, Duration::from_millis(500)).await;
+    assert_eq!(full_updates.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_leaving_before_its_sync_gets_none() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = connected_host(&url).await;
+
+    // The host learns about the join and the departure within the same update.
+    let (mut gone, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    gone.send(Message::Close(None)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    host.update(0.01);
+
+    // The sequence numbers of the host show that no full sync went out in between.
+    let (mut client, _) = join(&url, GAME, "room", false).await;
+    let full_updates =
+        full_updates_within(&mut host, &mut client, Duration::from_millis(500)).await;
+    assert_eq!(full_updates.len(), 1);
+    assert_eq!(full_updates[0][1..5], 0u32.to_be_bytes());
+}
@@ -0,0 +1,70 @@
+//! Every configured game gets its frontend served from its own directory, next to the top level lobby.
+
+mod common;
+
+use common::{http_request, start_relay_with_state};
+use relay_server::lobby::AppState;
+use std::path::PathBuf;
+
+/// Creates a static root with a lobby page and the frontend of the game `alpha` in a fresh temporary directory.
+fn static_root(name: &str) -> PathBuf {
+    let root =
+        std::env::temp_dir().join(format!("relay-frontends-{}-{}", name, std::process::id()));
+    let game_dir = root.join("games").join("alpha");
+    std::fs::create_dir_all(&game_dir).unwrap();
+    std::fs::write(root.join("index.html"), "lobby").unwrap();
+    std::fs::write(game_dir.join("index.html"), "alpha index").unwrap();
+    std::fs::write(game_dir.join("alpha.js"), "alpha script").unwrap();
+    root
+}
+
+/// Starts the relay with the game `alpha` registered on top of the given static root.
+async fn start(root: PathBuf) -> String {
+    let state = AppState {
+        static_root: root,
+        ..AppState::default()
+    };
+    start_relay_with_state(&["alpha", "beta"], state).await
+}
+
+async fn get(url: &str, path: &str) -> (u16, String) {
+    http_request(url, "GET", path, None, "").await
+}
+
+#[tokio::test]
+async fn game_files_come_from_the_game_directory() {
+    let url = start(static_root("files")).await;
+
+    assert_eq!(
+        get(&url, "/games/alpha/alpha.js").await,
+        (200, "alpha script".to_string())
+    );
+    assert_eq!(
+        get(&url, "/games/alpha/").await,
+        (200, "alpha index".to_string())
+    );
+    // Missing files of the game fall back to its own index, not the lobby, like the lobby with a 404 status.
+    assert_eq!(get(&url, "/games/alpha/some/route").await.1, "alpha index");
+    assert_eq!(get(&url, "/games/alpha").await.0, 308);
+}
+
+#[tokio::test]
+async fn lobby_stays_at_top_level() {
+    let url = start(static_root("lobby")).await;
+
+    assert_eq!(get(&url, "/").await, (200, "lobby".to_string()));
+    assert_eq!(get(&url, "/unknown/page").await.1, "lobby");
+    assert_eq!(get(&url, "/rooms").await, (200, "[]".to_string()));
+}
+
+#[tokio::test]
+async fn unknown_games_and_escapes_are_not_served() {
+    let url = start(static_root("escapes")).await;
+
+    assert_eq!(get(&url, "/games/gamma/alpha.js").await.0, 404);
+    assert_eq!(get(&url, "/games/../index.html").await.0, 404);
+    assert_eq!(
+        get(&url, "/games/alpha/../../index.html").await.1,
+        "alpha index"
+    );
+}
@@ -0,0 +1,82 @@
+//! A headless host runs the full server side by stepping alone, without queuing anything for a frontend.
+
+mod common;
+
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer, ViewStateUpdate};
+use common::{Sum, SumBackend, is_connected, poll_until};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "headless";
+
+type Layer = TransportLayer<u32, u32, SumBackend, Sum>;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn headless_host_serves_a_client_without_queuing_updates() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_headless_host(url.clone(), GAME.to_string());
+    host.start_game_server("room".to_string(), 0);
+    poll_until(|| {
+        host.step_without_render(0.0);
+        is_connected(&host)
+    })
+    .await;
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    client.start_game_client("room".to_string());
+
+    // The host steps with simulated time only, the timer of the backend runs out after a few steps.
+    let mut sum = None;
+    let mut sent = false;
+    poll_until(|| {
+        host.step_without_render(0.1);
+        assert_eq!(host.pending_update_count(), 0);
+        client.update(0.01);
+        if sum == Some(100) && !sent {
+            client.register_server_rpc(5);
+            sent = true;
+        }
+        while let Some(update) = client.get_next_update() {
+            match update {
+                ViewStateUpdate::Full(Sum(full)) | ViewStateUpdate::FullReset(Sum(full)) => {
+                    sum = Some(full)
+                }
+                ViewStateUpdate::Incremental(amount) => sum = sum.map(|sum| sum + amount),
+                ViewStateUpdate::Tick => {}
+            }
+        }
+        sum == Some(105)
+    })
+    .await;
+    assert!(matches!(
+        host.connection_state(),
+        ConnectionState::Connected {
+            is_server: true,
+            ..
+        }
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stepping_drops_the_updates_of_a_regular_host() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url, GAME.to_string());
+    host.start_game_server("room".to_string(), 0);
+    poll_until(|| {
+        host.step_without_render(0.01);
+        is_connected(&host)
+    })
+    .await;
+    host.register_server_rpc(3);
+    host.step_without_render(0.01);
+    assert_eq!(host.pending_update_count(), 0);
+    host.register_server_rpc(4);
+    host.update(0.01);
+    assert_eq!(host.pending_update_count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "A headless host can not join as client")]
+fn headless_host_can_not_join() {
+    let mut host =
+        Layer::generate_headless_host("ws://127.0.0.1:1/ws".to_string(), GAME.to_string());
+    host.start_game_client("room".to_string());
+}
@@ -0,0 +1,78 @@
+//! Clients learn why the host is gone: it ended or aborted the game, or the connection to it broke down.
+
+mod common;
+
+use common::{Socket, join, next_binary, raw_frame, start_relay};
+use futures_util::SinkExt;
+use protocol::{HAND_SHAKE_RESPONSE, HostDeparture, NEW_CLIENT, SERVER_DISCONNECTS, SERVER_ERROR};
+
+/// Opens a room with a raw host and a raw client and returns both.
+async fn open_room() -> (Socket, Socket) {
+    let url = start_relay(&["game"]).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+    (host, client)
+}
+
+/// Waits for the closing message of the client and reads the departure from it.
+async fn departure_seen_by(client: &mut Socket) -> Option<HostDeparture> {
+    let closing = next_binary(client).await;
+    assert_eq!(closing[0], SERVER_ERROR);
+    HostDeparture::from_message(&String::from_utf8_lossy(&closing[1..]))
+}
+
+#[tokio::test]
+async fn host_without_code_ended_the_game() {
+    let (mut host, mut client) = open_room().await;
+    host.send(raw_frame(vec![SERVER_DISCONNECTS]))
+        .await
+        .unwrap();
+    assert_eq!(
+        departure_seen_by(&mut client).await,
+        Some(HostDeparture::Ended)
+    );
+}
+
+#[tokio::test]
+async fn host_aborting_the_game_gets_reported() {
+    let (mut host, mut client) = open_room().await;
+    let message = vec![SERVER_DISCONNECTS, HostDeparture::Aborted.code()];
+    host.send(raw_frame(message)).await.unwrap();
+    assert_eq!(
+        departure_seen_by(&mut client).await,
+        Some(HostDeparture::Aborted)
+    );
+}
+
+#[tokio::test]
+async fn vanished_host_counts_as_lost_connection() {
+    let (host, mut client) = open_room().await;
+    // No disconnect message and no close frame, the socket is simply gone.
+    drop(host);
+    assert_eq!(
+        departure_seen_by(&mut client).await,
+        Some(HostDeparture::Lost)
+    );
+}
+
+#[test]
+fn departure_codes_and_messages_round_trip() {
+    for departure in [
+        HostDeparture::Ended,
+        HostDeparture::Aborted,
+        HostDeparture::Lost,
+        HostDeparture::Closed,
+        HostDeparture::Migrated,
+    ] {
+        assert_eq!(HostDeparture::from_code(departure.code()), Some(departure));
+        assert_eq!(
+            HostDeparture::from_message(departure.message()),
+            Some(departure)
+        );
+    }
+    assert_eq!(HostDeparture::from_code(200), None);
+    assert_eq!(HostDeparture::from_message("Game full."), None);
+}
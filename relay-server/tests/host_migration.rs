@@ -0,0 +1,211 @@
+//! In a game with host migration, the room survives its host leaving: the client that joined first becomes the new host.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::{
+    ConnectionState, HostDeparture, TransportLayer, ViewStateUpdate,
+};
+use common::{
+    Frontend, GameEntryBuilder, drive_frontends_until, http_request, join, start_relay_with_entries,
+};
+use protocol::SERVER_ERROR;
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "counter";
+
+/// The sum of all numbers the players added.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+struct Count(u32);
+
+impl ViewStateArchitecture<u32, u32> for Count {
+    fn try_apply(&mut self, delta: &u32) -> Result<(), String> {
+        self.0 += delta;
+        Ok(())
+    }
+}
+
+/// Adds the number of every RPC, and can continue from the count of a client.
+struct CounterBackend {
+    view_state: Count,
+    command_list: Vec<BackendCommand<u32>>,
+}
+
+impl BackEndArchitecture<u32, u32, Count> for CounterBackend {
+    fn new(_: u16) -> Self {
+        CounterBackend {
+            view_state: Count(0),
+            command_list: Vec::new(),
+        }
+    }
+    fn from_view_state(_: u16, view_state: &Count, _: u16) -> Option<Self> {
+        Some(CounterBackend {
+            view_state: view_state.clone(),
+            command_list: Vec::new(),
+        })
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, amount: u32, _: &PlayerSet) {
+        self.view_state.0 += amount;
+        self.command_list.push(BackendCommand::Delta(amount));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Count {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u32>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u32, u32, CounterBackend, Count>;
+
+/// The count a frontend sees.
+impl Frontend<Count, u32> for Option<u32> {
+    fn apply(&mut self, update: ViewStateUpdate<Count, u32>) {
+        match update {
+            ViewStateUpdate::Full(Count(count)) | ViewStateUpdate::FullReset(Count(count)) => {
+                *self = Some(count)
+            }
+            ViewStateUpdate::Incremental(amount) => {
+                *self.as_mut().expect("Delta before full update") += amount
+            }
+            ViewStateUpdate::Tick => {}
+        }
+    }
+}
+
+fn new_layer(url: &str) -> Layer {
+    Layer::generate_transport_layer(url.to_string(), GAME.to_string())
+}
+
+fn is_host(layer: &Layer) -> bool {
+    matches!(
+        layer.connection_state(),
+        ConnectionState::Connected {
+            is_server: true,
+            ..
+        }
+    )
+}
+
+/// Starts a relay with the counter game, which has host migration.
+async fn start_relay() -> String {
+    let entry = GameEntryBuilder::new(GAME)
+        .max_players(4)
+        .host_migration()
+        .build();
+    start_relay_with_entries([entry]).await
+}
+
+/// Opens the room with a host and two clients, and lets the first client add to the count.
+/// The counts are in the order host, first client, second client.
+async fn start_room(url: &str) -> (Layer, Layer, Layer, [Option<u32>; 3]) {
+    let mut counts = [None; 3];
+    let mut host = new_layer(url);
+    host.start_game_server("table".to_string(), 0);
+    drive_frontends_until(&mut [&mut host], &mut counts[..1], |layers, _| {
+        is_host(layers[0])
+    })
+    .await;
+    let mut first = new_layer(url);
+    first.start_game_client("table".to_string());
+    drive_frontends_until(
+        &mut [&mut host, &mut first],
+        &mut counts[..2],
+        |_, counts| counts[1].is_some(),
+    )
+    .await;
+    let mut second = new_layer(url);
+    second.start_game_client("table".to_string());
+    first.register_server_rpc(5);
+    drive_frontends_until(
+        &mut [&mut host, &mut first, &mut second],
+        &mut counts,
+        |_, counts| counts.iter().all(|count| *count == Some(5)),
+    )
+    .await;
+    (host, first, second, counts)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn first_client_becomes_the_new_host() {
+    let url = start_relay().await;
+    let (mut host, mut first, mut second, mut counts) = start_room(&url).await;
+
+    host.disconnect();
+    drive_frontends_until(
+        &mut [&mut first, &mut second],
+        &mut counts[1..],
+        |layers, _| is_host(layers[0]) && !layers[1].is_migrating() && layers[1].has_initial_sync(),
+    )
+    .await;
+    assert!(!first.is_migrating());
+    assert!(matches!(
+        second.connection_state(),
+        ConnectionState::Connected {
+            is_server: false,
+            ..
+        }
+    ));
+
+    // The game goes on with the count from before.
+    second.register_server_rpc(2);
+    drive_frontends_until(
+        &mut [&mut first, &mut second],
+        &mut counts[1..],
+        |_, counts| counts.iter().all(|count| *count == Some(7)),
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn aborted_game_does_not_migrate() {
+    let url = start_relay().await;
+    let (mut host, mut first, mut second, mut counts) = start_room(&url).await;
+
+    host.disconnect_with(HostDeparture::Aborted);
+    drive_frontends_until(
+        &mut [&mut first, &mut second],
+        &mut counts[1..],
+        |layers, _| {
+            layers
+                .iter()
+                .all(|layer| layer.host_departure() == Some(HostDeparture::Aborted))
+        },
+    )
+    .await;
+    assert!(!first.is_migrating());
+    assert!(!second.is_migrating());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_the_new_host_may_take_the_room_over() {
+    let url = start_relay().await;
+    let (mut host, mut first, mut second, mut counts) = start_room(&url).await;
+
+    // The second client learns about the migration, while the first one has not taken the room over yet.
+    host.disconnect();
+    drive_frontends_until(&mut [&mut second], &mut counts[2..], |layers, _| {
+        layers[0].is_migrating()
+    })
+    .await;
+
+    // Coming from the same address as the new host does not help without its migration token.
+    let (_socket, answer) = join(&url, GAME, "table", true).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+    assert!(String::from_utf8_lossy(&answer[1..]).contains("reserved for its new host"));
+    // Meanwhile the lobby does not offer the room to anyone.
+    let (_, body) = http_request(&url, "GET", "/rooms", None, "").await;
+    assert_eq!(body, "[]");
+
+    drive_frontends_until(
+        &mut [&mut first, &mut second],
+        &mut counts[1..],
+        |layers, _| is_host(layers[0]) && layers[1].has_initial_sync(),
+    )
+    .await;
+    assert_eq!(counts[2], Some(5));
+}
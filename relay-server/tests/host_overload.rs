@@ -0,0 +1,39 @@
+//! A client gets dropped, when its host does not keep up with reading.
+
+mod common;
+
+use common::{frame_bytes, join, raw_frame, start_relay};
+use futures_util::{SinkExt, StreamExt};
+use protocol::{HAND_SHAKE_RESPONSE, MAX_RPC_PAYLOAD_SIZE, SERVER_ERROR, SERVER_RPC};
+use std::time::Duration;
+
+#[tokio::test]
+async fn flooding_a_stalled_host_drops_the_client() {
+    let url = start_relay(&["game"]).await;
+    // The host never reads, so the channel to it fills up once the socket buffers are full.
+    let (_host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut writer, mut reader) = client.split();
+
+    let flood = tokio::spawn(async move {
+        let mut frame = vec![SERVER_RPC, 0, 0, 0, 0];
+        frame.resize(frame.len() + MAX_RPC_PAYLOAD_SIZE, 1);
+        while writer.send(raw_frame(frame.clone())).await.is_ok() {}
+    });
+
+    let closing = loop {
+        let message = tokio::time::timeout(Duration::from_secs(30), reader.next())
+            .await
+            .expect("Client was not dropped in time")
+            .expect("Socket ended")
+            .expect("Socket error");
+        if let Some(bytes) = frame_bytes(message) {
+            break bytes;
+        }
+    };
+    assert_eq!(closing[0], SERVER_ERROR);
+    assert_eq!(&closing[1..], b"Host overloaded.");
+    flood.abort();
+}
@@ -0,0 +1,82 @@
+//! In debug builds the host checks the invariants of the view state right after the backend changed it.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::TransportLayer;
+use common::{drive_until, is_connected};
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "invariant";
+
+/// The sum of all numbers the players sent, which must never exceed ten.
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct Sum(u32);
+
+impl ViewStateArchitecture<u32, u32> for Sum {
+    fn try_apply(&mut self, delta: &u32) -> Result<(), String> {
+        self.0 += delta;
+        Ok(())
+    }
+
+    fn debug_assert_valid(&self) {
+        assert!(self.0 <= 10, "Sum exceeds ten");
+    }
+}
+
+/// Adds every rpc without any check, so it may break the invariant.
+struct CarelessBackend {
+    view_state: Sum,
+    command_list: Vec<BackendCommand<u32>>,
+}
+
+impl BackEndArchitecture<u32, u32, Sum> for CarelessBackend {
+    fn new(_: u16) -> Self {
+        CarelessBackend {
+            view_state: Sum::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, amount: u32, _: &PlayerSet) {
+        self.view_state.0 += amount;
+        self.command_list.push(BackendCommand::Delta(amount));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Sum {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u32>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u32, u32, CarelessBackend, Sum>;
+
+/// Connects a host to a fresh relay.
+async fn connected_host() -> Layer {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url, GAME.to_string());
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    host
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn valid_changes_pass_the_check() {
+    let mut host = connected_host().await;
+    host.register_server_rpc(4);
+    host.register_server_rpc(6);
+    host.update(0.01);
+    assert_eq!(host.pending_update_count(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[should_panic(expected = "Sum exceeds ten")]
+async fn broken_invariant_panics_right_after_the_drain() {
+    let mut host = connected_host().await;
+    host.register_server_rpc(11);
+    host.update(0.01);
+}
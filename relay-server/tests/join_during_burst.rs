@@ -0,0 +1,113 @@
+//! A client joining in the middle of a burst of deltas applies every delta exactly once.
+
+mod common;
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use backbone_lib::traits::ViewStateArchitecture;
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{
+    IdleBackend, Socket, drive_until, is_connected, join, next_binary, poll_until, raw_frame,
+};
+use futures_util::SinkExt;
+use protocol::{DELTA_UPDATE, FULL_UPDATE, HAND_SHAKE_RESPONSE, NEW_CLIENT};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "burst";
+
+/// The view state sums up all deltas, so a delta applied twice shows in the total.
+#[derive(Clone, Serialize, Deserialize, Default, PartialEq, Debug)]
+struct Counter(u32);
+
+impl ViewStateArchitecture<u8, u8> for Counter {
+    fn try_apply(&mut self, delta: &u8) -> Result<(), String> {
+        self.0 += *delta as u32;
+        Ok(())
+    }
+}
+
+type Layer = TransportLayer<u8, u8, IdleBackend<Counter>, Counter>;
+
+/// Sends a view state update with the given sequence number and encoded payload from the raw host.
+async fn send_update(host: &mut Socket, message_type: u8, sequence: u32, payload: Vec<u8>) {
+    let mut frame = vec![message_type];
+    frame.extend_from_slice(&sequence.to_be_bytes());
+    frame.extend(payload);
+    host.send(raw_frame(frame)).await.unwrap();
+}
+
+/// Sends a single delta with the given sequence number from the raw host.
+async fn send_delta(host: &mut Socket, sequence: u32, delta: u8) {
+    send_update(
+        host,
+        DELTA_UPDATE,
+        sequence,
+        ActiveCodec::encode_sequence(&[delta]).unwrap(),
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delta_reflected_by_full_update_is_not_applied_again() {
+    let url = common::start_relay(&[GAME]).await;
+    let (mut host, answer) = join(&url, GAME, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    // The burst starts before anyone listens.
+    send_delta(&mut host, 0, 1).await;
+    send_delta(&mut host, 1, 1).await;
+
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut client], |layers| is_connected(layers[0])).await;
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+
+    // The burst goes on while the host learns about the client: the relay drops this delta for the unsynced client.
+    send_delta(&mut host, 2, 1).await;
+    // The full update reflects all three deltas so far.
+    send_update(
+        &mut host,
+        FULL_UPDATE,
+        3,
+        ActiveCodec::encode(&Counter(3)).unwrap(),
+    )
+    .await;
+    // A delta of the burst, that interleaved across frames and reaches the client after the full update.
+    send_delta(&mut host, 3, 1).await;
+    send_delta(&mut host, 2, 1).await;
+    // The burst continues.
+    send_delta(&mut host, 4, 2).await;
+
+    let mut received = Vec::new();
+    let mut counter = Counter::default();
+    poll_until(|| {
+        client.update(0.01);
+        while let Some(update) = client.get_next_update() {
+            match &update {
+                ViewStateUpdate::Full(state) | ViewStateUpdate::FullReset(state) => {
+                    counter = state.clone();
+                }
+                ViewStateUpdate::Incremental(delta) => counter.try_apply(delta).unwrap(),
+                ViewStateUpdate::Tick => {}
+            }
+            received.push(update);
+        }
+        counter.0 >= 5
+    })
+    .await;
+
+    // Give a wrongly forwarded delta the chance to arrive.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    client.update(0.01);
+    assert!(client.get_next_update().is_none());
+    assert_eq!(counter, Counter(5));
+    assert!(matches!(
+        received.as_slice(),
+        [
+            ViewStateUpdate::Full(Counter(3)),
+            ViewStateUpdate::Incremental(2)
+        ]
+    ));
+    assert!(is_connected(&client));
+}
@@ -0,0 +1,68 @@
+//! The host learns about a client joining before it gets any RPC of that client.
+
+mod common;
+
+use backbone_lib::codec::{ActiveCodec, WireCodec};
+use common::{join, join_message, next_binary, raw_frame, start_relay};
+use futures_util::SinkExt;
+use protocol::{GameId, HAND_SHAKE_RESPONSE, JoinRequest, NEW_CLIENT, RoomId, SERVER_RPC};
+use std::collections::HashSet;
+use tokio_tungstenite::connect_async;
+
+/// The amount of clients that join at the same time.
+const CLIENTS: usize = 20;
+
+#[tokio::test]
+async fn join_precedes_first_rpc() {
+    let url = start_relay(&["game"]).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    // Every client sends its RPC right behind the join request, without waiting for the response.
+    let mut clients = Vec::new();
+    for _ in 0..CLIENTS {
+        let url = url.clone();
+        clients.push(tokio::spawn(async move {
+            let (mut socket, _) = connect_async(url.as_str()).await.unwrap();
+            let request = JoinRequest {
+                game_id: GameId::new("game"),
+                room_id: RoomId::new("room"),
+                rule_variation: 0,
+                create_room: false,
+                codec: ActiveCodec::ID,
+                migration_token: None,
+            };
+            socket.send(join_message(&request)).await.unwrap();
+            socket
+                .send(raw_frame(vec![SERVER_RPC, 0, 0, 0, 0, 42]))
+                .await
+                .unwrap();
+            socket
+        }));
+    }
+    let mut sockets = Vec::new();
+    for client in clients {
+        sockets.push(client.await.unwrap());
+    }
+
+    let mut joined = HashSet::new();
+    let mut rpcs = 0;
+    while rpcs < CLIENTS {
+        let frame = next_binary(&mut host).await;
+        let player = u16::from_be_bytes([frame[1], frame[2]]);
+        match frame[0] {
+            NEW_CLIENT => assert!(joined.insert(player), "Player {} joined twice", player),
+            SERVER_RPC => {
+                assert!(
+                    joined.contains(&player),
+                    "RPC of player {} before its join",
+                    player
+                );
+                assert_eq!(frame[7], 42);
+                rpcs += 1;
+            }
+            other => panic!("Unexpected message {} on the host", other),
+        }
+    }
+    assert_eq!(joined.len(), CLIENTS);
+}
@@ -0,0 +1,78 @@
+//! A client the host kicked can not rejoin the room until its ban ran out.
+
+mod common;
+
+use axum::http::HeaderMap;
+use common::{Socket, join, next_binary, raw_frame, start_relay_with_state};
+use futures_util::SinkExt;
+use protocol::{
+    CLIENT_DISCONNECTS, CLIENT_GETS_KICKED, HAND_SHAKE_RESPONSE, JoinRejection, NEW_CLIENT,
+    SERVER_ERROR,
+};
+use relay_server::lobby::AppState;
+use relay_server::peer_address;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// Opens a room on a relay with the given ban duration, joins a client and lets the host kick it.
+async fn kick_client(ban_duration: Duration) -> (String, Socket) {
+    let state = AppState {
+        ban_duration,
+        ..AppState::default()
+    };
+    let url = start_relay_with_state(&["game"], state).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+
+    host.send(raw_frame(vec![CLIENT_GETS_KICKED, 0, 1]))
+        .await
+        .unwrap();
+    assert_eq!(next_binary(&mut client).await[0], SERVER_ERROR);
+    loop {
+        if next_binary(&mut host).await[0] == CLIENT_DISCONNECTS {
+            break;
+        }
+    }
+    (url, host)
+}
+
+#[tokio::test]
+async fn kicked_client_can_not_rejoin_right_away() {
+    let (url, _host) = kick_client(Duration::from_secs(60)).await;
+
+    let (_client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+    let text = String::from_utf8_lossy(&answer[1..]);
+    assert_eq!(
+        JoinRejection::from_message(&text),
+        Some(JoinRejection::Banned)
+    );
+}
+
+#[tokio::test]
+async fn kicked_client_may_rejoin_after_the_ban() {
+    let (url, _host) = kick_client(Duration::from_millis(200)).await;
+    tokio::time::sleep(Duration::from_millis(400)).await;
+
+    let (_client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+}
+
+#[test]
+fn proxy_address_is_taken_from_forwarded_header() {
+    let local: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+    let remote: SocketAddr = "203.0.113.7:4000".parse().unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-forwarded-for",
+        "198.51.100.1, 198.51.100.2".parse().unwrap(),
+    );
+
+    let forwarded: IpAddr = "198.51.100.2".parse().unwrap();
+    assert_eq!(peer_address(local, &headers), forwarded);
+    assert_eq!(peer_address(remote, &headers), remote.ip());
+    assert_eq!(peer_address(local, &HeaderMap::new()), local.ip());
+}
@@ -0,0 +1,103 @@
+//! The lobby chat reaches every connection to the lobby, without any handshake and independent of the rooms.
+
+mod common;
+
+use futures_util::{SinkExt, StreamExt};
+use protocol::MAX_CHAT_MESSAGE_SIZE;
+use std::time::Duration;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Waits for the next text frame, skipping pings.
+async fn next_text(socket: &mut common::Socket) -> String {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("No message in time")
+            .expect("Socket ended")
+            .expect("Socket error");
+        if let Message::Text(text) = message {
+            return text.to_string();
+        }
+    }
+}
+
+/// Ends the line a client repeats until it hears it back, which tells that its subscription to the chat is in place.
+const PRESENCE: &str = " is here";
+
+/// Waits for the next text frame like [`next_text`], skipping the presence lines.
+async fn next_chat_line(socket: &mut common::Socket) -> String {
+    loop {
+        let text = next_text(socket).await;
+        if !text.ends_with(PRESENCE) {
+            return text;
+        }
+    }
+}
+
+/// Connects to the lobby and repeats the presence line until it comes back.
+async fn join_lobby(lobby_url: &str, name: &str) -> common::Socket {
+    let (mut socket, _) = connect_async(lobby_url).await.unwrap();
+    let line = format!("{name}{PRESENCE}");
+    for _ in 0..50 {
+        socket
+            .send(Message::Text(line.clone().into()))
+            .await
+            .unwrap();
+        if let Ok(text) =
+            tokio::time::timeout(Duration::from_millis(100), next_text(&mut socket)).await
+            && text == line
+        {
+            return socket;
+        }
+    }
+    panic!("{name} did not hear back from the lobby in time");
+}
+
+#[tokio::test]
+async fn chat_lines_reach_everyone_in_the_lobby() {
+    let url = common::start_relay(&["game"]).await;
+    let lobby_url = url.replace("/ws", "/lobby");
+    // Both subscriptions have to be in place before the first line goes out.
+    let mut alice = join_lobby(&lobby_url, "Alice").await;
+    let mut bob = join_lobby(&lobby_url, "Bob").await;
+
+    bob.send(Message::Text("Anyone up for Ternio?".into()))
+        .await
+        .unwrap();
+    alice
+        .send(Message::Binary(vec![1, 2, 3].into()))
+        .await
+        .unwrap();
+    alice.send(Message::Text("Sure!".into())).await.unwrap();
+    for socket in [&mut alice, &mut bob] {
+        assert_eq!(next_chat_line(socket).await, "Anyone up for Ternio?");
+        assert_eq!(next_chat_line(socket).await, "Sure!");
+    }
+}
+
+#[tokio::test]
+async fn oversized_lines_end_the_connection() {
+    let url = common::start_relay(&[]).await;
+    let lobby_url = url.replace("/ws", "/lobby");
+    let (mut chatter, _) = connect_async(&lobby_url).await.unwrap();
+    let line = "x".repeat(MAX_CHAT_MESSAGE_SIZE);
+    chatter
+        .send(Message::Text(line.clone().into()))
+        .await
+        .unwrap();
+    assert_eq!(next_text(&mut chatter).await, line);
+
+    let line = "x".repeat(MAX_CHAT_MESSAGE_SIZE + 1);
+    chatter.send(Message::Text(line.into())).await.unwrap();
+    let ending = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match chatter.next().await {
+                Some(Ok(Message::Text(_))) => panic!("Oversized line got through"),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            }
+        }
+    });
+    ending.await.expect("Connection not ended in time");
+}
@@ -0,0 +1,113 @@
+//! The maintenance task sweeps the dead rooms and closes the idle ones in the configured interval, the metrics count
+//! the load of the relay.
+
+use protocol::{CompoundRoomId, HostDeparture, SERVER_DISCONNECTS};
+use relay_server::lobby::{AppState, Room};
+use relay_server::{MaintenanceIntervals, RelayMetrics, collect_metrics, maintenance_task};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// A state with a room of a living host and a room whose host is gone.
+async fn state_with_dead_room() -> Arc<AppState> {
+    let state = Arc::new(AppState::default());
+    let (gone_host, receiver) = mpsc::channel(1);
+    drop(receiver);
+    let mut rooms = state.rooms.lock().await;
+    rooms.insert(CompoundRoomId::new("alive#game"), Room::headless(0));
+    rooms.insert(
+        CompoundRoomId::new("dead#game"),
+        Room {
+            to_host_sender: gone_host,
+            amount_of_players: 3,
+            ..Room::headless(0)
+        },
+    );
+    drop(rooms);
+    state
+}
+
+/// A room as a real host opens it, with an open channel to the host.
+fn hosted_room() -> Room {
+    Room {
+        headless: false,
+        ..Room::headless(0)
+    }
+}
+
+#[tokio::test]
+async fn metrics_count_rooms_and_players() {
+    let state = state_with_dead_room().await;
+    assert_eq!(
+        collect_metrics(&state).await,
+        RelayMetrics {
+            rooms: 2,
+            dead_rooms: 1,
+            players: 4,
+            connection_tasks: 0,
+        }
+    );
+}
+
+#[tokio::test]
+async fn maintenance_sweeps_dead_rooms_in_the_configured_interval() {
+    let state = state_with_dead_room().await;
+    let intervals = MaintenanceIntervals {
+        dead_room_sweep: Duration::from_millis(50),
+        idle_room_sweep: Duration::ZERO,
+        idle_room_threshold: Duration::ZERO,
+        stale_connection_sweep: Duration::ZERO,
+        metrics: Duration::ZERO,
+    };
+    let maintenance = tokio::spawn(maintenance_task(state.clone(), intervals));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while state.rooms.lock().await.len() > 1 {
+        assert!(Instant::now() < deadline, "Dead room not swept in time");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let rooms = state.rooms.lock().await;
+    assert!(rooms.contains_key(&CompoundRoomId::new("alive#game")));
+    drop(rooms);
+    maintenance.abort();
+}
+
+#[tokio::test]
+async fn maintenance_closes_idle_rooms() {
+    let state = Arc::new(AppState::default());
+    let busy = hosted_room();
+    let idle = hosted_room();
+    let busy_activity = busy.last_activity.clone();
+    let mut idle_client = idle.host_to_client_broadcaster.subscribe(false);
+    let mut rooms = state.rooms.lock().await;
+    rooms.insert(CompoundRoomId::new("busy#game"), busy);
+    rooms.insert(CompoundRoomId::new("idle#game"), idle);
+    rooms.insert(CompoundRoomId::new("persistent#game"), Room::headless(0));
+    drop(rooms);
+    let intervals = MaintenanceIntervals {
+        dead_room_sweep: Duration::ZERO,
+        idle_room_sweep: Duration::from_millis(20),
+        idle_room_threshold: Duration::from_millis(200),
+        stale_connection_sweep: Duration::ZERO,
+        metrics: Duration::ZERO,
+    };
+    let maintenance = tokio::spawn(maintenance_task(state.clone(), intervals));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while state.rooms.lock().await.len() > 2 {
+        assert!(Instant::now() < deadline, "Idle room not closed in time");
+        busy_activity.touch();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let rooms = state.rooms.lock().await;
+    assert!(rooms.contains_key(&CompoundRoomId::new("busy#game")));
+    // The headless host of a persistent room is never idle.
+    assert!(rooms.contains_key(&CompoundRoomId::new("persistent#game")));
+    drop(rooms);
+    // The clients of the closed room learn why.
+    assert_eq!(
+        idle_client.recv().await.unwrap()[..],
+        [SERVER_DISCONNECTS, HostDeparture::Closed.code()]
+    );
+    maintenance.abort();
+}
@@ -0,0 +1,290 @@
+//! The routing logic of a client connection, driven through mocked web sockets without any network.
+
+mod common;
+
+use axum::extract::ws::Message;
+use bytes::Bytes;
+use common::mock_endpoint;
+use protocol::*;
+use relay_server::lobby::{ActivityTracker, RoomBroadcast};
+use relay_server::message_relay::{
+    ConnectionOptions, HostRoom, handle_client_logic, handle_server_logic, message_histogram,
+};
+use relay_server::results::ResultsSink;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast, mpsc};
+
+/// The id the relay assigned to the mocked client.
+const PLAYER_ID: u16 = 3;
+
+const OPTIONS: ConnectionOptions = ConnectionOptions {
+    text_frames: false,
+    send_timeout: Duration::from_secs(1),
+    max_rpcs_per_sec: None,
+};
+
+/// Extracts the bytes of a binary frame.
+fn binary(message: Message) -> Bytes {
+    match message {
+        Message::Binary(bytes) => bytes,
+        other => panic!("Expected a binary frame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn client_messages_get_the_player_id_injected() {
+    let (sink, stream, to_relay, _from_relay) = mock_endpoint();
+    let (_broadcast_sender, broadcast_receiver) = broadcast::channel(16);
+    let (host_sender, mut host_receiver) = mpsc::channel(16);
+    let connection = tokio::spawn(handle_client_logic(
+        Arc::new(Mutex::new(sink)),
+        stream,
+        broadcast_receiver,
+        host_sender,
+        PLAYER_ID,
+        ActivityTracker::new(),
+        OPTIONS,
+    ));
+
+    to_relay
+        .send(Message::Binary(vec![SERVER_RPC, 0, 0, 0, 5, 7, 8].into()))
+        .unwrap();
+    to_relay
+        .send(Message::Binary(vec![READY, 1].into()))
+        .unwrap();
+    assert_eq!(
+        host_receiver.recv().await.unwrap().as_ref(),
+        [SERVER_RPC, 0, 3, 0, 0, 0, 5, 7, 8]
+    );
+    assert_eq!(
+        host_receiver.recv().await.unwrap().as_ref(),
+        [READY, 0, 3, 1]
+    );
+
+    // Only the relay reports the backlog.
+    to_relay
+        .send(Message::Binary(vec![CLIENT_BACKLOG, 0, 0].into()))
+        .unwrap();
+    assert_eq!(
+        connection.await.unwrap(),
+        "Relay message received from a client."
+    );
+}
+
+/// Runs the client logic until the client sends the frame and returns the reason the connection ended with.
+async fn client_reason(frame: Vec<u8>) -> &'static str {
+    let (sink, stream, to_relay, _from_relay) = mock_endpoint();
+    let (_broadcast_sender, broadcast_receiver) = broadcast::channel(16);
+    let (host_sender, _host_receiver) = mpsc::channel(16);
+    to_relay.send(Message::Binary(frame.into())).unwrap();
+    handle_client_logic(
+        Arc::new(Mutex::new(sink)),
+        stream,
+        broadcast_receiver,
+        host_sender,
+        PLAYER_ID,
+        ActivityTracker::new(),
+        OPTIONS,
+    )
+    .await
+}
+
+/// Runs the host logic until the host sends the frame and returns the reason the connection ended with.
+async fn host_reason(frame: Vec<u8>) -> &'static str {
+    let (sink, stream, to_relay, _from_relay) = mock_endpoint();
+    let (_host_sender, host_receiver) = mpsc::channel(16);
+    to_relay.send(Message::Binary(frame.into())).unwrap();
+    handle_server_logic(
+        Arc::new(Mutex::new(sink)),
+        stream,
+        host_receiver,
+        RoomBroadcast::new(16),
+        HostRoom {
+            room_id: CompoundRoomId::new("room#game"),
+            results: ResultsSink::default(),
+        },
+        ActivityTracker::new(),
+        OPTIONS,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn messages_of_the_wrong_role_get_named_as_such() {
+    assert_eq!(
+        client_reason(vec![LOG, b'x']).await,
+        "Host message received from a client."
+    );
+    assert_eq!(
+        client_reason(vec![PAUSE, 1]).await,
+        "Host message received from a client."
+    );
+    assert_eq!(
+        client_reason(vec![SPECTATOR_COUNT, 0, 1]).await,
+        "Relay message received from a client."
+    );
+    assert_eq!(
+        host_reason(vec![SET_NAME, b'x']).await,
+        "Client message received from the host."
+    );
+    assert_eq!(
+        host_reason(vec![REQUEST_SYNC]).await,
+        "Client message received from the host."
+    );
+    assert_eq!(
+        host_reason(vec![READY_STATE, 0, 0, 0, 1]).await,
+        "Relay message received from the host."
+    );
+}
+
+#[tokio::test]
+async fn unknown_messages_stay_illegal() {
+    assert_eq!(
+        client_reason(vec![200]).await,
+        "Illegal Command from client"
+    );
+    assert_eq!(
+        host_reason(vec![200]).await,
+        "Illegal Server -> Client command."
+    );
+}
+
+#[tokio::test]
+async fn deltas_reach_the_client_only_after_a_full_update() {
+    let (sink, stream, _to_relay, mut from_relay) = mock_endpoint();
+    let (broadcast_sender, broadcast_receiver) = broadcast::channel(16);
+    let (host_sender, _host_receiver) = mpsc::channel(16);
+    let connection = tokio::spawn(handle_client_logic(
+        Arc::new(Mutex::new(sink)),
+        stream,
+        broadcast_receiver,
+        host_sender,
+        PLAYER_ID,
+        ActivityTracker::new(),
+        OPTIONS,
+    ));
+
+    let frames: [&[u8]; 5] = [
+        &[DELTA_UPDATE, 0, 0, 0, 0, 1],
+        &[FULL_UPDATE, 0, 0, 0, 1, 2],
+        &[DELTA_UPDATE_EXCEPT, 0, 3, 0, 0, 0, 1, 3],
+        &[DELTA_UPDATE_TO, 0, 3, 0, 0, 0, 2, 4],
+        &[SERVER_DISCONNECTS, HostDeparture::Ended as u8],
+    ];
+    for frame in frames {
+        broadcast_sender
+            .send(Bytes::copy_from_slice(frame))
+            .unwrap();
+    }
+
+    assert_eq!(connection.await.unwrap(), HostDeparture::Ended.message());
+    assert_eq!(
+        binary(from_relay.recv().await.unwrap()).as_ref(),
+        [FULL_UPDATE, 0, 0, 0, 1, 2]
+    );
+    // The excluded client gets the delta unchanged, for its shadow view state only.
+    assert_eq!(
+        binary(from_relay.recv().await.unwrap()).as_ref(),
+        [DELTA_UPDATE_EXCEPT, 0, 3, 0, 0, 0, 1, 3]
+    );
+    assert_eq!(
+        binary(from_relay.recv().await.unwrap()).as_ref(),
+        [DELTA_UPDATE, 0, 0, 0, 2, 4]
+    );
+    assert!(from_relay.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn malformed_host_messages_never_reach_the_clients() {
+    let (sink, stream, to_relay, _from_relay) = mock_endpoint();
+    let (_host_sender, host_receiver) = mpsc::channel(16);
+    let broadcast = RoomBroadcast::new(16);
+    let mut client = broadcast.subscribe(false);
+    let connection = tokio::spawn(handle_server_logic(
+        Arc::new(Mutex::new(sink)),
+        stream,
+        host_receiver,
+        broadcast,
+        HostRoom {
+            room_id: CompoundRoomId::new("room#game"),
+            results: ResultsSink::default(),
+        },
+        ActivityTracker::new(),
+        OPTIONS,
+    ));
+
+    let frames: [&[u8]; 5] = [
+        &[DELTA_UPDATE_EXCEPT, 0, 3],
+        &[DELTA_UPDATE_TO, 0],
+        &[REJECT_ACTION, 0],
+        &[DELTA_UPDATE, 0, 0, 0, 1, 4],
+        &[SERVER_DISCONNECTS, HostDeparture::Ended as u8],
+    ];
+    for frame in frames {
+        to_relay
+            .send(Message::Binary(frame.to_vec().into()))
+            .unwrap();
+    }
+    assert_eq!(connection.await.unwrap(), HostDeparture::Ended.message());
+
+    // Only the well formed delta got through, the host connection outlived the malformed ones.
+    assert_eq!(
+        client.recv().await.unwrap().as_ref(),
+        [DELTA_UPDATE, 0, 0, 0, 1, 4]
+    );
+}
+
+#[tokio::test]
+async fn host_traffic_gets_counted_per_message_type() {
+    let (sink, stream, to_relay, mut from_relay) = mock_endpoint();
+    let (host_sender, host_receiver) = mpsc::channel(16);
+    let broadcast = RoomBroadcast::new(16);
+    let _client = broadcast.subscribe(false);
+    let counters = broadcast.counters().clone();
+    let connection = tokio::spawn(handle_server_logic(
+        Arc::new(Mutex::new(sink)),
+        stream,
+        host_receiver,
+        broadcast,
+        HostRoom {
+            room_id: CompoundRoomId::new("room#game"),
+            results: ResultsSink::default(),
+        },
+        ActivityTracker::new(),
+        OPTIONS,
+    ));
+
+    host_sender
+        .send(Bytes::from_static(&[NEW_CLIENT, 0, 1]))
+        .await
+        .unwrap();
+    host_sender
+        .send(Bytes::from_static(&[SERVER_RPC, 0, 1, 0, 0, 0, 0, 7]))
+        .await
+        .unwrap();
+    // Both reached the host, before its disconnect ends the task forwarding them.
+    from_relay.recv().await.unwrap();
+    from_relay.recv().await.unwrap();
+    let frames: [&[u8]; 4] = [
+        &[FULL_UPDATE, 0, 0, 0, 1, 2],
+        &[DELTA_UPDATE, 0, 0, 0, 2, 3],
+        &[DELTA_UPDATE, 0, 0, 0, 3, 4],
+        &[SERVER_DISCONNECTS, HostDeparture::Ended as u8],
+    ];
+    for frame in frames {
+        to_relay
+            .send(Message::Binary(frame.to_vec().into()))
+            .unwrap();
+    }
+    assert_eq!(connection.await.unwrap(), HostDeparture::Ended.message());
+
+    assert_eq!(
+        message_histogram(&counters, false),
+        "SERVER_DISCONNECTS=1 DELTA_UPDATE=2 FULL_UPDATE=1"
+    );
+    assert_eq!(
+        message_histogram(&counters, true),
+        "NEW_CLIENT=1 SERVER_RPC=1"
+    );
+}
@@ -0,0 +1,62 @@
+//! The relay enforces the maximum size of display names, from clients as well as from the host.
+
+mod common;
+
+use common::{join, next_binary, raw_frame, start_relay};
+use futures_util::SinkExt;
+use protocol::{
+    HAND_SHAKE_RESPONSE, MAX_DISPLAY_NAME_SIZE, NEW_CLIENT, PLAYER_NAME, SERVER_ERROR, SET_NAME,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Builds a frame of the message type with a name of the indicated size, after the eventual player id.
+fn name_frame(message_type: u8, player_id: Option<u16>, name_size: usize) -> Message {
+    let mut frame = vec![message_type];
+    if let Some(player_id) = player_id {
+        frame.extend(player_id.to_be_bytes());
+    }
+    frame.extend("n".repeat(name_size).bytes());
+    raw_frame(frame)
+}
+
+#[tokio::test]
+async fn oversized_names_end_the_connection() {
+    let url = start_relay(&["game"]).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let joined = next_binary(&mut host).await;
+    assert_eq!(joined[0], NEW_CLIENT);
+    let client_id = u16::from_be_bytes([joined[1], joined[2]]);
+
+    // Names at the limit pass in both directions.
+    let name = name_frame(SET_NAME, None, MAX_DISPLAY_NAME_SIZE);
+    client.send(name).await.unwrap();
+    let forwarded = next_binary(&mut host).await;
+    assert_eq!(forwarded[0], SET_NAME);
+    assert_eq!(forwarded.len(), 3 + MAX_DISPLAY_NAME_SIZE);
+    let name = name_frame(PLAYER_NAME, Some(client_id), MAX_DISPLAY_NAME_SIZE);
+    host.send(name).await.unwrap();
+    let announced = next_binary(&mut client).await;
+    assert_eq!(announced[0], PLAYER_NAME);
+    assert_eq!(announced.len(), 3 + MAX_DISPLAY_NAME_SIZE);
+
+    // One byte more ends the connection of the sender.
+    let name = name_frame(PLAYER_NAME, Some(client_id), MAX_DISPLAY_NAME_SIZE + 1);
+    host.send(name).await.unwrap();
+    let closing = next_binary(&mut host).await;
+    assert_eq!(closing[0], SERVER_ERROR);
+    assert_eq!(&closing[1..], b"Display name too long.");
+
+    let (mut host, answer) = join(&url, "game", "other", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, "game", "other", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+    let name = name_frame(SET_NAME, None, MAX_DISPLAY_NAME_SIZE + 1);
+    client.send(name).await.unwrap();
+    let closing = next_binary(&mut client).await;
+    assert_eq!(closing[0], SERVER_ERROR);
+    assert_eq!(&closing[1..], b"Display name too long.");
+}
@@ -0,0 +1,90 @@
+//! Rooms from the configuration get opened at boot with a headless host, until a real host takes them over.
+//! Only then the lobby lists them, so every listed room can be joined.
+
+mod common;
+
+use common::{http_request, join, start_relay_with_state};
+use futures_util::SinkExt;
+use protocol::{HAND_SHAKE_RESPONSE, SERVER_ERROR};
+use relay_server::lobby::{AppState, parse_config};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Starts a relay with a persistent room "table" of the game, opened like on boot.
+async fn start_with_table() -> String {
+    let configs = parse_config(
+        r#"[{"name": "game", "max_players": 4, "allowed_variations": [0, 2],
+            "persistent_rooms": [{"room": "table", "rule_variation": 2}]}]"#,
+    )
+    .unwrap();
+    let state = AppState {
+        configs: RwLock::new(configs),
+        ..AppState::default()
+    };
+    state.open_persistent_rooms().await;
+    start_relay_with_state(&[], state).await
+}
+
+/// Checks that the answer tells the client, that the room waits for its host.
+fn assert_waits_for_host(answer: &[u8]) {
+    assert_eq!(answer[0], SERVER_ERROR);
+    assert!(String::from_utf8_lossy(&answer[1..]).contains("waits for a host"));
+}
+
+#[tokio::test]
+async fn configured_room_is_listed_at_boot() {
+    let url = start_with_table().await;
+    let (status, body) = http_request(&url, "GET", "/enlist", None, "").await;
+    assert_eq!(status, 200);
+    assert!(body.contains("table#game"));
+    assert!(body.contains("Variation: 002"));
+    assert!(body.contains("is alive: true"));
+    assert!(body.contains("awaits host: true"));
+
+    let (_, body) = http_request(&url, "GET", "/rooms?game=game", None, "").await;
+    assert_eq!(body, "[]");
+}
+
+#[tokio::test]
+async fn room_gets_listed_once_a_host_took_it_over() {
+    let url = start_with_table().await;
+    let (_host, answer) = join(&url, "game", "table", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (_, body) = http_request(&url, "GET", "/rooms?game=game", None, "").await;
+    let rooms: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(rooms.as_array().unwrap().len(), 1);
+    assert_eq!(rooms[0]["room"], "table");
+    let (_client, answer) = join(&url, "game", "table", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (_, body) = http_request(&url, "GET", "/enlist", None, "").await;
+    assert!(body.contains("awaits host: false"));
+}
+
+#[tokio::test]
+async fn host_takes_over_and_the_room_reopens_after_it_left() {
+    let url = start_with_table().await;
+    let (_rejected, answer) = join(&url, "game", "table", false).await;
+    assert_waits_for_host(&answer);
+
+    let (mut host, answer) = join(&url, "game", "table", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_client, answer) = join(&url, "game", "table", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    // A second host can not take over a room with a real host.
+    let (_intruder, answer) = join(&url, "game", "table", true).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+
+    host.send(Message::Close(None)).await.unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let (_, answer) = join(&url, "game", "table", false).await;
+        if String::from_utf8_lossy(&answer[1..]).contains("waits for a host") {
+            break;
+        }
+        assert!(Instant::now() < deadline, "Room did not reopen in time");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
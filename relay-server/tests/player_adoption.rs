@@ -0,0 +1,88 @@
+//! The backend may take over the seat of a departed player instead of terminating the room.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet};
+use backbone_lib::transport_layer::TransportLayer;
+use common::{Empty, drive_until, is_connected, join, raw_frame};
+use futures_util::SinkExt;
+use protocol::{CLIENT_DISCONNECTS_SELF, HAND_SHAKE_RESPONSE};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "adoption";
+
+/// A two player backend that plays for a departed player, until somebody else joins.
+struct SubstitutingBackend {
+    view_state: Empty,
+    command_list: Vec<BackendCommand<u8>>,
+    /// The seat the backend plays for.
+    adopted: Option<u16>,
+}
+
+impl BackEndArchitecture<u8, u8, Empty> for SubstitutingBackend {
+    fn new(_: u16) -> Self {
+        SubstitutingBackend {
+            view_state: Empty,
+            command_list: Vec::new(),
+            adopted: None,
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {
+        if let Some(player) = self.adopted.take() {
+            self.command_list
+                .push(BackendCommand::ReleasePlayer { player });
+        }
+    }
+    fn player_departure(&mut self, player: u16, _: u16) {
+        self.adopted = Some(player);
+        self.command_list
+            .push(BackendCommand::AdoptPlayer { player });
+    }
+    fn inform_rpc(&mut self, _: u16, _: u8, _: &PlayerSet) {}
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn min_players(&self) -> u16 {
+        2
+    }
+    fn get_view_state(&self) -> &Empty {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u8>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u8, u8, SubstitutingBackend, Empty>;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn adopted_seat_counts_as_present_until_released() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    assert!(host.adopted_players().is_empty());
+    assert!(!host.enough_players());
+
+    let (mut leaving, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    drive_until(&mut [&mut host], |layers| layers[0].enough_players()).await;
+    leaving
+        .send(raw_frame(vec![CLIENT_DISCONNECTS_SELF]))
+        .await
+        .unwrap();
+    drive_until(&mut [&mut host], |layers| {
+        !layers[0].adopted_players().is_empty()
+    })
+    .await;
+    assert_eq!(host.adopted_players().len(), 1);
+    assert!(host.enough_players());
+    assert!(is_connected(&host));
+
+    // The returning player takes the seat over again.
+    let (_returning, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    drive_until(&mut [&mut host], |layers| {
+        layers[0].adopted_players().is_empty()
+    })
+    .await;
+    assert!(host.enough_players());
+}
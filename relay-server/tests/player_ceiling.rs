@@ -0,0 +1,24 @@
+//! The protocol ceiling of players per room wins over the game configuration.
+
+mod common;
+
+use common::{GameEntryBuilder, join, start_relay_with_entries};
+use protocol::{ABSOLUTE_MAX_CLIENTS, HAND_SHAKE_RESPONSE, SERVER_ERROR};
+
+#[tokio::test]
+async fn ceiling_beats_configured_maximum() {
+    let entry = GameEntryBuilder::new("game").max_players(u16::MAX).build();
+    let url = start_relay_with_entries([entry]).await;
+
+    let (_host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let mut clients = Vec::new();
+    for _ in 1..ABSOLUTE_MAX_CLIENTS {
+        let (client, answer) = join(&url, "game", "room", false).await;
+        assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+        clients.push(client);
+    }
+
+    let (_rejected, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+}
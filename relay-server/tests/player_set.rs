@@ -0,0 +1,95 @@
+//! The backend learns who is connected from the player set the transport layer lends to its calls.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{drive_until, is_connected, join, poll_until};
+use protocol::HAND_SHAKE_RESPONSE;
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "presence";
+
+/// The players connected at the last rpc.
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct Present(Vec<u16>);
+
+impl ViewStateArchitecture<(), Vec<u16>> for Present {
+    fn try_apply(&mut self, delta: &Vec<u16>) -> Result<(), String> {
+        self.0 = delta.clone();
+        Ok(())
+    }
+}
+
+/// Answers every rpc with the connected players, without tracking arrivals and departures itself.
+struct PresenceBackend {
+    view_state: Present,
+    command_list: Vec<BackendCommand<Vec<u16>>>,
+}
+
+impl BackEndArchitecture<(), Vec<u16>, Present> for PresenceBackend {
+    fn new(_: u16) -> Self {
+        PresenceBackend {
+            view_state: Present::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, _: (), players: &PlayerSet) {
+        self.view_state.0 = players.iter().collect();
+        self.command_list
+            .push(BackendCommand::Delta(self.view_state.0.clone()));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Present {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<Vec<u16>>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<(), Vec<u16>, PresenceBackend, Present>;
+
+/// Asks the backend of the host for the connected players until they are the expected amount.
+async fn present_players(host: &mut Layer, amount: usize) -> Vec<u16> {
+    let mut present = None;
+    poll_until(|| {
+        host.register_server_rpc(());
+        host.update(0.01);
+        while let Some(update) = host.get_next_update() {
+            if let ViewStateUpdate::Incremental(players) = update
+                && players.len() == amount
+            {
+                present = Some(players);
+            }
+        }
+        present.is_some()
+    })
+    .await;
+    present.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn backend_sees_arrivals_and_departures() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    assert_eq!(present_players(&mut host, 1).await, [0]);
+
+    let (first, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (second, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let everyone = present_players(&mut host, 3).await;
+    assert_eq!(everyone[0], 0);
+
+    drop(second);
+    let remaining = present_players(&mut host, 2).await;
+    assert_eq!(remaining, everyone[..2]);
+    drop(first);
+    assert_eq!(present_players(&mut host, 1).await, [0]);
+}
@@ -0,0 +1,204 @@
+//! Client-side prediction reconciles every predicted delta only with the delta answering its RPC.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{Frontend, drive_frontends_until, is_connected};
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "prediction";
+
+/// The cell the host reserves, which the clients can not know of.
+const RESERVED_CELL: u8 = 3;
+
+/// Cells claimed by the players, a delta is a claimed cell with the player that claimed it.
+#[derive(Clone, Serialize, Deserialize, Default, PartialEq, Debug)]
+struct Cells([Option<u16>; 4]);
+
+impl ViewStateArchitecture<u8, (u8, u16)> for Cells {
+    fn try_apply(&mut self, &(cell, player): &(u8, u16)) -> Result<(), String> {
+        match self.0.get_mut(cell as usize) {
+            Some(slot @ None) => {
+                *slot = Some(player);
+                Ok(())
+            }
+            _ => Err(format!("Cell {cell} is not free")),
+        }
+    }
+
+    fn predict(&self, player: u16, &cell: &u8) -> Option<(u8, u16)> {
+        let mut predicted = self.clone();
+        predicted.try_apply(&(cell, player)).ok()?;
+        Some((cell, player))
+    }
+
+    fn confirms_prediction(predicted: &(u8, u16), authoritative: &(u8, u16)) -> bool {
+        predicted == authoritative
+    }
+}
+
+/// Claims free cells for the players, but silently ignores the reserved cell.
+struct CellsBackend {
+    view_state: Cells,
+    command_list: Vec<BackendCommand<(u8, u16)>>,
+}
+
+impl BackEndArchitecture<u8, (u8, u16), Cells> for CellsBackend {
+    fn new(_: u16) -> Self {
+        CellsBackend {
+            view_state: Cells::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, player: u16, cell: u8, _: &PlayerSet) {
+        if cell == RESERVED_CELL || self.view_state.try_apply(&(cell, player)).is_err() {
+            return;
+        }
+        self.command_list
+            .push(BackendCommand::Delta((cell, player)));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Cells {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<(u8, u16)>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u8, (u8, u16), CellsBackend, Cells>;
+
+/// The view state as a frontend sees it, with the amount of full updates it got.
+#[derive(Default)]
+struct CellsFrontend {
+    cells: Option<Cells>,
+    full_updates: usize,
+}
+
+/// Every delta has to fit the view state of the frontend.
+impl Frontend<Cells, (u8, u16)> for CellsFrontend {
+    fn apply(&mut self, update: ViewStateUpdate<Cells, (u8, u16)>) {
+        match update {
+            ViewStateUpdate::Full(cells) | ViewStateUpdate::FullReset(cells) => {
+                self.cells = Some(cells);
+                self.full_updates += 1;
+            }
+            ViewStateUpdate::Incremental(delta) => {
+                let cells = self.cells.as_mut().expect("Delta before full update");
+                cells
+                    .try_apply(&delta)
+                    .expect("Delta does not fit the frontend");
+            }
+            ViewStateUpdate::Tick => {}
+        }
+    }
+}
+
+/// Keeps updating the layers for a while, so that everything in flight arrives.
+async fn settle(layers: &mut [&mut Layer], frontends: &mut [CellsFrontend]) {
+    let passes = std::cell::Cell::new(0);
+    drive_frontends_until(layers, frontends, |_, _| {
+        passes.set(passes.get() + 1);
+        passes.get() > 20
+    })
+    .await;
+}
+
+/// Opens the room with two clients, of which the second predicts its RPCs.
+/// The frontends are in the order host, other client, predicting client.
+async fn start_room(url: String) -> (Layer, Layer, Layer, [CellsFrontend; 3]) {
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut predicting = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut other = Layer::generate_transport_layer(url, GAME.to_string());
+    predicting.set_prediction(true);
+    let mut frontends: [CellsFrontend; 3] = Default::default();
+
+    host.start_game_server("room".to_string(), 0);
+    drive_frontends_until(&mut [&mut host], &mut frontends[..1], |layers, _| {
+        is_connected(layers[0])
+    })
+    .await;
+    predicting.start_game_client("room".to_string());
+    other.start_game_client("room".to_string());
+    drive_frontends_until(
+        &mut [&mut host, &mut other, &mut predicting],
+        &mut frontends,
+        |_, frontends| {
+            frontends[1..]
+                .iter()
+                .all(|frontend| frontend.cells.is_some())
+        },
+    )
+    .await;
+    (host, other, predicting, frontends)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_foreign_delta_keeps_the_pending_prediction() {
+    let url = common::start_relay(&[GAME]).await;
+    let (mut host, mut other, mut predicting, mut frontends) = start_room(url).await;
+    let other_id = other.my_player_id().unwrap();
+    let predicting_id = predicting.my_player_id().unwrap();
+
+    // The delta of the other client waits in the socket of the predicting one, which is not updated meanwhile.
+    other.register_server_rpc(1);
+    drive_frontends_until(
+        &mut [&mut host, &mut other],
+        &mut frontends[..2],
+        |_, frontends| {
+            frontends
+                .iter()
+                .all(|frontend| frontend.cells.as_ref().unwrap().0[1].is_some())
+        },
+    )
+    .await;
+
+    // The predicting client sees the foreign delta right after its own prediction.
+    predicting.register_server_rpc(0);
+    let expected = Cells([Some(predicting_id), Some(other_id), None, None]);
+    let mut layers = [&mut host, &mut other, &mut predicting];
+    drive_frontends_until(&mut layers, &mut frontends, |_, frontends| {
+        frontends[..2]
+            .iter()
+            .all(|frontend| frontend.cells.as_ref() == Some(&expected))
+    })
+    .await;
+    // The confirming delta and the acknowledgement arrive for sure meanwhile.
+    settle(&mut layers, &mut frontends).await;
+    assert_eq!(frontends[2].cells, Some(expected));
+    assert_eq!(
+        frontends[2].full_updates, 1,
+        "The prediction got rolled back"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn an_unanswered_prediction_gets_rolled_back() {
+    let url = common::start_relay(&[GAME]).await;
+    let (mut host, mut other, mut predicting, mut frontends) = start_room(url).await;
+    let predicting_id = predicting.my_player_id().unwrap();
+
+    // The host ignores the reserved cell, only its acknowledgement tells the predicting client.
+    predicting.register_server_rpc(RESERVED_CELL);
+    let mut layers = [&mut host, &mut other, &mut predicting];
+    drive_frontends_until(&mut layers, &mut frontends, |_, frontends| {
+        frontends[2].full_updates == 2
+    })
+    .await;
+    assert_eq!(frontends[2].cells, Some(Cells::default()));
+
+    // Later predictions work as before.
+    layers[2].register_server_rpc(2);
+    let expected = Cells([None, None, Some(predicting_id), None]);
+    drive_frontends_until(&mut layers, &mut frontends, |_, frontends| {
+        frontends[1].cells.as_ref() == Some(&expected)
+    })
+    .await;
+    settle(&mut layers, &mut frontends).await;
+    assert_eq!(frontends[2].cells, Some(expected));
+    assert_eq!(frontends[2].full_updates, 2);
+}
@@ -0,0 +1,127 @@
+//! The relay aggregates the readiness of the clients, and the backend of the host learns once everyone is ready.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{Frontend, drive_frontends_until, is_connected};
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "ready-check";
+
+/// The view state counts how often everyone was ready.
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct ReadyRounds(u8);
+
+impl ViewStateArchitecture<(), u8> for ReadyRounds {
+    fn try_apply(&mut self, delta: &u8) -> Result<(), String> {
+        self.0 = *delta;
+        Ok(())
+    }
+}
+
+/// A backend that announces every time everyone got ready as a delta.
+struct ReadyBackend {
+    view_state: ReadyRounds,
+    command_list: Vec<BackendCommand<u8>>,
+}
+
+impl BackEndArchitecture<(), u8, ReadyRounds> for ReadyBackend {
+    fn new(_: u16) -> Self {
+        ReadyBackend {
+            view_state: ReadyRounds::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, _: (), _: &PlayerSet) {}
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn all_players_ready(&mut self) {
+        self.view_state.0 += 1;
+        self.command_list
+            .push(BackendCommand::Delta(self.view_state.0));
+    }
+    fn get_view_state(&self) -> &ReadyRounds {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u8>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<(), u8, ReadyBackend, ReadyRounds>;
+
+/// The rounds of readiness a frontend got told about.
+impl Frontend<ReadyRounds, u8> for Vec<u8> {
+    fn apply(&mut self, update: ViewStateUpdate<ReadyRounds, u8>) {
+        if let ViewStateUpdate::Incremental(round) = update {
+            self.push(round);
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn backend_learns_when_everyone_is_ready() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut first = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut second = Layer::generate_transport_layer(url, GAME.to_string());
+    let mut rounds = [Vec::new(), Vec::new(), Vec::new()];
+    assert!(!first.set_ready(true));
+
+    host.start_game_server("room".to_string(), 0);
+    drive_frontends_until(&mut [&mut host], &mut rounds[..1], |layers, _| {
+        is_connected(layers[0])
+    })
+    .await;
+    first.start_game_client("room".to_string());
+    second.start_game_client("room".to_string());
+    drive_frontends_until(
+        &mut [&mut host, &mut first, &mut second],
+        &mut rounds,
+        |layers, _| layers[0].ready_count() == Some((0, 3)),
+    )
+    .await;
+    assert!(rounds[0].is_empty());
+    assert!(is_connected(&first) && is_connected(&second));
+    assert_eq!(first.ready_count(), None);
+
+    assert!(host.set_ready(true));
+    assert!(first.set_ready(true));
+    drive_frontends_until(
+        &mut [&mut host, &mut first, &mut second],
+        &mut rounds,
+        |layers, _| layers[0].ready_count() == Some((2, 3)),
+    )
+    .await;
+    assert!(rounds[0].is_empty());
+
+    assert!(second.set_ready(true));
+    drive_frontends_until(
+        &mut [&mut host, &mut first, &mut second],
+        &mut rounds,
+        |layers, _| layers[0].ready_count() == Some((3, 3)),
+    )
+    .await;
+    assert_eq!(rounds[0], [1]);
+
+    // Taking the readiness back and leaving makes everyone who is left ready again.
+    assert!(second.set_ready(false));
+    drive_frontends_until(
+        &mut [&mut host, &mut first, &mut second],
+        &mut rounds,
+        |layers, _| layers[0].ready_count() == Some((2, 3)),
+    )
+    .await;
+    assert_eq!(rounds[0], [1]);
+    second.disconnect();
+    drive_frontends_until(
+        &mut [&mut host, &mut first],
+        &mut rounds[..2],
+        |layers, _| layers[0].ready_count() == Some((2, 2)),
+    )
+    .await;
+    assert_eq!(rounds[0], [1, 2]);
+}
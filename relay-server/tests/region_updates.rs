@@ -0,0 +1,202 @@
+//! A large view state gets synchronized region by region, so a small change only sends the changed region.
+
+mod common;
+
+use backbone_lib::traits::{
+    BackEndArchitecture, BackendCommand, PlayerSet, RegionedViewState, ViewStateArchitecture,
+};
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer, ViewStateUpdate};
+use common::{drive_until, is_connected, join, next_binary};
+use protocol::{FULL_UPDATE, HAND_SHAKE_RESPONSE, PARTIAL_UPDATE};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "go";
+
+/// The amount of rows and columns of the board.
+const BOARD_SIZE: usize = 19;
+
+/// A Go board, every row is a region.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+struct Board {
+    /// The stones, 0 for an empty point and the player id plus 1 otherwise.
+    rows: Vec<Vec<u8>>,
+}
+
+impl ViewStateArchitecture<(u8, u8), ()> for Board {
+    fn try_apply(&mut self, _: &()) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl RegionedViewState for Board {
+    type Region = Vec<u8>;
+
+    fn region_count(&self) -> u16 {
+        self.rows.len() as u16
+    }
+
+    fn region(&self, index: u16) -> Vec<u8> {
+        self.rows[index as usize].clone()
+    }
+
+    fn set_region(&mut self, index: u16, region: Vec<u8>) -> Result<(), String> {
+        let Some(row) = self.rows.get_mut(index as usize) else {
+            return Err(format!("No row {}", index));
+        };
+        if region.len() != BOARD_SIZE {
+            return Err(format!("Row of {} points", region.len()));
+        }
+        *row = region;
+        Ok(())
+    }
+}
+
+/// Places a stone for every RPC and marks the row as changed.
+struct GoBackend {
+    view_state: Board,
+    command_list: Vec<BackendCommand<()>>,
+}
+
+impl BackEndArchitecture<(u8, u8), (), Board> for GoBackend {
+    fn new(_: u16) -> Self {
+        GoBackend {
+            view_state: Board {
+                rows: vec![vec![0; BOARD_SIZE]; BOARD_SIZE],
+            },
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, player: u16, (row, column): (u8, u8), _: &PlayerSet) {
+        self.view_state.rows[row as usize][column as usize] = player as u8 + 1;
+        self.command_list
+            .push(BackendCommand::UpdateRegions(vec![row as u16]));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Board {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<()>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<(u8, u8), (), GoBackend, Board>;
+
+/// Starts a host with regions enabled and waits until it is connected.
+async fn start_host(url: &str) -> Layer {
+    let mut host = Layer::generate_transport_layer(url.to_string(), GAME.to_string());
+    host.enable_regions();
+    host.start_game_server("board".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    host
+}
+
+/// Takes the latest full update of the client, if any.
+fn latest_board(client: &mut Layer) -> Option<Board> {
+    let mut board = None;
+    while let Some(update) = client.get_next_update() {
+        if let ViewStateUpdate::Full(state) = update {
+            board = Some(state);
+        }
+    }
+    board
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn small_change_sends_only_its_region() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = start_host(&url).await;
+    let mut client = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    client.enable_regions();
+    client.start_game_client("board".to_string());
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        layers[1].has_initial_sync()
+    })
+    .await;
+
+    // An observer on the raw socket measures the frames.
+    let (mut observer, answer) = join(&url, GAME, "board", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let mut full_update = Vec::new();
+    while full_update.is_empty() {
+        host.update(0.01);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        if let Ok(frame) =
+            tokio::time::timeout(Duration::from_millis(10), next_binary(&mut observer)).await
+            && frame[0] == FULL_UPDATE
+        {
+            full_update = frame;
+        }
+    }
+
+    client.register_server_rpc((5, 7));
+    let mut synced = None;
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        synced = latest_board(layers[1]).filter(|board| board.rows[5][7] != 0);
+        synced.is_some()
+    })
+    .await;
+    let synced = synced.unwrap();
+    assert_eq!(synced.rows[5][7], 2);
+    assert_eq!(
+        synced
+            .rows
+            .iter()
+            .flatten()
+            .filter(|&&stone| stone != 0)
+            .count(),
+        1
+    );
+    // The host applied the change locally the same way.
+    assert_eq!(latest_board(&mut host), Some(synced));
+
+    let partial_update = next_binary(&mut observer).await;
+    assert_eq!(partial_update[0], PARTIAL_UPDATE);
+    assert!(partial_update.len() * 10 < full_update.len());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_without_regions_drops_the_connection() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = start_host(&url).await;
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    client.start_game_client("board".to_string());
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        layers[1].has_initial_sync()
+    })
+    .await;
+
+    host.register_server_rpc((0, 0));
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        matches!(
+            layers[1].connection_state(),
+            ConnectionState::Disconnected { .. }
+        )
+    })
+    .await;
+    let ConnectionState::Disconnected {
+        error_string: Some(error),
+    } = client.connection_state()
+    else {
+        panic!("Client got disconnected without an error");
+    };
+    assert!(error.contains("regions"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn host_without_regions_ignores_them() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url, GAME.to_string());
+    host.start_game_server("board".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    while host.get_next_update().is_some() {}
+
+    host.register_server_rpc((0, 0));
+    host.update(0.01);
+    assert!(is_connected(&host));
+    assert!(host.get_next_update().is_none());
+}
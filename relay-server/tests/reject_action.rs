@@ -0,0 +1,128 @@
+//! A rejected action only reaches the player that sent it, neither the other players nor the host hear of it.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet};
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{Frontend, Sum, drive_frontends_until, is_connected};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "reject";
+
+/// Adds every rpc as a delta, but rejects a zero.
+struct RejectingBackend {
+    view_state: Sum,
+    command_list: Vec<BackendCommand<u32>>,
+}
+
+impl BackEndArchitecture<u32, u32, Sum> for RejectingBackend {
+    fn new(_: u16) -> Self {
+        RejectingBackend {
+            view_state: Sum::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, player: u16, amount: u32, _: &PlayerSet) {
+        if amount == 0 {
+            self.command_list.push(BackendCommand::RejectAction {
+                player,
+                reason: format!("Player {player} sent a zero."),
+            });
+            return;
+        }
+        self.view_state.0 += amount;
+        self.command_list.push(BackendCommand::Delta(amount));
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Sum {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u32>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u32, u32, RejectingBackend, Sum>;
+
+/// The sum a frontend sees, with the rejections it got in the order they were taken.
+#[derive(Default)]
+struct SumFrontend {
+    sum: Option<u32>,
+    rejections: Vec<String>,
+}
+
+impl Frontend<Sum, u32> for SumFrontend {
+    fn apply(&mut self, update: ViewStateUpdate<Sum, u32>) {
+        match update {
+            ViewStateUpdate::Full(Sum(full)) | ViewStateUpdate::FullReset(Sum(full)) => {
+                self.sum = Some(full)
+            }
+            ViewStateUpdate::Incremental(amount) => self.sum = self.sum.map(|sum| sum + amount),
+            ViewStateUpdate::Tick => {}
+        }
+    }
+
+    fn reject(&mut self, reason: String) {
+        self.rejections.push(reason);
+    }
+}
+
+/// Checks if all frontends see the sum.
+fn all_see(frontends: &[SumFrontend], sum: u32) -> bool {
+    frontends.iter().all(|frontend| frontend.sum == Some(sum))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_the_offending_player_gets_the_rejection() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut first = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut second = Layer::generate_transport_layer(url, GAME.to_string());
+    let mut frontends: [SumFrontend; 3] = Default::default();
+
+    host.start_game_server("room".to_string(), 0);
+    drive_frontends_until(&mut [&mut host], &mut frontends[..1], |layers, _| {
+        is_connected(layers[0])
+    })
+    .await;
+    first.start_game_client("room".to_string());
+    second.start_game_client("room".to_string());
+    let mut layers = [&mut host, &mut first, &mut second];
+    drive_frontends_until(&mut layers, &mut frontends, |_, frontends| {
+        all_see(&frontends[1..], 0)
+    })
+    .await;
+
+    // The rejection goes out before the following delta, so the other players would have seen it by then.
+    // The RPCs of two clients may reach the host in any order, so the next one waits for the rejection.
+    layers[2].register_server_rpc(0);
+    drive_frontends_until(&mut layers, &mut frontends, |_, frontends| {
+        !frontends[2].rejections.is_empty()
+    })
+    .await;
+    layers[1].register_server_rpc(5);
+    drive_frontends_until(&mut layers, &mut frontends, |_, frontends| {
+        all_see(frontends, 5)
+    })
+    .await;
+    let offender = layers[2].my_player_id().unwrap();
+    assert_eq!(
+        frontends[2].rejections,
+        [format!("Player {offender} sent a zero.")]
+    );
+    assert!(frontends[0].rejections.is_empty());
+    assert!(frontends[1].rejections.is_empty());
+
+    // The rejection of the host only goes to its own frontend.
+    layers[0].register_server_rpc(0);
+    layers[0].register_server_rpc(1);
+    drive_frontends_until(&mut layers, &mut frontends, |_, frontends| {
+        all_see(frontends, 6)
+    })
+    .await;
+    assert_eq!(frontends[0].rejections, ["Player 0 sent a zero."]);
+    assert!(frontends[1].rejections.is_empty());
+    assert_eq!(frontends[2].rejections.len(), 1);
+}
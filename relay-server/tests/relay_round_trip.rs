@@ -0,0 +1,346 @@
+//! End-to-end test: a host and a client exchange a move through an in-process relay.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::{ConnectionState, TransportLayer, ViewStateUpdate};
+use common::{drive_until, is_connected, poll_until};
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "round-trip";
+
+/// The view state remembers the last received rpc with its sender.
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct LastRpc {
+    last: Option<(u16, u8)>,
+}
+
+impl ViewStateArchitecture<u8, (u16, u8)> for LastRpc {
+    fn try_apply(&mut self, delta: &(u16, u8)) -> Result<(), String> {
+        self.last = Some(*delta);
+        Ok(())
+    }
+}
+
+/// The rpc payload that makes the backend reset the game instead of echoing.
+const RESET_PAYLOAD: u8 = 0;
+
+/// The rpc payload that makes the backend pause the game instead of echoing.
+const PAUSE_PAYLOAD: u8 = 255;
+
+/// A backend that echoes every rpc as a delta carrying the player id it came from.
+struct EchoBackend {
+    view_state: LastRpc,
+    command_list: Vec<BackendCommand<(u16, u8)>>,
+}
+
+impl BackEndArchitecture<u8, (u16, u8), LastRpc> for EchoBackend {
+    fn new(_: u16) -> Self {
+        EchoBackend {
+            view_state: LastRpc::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, player: u16, payload: u8, _: &PlayerSet) {
+        self.view_state.last = Some((player, payload));
+        if payload == RESET_PAYLOAD {
+            self.command_list.push(BackendCommand::ResetViewState);
+        } else if payload == PAUSE_PAYLOAD {
+            self.command_list.push(BackendCommand::SetPaused(true));
+        } else {
+            self.command_list
+                .push(BackendCommand::Delta((player, payload)));
+        }
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &LastRpc {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<(u16, u8)>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u8, (u16, u8), EchoBackend, LastRpc>;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_rpc_reaches_host_with_injected_player_id() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    assert_eq!(host.my_player_id(), None);
+
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        is_connected(layers[1])
+    })
+    .await;
+    assert_eq!(host.my_player_id(), Some(0));
+    assert_eq!(client.my_player_id(), Some(1));
+
+    assert_eq!(
+        client.connection_state(),
+        &ConnectionState::Connected {
+            is_server: false,
+            player_id: 1,
+            rule_set: 0
+        }
+    );
+    assert_eq!(client.room_id(), Some(format!("room#{}", GAME).as_str()));
+    assert_eq!(client.game_name(), GAME);
+    assert_eq!(client.connection_string(), host.connection_string());
+
+    client.register_server_rpc(42);
+    assert_eq!(client.pending_rpc_count(), 1);
+    let mut received = None;
+    poll_until(|| {
+        client.update(0.01);
+        assert_eq!(client.pending_rpc_count(), 0);
+        host.update(0.01);
+        let mut pending = host.pending_update_count();
+        while let Some(update) = host.get_next_update() {
+            pending -= 1;
+            if let ViewStateUpdate::Incremental(delta) = update {
+                received = Some(delta);
+            }
+        }
+        assert_eq!(pending, 0);
+        received.is_some()
+    })
+    .await;
+    assert_eq!(received, Some((1, 42)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cleared_rpcs_never_reach_the_host() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        layers[1].has_initial_sync()
+    })
+    .await;
+
+    // The player takes both inputs back in the same frame.
+    client.register_server_rpc(7);
+    client.register_server_rpc(8);
+    assert_eq!(
+        client.pending_rpcs().iter().copied().collect::<Vec<_>>(),
+        [7, 8]
+    );
+    client.clear_pending_rpcs();
+    assert!(client.pending_rpcs().is_empty());
+
+    // The host only gets the input registered afterwards.
+    client.register_server_rpc(9);
+    let mut received = Vec::new();
+    poll_until(|| {
+        client.update(0.01);
+        host.update(0.01);
+        while let Some(update) = host.get_next_update() {
+            if let ViewStateUpdate::Incremental(delta) = update {
+                received.push(delta);
+            }
+        }
+        !received.is_empty()
+    })
+    .await;
+    assert_eq!(received, [(1, 9)]);
+}
+
+/// Drains the updates of a layer and tells which kinds of full synchronization were among them.
+fn drain_full_syncs(layer: &mut Layer) -> (bool, bool) {
+    let (mut full, mut reset) = (false, false);
+    while let Some(update) = layer.get_next_update() {
+        match update {
+            ViewStateUpdate::Full(_) => full = true,
+            ViewStateUpdate::FullReset(_) => reset = true,
+            ViewStateUpdate::Incremental(_) | ViewStateUpdate::Tick => {}
+        }
+    }
+    (full, reset)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reset_arrives_distinct_from_initial_sync() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    assert_eq!(drain_full_syncs(&mut host), (true, false));
+    client.start_game_client("room".to_string());
+
+    poll_until(|| {
+        client.update(0.01);
+        host.update(0.01);
+        let (full, reset) = drain_full_syncs(&mut client);
+        assert!(!reset, "The initial sync must not be flagged as reset");
+        full
+    })
+    .await;
+
+    client.register_server_rpc(RESET_PAYLOAD);
+    let (mut host_reset, mut client_reset) = (false, false);
+    poll_until(|| {
+        client.update(0.01);
+        host.update(0.01);
+        let (host_full, host_got_reset) = drain_full_syncs(&mut host);
+        let (client_full, client_got_reset) = drain_full_syncs(&mut client);
+        assert!(
+            !host_full && !client_full,
+            "A reset must not arrive as plain full sync"
+        );
+        host_reset |= host_got_reset;
+        client_reset |= client_got_reset;
+        host_reset && client_reset
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pause_reaches_clients_including_late_joiners() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut late_client = Layer::generate_transport_layer(url, GAME.to_string());
+
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        is_connected(layers[1])
+    })
+    .await;
+    assert!(!client.is_paused());
+
+    client.register_server_rpc(PAUSE_PAYLOAD);
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        layers[0].is_paused() && layers[1].is_paused()
+    })
+    .await;
+
+    late_client.start_game_client("room".to_string());
+    drive_until(&mut [&mut host, &mut client, &mut late_client], |layers| {
+        layers[2].is_paused()
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn display_names_reach_everyone_including_late_joiners() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut late_client = Layer::generate_transport_layer(url, GAME.to_string());
+
+    assert!(!host.set_display_name("Too early"));
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    assert!(host.set_display_name("Host"));
+    assert_eq!(host.display_name(0), Some("Host"));
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        is_connected(layers[1])
+    })
+    .await;
+
+    assert!(!client.set_display_name(&"x".repeat(protocol::MAX_DISPLAY_NAME_SIZE + 1)));
+    assert!(client.set_display_name("Ålice"));
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        layers[0].display_name(1) == Some("Ålice")
+            && layers[1].display_name(1) == Some("Ålice")
+            && layers[1].display_name(0) == Some("Host")
+    })
+    .await;
+
+    late_client.start_game_client("room".to_string());
+    drive_until(&mut [&mut host, &mut client, &mut late_client], |layers| {
+        layers[2].display_name(0) == Some("Host") && layers[2].display_name(1) == Some("Ålice")
+    })
+    .await;
+
+    // A player that leaves loses its name for everyone.
+    client.disconnect();
+    drive_until(&mut [&mut host, &mut late_client], |layers| {
+        layers[0].display_name(1).is_none() && layers[1].display_name(1).is_none()
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn initial_sync_flag_follows_the_first_full_update() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    assert!(!host.has_initial_sync());
+
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    assert!(host.has_initial_sync());
+
+    client.start_game_client("room".to_string());
+    drive_until(&mut [&mut client], |layers| is_connected(layers[0])).await;
+    // The host has not seen the client yet, so it sent no full update.
+    assert!(!client.has_initial_sync());
+    drive_until(&mut [&mut host, &mut client], |layers| {
+        layers[1].has_initial_sync()
+    })
+    .await;
+    let (full, _) = drain_full_syncs(&mut client);
+    assert!(full);
+
+    client.disconnect();
+    assert!(!client.has_initial_sync());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requested_full_sync_reaches_only_the_requesting_client() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut other_client = Layer::generate_transport_layer(url, GAME.to_string());
+    assert!(!client.request_full_sync());
+
+    host.start_game_server("room".to_string(), 0);
+    drive_until(&mut [&mut host], |layers| is_connected(layers[0])).await;
+    assert!(!host.request_full_sync());
+    client.start_game_client("room".to_string());
+    other_client.start_game_client("room".to_string());
+    drive_until(&mut [&mut host, &mut client, &mut other_client], |layers| {
+        layers[1].has_initial_sync() && layers[2].has_initial_sync()
+    })
+    .await;
+    drain_full_syncs(&mut client);
+    drain_full_syncs(&mut other_client);
+
+    // A delta before the request must not get lost or applied twice.
+    client.register_server_rpc(7);
+    client.update(0.01);
+    assert!(client.request_full_sync());
+    let mut synced_state = None;
+    poll_until(|| {
+        client.update(0.01);
+        host.update(0.01);
+        other_client.update(0.01);
+        while let Some(update) = client.get_next_update() {
+            if let ViewStateUpdate::Full(state) = update {
+                synced_state = Some(state.last);
+            }
+        }
+        let (full, _) = drain_full_syncs(&mut other_client);
+        assert!(!full, "Only the requesting client gets the full sync");
+        synced_state.is_some()
+    })
+    .await;
+    assert!(matches!(synced_state, Some(Some((_, 7)))));
+}
@@ -0,0 +1,105 @@
+//! Game results reported by the host end up at the configured results sink.
+
+mod common;
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use axum::routing::post;
+use common::{join, raw_frame, start_relay_with_state};
+use futures_util::SinkExt;
+use protocol::{HAND_SHAKE_RESPONSE, RESULT};
+use relay_server::lobby::AppState;
+use relay_server::results::{ROOM_ID_HEADER, ResultsSink};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Starts a results service that passes every posted room id and blob into the returned channel.
+async fn start_results_service() -> (String, mpsc::UnboundedReceiver<(String, Vec<u8>)>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/results",
+        post(move |headers: HeaderMap, body: Bytes| async move {
+            let room_id = headers
+                .get(ROOM_ID_HEADER)
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+                .unwrap_or_default();
+            sender.send((room_id, body.to_vec())).unwrap();
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}/results", address), receiver)
+}
+
+#[tokio::test]
+async fn result_gets_posted_to_sink() {
+    let (results_url, mut results) = start_results_service().await;
+    let state = AppState {
+        results: ResultsSink::posting_to(&results_url).unwrap(),
+        ..AppState::default()
+    };
+    let url = start_relay_with_state(&["game"], state).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    host.send(raw_frame(vec![RESULT, 7, 0, 42])).await.unwrap();
+    let (room_id, blob) = tokio::time::timeout(Duration::from_secs(5), results.recv())
+        .await
+        .expect("No result in time")
+        .unwrap();
+    assert_eq!(room_id, "room#game");
+    assert_eq!(blob, vec![7, 0, 42]);
+}
+
+#[test]
+fn results_sink_requires_http_url() {
+    assert!(ResultsSink::posting_to("https://example.com/results").is_err());
+    assert!(ResultsSink::posting_to("not a url").is_err());
+    assert!(ResultsSink::posting_to("http://localhost:9000/results").is_ok());
+}
+
+#[tokio::test]
+async fn posts_beyond_the_limit_get_dropped_until_one_times_out() {
+    // The service takes every result in, but never answers.
+    let (sender, mut results) = mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/results",
+        post(move |body: Bytes| async move {
+            sender.send(body.to_vec()).unwrap();
+            std::future::pending::<()>().await
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let results_url = format!("http://{}/results", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let state = AppState {
+        results: ResultsSink::posting_to(&results_url)
+            .unwrap()
+            .with_post_limits(1, Duration::from_secs(1)),
+        ..AppState::default()
+    };
+    let url = start_relay_with_state(&["game"], state).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    host.send(raw_frame(vec![RESULT, 1])).await.unwrap();
+    let first = tokio::time::timeout(Duration::from_secs(5), results.recv())
+        .await
+        .expect("No result in time")
+        .unwrap();
+    assert_eq!(first, vec![1]);
+    // The first post is still underway, so this one gets dropped.
+    host.send(raw_frame(vec![RESULT, 2])).await.unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    // The first post timed out meanwhile and made room for the next one.
+    host.send(raw_frame(vec![RESULT, 3])).await.unwrap();
+    let next = tokio::time::timeout(Duration::from_secs(5), results.recv())
+        .await
+        .expect("No result in time")
+        .unwrap();
+    assert_eq!(next, vec![3]);
+}
@@ -0,0 +1,48 @@
+//! A full room rejects joiners with the reason matching their would-be role.
+
+mod common;
+
+use common::{GameEntryBuilder, join, start_relay_with_entries};
+use protocol::{HAND_SHAKE_RESPONSE, JoinRejection, SERVER_ERROR};
+
+/// Starts a relay with a single game of two players and the indicated spectator slots.
+async fn start_with_spectators(max_spectators: Option<u16>) -> String {
+    let entry = GameEntryBuilder::new("game")
+        .max_players(2)
+        .max_spectators(max_spectators)
+        .build();
+    start_relay_with_entries([entry]).await
+}
+
+/// Checks that the answer is a rejection with the given reason.
+fn assert_rejected(answer: &[u8], rejection: JoinRejection) {
+    assert_eq!(answer[0], SERVER_ERROR);
+    let text = String::from_utf8_lossy(&answer[1..]);
+    assert_eq!(JoinRejection::from_message(&text), Some(rejection));
+}
+
+#[tokio::test]
+async fn full_game_without_spectators() {
+    let url = start_with_spectators(None).await;
+    let (_host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_player, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (_rejected, answer) = join(&url, "game", "room", false).await;
+    assert_rejected(&answer, JoinRejection::GameFull);
+}
+
+#[tokio::test]
+async fn full_spectator_slots() {
+    let url = start_with_spectators(Some(1)).await;
+    let (_host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_player, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_spectator, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (_rejected, answer) = join(&url, "game", "room", false).await;
+    assert_rejected(&answer, JoinRejection::SpectatorsFull);
+}
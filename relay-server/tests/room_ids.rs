@@ -0,0 +1,75 @@
+//! Room names may contain any character, including the `#` separator of the compound room id.
+
+mod common;
+
+use common::join;
+use protocol::{
+    CompoundRoomId, GameId, HAND_SHAKE_RESPONSE, HandshakeResponse, MAX_ROOM_NAME_LENGTH, RoomId,
+    SERVER_ERROR,
+};
+
+/// The compound id of the room of the game.
+fn compound_room_id(room: &str, game: &str) -> CompoundRoomId {
+    CompoundRoomId::compound(&RoomId::new(room), &GameId::new(game))
+}
+
+#[test]
+fn compound_ids_do_not_collide() {
+    assert_ne!(compound_room_id("a#b", "c"), compound_room_id("a", "b#c"));
+    assert_ne!(compound_room_id("a%23", "c"), compound_room_id("a#", "c"));
+}
+
+#[test]
+fn compound_ids_know_their_game() {
+    assert!(compound_room_id("a#b", "c").is_of_game(&GameId::new("c")));
+    assert!(!compound_room_id("a", "b#c").is_of_game(&GameId::new("c")));
+    assert!(!compound_room_id("a", "xc").is_of_game(&GameId::new("c")));
+}
+
+#[test]
+fn compound_ids_split_back() {
+    for (room, game) in [
+        ("plain", "tic-tac-toe"),
+        ("a#b", "c#d"),
+        ("100%#", "%23"),
+        ("Spielzimmer für Grüße 🎲", "ゲーム"),
+    ] {
+        let compound_id = compound_room_id(room, game);
+        assert_eq!(
+            compound_id.split(),
+            Some((RoomId::new(room), GameId::new(game)))
+        );
+    }
+}
+
+#[tokio::test]
+async fn rooms_with_separator_in_names_coexist() {
+    let url = common::start_relay(&["c", "b#c"]).await;
+
+    let (_first_host, answer) = join(&url, "c", "a#b", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_second_host, answer) = join(&url, "b#c", "a", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    // A client finds the room with the multibyte name again.
+    let room = "Zimmer #1 🎲";
+    let (_host, answer) = join(&url, "c", room, true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_client, answer) = join(&url, "c", room, false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let response = HandshakeResponse::from_frame(&answer).unwrap();
+    assert_eq!(response.room_id, compound_room_id(room, "c").as_str());
+}
+
+#[tokio::test]
+async fn overlong_room_names_get_rejected() {
+    let url = common::start_relay(&["game"]).await;
+
+    let room = "ü".repeat(MAX_ROOM_NAME_LENGTH);
+    let (_host, answer) = join(&url, "game", &room, true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let room = "ü".repeat(MAX_ROOM_NAME_LENGTH + 1);
+    let (_host, answer) = join(&url, "game", &room, true).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+}
@@ -0,0 +1,63 @@
+//! The public room listing shows the rule variation of every room.
+
+mod common;
+
+use common::{
+    GameEntryBuilder, http_request, join_with_variation, start_relay, start_relay_with_entries,
+};
+use protocol::HAND_SHAKE_RESPONSE;
+
+#[tokio::test]
+async fn listing_shows_rule_variation() {
+    let url = start_relay(&["ternio", "chess"]).await;
+    let (_speed, answer) = join_with_variation(&url, "ternio", "speed", true, 2).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_chess, answer) = join_with_variation(&url, "chess", "blitz", true, 0).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (status, body) = http_request(&url, "GET", "/rooms?game=ternio", None, "").await;
+    assert_eq!(status, 200);
+    let rooms: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(rooms.as_array().unwrap().len(), 1);
+    assert_eq!(rooms[0]["room"], "speed");
+    assert_eq!(rooms[0]["game"], "ternio");
+    assert_eq!(rooms[0]["rule_variation"], 2);
+    assert_eq!(rooms[0]["amount_of_players"], 1);
+
+    let (_, body) = http_request(&url, "GET", "/rooms", None, "").await;
+    let rooms: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(rooms.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn listing_shows_variation_name() {
+    let entry = GameEntryBuilder::new("ternio")
+        .max_players(3)
+        .variation_name(1, "Blitz")
+        .build();
+    let url = start_relay_with_entries([entry]).await;
+    let (_blitz, answer) = join_with_variation(&url, "ternio", "fast", true, 1).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_plain, answer) = join_with_variation(&url, "ternio", "slow", true, 0).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (_, body) = http_request(&url, "GET", "/rooms", None, "").await;
+    let rooms: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let fast = rooms
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|room| room["room"] == "fast")
+        .unwrap();
+    assert_eq!(fast["variation_name"], "Blitz");
+    let slow = rooms
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|room| room["room"] == "slow")
+        .unwrap();
+    assert!(slow.get("variation_name").is_none());
+
+    let (_, body) = http_request(&url, "GET", "/enlist", None, "").await;
+    assert!(body.contains("001 (Blitz)"));
+}
@@ -0,0 +1,54 @@
+//! A game with a room limit rejects the creation of further rooms, until one of its rooms closes.
+
+mod common;
+
+use common::{GameEntryBuilder, join, start_relay_with_entries};
+use futures_util::SinkExt;
+use protocol::{HAND_SHAKE_RESPONSE, SERVER_ERROR};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Starts a relay with a game limited to two rooms and an unlimited one.
+async fn start_with_quota() -> String {
+    start_relay_with_entries([
+        GameEntryBuilder::new("Ternio")
+            .max_players(3)
+            .max_rooms(2)
+            .build(),
+        GameEntryBuilder::new("free").max_players(2).build(),
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn full_quota_rejects_the_next_room() {
+    let url = start_with_quota().await;
+    let (mut first, answer) = join(&url, "Ternio", "first", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_second, answer) = join(&url, "Ternio", "second", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let (_third, answer) = join(&url, "Ternio", "third", true).await;
+    assert_eq!(answer[0], SERVER_ERROR);
+    assert_eq!(
+        String::from_utf8_lossy(&answer[1..]),
+        "Game Ternio has reached its limit of 2 rooms."
+    );
+    // Other games and joining clients are not affected.
+    let (_other, answer) = join(&url, "free", "third", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_client, answer) = join(&url, "Ternio", "second", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    // A closed room frees its slot.
+    first.send(Message::Close(None)).await.unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let (_third, answer) = join(&url, "Ternio", "third", true).await;
+        if answer[0] == HAND_SHAKE_RESPONSE {
+            break;
+        }
+        assert!(Instant::now() < deadline, "Slot not freed in time");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
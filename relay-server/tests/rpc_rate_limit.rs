@@ -0,0 +1,80 @@
+//! The relay limits the RPCs of a client to the rate configured for its game and drops the ones beyond.
+
+mod common;
+
+use common::{GameEntryBuilder, join, next_binary, raw_frame, start_relay_with_entries};
+use futures_util::SinkExt;
+use protocol::{HAND_SHAKE_RESPONSE, NEW_CLIENT, SERVER_RPC, SET_NAME};
+use relay_server::lobby::parse_config;
+use relay_server::message_relay::RpcRateLimit;
+use std::time::{Duration, Instant};
+
+/// Sends a burst of RPCs from a fresh client of the game and returns how many of them the host got.
+async fn forwarded_of_burst(url: &str, game: &str, burst: u8) -> usize {
+    let (mut host, answer) = join(url, game, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(url, game, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+
+    for index in 0..burst {
+        let frame = vec![SERVER_RPC, 0, 0, 0, index, index];
+        client.send(raw_frame(frame)).await.unwrap();
+    }
+    // A name is no RPC and always passes, so it marks the end of the burst.
+    let frame = vec![SET_NAME, b'x'];
+    client.send(raw_frame(frame)).await.unwrap();
+
+    let mut forwarded = 0;
+    loop {
+        let frame = next_binary(&mut host).await;
+        match frame[0] {
+            SERVER_RPC => forwarded += 1,
+            SET_NAME => return forwarded,
+            other => panic!("Unexpected message {other}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn every_game_gets_its_own_rate() {
+    let url = start_relay_with_entries([
+        GameEntryBuilder::new("drawing")
+            .max_rpcs_per_sec(1000)
+            .build(),
+        GameEntryBuilder::new("board").max_rpcs_per_sec(3).build(),
+    ])
+    .await;
+
+    assert_eq!(forwarded_of_burst(&url, "drawing", 30).await, 30);
+    // The bucket holds a second's worth of RPCs, the burst is over long before the next one fits in.
+    let forwarded = forwarded_of_burst(&url, "board", 30).await;
+    assert!((3..=4).contains(&forwarded), "Forwarded {forwarded} RPCs");
+}
+
+#[test]
+fn bucket_refills_with_the_rate() {
+    let start = Instant::now();
+    let mut limit = RpcRateLimit::new(2);
+    assert!(limit.allows(start));
+    assert!(limit.allows(start));
+    assert!(!limit.allows(start));
+    // Half a second brings back one token, a long pause no more than the capacity.
+    assert!(limit.allows(start + Duration::from_millis(500)));
+    assert!(!limit.allows(start + Duration::from_millis(500)));
+    let later = start + Duration::from_secs(60);
+    assert!(limit.allows(later));
+    assert!(limit.allows(later));
+    assert!(!limit.allows(later));
+}
+
+#[test]
+fn zero_rate_gets_rejected() {
+    let config = r#"[{"name": "game", "max_players": 2, "max_rpcs_per_sec": 0}]"#;
+    assert!(parse_config(config).is_err());
+    let config = r#"[{"name": "game", "max_players": 2, "max_rpcs_per_sec": 20}]"#;
+    assert_eq!(
+        parse_config(config).unwrap()["game"].max_rpcs_per_sec,
+        Some(20)
+    );
+}
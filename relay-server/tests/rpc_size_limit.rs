@@ -0,0 +1,64 @@
+//! The relay enforces the maximum payload size of client RPCs.
+
+mod common;
+
+use common::{join, next_binary, raw_frame, start_relay};
+use futures_util::SinkExt;
+use protocol::{
+    HAND_SHAKE_RESPONSE, MAX_RPC_PAYLOAD_SIZE, NEW_CLIENT, RPC_SEQUENCE_SIZE, SERVER_ERROR,
+    SERVER_RPC,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Builds an RPC frame with a payload of the indicated size.
+fn rpc_frame(payload_size: usize) -> Message {
+    let mut frame = vec![SERVER_RPC, 0, 0, 0, 0];
+    frame.resize(1 + RPC_SEQUENCE_SIZE + payload_size, 7);
+    raw_frame(frame)
+}
+
+#[tokio::test]
+async fn oversized_rpc_gets_rejected() {
+    let url = start_relay(&["game"]).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+
+    // An RPC at the limit passes with the player id injected.
+    client.send(rpc_frame(MAX_RPC_PAYLOAD_SIZE)).await.unwrap();
+    let forwarded = next_binary(&mut host).await;
+    assert_eq!(forwarded[0], SERVER_RPC);
+    assert_eq!(
+        forwarded.len(),
+        3 + RPC_SEQUENCE_SIZE + MAX_RPC_PAYLOAD_SIZE
+    );
+
+    // One byte more ends the connection of the client.
+    client
+        .send(rpc_frame(MAX_RPC_PAYLOAD_SIZE + 1))
+        .await
+        .unwrap();
+    let closing = next_binary(&mut client).await;
+    assert_eq!(closing[0], SERVER_ERROR);
+    assert_eq!(&closing[1..], b"RPC payload too large.");
+}
+
+#[tokio::test]
+async fn rpc_without_sequence_number_gets_rejected() {
+    let url = start_relay(&["game"]).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+
+    client
+        .send(raw_frame(vec![SERVER_RPC, 0, 0, 0]))
+        .await
+        .unwrap();
+    let closing = next_binary(&mut client).await;
+    assert_eq!(closing[0], SERVER_ERROR);
+    assert_eq!(&closing[1..], b"Malformed message received.");
+}
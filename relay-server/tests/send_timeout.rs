@@ -0,0 +1,51 @@
+//! A client that never reads gets dropped after the send timeout instead of pinning its task.
+
+mod common;
+
+use common::{Socket, join, next_binary, raw_frame, start_relay_with_state};
+use futures_util::SinkExt;
+use protocol::{CLIENT_DISCONNECTS, DELTA_UPDATE, FULL_UPDATE, HAND_SHAKE_RESPONSE, NEW_CLIENT};
+use relay_server::lobby::AppState;
+use std::time::Duration;
+
+/// Sends a view state update with the given sequence number and payload from the host.
+async fn send_update(host: &mut Socket, message_type: u8, sequence: u32, payload: &[u8]) {
+    let mut frame = vec![message_type];
+    frame.extend_from_slice(&sequence.to_be_bytes());
+    frame.extend_from_slice(payload);
+    host.send(raw_frame(frame)).await.unwrap();
+}
+
+#[tokio::test]
+async fn client_that_never_reads_gets_dropped() {
+    let state = AppState {
+        send_timeout: Duration::from_millis(300),
+        ..AppState::default()
+    };
+    let url = start_relay_with_state(&["game"], state).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut client, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    assert_eq!(next_binary(&mut host).await[0], NEW_CLIENT);
+
+    // Synchronize the client, so it gets the deltas forwarded.
+    send_update(&mut host, FULL_UPDATE, 0, b"{}").await;
+    assert_eq!(next_binary(&mut client).await[0], FULL_UPDATE);
+
+    // From now on the client does not read any more. Far more data than the socket buffers hold,
+    // but fewer messages than the broadcast channel holds, so the client does not get dropped for lagging.
+    let payload = vec![0u8; 256 * 1024];
+    for sequence in 1..=200 {
+        send_update(&mut host, DELTA_UPDATE, sequence, &payload).await;
+    }
+
+    loop {
+        let message = next_binary(&mut host).await;
+        if message[0] == CLIENT_DISCONNECTS {
+            assert_eq!(&message[1..3], &1u16.to_be_bytes());
+            break;
+        }
+    }
+    drop(client);
+}
@@ -0,0 +1,65 @@
+//! The relay tells everyone in a room how many spectators are watching, without telling who they are.
+
+mod common;
+
+use backbone_lib::transport_layer::TransportLayer;
+use common::{
+    Empty, GameEntryBuilder, IdleBackend, Socket, drive_until, is_connected, join, next_binary,
+    start_relay_with_entries,
+};
+use futures_util::SinkExt;
+use protocol::{CLIENT_DISCONNECTS, HAND_SHAKE_RESPONSE, NEW_CLIENT, SPECTATOR_COUNT};
+use tokio_tungstenite::tungstenite::Message;
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "watched";
+
+type Layer = TransportLayer<(), (), IdleBackend<Empty>, Empty>;
+
+/// Reads the messages of the raw host up to the next spectator count.
+async fn next_count(host: &mut Socket) -> Vec<u8> {
+    loop {
+        let frame = next_binary(host).await;
+        if frame[0] == SPECTATOR_COUNT {
+            return frame;
+        }
+        assert!(matches!(frame[0], NEW_CLIENT | CLIENT_DISCONNECTS));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spectators_get_counted_anonymously() {
+    let entry = GameEntryBuilder::new(GAME)
+        .max_players(2)
+        .max_spectators(Some(2))
+        .build();
+    let url = start_relay_with_entries([entry]).await;
+    let (mut host, answer) = join(&url, GAME, "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    let mut player = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    player.start_game_client("room".to_string());
+    drive_until(&mut [&mut player], |layers| is_connected(layers[0])).await;
+    assert_eq!(player.spectator_count(), 0);
+
+    let (mut first, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    // The count is all a spectator learns, not even its own id.
+    assert_eq!(next_binary(&mut first).await, vec![SPECTATOR_COUNT, 0, 1]);
+    assert_eq!(next_count(&mut host).await, vec![SPECTATOR_COUNT, 0, 1]);
+    let (mut second, _) = join(&url, GAME, "room", false).await;
+    assert_eq!(next_binary(&mut second).await, vec![SPECTATOR_COUNT, 0, 2]);
+    assert_eq!(next_count(&mut host).await, vec![SPECTATOR_COUNT, 0, 2]);
+    drive_until(&mut [&mut player], |layers| {
+        layers[0].spectator_count() == 2
+    })
+    .await;
+
+    first.send(Message::Close(None)).await.unwrap();
+    assert_eq!(next_binary(&mut second).await, vec![SPECTATOR_COUNT, 0, 1]);
+    assert_eq!(next_count(&mut host).await, vec![SPECTATOR_COUNT, 0, 1]);
+    drive_until(&mut [&mut player], |layers| {
+        layers[0].spectator_count() == 1
+    })
+    .await;
+}
@@ -0,0 +1,71 @@
+//! Spectators only get the public messages of the host, private deltas for single players never reach them.
+
+mod common;
+
+use common::{GameEntryBuilder, Socket, join, next_binary, raw_frame, start_relay_with_entries};
+use futures_util::SinkExt;
+use protocol::{
+    DELTA_UPDATE, DELTA_UPDATE_TO, FULL_UPDATE, HAND_SHAKE_RESPONSE, PAUSE, SPECTATOR_COUNT,
+};
+
+/// Sends a frame from the raw host.
+async fn send(host: &mut Socket, frame: Vec<u8>) {
+    host.send(raw_frame(frame)).await.unwrap();
+}
+
+/// Builds a view state update with the sequence number and a marker byte as payload.
+fn update(message_type: u8, sequence: u32, marker: u8) -> Vec<u8> {
+    let mut frame = vec![message_type];
+    frame.extend_from_slice(&sequence.to_be_bytes());
+    frame.push(marker);
+    frame
+}
+
+/// Builds a delta for a single client.
+fn delta_to(client: u16, sequence: u32, marker: u8) -> Vec<u8> {
+    let mut frame = vec![DELTA_UPDATE_TO];
+    frame.extend_from_slice(&client.to_be_bytes());
+    frame.extend_from_slice(&update(DELTA_UPDATE, sequence, marker)[1..]);
+    frame
+}
+
+#[tokio::test]
+async fn spectator_never_receives_a_targeted_delta() {
+    let entry = GameEntryBuilder::new("game")
+        .max_players(2)
+        .max_spectators(Some(1))
+        .build();
+    let url = start_relay_with_entries([entry]).await;
+    let (mut host, answer) = join(&url, "game", "room", true).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    // Player 1 takes the last player slot, client 2 watches.
+    let (mut player, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (mut spectator, answer) = join(&url, "game", "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+
+    send(&mut host, update(FULL_UPDATE, 0, 0)).await;
+    send(&mut host, delta_to(1, 1, 1)).await;
+    // Even addressing the spectator directly does not get a private delta to it.
+    send(&mut host, delta_to(2, 2, 2)).await;
+    send(&mut host, update(DELTA_UPDATE, 3, 3)).await;
+    send(&mut host, vec![PAUSE, 1]).await;
+
+    // Both learn that someone is watching.
+    assert_eq!(next_binary(&mut player).await, vec![SPECTATOR_COUNT, 0, 1]);
+    assert_eq!(next_binary(&mut player).await, update(FULL_UPDATE, 0, 0));
+    assert_eq!(next_binary(&mut player).await, update(DELTA_UPDATE, 1, 1));
+    assert_eq!(next_binary(&mut player).await, update(DELTA_UPDATE, 3, 3));
+    assert_eq!(next_binary(&mut player).await, vec![PAUSE, 1]);
+
+    assert_eq!(
+        next_binary(&mut spectator).await,
+        vec![SPECTATOR_COUNT, 0, 1]
+    );
+    assert_eq!(next_binary(&mut spectator).await, update(FULL_UPDATE, 0, 0));
+    assert_eq!(
+        next_binary(&mut spectator).await,
+        update(DELTA_UPDATE, 3, 3)
+    );
+    assert_eq!(next_binary(&mut spectator).await, vec![PAUSE, 1]);
+}
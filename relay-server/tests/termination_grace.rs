@@ -0,0 +1,163 @@
+//! A termination of the room by the backend may be deferred, so a returning player can save the game.
+//! Only the backend decides, whether a newcomer returns to the seat, a spectator does not.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet, ViewStateArchitecture};
+use backbone_lib::transport_layer::TransportLayer;
+use common::{
+    GameEntryBuilder, is_connected, join, next_binary, poll_until, raw_frame,
+    start_relay_with_entries,
+};
+use futures_util::SinkExt;
+use protocol::{CLIENT_DISCONNECTS_SELF, FULL_UPDATE, HAND_SHAKE_RESPONSE};
+use serde::{Deserialize, Serialize};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "grace";
+
+/// The view state counts the players that arrived.
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct Arrivals(u16);
+
+impl ViewStateArchitecture<u8, u8> for Arrivals {
+    fn try_apply(&mut self, _: &u8) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The seats of the game, the host included. The relay makes everyone beyond them a spectator.
+const SEATS: u16 = 2;
+
+/// A backend that terminates the room whenever a player leaves, a newcomer taking over a free seat cancels it.
+struct FragileBackend {
+    view_state: Arrivals,
+    /// The clients present, spectators included, as the relay counts them to tell players and spectators apart.
+    present: u16,
+    command_list: Vec<BackendCommand<u8>>,
+}
+
+impl BackEndArchitecture<u8, u8, Arrivals> for FragileBackend {
+    fn new(_: u16) -> Self {
+        FragileBackend {
+            view_state: Arrivals::default(),
+            present: 0,
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {
+        self.view_state.0 += 1;
+        if self.present < SEATS {
+            self.command_list.push(BackendCommand::CancelTermination);
+        }
+        self.present += 1;
+    }
+    fn player_departure(&mut self, _: u16, _: u16) {
+        self.present -= 1;
+        self.command_list.push(BackendCommand::TerminateRoom);
+    }
+    fn inform_rpc(&mut self, _: u16, _: u8, _: &PlayerSet) {}
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Arrivals {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u8>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u8, u8, FragileBackend, Arrivals>;
+
+/// Updates the host without letting any time pass until the condition holds,
+/// so the grace period only runs down with the explicit updates of the tests.
+async fn drive_frozen_until(host: &mut Layer, condition: impl Fn(&Layer) -> bool) {
+    poll_until(|| {
+        host.update(0.0);
+        condition(host)
+    })
+    .await;
+}
+
+/// Starts a host with the grace period and lets a client join and leave again.
+async fn host_after_departure(url: &str, grace: f32) -> Layer {
+    let mut host = Layer::generate_transport_layer(url.to_string(), GAME.to_string());
+    host.set_termination_grace(grace);
+    host.start_game_server("room".to_string(), 0);
+    drive_frozen_until(&mut host, is_connected).await;
+
+    let (mut client, answer) = join(url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    drive_frozen_until(&mut host, |host| host.ready_count() == Some((0, 2))).await;
+    client
+        .send(raw_frame(vec![CLIENT_DISCONNECTS_SELF]))
+        .await
+        .unwrap();
+    drive_frozen_until(&mut host, Layer::is_termination_pending).await;
+    host
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn termination_happens_after_the_grace_period() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = host_after_departure(&url, 1.0).await;
+    assert!(is_connected(&host));
+
+    // Even a late update does not end the game before the grace period is over.
+    host.update(0.5);
+    assert!(is_connected(&host));
+    host.update(0.6);
+    assert!(!is_connected(&host));
+    assert!(!host.is_termination_pending());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn returning_player_cancels_the_termination() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = host_after_departure(&url, 1.0).await;
+
+    let (mut returning, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    drive_frozen_until(&mut host, |host| !host.is_termination_pending()).await;
+    assert_eq!(host.ready_count(), Some((0, 2)));
+
+    host.update(2.0);
+    assert!(is_connected(&host));
+    // The returning player is synchronized like any joining client.
+    while next_binary(&mut returning).await[0] != FULL_UPDATE {}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spectator_does_not_cancel_the_termination() {
+    let entry = GameEntryBuilder::new(GAME)
+        .max_players(SEATS)
+        .max_spectators(Some(2))
+        .build();
+    let url = start_relay_with_entries([entry]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    host.set_termination_grace(1.0);
+    host.start_game_server("room".to_string(), 0);
+    drive_frozen_until(&mut host, is_connected).await;
+
+    let (mut player, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    let (_first_spectator, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    drive_frozen_until(&mut host, |host| host.ready_count() == Some((0, 3))).await;
+    player
+        .send(raw_frame(vec![CLIENT_DISCONNECTS_SELF]))
+        .await
+        .unwrap();
+    drive_frozen_until(&mut host, Layer::is_termination_pending).await;
+
+    // The relay still counts the first spectator, so the newcomer watches as well and the backend keeps the termination.
+    let (_second_spectator, answer) = join(&url, GAME, "room", false).await;
+    assert_eq!(answer[0], HAND_SHAKE_RESPONSE);
+    drive_frozen_until(&mut host, |host| {
+        host.ready_count() == Some((0, 3)) && host.spectator_count() == 2
+    })
+    .await;
+    assert!(host.is_termination_pending());
+
+    host.update(1.1);
+    assert!(!is_connected(&host));
+}
@@ -0,0 +1,102 @@
+//! A tick of the backend reaches the local and the remote frontend after the deltas drained with it.
+
+mod common;
+
+use backbone_lib::traits::{BackEndArchitecture, BackendCommand, PlayerSet};
+use backbone_lib::transport_layer::{TransportLayer, ViewStateUpdate};
+use common::{Frontend, Sum, drive_frontends_until, is_connected};
+
+/// The game name registered in the relay for this test.
+const GAME: &str = "tick";
+
+/// Adds every rpc as a delta followed by a tick, a zero only ticks.
+struct TickBackend {
+    view_state: Sum,
+    command_list: Vec<BackendCommand<u32>>,
+}
+
+impl BackEndArchitecture<u32, u32, Sum> for TickBackend {
+    fn new(_: u16) -> Self {
+        TickBackend {
+            view_state: Sum::default(),
+            command_list: Vec::new(),
+        }
+    }
+    fn player_arrival(&mut self, _: u16) {}
+    fn player_departure(&mut self, _: u16, _: u16) {}
+    fn inform_rpc(&mut self, _: u16, amount: u32, _: &PlayerSet) {
+        if amount > 0 {
+            self.view_state.0 += amount;
+            self.command_list.push(BackendCommand::Delta(amount));
+        }
+        self.command_list.push(BackendCommand::Tick);
+    }
+    fn timer_triggered(&mut self, _: u16, _: &PlayerSet) {}
+    fn get_view_state(&self) -> &Sum {
+        &self.view_state
+    }
+    fn drain_commands(&mut self) -> Vec<BackendCommand<u32>> {
+        std::mem::take(&mut self.command_list)
+    }
+}
+
+type Layer = TransportLayer<u32, u32, TickBackend, Sum>;
+
+/// A readable form of the updates, as the view state update itself can not be compared.
+#[derive(PartialEq, Debug)]
+enum Seen {
+    Full(u32),
+    Delta(u32),
+    Tick,
+}
+
+/// Collects the updates of a layer.
+impl Frontend<Sum, u32> for Vec<Seen> {
+    fn apply(&mut self, update: ViewStateUpdate<Sum, u32>) {
+        self.push(match update {
+            ViewStateUpdate::Full(Sum(sum)) | ViewStateUpdate::FullReset(Sum(sum)) => {
+                Seen::Full(sum)
+            }
+            ViewStateUpdate::Incremental(amount) => Seen::Delta(amount),
+            ViewStateUpdate::Tick => Seen::Tick,
+        });
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tick_follows_the_deltas_on_host_and_client() {
+    let url = common::start_relay(&[GAME]).await;
+    let mut host = Layer::generate_transport_layer(url.clone(), GAME.to_string());
+    let mut client = Layer::generate_transport_layer(url, GAME.to_string());
+    // The tick has to flush the buffered delta, so the long interval must not hold it back.
+    host.set_delta_flush_interval(60_000);
+    let mut seen = [Vec::new(), Vec::new()];
+
+    host.start_game_server("room".to_string(), 0);
+    drive_frontends_until(&mut [&mut host], &mut seen[..1], |layers, _| {
+        is_connected(layers[0])
+    })
+    .await;
+    client.start_game_client("room".to_string());
+    drive_frontends_until(&mut [&mut host, &mut client], &mut seen, |_, seen| {
+        seen[1] == [Seen::Full(0)]
+    })
+    .await;
+
+    client.register_server_rpc(3);
+    drive_frontends_until(&mut [&mut host, &mut client], &mut seen, |_, seen| {
+        seen[1].len() == 3
+    })
+    .await;
+    assert_eq!(seen[1], [Seen::Full(0), Seen::Delta(3), Seen::Tick]);
+    assert_eq!(seen[0][1..], [Seen::Delta(3), Seen::Tick]);
+
+    // A tick alone leaves the view state untouched.
+    client.register_server_rpc(0);
+    drive_frontends_until(&mut [&mut host, &mut client], &mut seen, |_, seen| {
+        seen[1].len() == 4
+    })
+    .await;
+    assert_eq!(seen[1][3], Seen::Tick);
+    assert_eq!(seen[0][1..], [Seen::Delta(3), Seen::Tick, Seen::Tick]);
+}